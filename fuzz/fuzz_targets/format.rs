@@ -0,0 +1,13 @@
+#![no_main]
+
+use fat32_exam::fat32::{mkfs, FormatOptions};
+use libfuzzer_sys::fuzz_target;
+
+// Formate une petite image avec des options quelconques puis la parcourt
+fuzz_target!(|input: (FormatOptions, u16)| {
+    let (options, sectors) = input;
+    let mut image = vec![0u8; sectors as usize * 512];
+    if mkfs::format(&mut image, &options).is_ok() {
+        fat32_exam::fuzz::mount(&image);
+    }
+});
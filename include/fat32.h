@@ -0,0 +1,49 @@
+/* Interface C de fat32-exam (feature ffi), voir src/ffi.rs */
+#ifndef FAT32_H
+#define FAT32_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define FAT32_OK                    0
+#define FAT32_ERR_NULL             -1
+#define FAT32_ERR_INVALID_IMAGE    -2
+#define FAT32_ERR_NOT_FOUND        -3
+#define FAT32_ERR_NOT_A_DIRECTORY  -4
+#define FAT32_ERR_IS_A_DIRECTORY   -5
+#define FAT32_ERR_INVALID_PATH     -6
+#define FAT32_ERR_BUFFER_TOO_SMALL -7
+#define FAT32_ERR_LIMIT            -8
+#define FAT32_ERR_CORRUPTED        -9
+#define FAT32_ERR_IO              -10
+#define FAT32_ERR_NO_MEMORY       -11
+
+#define FAT32_NAME_MAX 255
+
+/* Volume ouvert (opaque) */
+typedef struct Fat32Handle Fat32Handle;
+
+typedef struct Fat32DirEntry {
+    /* Nom long (ou court), UTF-8 terminé par un zéro */
+    uint8_t name[FAT32_NAME_MAX + 1];
+    uint32_t size;
+    uint32_t cluster;
+    uint8_t attributes;
+    bool is_directory;
+} Fat32DirEntry;
+
+/* L'image doit rester valide et inchangée jusqu'à fat32_close */
+int32_t fat32_open(const uint8_t *data, size_t len, Fat32Handle **out);
+
+/* *count reçoit le nombre d'entrées; FAT32_ERR_BUFFER_TOO_SMALL s'il dépasse capacity */
+int32_t fat32_list(const Fat32Handle *handle, const char *path,
+                   Fat32DirEntry *entries, size_t capacity, size_t *count);
+
+/* *read reçoit le nombre d'octets lus, 0 en fin de fichier */
+int32_t fat32_read(const Fat32Handle *handle, const char *path, uint64_t offset,
+                   uint8_t *buf, size_t len, size_t *read);
+
+void fat32_close(Fat32Handle *handle);
+
+#endif /* FAT32_H */
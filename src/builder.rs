@@ -0,0 +1,326 @@
+//! Construction d'images FAT32 en mémoire par programme
+//!
+//! ```
+//! use fat32_exam::builder::{FsBuilder, MB};
+//!
+//! let image = FsBuilder::new(MB)
+//!     .file("HELLO.TXT", b"Hello\n")
+//!     .dir("DOCS", |d| d.file("INFO.TXT", b"...").dir("EMPTY", |d| d))
+//!     .build()
+//!     .unwrap();
+//! let fs = fat32_exam::Fat32::new(&image).unwrap();
+//! assert!(fs.resolve_path("/DOCS/INFO.TXT", fs.root_cluster()).is_some());
+//! ```
+//!
+//! Avec la feature `std`, `FsBuilder::from_host_dir` reprend un dossier de l'hôte.
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::fat32::{Fat32, FatDateTime, FormatOptions, FsError};
+use crate::fat32::mkfs::format;
+use crate::fat32::write::exact_short_name;
+
+/// Taille de secteur des images construites (celle de `mkfs`)
+const SECTOR_SIZE: usize = 512;
+
+/// Un kibioctet
+pub const KB: usize = 1024;
+/// Un mébioctet
+pub const MB: usize = 1024 * KB;
+
+/// Taille d'une entrée de répertoire
+const ENTRY_SIZE: usize = 32;
+/// Caractères d'un nom long par entrée LFN
+const LFN_CHARS: usize = 13;
+
+/// Entrée d'un répertoire à construire, avec son instant Unix de modification éventuel
+#[derive(Debug, Clone)]
+enum Node {
+    File(String, Vec<u8>, Option<u64>),
+    Dir(String, DirBuilder, Option<u64>),
+}
+
+/// Contenu d'un répertoire, écrit dans l'ordre des appels
+#[derive(Debug, Clone, Default)]
+pub struct DirBuilder {
+    nodes: Vec<Node>,
+}
+
+impl DirBuilder {
+    pub fn new() -> Self {
+        DirBuilder::default()
+    }
+
+    /// Ajoute un fichier (nom 8.3 ou long)
+    pub fn file(mut self, name: &str, data: &[u8]) -> Self {
+        self.nodes.push(Node::File(String::from(name), data.to_vec(), None));
+        self
+    }
+
+    /// Ajoute un sous-répertoire dont `content` décrit le contenu
+    pub fn dir(mut self, name: &str, content: impl FnOnce(DirBuilder) -> DirBuilder) -> Self {
+        self.nodes.push(Node::Dir(String::from(name), content(DirBuilder::new()), None));
+        self
+    }
+
+    /// Écrit le contenu dans le répertoire `dir` de `fs`, dates en heure locale décalée de `utc_offset`
+    ///
+    /// Un nom répété dans le même répertoire, fichier ou dossier, est une erreur.
+    fn write(&self, fs: &mut Fat32, dir: u32, utc_offset: i32) -> Result<(), FsError> {
+        for node in &self.nodes {
+            let (name, modified) = match node {
+                Node::File(name, data, modified) => {
+                    // write_file remplacerait le fichier précédent
+                    if fs.locate_entry(dir, name).is_some() {
+                        return Err(FsError::AlreadyExists);
+                    }
+                    fs.write_file(dir, name, data)?;
+                    (name, modified)
+                }
+                Node::Dir(name, content, modified) => {
+                    let entry = fs.create_dir(dir, name)?;
+                    content.write(fs, entry.cluster(), utc_offset)?;
+                    (name, modified)
+                }
+            };
+            if let Some(seconds) = modified {
+                fs.set_timestamps(dir, name, FatDateTime::from_unix_local(*seconds, utc_offset))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Nombre de clusters occupés par ce répertoire et son contenu
+    fn clusters(&self, cluster_size: usize) -> usize {
+        // Une entrée courte, plus les entrées LFN d'un nom qui n'est pas un nom 8.3
+        let slots = |name: &str| match exact_short_name(name) {
+            Some(_) => 1,
+            None => 1 + name.encode_utf16().count().div_ceil(LFN_CHARS),
+        };
+
+        // `.` et `..`, ou le label du volume à la racine
+        let mut entries = 2;
+        let mut clusters = 0;
+        for node in &self.nodes {
+            match node {
+                Node::File(name, data, _) => {
+                    entries += slots(name);
+                    clusters += data.len().div_ceil(cluster_size);
+                }
+                Node::Dir(name, content, _) => {
+                    entries += slots(name);
+                    clusters += content.clusters(cluster_size);
+                }
+            }
+        }
+        clusters + (entries * ENTRY_SIZE).div_ceil(cluster_size)
+    }
+}
+
+/// Image FAT32 à construire: taille, options de formatage et arborescence
+#[derive(Debug, Clone)]
+pub struct FsBuilder {
+    size: usize,
+    options: FormatOptions,
+    root: DirBuilder,
+    utc_offset: i32,
+}
+
+impl FsBuilder {
+    /// Image de `size` octets, formatée avec les options par défaut
+    pub fn new(size: usize) -> Self {
+        FsBuilder { size, options: FormatOptions::default(), root: DirBuilder::new(), utc_offset: 0 }
+    }
+
+    /// Change la taille de l'image
+    pub fn size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Ajuste la taille de l'image au contenu, avec une marge d'un huitième
+    pub fn fit(mut self) -> Self {
+        let cluster_size = self.options.sectors_per_cluster as usize * SECTOR_SIZE;
+        let data = self.root.clusters(cluster_size) * cluster_size;
+        // FAT (4 octets par cluster et par copie) et zone réservée incluses dans la marge
+        let size = data + data / 8 + 64 * KB;
+        self.size = size.div_ceil(cluster_size) * cluster_size;
+        self
+    }
+
+    /// Remplace les options de formatage (taille de cluster, label...)
+    pub fn options(mut self, options: FormatOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Décalage sur UTC, en secondes, de l'heure locale écrite dans les dates FAT (0 par défaut)
+    ///
+    /// FAT ne stocke pas de fuseau: une carte lue par un appareil réglé en
+    /// UTC+1 attend des dates en UTC+1 (`utc_offset(3600)`).
+    pub fn utc_offset(mut self, seconds: i32) -> Self {
+        self.utc_offset = seconds;
+        self
+    }
+
+    /// Ajoute un fichier à la racine
+    pub fn file(mut self, name: &str, data: &[u8]) -> Self {
+        self.root = self.root.file(name, data);
+        self
+    }
+
+    /// Ajoute un répertoire à la racine
+    pub fn dir(mut self, name: &str, content: impl FnOnce(DirBuilder) -> DirBuilder) -> Self {
+        self.root = self.root.dir(name, content);
+        self
+    }
+
+    /// Formate l'image et y écrit l'arborescence
+    pub fn build(&self) -> Result<Vec<u8>, FsError> {
+        let mut data = vec![0u8; self.size];
+        format(&mut data, &self.options)?;
+
+        let mut fs = Fat32::new_mut(&mut data).ok_or(FsError::InvalidGeometry)?;
+        let root = fs.root_cluster();
+        self.root.write(&mut fs, root, self.utc_offset)?;
+        Ok(data)
+    }
+}
+
+#[cfg(feature = "std")]
+impl FsBuilder {
+    /// Image contenant l'arborescence du dossier hôte `path`, dates de modification comprises
+    ///
+    /// La taille est ajustée au contenu (`fit`); les noms qui ne sont pas des
+    /// noms 8.3 reçoivent des entrées LFN. Les liens symboliques sont ignorés.
+    /// Les dates sont écrites en UTC, ou dans le fuseau donné par `utc_offset`.
+    pub fn from_host_dir(path: impl AsRef<std::path::Path>, options: FormatOptions) -> std::io::Result<Self> {
+        let root = host_dir(path.as_ref())?;
+        Ok(FsBuilder { size: 0, options, root, utc_offset: 0 }.fit())
+    }
+}
+
+/// Contenu d'un dossier hôte, trié par nom
+#[cfg(feature = "std")]
+fn host_dir(path: &std::path::Path) -> std::io::Result<DirBuilder> {
+    use std::io::{Error, ErrorKind};
+
+    let mut children = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    children.sort();
+
+    let mut dir = DirBuilder::new();
+    for child in children {
+        let name = match child.file_name().and_then(|n| n.to_str()) {
+            Some(name) => String::from(name),
+            None => return Err(Error::new(ErrorKind::InvalidData, alloc::format!("{}: not valid UTF-8", child.display()))),
+        };
+        // Les liens symboliques sont ignorés: un lien vers un ancêtre ne finirait pas
+        let metadata = std::fs::symlink_metadata(&child)?;
+        let modified = metadata.modified().ok()
+            .and_then(|t| t.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+
+        if metadata.is_dir() {
+            dir.nodes.push(Node::Dir(name, host_dir(&child)?, modified));
+        } else if metadata.is_file() {
+            dir.nodes.push(Node::File(name, std::fs::read(&child)?, modified));
+        }
+    }
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_tree() {
+        let image = FsBuilder::new(MB)
+            .options(FormatOptions::default().with_cluster_size(1024).unwrap())
+            .file("A.TXT", b"a")
+            .dir("My Documents", |d| {
+                d.file("report 2024.txt", &[7u8; 3000])
+                    .dir("SUB", |d| d.dir("EMPTY", |d| d))
+            })
+            .build()
+            .unwrap();
+
+        let fs = Fat32::new(&image).unwrap();
+        assert_eq!(fs.bytes_per_cluster(), 1024);
+        let root = fs.root_cluster();
+        let report = fs.resolve_path("/My Documents/report 2024.txt", root).unwrap();
+        assert_eq!(fs.read_file(&report), [7u8; 3000]);
+        assert!(fs.resolve_path("/MY DOCUMENTS/SUB/EMPTY", root).unwrap().is_directory());
+        assert_eq!(fs.read_file(&fs.resolve_path("/A.TXT", root).unwrap()), b"a");
+    }
+
+    #[test]
+    fn test_fit_and_timestamps() {
+        let big = alloc::vec![1u8; 300 * KB];
+        let builder = FsBuilder::new(0)
+            .file("a rather long file name.bin", &big)
+            .dir("DIR", |d| (0..40).fold(d, |d, i| d.file(&alloc::format!("F{}.TXT", i), b"x")))
+            .fit();
+        let image = builder.build().unwrap();
+        assert!(image.len() < 512 * KB);
+
+        let mut image = image;
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+        let time = FatDateTime::from_unix_seconds(1_709_209_810);
+        fs.set_timestamps(root, "DIR", time).unwrap();
+        assert_eq!(fs.resolve_path("/DIR", root).unwrap().modified(), time);
+        // Seconde impaire: gardée par les centièmes de la création seulement
+        let odd = FatDateTime::from_unix_seconds(1_709_209_811);
+        let dir = fs.set_timestamps(root, "DIR", odd).unwrap();
+        assert_eq!((dir.created(), dir.modified().second), (odd, 10));
+        assert_eq!(fs.set_timestamps(root, "MISSING", time).unwrap_err(), FsError::NotFound);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_host_dir() {
+        let host = std::env::temp_dir().join(alloc::format!("fat32-builder-{}", std::process::id()));
+        std::fs::create_dir_all(host.join("Photos 2024/raw")).unwrap();
+        std::fs::write(host.join("Photos 2024/summer holiday.jpg"), [9u8; 5000]).unwrap();
+        std::fs::write(host.join("CONFIG.TXT"), b"key=value\n").unwrap();
+        let date = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_709_209_810);
+        std::fs::File::open(host.join("CONFIG.TXT")).unwrap().set_modified(date).unwrap();
+
+        let builder = FsBuilder::from_host_dir(&host, FormatOptions::default()).unwrap();
+        let image = builder.build().unwrap();
+        let local = builder.utc_offset(3600).build().unwrap();
+        std::fs::remove_dir_all(&host).unwrap();
+
+        let fs = Fat32::new(&image).unwrap();
+        let root = fs.root_cluster();
+        let photo = fs.resolve_path("/Photos 2024/summer holiday.jpg", root).unwrap();
+        assert_eq!(fs.read_file(&photo), [9u8; 5000]);
+        assert!(fs.resolve_path("/Photos 2024/raw", root).unwrap().is_directory());
+        let config = fs.resolve_path("/CONFIG.TXT", root).unwrap();
+        assert_eq!(alloc::format!("{}", config.modified()), "2024-02-29 12:30");
+        assert_eq!(config.created().second, 10);
+
+        // Heure locale UTC+1
+        let fs = Fat32::new(&local).unwrap();
+        let config = fs.resolve_path("/CONFIG.TXT", root).unwrap();
+        assert_eq!(alloc::format!("{}", config.modified()), "2024-02-29 13:30");
+        assert_eq!(config.modified().to_unix_seconds(3600), Some(1_709_209_810));
+    }
+
+    #[test]
+    fn test_build_errors() {
+        assert_eq!(FsBuilder::new(4 * KB).build().unwrap_err(), FsError::InvalidGeometry);
+        let duplicate = FsBuilder::new(MB).file("A.TXT", b"1").dir("a.txt", |d| d);
+        assert_eq!(duplicate.build().unwrap_err(), FsError::AlreadyExists);
+        let duplicate = FsBuilder::new(MB).dir("DOCS", |d| d.file("A.TXT", b"1").file("a.txt", b"2"));
+        assert_eq!(duplicate.build().unwrap_err(), FsError::AlreadyExists);
+        let duplicate = FsBuilder::new(MB).dir("DOCS", |d| d).file("docs", b"1");
+        assert_eq!(duplicate.build().unwrap_err(), FsError::AlreadyExists);
+    }
+}
@@ -0,0 +1,194 @@
+//! Anomalies des structures sur disque et politique de lecture (strict ou tolérant)
+//!
+//! Une image peut être lisible malgré des champs incohérents. Un usage
+//! forensique veut échouer dès la première anomalie (`strict`), une
+//! récupération veut tout lire en corrigeant ce qui peut l'être (`repair`),
+//! le comportement par défaut accepte les anomalies sans rien signaler.
+
+use core::fmt;
+
+use super::directory::{ATTR_DIRECTORY, ATTR_LONG_NAME, ATTR_VOLUME_ID};
+
+/// Bits d'attribut réservés (non définis par la spécification)
+pub(crate) const ATTR_RESERVED: u8 = 0xC0;
+/// Bits définis de l'octet 12 d'une entrée (minuscules du nom et de l'extension)
+const CASE_FLAGS: u8 = 0x18;
+
+/// Champs du boot sector qui doivent être nuls en FAT32 (position, taille)
+const BOOT_ZERO_FIELDS: &[(usize, usize)] = &[
+    (17, 2), // nombre d'entrées de la racine FAT12/16
+    (19, 2), // nombre de secteurs sur 16 bits
+    (22, 2), // secteurs par FAT sur 16 bits
+    (52, 12), // zone réservée du BPB FAT32
+];
+
+/// Politique appliquée aux anomalies rencontrées à la lecture
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Rejette la structure à la première anomalie
+    pub strict: bool,
+    /// Hors mode strict, corrige les anomalies au lieu de les garder telles quelles
+    pub repair: bool,
+    /// Parcourt tous les slots de la chaîne d'un répertoire: une entrée libre
+    /// (0x00) est sautée au lieu de terminer le répertoire
+    pub full_scan: bool,
+}
+
+impl ParseOptions {
+    /// Échec à la première anomalie
+    pub fn strict() -> Self {
+        ParseOptions { strict: true, ..Self::default() }
+    }
+
+    /// Lecture maximale, anomalies corrigées
+    pub fn repair() -> Self {
+        ParseOptions { repair: true, ..Self::default() }
+    }
+
+    /// Mêmes options, avec le parcours complet des répertoires
+    ///
+    /// Utile sur un répertoire récupéré, où des entrées valides suivent des
+    /// slots remis à zéro.
+    pub fn with_full_scan(self) -> Self {
+        ParseOptions { full_scan: true, ..self }
+    }
+}
+
+/// Anomalie détectée, avec la position en octets dans la structure lue
+/// (secteur de boot ou données du répertoire) ou le premier cluster du fichier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub enum Anomaly {
+    /// La somme de contrôle des entrées LFN ne correspond pas au nom court qui les suit
+    LfnChecksum(usize),
+    /// Combinaison d'attributs impossible ou bits réservés positionnés
+    InvalidAttributes(usize),
+    /// Champ réservé ou inutilisé en FAT32 non nul
+    ReservedField(usize),
+    /// La chaîne du fichier couvre moins d'octets que la taille de son entrée
+    ShortChain { cluster: u32, size: u32, available: u64 },
+    /// La chaîne continue après les clusters qui contiennent le fichier
+    LongChain { cluster: u32, size: u32 },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::LfnChecksum(at) => write!(f, "LFN checksum mismatch at offset {}", at),
+            Anomaly::InvalidAttributes(at) => write!(f, "invalid attributes at offset {}", at),
+            Anomaly::ReservedField(at) => write!(f, "nonzero reserved field at offset {}", at),
+            Anomaly::ShortChain { cluster, size, available } => {
+                write!(f, "chain from cluster {} holds {} of {} bytes", cluster, available, size)
+            }
+            Anomaly::LongChain { cluster, size } => {
+                write!(f, "chain from cluster {} continues past the {} bytes of the file", cluster, size)
+            }
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Applique la politique à une anomalie: erreur en mode strict, sinon `Ok(repair)`
+    pub(crate) fn handle(&self, anomaly: Anomaly) -> Result<bool, Anomaly> {
+        if self.strict {
+            return Err(anomaly);
+        }
+        if self.repair {
+            log_warn!("{}, repaired", anomaly);
+        } else {
+            log_debug!("{}, ignored", anomaly);
+        }
+        Ok(self.repair)
+    }
+}
+
+/// Vérifie les champs du boot sector qui doivent être nuls en FAT32
+pub fn check_boot_sector(sector: &[u8; 512]) -> Result<(), Anomaly> {
+    for &(at, len) in BOOT_ZERO_FIELDS {
+        if sector[at..at + len].iter().any(|&b| b != 0) {
+            return Err(Anomaly::ReservedField(at));
+        }
+    }
+    Ok(())
+}
+
+/// Anomalie d'une entrée courte de 32 octets située à `offset`, sans tenir compte des noms longs
+pub(crate) fn check_short_entry(raw: &[u8], offset: usize) -> Option<Anomaly> {
+    let attr = raw[11];
+    if attr & ATTR_RESERVED != 0 || (attr & ATTR_DIRECTORY != 0 && attr & ATTR_VOLUME_ID != 0 && attr != ATTR_LONG_NAME) {
+        return Some(Anomaly::InvalidAttributes(offset + 11));
+    }
+    if raw[12] & !CASE_FLAGS != 0 {
+        return Some(Anomaly::ReservedField(offset + 12));
+    }
+    // Un répertoire a une taille nulle
+    if attr & ATTR_DIRECTORY != 0 && raw[28..32] != [0; 4] {
+        return Some(Anomaly::ReservedField(offset + 28));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boot_sector_fields() {
+        let mut sector = [0u8; 512];
+        assert_eq!(check_boot_sector(&sector), Ok(()));
+        sector[60] = 1;
+        assert_eq!(check_boot_sector(&sector), Err(Anomaly::ReservedField(52)));
+        sector[20] = 1;
+        assert_eq!(check_boot_sector(&sector), Err(Anomaly::ReservedField(19)));
+    }
+
+    #[test]
+    fn test_short_entry_checks() {
+        let mut raw = [0u8; 32];
+        raw[0..11].copy_from_slice(b"DIR        ");
+        raw[11] = ATTR_DIRECTORY;
+        assert_eq!(check_short_entry(&raw, 0), None);
+
+        raw[28] = 1;
+        assert_eq!(check_short_entry(&raw, 64), Some(Anomaly::ReservedField(92)));
+        raw[11] = ATTR_DIRECTORY | ATTR_VOLUME_ID;
+        assert_eq!(check_short_entry(&raw, 0), Some(Anomaly::InvalidAttributes(11)));
+        raw[11] = 0x80;
+        assert_eq!(check_short_entry(&raw, 0), Some(Anomaly::InvalidAttributes(11)));
+
+        assert_eq!(ParseOptions::strict().handle(Anomaly::ReservedField(12)), Err(Anomaly::ReservedField(12)));
+        assert_eq!(ParseOptions::repair().handle(Anomaly::ReservedField(12)), Ok(true));
+        assert_eq!(ParseOptions::default().handle(Anomaly::ReservedField(12)), Ok(false));
+    }
+
+    #[test]
+    fn test_lfn_checksum_policies() {
+        use crate::fat32::{Fat32, FsError};
+        use crate::testimg::{corrupt, TestImage, LONG_NAME_PATHS};
+
+        let mut image = TestImage::default().long_names().build().unwrap();
+        corrupt::break_lfn_checksum(&mut image, LONG_NAME_PATHS[1]).unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let docs = fs.resolve_path("/My Documents", fs.root_cluster()).unwrap().cluster();
+        let long_names = |fs: &Fat32| -> usize {
+            fs.read_directory_with_lfn(docs).iter().filter(|(_, long)| long.is_some()).count()
+        };
+
+        // Par défaut le nom long est gardé tel quel
+        let accepted = long_names(&fs);
+        assert!(fs.find_entry(docs, "notes about fat32.md").is_some());
+
+        let fs = fs.with_options(ParseOptions::repair()).unwrap();
+        assert_eq!(long_names(&fs), accepted - 1);
+        assert!(fs.find_entry(docs, "notes about fat32.md").is_none());
+
+        let fs = fs.with_options(ParseOptions::strict()).unwrap();
+        assert!(matches!(fs.try_read_directory(docs), Err(FsError::Anomaly(Anomaly::LfnChecksum(_)))));
+        assert!(fs.read_directory_with_lfn(docs).is_empty());
+        assert!(fs.try_read_directory(fs.root_cluster()).is_ok());
+
+        image[20] = 1;
+        let fs = Fat32::new(&image).unwrap();
+        assert_eq!(fs.with_options(ParseOptions::strict()).err(), Some(FsError::Anomaly(Anomaly::ReservedField(19))));
+    }
+}
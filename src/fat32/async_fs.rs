@@ -0,0 +1,342 @@
+//! Variante asynchrone de la lecture (feature async)
+//!
+//! Pour les firmwares où la carte SD est pilotée par interruption ou DMA
+//! (embassy, par exemple): chaque accès au support est un `await` et
+//! n'immobilise pas l'exécuteur. `AsyncFat32` lit le support directement,
+//! sans charger l'image en mémoire; un seul secteur de FAT est gardé.
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::future::Future;
+
+use super::device::{BlockDevice, SECTOR_SIZE};
+use super::{cluster_runs, parse_directory_checked, BootSector, DirEntry, Fat32, FatEntry, FsError, Limits, ParseOptions};
+
+/// Support adressé par secteurs dont les lectures sont asynchrones
+pub trait AsyncBlockDevice {
+    /// Taille d'un secteur en octets
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    /// Nombre de secteurs du support
+    fn sector_count(&self) -> u64;
+
+    /// Lit les secteurs consécutifs à partir de `start` pour remplir `buf`
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> impl Future<Output = Result<(), FsError>>;
+}
+
+/// Périphérique synchrone utilisé à travers l'interface asynchrone
+///
+/// Chaque lecture se termine dès le premier `poll`; utile pour les tests et
+/// les supports en mémoire.
+#[derive(Debug)]
+pub struct Blocking<D>(pub D);
+
+impl<D: BlockDevice> AsyncBlockDevice for Blocking<D> {
+    fn sector_size(&self) -> usize {
+        self.0.sector_size()
+    }
+
+    fn sector_count(&self) -> u64 {
+        self.0.sector_count()
+    }
+
+    async fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        self.0.read_sectors(start, buf)
+    }
+}
+
+/// Fichier ouvert par `AsyncFat32::open`, lu avec `AsyncFat32::read`
+#[derive(Debug, Clone)]
+pub struct AsyncFile {
+    entry: DirEntry,
+    /// Plages (premier cluster, nombre de clusters) de la chaîne
+    extents: Vec<(u32, u32)>,
+    len: u64,
+    pos: u64,
+}
+
+impl AsyncFile {
+    pub fn entry(&self) -> &DirEntry {
+        &self.entry
+    }
+
+    /// Nombre d'octets lisibles
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Place la position à `pos`, bornée par la fin du fichier
+    pub fn seek(&mut self, pos: u64) -> u64 {
+        self.pos = pos.min(self.len);
+        self.pos
+    }
+}
+
+/// Système de fichiers FAT32 en lecture seule sur un support asynchrone
+pub struct AsyncFat32<D> {
+    device: D,
+    boot_sector: BootSector,
+    limits: Limits,
+    /// Dernier secteur de FAT lu (numéro, contenu)
+    fat_sector: Option<(u64, Vec<u8>)>,
+}
+
+impl<D: AsyncBlockDevice> AsyncFat32<D> {
+    /// Lit le boot sector et vérifie que la géométrie correspond au support
+    pub async fn mount(mut device: D) -> Result<Self, FsError> {
+        let mut sector = vec![0u8; device.sector_size().max(512)];
+        device.read_sectors(0, &mut sector[..device.sector_size()]).await?;
+        let boot_bytes: &[u8; 512] = sector[..512].try_into().map_err(|_| FsError::OutOfBounds)?;
+        let boot_sector = BootSector::from_bytes(boot_bytes).ok_or(FsError::InvalidGeometry)?;
+        boot_sector.check_geometry()?;
+        boot_sector.check_image_size(device.sector_count() * device.sector_size() as u64)?;
+        if boot_sector.bytes_per_sector as usize != device.sector_size() {
+            return Err(FsError::InvalidGeometry);
+        }
+        Ok(AsyncFat32 { device, boot_sector, limits: Limits::default(), fat_sector: None })
+    }
+
+    /// Remplace les limites de ressources appliquées aux lectures
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn boot_sector(&self) -> &BootSector {
+        &self.boot_sector
+    }
+
+    pub fn root_cluster(&self) -> u32 {
+        self.boot_sector.root_cluster
+    }
+
+    /// Rend le support
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Entrée de la FAT pour `cluster`, en relisant au plus un secteur
+    async fn fat_entry(&mut self, cluster: u32) -> Result<FatEntry, FsError> {
+        let sector_size = self.boot_sector.bytes_per_sector as u64;
+        let offset = cluster as u64 * 4;
+        if offset / sector_size >= self.boot_sector.sectors_per_fat as u64 {
+            return Ok(FatEntry::EndOfChain);
+        }
+        let sector = self.boot_sector.fat_start_sector() as u64 + offset / sector_size;
+
+        let cached = matches!(&self.fat_sector, Some((number, _)) if *number == sector);
+        if !cached {
+            let mut data = vec![0u8; sector_size as usize];
+            self.device.read_sectors(sector, &mut data).await?;
+            self.fat_sector = Some((sector, data));
+        }
+        let data = match &self.fat_sector {
+            Some((_, data)) => data,
+            None => return Err(FsError::OutOfBounds),
+        };
+        let at = (offset % sector_size) as usize;
+        let value = u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+        Ok(FatEntry::from_raw_checked(value, self.boot_sector.cluster_count()))
+    }
+
+    /// Chaîne de `start`, limitée à `max_length` clusters (`None`: `max_chain_length`)
+    async fn chain(&mut self, start: u32, max_length: Option<usize>) -> Result<Vec<u32>, FsError> {
+        let mut chain = Vec::new();
+        let mut current = start;
+        while current >= 2 {
+            if chain.len() >= max_length.unwrap_or(self.limits.max_chain_length) {
+                if max_length.is_some() {
+                    break;
+                }
+                return Err(FsError::LimitExceeded);
+            }
+            // Une chaîne qui reboucle est coupée au premier cluster déjà visité
+            if chain.contains(&current) {
+                break;
+            }
+            chain.push(current);
+            current = match self.fat_entry(current).await? {
+                FatEntry::Data(next) => next,
+                FatEntry::Invalid(next) => return Err(FsError::CorruptFat { cluster: current, next }),
+                _ => break,
+            };
+        }
+        Ok(chain)
+    }
+
+    /// Contenu des clusters de `chain`, une lecture par plage contiguë
+    async fn read_chain(&mut self, chain: &[u32]) -> Result<Vec<u8>, FsError> {
+        let cluster_size = self.boot_sector.bytes_per_cluster() as usize;
+        let mut data = vec![0u8; chain.len() * cluster_size];
+        let mut pos = 0;
+        for (start, len) in cluster_runs(chain) {
+            let end = pos + len as usize * cluster_size;
+            let sector = self.boot_sector.cluster_to_sector(start);
+            self.device.read_sectors(sector, &mut data[pos..end]).await?;
+            pos = end;
+        }
+        Ok(data)
+    }
+
+    /// Entrées du répertoire `cluster`, avec leurs noms longs
+    pub async fn read_directory(&mut self, cluster: u32) -> Result<Vec<(DirEntry, Option<String>)>, FsError> {
+        let chain = self.chain(cluster, None).await?;
+        let data = self.read_chain(&chain).await?;
+        let entries = parse_directory_checked(&data, &ParseOptions::default())?;
+        if entries.len() > self.limits.max_dir_entries {
+            return Err(FsError::LimitExceeded);
+        }
+        Ok(entries)
+    }
+
+    /// Cherche une entrée par nom (insensible à la casse)
+    pub async fn find_entry(&mut self, dir_cluster: u32, name: &str) -> Result<Option<DirEntry>, FsError> {
+        let folded = super::casefold::fold(name);
+        let entries = self.read_directory(dir_cluster).await?;
+        Ok(entries
+            .into_iter()
+            .find(|(entry, long_name)| Fat32::name_matches(entry, long_name.as_deref(), &folded))
+            .map(|(entry, _)| entry))
+    }
+
+    /// Résout un chemin absolu
+    pub async fn resolve_path(&mut self, path: &str) -> Result<DirEntry, FsError> {
+        let components = || path.split('/').filter(|s| !s.is_empty());
+        if components().count() > self.limits.max_path_depth {
+            return Err(FsError::LimitExceeded);
+        }
+
+        let mut cluster = self.root_cluster();
+        let mut components = components().peekable();
+        while let Some(component) = components.next() {
+            let entry = self.find_entry(cluster, component).await?.ok_or(FsError::NotFound)?;
+            if components.peek().is_none() {
+                return Ok(entry);
+            }
+            if !entry.is_directory() {
+                return Err(FsError::NotFound);
+            }
+            cluster = entry.cluster();
+        }
+        Err(FsError::NotFound)
+    }
+
+    /// Ouvre le fichier `path`; sa chaîne est lue une fois
+    pub async fn open(&mut self, path: &str) -> Result<AsyncFile, FsError> {
+        let entry = self.resolve_path(path).await?;
+        if entry.is_directory() {
+            return Err(FsError::IsADirectory);
+        }
+        if entry.size as u64 > self.limits.max_file_size {
+            return Err(FsError::LimitExceeded);
+        }
+
+        let cluster_size = self.boot_sector.bytes_per_cluster() as usize;
+        let needed = (entry.size as usize).div_ceil(cluster_size);
+        if needed > self.limits.max_chain_length {
+            return Err(FsError::LimitExceeded);
+        }
+        let chain = self.chain(entry.cluster(), Some(needed)).await?;
+        let len = (chain.len() * cluster_size).min(entry.size as usize) as u64;
+        Ok(AsyncFile { entry, extents: cluster_runs(&chain), len, pos: 0 })
+    }
+
+    /// Lit à partir de la position de `file` et l'avance; 0 en fin de fichier
+    pub async fn read(&mut self, file: &mut AsyncFile, buf: &mut [u8]) -> Result<usize, FsError> {
+        let cluster_size = self.boot_sector.bytes_per_cluster() as u64;
+        let mut cluster_buf = vec![0u8; cluster_size as usize];
+        let mut done = 0;
+
+        while done < buf.len() && file.pos < file.len {
+            let mut index = file.pos / cluster_size;
+            let cluster = file.extents.iter().find_map(|&(start, count)| {
+                if index < count as u64 {
+                    Some(start + index as u32)
+                } else {
+                    index -= count as u64;
+                    None
+                }
+            });
+            let cluster = match cluster {
+                Some(cluster) => cluster,
+                None => break,
+            };
+            let sector = self.boot_sector.cluster_to_sector(cluster);
+            self.device.read_sectors(sector, &mut cluster_buf).await?;
+
+            let start = (file.pos % cluster_size) as usize;
+            let take = (cluster_buf.len() - start).min(buf.len() - done).min((file.len - file.pos) as usize);
+            buf[done..done + take].copy_from_slice(&cluster_buf[start..start + take]);
+            done += take;
+            file.pos += take as u64;
+        }
+
+        Ok(done)
+    }
+
+    /// Lit un fichier entier
+    pub async fn read_file(&mut self, path: &str) -> Result<Vec<u8>, FsError> {
+        let mut file = self.open(path).await?;
+        let mut data = vec![0u8; file.len() as usize];
+        let read = self.read(&mut file, &mut data).await?;
+        data.truncate(read);
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH, LONG_NAME_PATHS, README_PATH, README_TEXT};
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    /// Exécute `future` jusqu'au bout (les lectures de `Blocking` sont immédiates)
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_async_matches_sync() {
+        let test = TestImage::default().long_names().fragmented();
+        let image = test.build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+
+        block_on(async {
+            let mut afs = AsyncFat32::mount(Blocking(image.clone())).await.unwrap();
+            let root = afs.root_cluster();
+
+            let names: Vec<_> = afs.read_directory(root).await.unwrap().into_iter().map(|(_, n)| n).collect();
+            let expected: Vec<_> = fs.read_directory_with_lfn(root).into_iter().map(|(_, n)| n).collect();
+            assert_eq!(names, expected);
+
+            assert_eq!(afs.read_file(README_PATH).await.unwrap(), README_TEXT);
+            assert_eq!(afs.read_file(FRAGMENTED_PATH).await.unwrap(), test.fragmented_data());
+            assert!(afs.resolve_path(LONG_NAME_PATHS[1]).await.is_ok());
+            assert_eq!(afs.open("/My Documents").await.unwrap_err(), FsError::IsADirectory);
+            assert_eq!(afs.open("/missing").await.unwrap_err(), FsError::NotFound);
+
+            let mut file = afs.open(FRAGMENTED_PATH).await.unwrap();
+            file.seek(file.len() - 5);
+            let mut buf = [0u8; 16];
+            assert_eq!(afs.read(&mut file, &mut buf).await.unwrap(), 5);
+            assert_eq!(afs.read(&mut file, &mut buf).await.unwrap(), 0);
+        });
+
+        assert_eq!(block_on(AsyncFat32::mount(Blocking(alloc::vec![0u8; 4096]))).err(), Some(FsError::InvalidGeometry));
+    }
+}
@@ -1,35 +1,112 @@
 //! Boot Sector FAT32 - Parse les 512 premiers octets du filesystem
 
+use super::FsError;
+
 /// Structure du boot sector contenant les paramètres FAT32
 #[derive(Debug, Clone)]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
 pub struct BootSector {
     pub bytes_per_sector: u16,
     pub sectors_per_cluster: u8,
     pub reserved_sectors: u16,
     pub fat_count: u8,
+    /// Secteurs qui précèdent le volume sur le disque (début de la partition)
+    pub hidden_sectors: u32,
     pub sectors_per_fat: u32,
     pub root_cluster: u32,
+    /// Champ 32 bits (offset 32), ou champ 16 bits (offset 19) s'il est nul
     pub total_sectors: u32,
+    pub fs_info_sector: u16,
+    pub backup_boot_sector: u16,
+    pub volume_serial: u32,
+    pub volume_label: [u8; 11],
 }
 
 impl BootSector {
     /// Parse le boot sector depuis 512 octets bruts
+    ///
+    /// Le nombre de secteurs est lu dans le champ 32 bits, ou dans le champ
+    /// 16 bits que certains outils remplissent seul sur les petits volumes;
+    /// `None` si les deux sont nuls.
     pub fn from_bytes(data: &[u8; 512]) -> Option<Self> {
         if data[510] != 0x55 || data[511] != 0xAA {
             return None;
         }
 
+        let total_sectors = match u32::from_le_bytes([data[32], data[33], data[34], data[35]]) {
+            0 => u16::from_le_bytes([data[19], data[20]]) as u32,
+            total => total,
+        };
+        if total_sectors == 0 {
+            return None;
+        }
+
+        let mut volume_label = [0u8; 11];
+        volume_label.copy_from_slice(&data[71..82]);
+
         Some(BootSector {
             bytes_per_sector: u16::from_le_bytes([data[11], data[12]]),
             sectors_per_cluster: data[13],
             reserved_sectors: u16::from_le_bytes([data[14], data[15]]),
             fat_count: data[16],
+            hidden_sectors: u32::from_le_bytes([data[28], data[29], data[30], data[31]]),
             sectors_per_fat: u32::from_le_bytes([data[36], data[37], data[38], data[39]]),
             root_cluster: u32::from_le_bytes([data[44], data[45], data[46], data[47]]),
-            total_sectors: u32::from_le_bytes([data[32], data[33], data[34], data[35]]),
+            total_sectors,
+            fs_info_sector: u16::from_le_bytes([data[48], data[49]]),
+            backup_boot_sector: u16::from_le_bytes([data[50], data[51]]),
+            volume_serial: u32::from_le_bytes([data[67], data[68], data[69], data[70]]),
+            volume_label,
         })
     }
 
+    /// Vérifie que la géométrie est utilisable sans débordement
+    ///
+    /// Secteurs de 512 à 4096 octets, clusters en puissance de deux, au moins
+    /// une FAT non vide, cluster racine valide et région de données dans le volume.
+    pub fn is_valid_geometry(&self) -> bool {
+        self.check_geometry().is_ok()
+    }
+
+    /// Comme `is_valid_geometry`, avec la raison du refus
+    ///
+    /// Un nombre de secteurs par cluster hors de 1, 2, 4... 128 donne
+    /// `InvalidClusterSize`, le reste `InvalidGeometry`.
+    pub fn check_geometry(&self) -> Result<(), FsError> {
+        if !self.sectors_per_cluster.is_power_of_two() {
+            return Err(FsError::InvalidClusterSize(self.sectors_per_cluster));
+        }
+        let fats = (self.fat_count as u64) * (self.sectors_per_fat as u64);
+        let data_start = self.reserved_sectors as u64 + fats;
+        let valid = matches!(self.bytes_per_sector, 512 | 1024 | 2048 | 4096)
+            && self.reserved_sectors > 0
+            && self.fat_count > 0
+            && self.sectors_per_fat > 0
+            && self.root_cluster >= 2
+            && data_start < self.total_sectors as u64;
+        if !valid {
+            return Err(FsError::InvalidGeometry);
+        }
+        Ok(())
+    }
+
+    /// Octets couverts par les régions réservée, FAT et données
+    ///
+    /// Les secteurs de fin qui ne forment pas un cluster complet ne comptent pas.
+    pub fn volume_bytes(&self) -> u64 {
+        let sectors = self.data_start_sector() as u64 + self.cluster_count() as u64 * self.sectors_per_cluster as u64;
+        sectors * self.bytes_per_sector as u64
+    }
+
+    /// Vérifie que le volume décrit tient dans une image de `len` octets
+    pub fn check_image_size(&self, len: u64) -> Result<(), FsError> {
+        let expected = self.volume_bytes();
+        if expected > len {
+            return Err(FsError::TruncatedImage { expected, actual: len });
+        }
+        Ok(())
+    }
+
     /// Retourne le secteur de début de la table FAT
     #[inline]
     pub fn fat_start_sector(&self) -> u32 {
@@ -39,13 +116,51 @@ impl BootSector {
     /// Retourne le secteur de début de la région de données
     #[inline]
     pub fn data_start_sector(&self) -> u32 {
-        self.reserved_sectors as u32 + (self.fat_count as u32 * self.sectors_per_fat)
+        (self.reserved_sectors as u32).saturating_add((self.fat_count as u32).saturating_mul(self.sectors_per_fat))
+    }
+
+    /// Nombre de clusters de la région de données (clusters 2 à `cluster_count + 1`)
+    ///
+    /// 0 si `sectors_per_cluster` est nul (boot sector non validé).
+    #[inline]
+    pub fn cluster_count(&self) -> u32 {
+        self.total_sectors.saturating_sub(self.data_start_sector()).checked_div(self.sectors_per_cluster as u32).unwrap_or(0)
     }
 
     /// Convertit un numéro de cluster en numéro de secteur
+    ///
+    /// Calculé en u64: un cluster proche de la limite de 28 bits multiplié
+    /// par 128 secteurs dépasse u32.
+    #[inline]
+    pub fn cluster_to_sector(&self, cluster: u32) -> u64 {
+        self.data_start_sector() as u64 + cluster.saturating_sub(2) as u64 * self.sectors_per_cluster as u64
+    }
+
+    /// Numéro de secteur sur le disque entier (LBA) d'un secteur du volume
+    ///
+    /// C'est la position affichée par un éditeur hexadécimal sur l'image du
+    /// disque complet, table de partitions comprise.
     #[inline]
-    pub fn cluster_to_sector(&self, cluster: u32) -> u32 {
-        self.data_start_sector() + (cluster - 2) * self.sectors_per_cluster as u32
+    pub fn absolute_sector(&self, sector: u64) -> u64 {
+        self.hidden_sectors as u64 + sector
+    }
+
+    /// Position en octets d'un secteur dans le volume
+    #[inline]
+    pub fn sector_offset(&self, sector: u64) -> u64 {
+        sector * self.bytes_per_sector as u64
+    }
+
+    /// Position en octets de la copie `copy` de la FAT (0 pour la première)
+    #[inline]
+    pub fn fat_offset(&self, copy: u8) -> u64 {
+        self.sector_offset(self.fat_start_sector() as u64 + copy as u64 * self.sectors_per_fat as u64)
+    }
+
+    /// Taille en octets d'une copie de la FAT
+    #[inline]
+    pub fn fat_bytes(&self) -> u64 {
+        self.sector_offset(self.sectors_per_fat as u64)
     }
 
     /// Retourne le nombre d'octets par cluster
@@ -53,6 +168,14 @@ impl BootSector {
     pub fn bytes_per_cluster(&self) -> u32 {
         self.bytes_per_sector as u32 * self.sectors_per_cluster as u32
     }
+
+    /// Retourne le label du volume sans les espaces de remplissage
+    pub fn label(&self) -> &str {
+        let len = self.volume_label.iter()
+            .rposition(|&b| b != b' ' && b != 0x00)
+            .map_or(0, |i| i + 1);
+        core::str::from_utf8(&self.volume_label[..len]).unwrap_or("")
+    }
 }
 
 #[cfg(test)]
@@ -78,6 +201,7 @@ mod tests {
         data[14] = 32;
         data[15] = 0;
         data[16] = 2;
+        data[32..36].copy_from_slice(&4096u32.to_le_bytes());
         data[44] = 2;
 
         let bs = BootSector::from_bytes(&data).unwrap();
@@ -87,4 +211,119 @@ mod tests {
         assert_eq!(bs.fat_count, 2);
         assert_eq!(bs.root_cluster, 2);
     }
+
+    #[test]
+    fn test_serial_and_label() {
+        let mut data = [0u8; 512];
+        data[510] = 0x55;
+        data[511] = 0xAA;
+        data[19] = 0x00;
+        data[20] = 0x10;
+        data[28..32].copy_from_slice(&2048u32.to_le_bytes());
+        data[48] = 1;
+        data[50] = 6;
+        data[67..71].copy_from_slice(&0x1234ABCDu32.to_le_bytes());
+        data[71..82].copy_from_slice(b"MYDISK     ");
+
+        let bs = BootSector::from_bytes(&data).unwrap();
+        assert_eq!(bs.hidden_sectors, 2048);
+        assert_eq!(bs.absolute_sector(100), 2148);
+        assert_eq!(bs.fs_info_sector, 1);
+        assert_eq!(bs.backup_boot_sector, 6);
+        assert_eq!(bs.volume_serial, 0x1234ABCD);
+        assert_eq!(bs.label(), "MYDISK");
+    }
+
+    #[test]
+    fn test_total_sectors_fields() {
+        let mut data = [0u8; 512];
+        data[510] = 0x55;
+        data[511] = 0xAA;
+
+        // Aucun des deux champs: pas de volume de 0 secteur
+        assert!(BootSector::from_bytes(&data).is_none());
+
+        data[19..21].copy_from_slice(&2880u16.to_le_bytes());
+        assert_eq!(BootSector::from_bytes(&data).unwrap().total_sectors, 2880);
+
+        // Le champ 32 bits l'emporte quand il est rempli
+        data[32..36].copy_from_slice(&100_000u32.to_le_bytes());
+        assert_eq!(BootSector::from_bytes(&data).unwrap().total_sectors, 100_000);
+    }
+
+    #[test]
+    fn test_sectors_per_cluster_values() {
+        let mut data = [0u8; 512];
+        data[510] = 0x55;
+        data[511] = 0xAA;
+        data[11..13].copy_from_slice(&512u16.to_le_bytes());
+        data[14] = 32;
+        data[16] = 2;
+        data[32..36].copy_from_slice(&65536u32.to_le_bytes());
+        data[36..40].copy_from_slice(&64u32.to_le_bytes());
+        data[44] = 2;
+
+        for legal in [1u8, 2, 4, 8, 16, 32, 64, 128] {
+            data[13] = legal;
+            assert_eq!(BootSector::from_bytes(&data).unwrap().check_geometry(), Ok(()));
+        }
+        for illegal in [0u8, 3, 6, 100, 255] {
+            data[13] = illegal;
+            let bs = BootSector::from_bytes(&data).unwrap();
+            assert_eq!(bs.check_geometry(), Err(FsError::InvalidClusterSize(illegal)));
+            assert!(!bs.is_valid_geometry());
+        }
+        data[13] = 0;
+        assert_eq!(BootSector::from_bytes(&data).unwrap().cluster_count(), 0);
+    }
+
+    #[test]
+    fn test_check_image_size() {
+        let mut data = [0u8; 512];
+        data[510] = 0x55;
+        data[511] = 0xAA;
+        data[11..13].copy_from_slice(&512u16.to_le_bytes());
+        data[13] = 8;
+        data[14] = 32;
+        data[16] = 2;
+        // 32 + 2 * 64 = 160 secteurs avant les données, 1003 clusters + 3 secteurs de reste
+        data[32..36].copy_from_slice(&(160 + 1003 * 8 + 3u32).to_le_bytes());
+        data[36..40].copy_from_slice(&64u32.to_le_bytes());
+        data[44] = 2;
+
+        let bs = BootSector::from_bytes(&data).unwrap();
+        let used = (160 + 1003 * 8) * 512;
+        assert_eq!(bs.volume_bytes(), used);
+        assert_eq!(bs.check_image_size(used), Ok(()));
+        assert_eq!(bs.check_image_size(used - 1), Err(FsError::TruncatedImage { expected: used, actual: used - 1 }));
+    }
+
+    #[test]
+    fn test_large_volume_offsets() {
+        // Carte SDXC de 128 Go: 64 secteurs par cluster, ~4 millions de clusters
+        let mut data = [0u8; 512];
+        data[510] = 0x55;
+        data[511] = 0xAA;
+        data[11..13].copy_from_slice(&512u16.to_le_bytes());
+        data[13] = 64;
+        data[14] = 32;
+        data[16] = 2;
+        data[28..32].copy_from_slice(&8192u32.to_le_bytes());
+        data[32..36].copy_from_slice(&250_068_992u32.to_le_bytes());
+        data[36..40].copy_from_slice(&30_528u32.to_le_bytes());
+        data[44] = 2;
+
+        let bs = BootSector::from_bytes(&data).unwrap();
+        assert_eq!(bs.check_geometry(), Ok(()));
+        let data_start = 32 + 2 * 30_528u64;
+        let last = bs.cluster_count() + 1;
+        assert_eq!(bs.cluster_to_sector(last), data_start + (last as u64 - 2) * 64);
+        assert!(bs.sector_offset(bs.cluster_to_sector(last)) > u32::MAX as u64);
+        assert_eq!(bs.volume_bytes(), (data_start + bs.cluster_count() as u64 * 64) * 512);
+        assert_eq!(bs.fat_offset(1), (32 + 30_528) * 512);
+        assert_eq!(bs.absolute_sector(bs.cluster_to_sector(last)), 8192 + bs.cluster_to_sector(last));
+
+        // Numéro de cluster au bord des 28 bits: pas de dépassement en u64
+        assert_eq!(bs.cluster_to_sector(0x0FFFFFF5), data_start + (0x0FFFFFF5u64 - 2) * 64);
+    }
 }
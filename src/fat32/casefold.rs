@@ -0,0 +1,93 @@
+//! Repliement de casse pour la comparaison des noms
+//!
+//! Windows compare les noms sans tenir compte de la casse, y compris hors de
+//! l'ASCII: `Übersicht.txt` se retrouve avec `übersicht.txt`. Par défaut, le
+//! repliement couvre l'ASCII et le supplément Latin-1 sans aucune table. La
+//! feature `unicode-case` étend le repliement simple à tout Unicode, au prix
+//! des tables de casse de `core` dans le binaire.
+
+extern crate alloc;
+use alloc::string::String;
+
+/// Caractère replié (minuscule) pour une comparaison insensible à la casse
+///
+/// Un caractère dont la minuscule s'écrit en plusieurs caractères (`İ`)
+/// reste tel quel, comme dans le repliement simple d'Unicode.
+pub fn fold_char(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+    fold_non_ascii(c)
+}
+
+/// Latin-1: À à Þ deviennent à à þ, sauf le signe ×
+#[cfg(not(feature = "unicode-case"))]
+fn fold_non_ascii(c: char) -> char {
+    match c {
+        '\u{C0}'..='\u{DE}' if c != '\u{D7}' => char::from_u32(c as u32 + 0x20).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// Unicode complet, avec les variantes qui n'ont pas de majuscule propre
+#[cfg(feature = "unicode-case")]
+fn fold_non_ascii(c: char) -> char {
+    match c {
+        // Sigma final et s long se replient comme leur forme courante
+        'ς' => 'σ',
+        'ſ' => 's',
+        _ => {
+            let mut lower = c.to_lowercase();
+            match (lower.next(), lower.next()) {
+                (Some(folded), None) => folded,
+                _ => c,
+            }
+        }
+    }
+}
+
+/// Nom replié, à comparer avec `matches_folded`
+pub fn fold(name: &str) -> String {
+    name.chars().map(fold_char).collect()
+}
+
+/// Vrai si `name` replié vaut `folded` (déjà passé par `fold`)
+pub fn matches_folded(name: &str, folded: &str) -> bool {
+    name.chars().map(fold_char).eq(folded.chars())
+}
+
+/// Compare deux noms sans tenir compte de la casse
+pub fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars().map(fold_char).eq(b.chars().map(fold_char))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin1_folding() {
+        assert!(eq_ignore_case("Übersicht.txt", "übersicht.TXT"));
+        assert!(eq_ignore_case("ÉTÉ", "été"));
+        assert_eq!(fold("ÀÞ×ß"), "àþ×ß");
+        assert!(!eq_ignore_case("Ü", "U"));
+        assert!(matches_folded("README.MD", &fold("readme.md")));
+    }
+
+    #[cfg(feature = "unicode-case")]
+    #[test]
+    fn test_unicode_folding() {
+        assert!(eq_ignore_case("ΣΟΦΊΑ", "σοφία"));
+        assert!(eq_ignore_case("ΟΔΟΣ", "οδο\u{3C2}"));
+        assert!(eq_ignore_case("Отчёт.doc", "ОТЧЁТ.DOC"));
+        assert!(eq_ignore_case("ŁÓDŹ", "łódź"));
+        // Minuscule en deux caractères: inchangé
+        assert_eq!(fold_char('İ'), 'İ');
+    }
+
+    #[cfg(not(feature = "unicode-case"))]
+    #[test]
+    fn test_no_tables_outside_latin1() {
+        assert!(!eq_ignore_case("ΣΟΦΙΑ", "σοφια"));
+    }
+}
@@ -0,0 +1,248 @@
+//! Façade au format de la crate fatfs (feature fatfs-compat)
+//!
+//! Reprend les noms et les signatures de lecture de fatfs (`root_dir`,
+//! `Dir::iter`, `open_dir`, `open_file`, `DirEntry::file_name`, `File` en
+//! `std::io::Read + Seek`) pour comparer les deux implémentations ou migrer
+//! sans réécrire les appels. Seule la construction change: fatfs prend un
+//! stockage `ReadWriteSeek`, ici l'image est en mémoire (voir `load_storage`).
+//! L'accès est en lecture seule.
+
+extern crate alloc;
+extern crate std;
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::{Fat32, File, FsError};
+
+impl From<FsError> for io::Error {
+    fn from(error: FsError) -> Self {
+        let kind = match error {
+            FsError::NotFound => io::ErrorKind::NotFound,
+            FsError::IsADirectory => io::ErrorKind::IsADirectory,
+            FsError::AlreadyExists => io::ErrorKind::AlreadyExists,
+            FsError::ReadOnly => io::ErrorKind::ReadOnlyFilesystem,
+            FsError::InvalidName | FsError::InvalidLongName(_) => io::ErrorKind::InvalidInput,
+            FsError::Anomaly(_)
+            | FsError::CorruptFat { .. }
+            | FsError::InvalidGeometry
+            | FsError::InvalidClusterSize(_)
+            | FsError::TruncatedImage { .. } => io::ErrorKind::InvalidData,
+            FsError::Cancelled => io::ErrorKind::Interrupted,
+            FsError::OutOfMemory => io::ErrorKind::OutOfMemory,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, error.to_string())
+    }
+}
+
+impl Read for File<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(File::read(self, buf))
+    }
+}
+
+impl Seek for File<'_, '_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len().checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position().checked_add_signed(delta),
+        };
+        match target {
+            Some(target) => Ok(File::seek(self, target)),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "seek before the start of the file")),
+        }
+    }
+}
+
+/// Cluster d'un répertoire; `..` vers la racine y pointe avec 0
+fn dir_cluster(fs: &Fat32<'_>, entry: &super::DirEntry) -> u32 {
+    match entry.cluster() {
+        0 => fs.root_cluster(),
+        cluster => cluster,
+    }
+}
+
+/// Lit tout un stockage fatfs (`Read + Seek`) en mémoire, pour `FileSystem::new`
+pub fn load_storage<T: Read + Seek>(mut storage: T) -> io::Result<Vec<u8>> {
+    storage.seek(SeekFrom::Start(0))?;
+    let mut image = Vec::new();
+    storage.read_to_end(&mut image)?;
+    Ok(image)
+}
+
+/// Options de montage, gardées pour la compatibilité des appels
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsOptions;
+
+impl FsOptions {
+    pub fn new() -> Self {
+        FsOptions
+    }
+}
+
+/// Système de fichiers monté, comme `fatfs::FileSystem`
+pub struct FileSystem<'a> {
+    fs: Fat32<'a>,
+}
+
+impl<'a> FileSystem<'a> {
+    /// Monte l'image `data`; erreur `InvalidData` si ce n'est pas du FAT32
+    pub fn new(data: &'a [u8], _options: FsOptions) -> io::Result<Self> {
+        Ok(FileSystem { fs: Fat32::try_new(data)? })
+    }
+
+    /// Système de fichiers de la bibliothèque, pour le reste de l'API
+    pub fn inner(&self) -> &Fat32<'a> {
+        &self.fs
+    }
+
+    pub fn root_dir(&self) -> Dir<'_, 'a> {
+        Dir { fs: &self.fs, cluster: self.fs.root_cluster() }
+    }
+
+    pub fn volume_label(&self) -> String {
+        self.fs.boot_sector().label().into()
+    }
+}
+
+/// Répertoire, comme `fatfs::Dir`
+#[derive(Clone, Copy)]
+pub struct Dir<'f, 'a> {
+    fs: &'f Fat32<'a>,
+    cluster: u32,
+}
+
+impl<'f, 'a> Dir<'f, 'a> {
+    /// Entrées du répertoire, `.` et `..` compris comme dans fatfs
+    pub fn iter(&self) -> impl Iterator<Item = io::Result<DirEntry<'f, 'a>>> + use<'f, 'a> {
+        let fs = self.fs;
+        let (entries, error) = match fs.try_read_directory(self.cluster) {
+            Ok(entries) => (entries, None),
+            Err(error) => (Vec::new(), Some(error)),
+        };
+        error
+            .map(|error| Err(error.into()))
+            .into_iter()
+            .chain(entries.into_iter().map(move |(entry, long_name)| Ok(DirEntry { fs, entry, long_name })))
+    }
+
+    /// Entrée au chemin `path`, relatif à ce répertoire
+    fn lookup(&self, path: &str) -> io::Result<super::DirEntry> {
+        Ok(self.fs.try_resolve_path(path.trim_start_matches('/'), self.cluster)?)
+    }
+
+    pub fn open_dir(&self, path: &str) -> io::Result<Dir<'f, 'a>> {
+        let entry = self.lookup(path)?;
+        if !entry.is_directory() {
+            return Err(io::Error::new(io::ErrorKind::NotADirectory, "not a directory"));
+        }
+        Ok(Dir { fs: self.fs, cluster: dir_cluster(self.fs, &entry) })
+    }
+
+    pub fn open_file(&self, path: &str) -> io::Result<File<'f, 'a>> {
+        let entry = self.lookup(path)?;
+        Ok(self.fs.open(&entry)?)
+    }
+}
+
+/// Entrée d'un répertoire, comme `fatfs::DirEntry`
+pub struct DirEntry<'f, 'a> {
+    fs: &'f Fat32<'a>,
+    entry: super::DirEntry,
+    long_name: Option<String>,
+}
+
+impl<'f, 'a> DirEntry<'f, 'a> {
+    /// Nom long, ou nom court à défaut
+    pub fn file_name(&self) -> String {
+        self.long_name.clone().unwrap_or_else(|| self.entry.display_name())
+    }
+
+    pub fn short_file_name(&self) -> String {
+        self.entry.display_name()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.entry.is_directory()
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.entry.is_directory()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.entry.size as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entry.size == 0
+    }
+
+    pub fn first_cluster(&self) -> Option<u32> {
+        Some(self.entry.cluster()).filter(|&cluster| cluster >= 2)
+    }
+
+    /// Octet d'attributs brut (`ATTR_*`)
+    pub fn attributes(&self) -> u8 {
+        self.entry.attr
+    }
+
+    /// Entrée de la bibliothèque
+    pub fn entry(&self) -> &super::DirEntry {
+        &self.entry
+    }
+
+    /// Ouvre le fichier; panique sur un répertoire, comme fatfs
+    pub fn to_file(&self) -> File<'f, 'a> {
+        self.fs.open(&self.entry).expect("DirEntry::to_file on a directory or an unreadable file")
+    }
+
+    /// Ouvre le répertoire; panique sur un fichier, comme fatfs
+    pub fn to_dir(&self) -> Dir<'f, 'a> {
+        assert!(self.is_dir(), "DirEntry::to_dir on a file");
+        Dir { fs: self.fs, cluster: dir_cluster(self.fs, &self.entry) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH, README_TEXT};
+
+    #[test]
+    fn test_fatfs_style_calls() {
+        let image = TestImage::default().long_names().fragmented().build().unwrap();
+        let image = load_storage(io::Cursor::new(image)).unwrap();
+        let fs = FileSystem::new(&image, FsOptions::new()).unwrap();
+        let root = fs.root_dir();
+
+        let names: Vec<String> = root.iter().map(|e| e.unwrap().file_name()).collect();
+        assert!(names.iter().any(|n| n == "Long File Name.txt"));
+
+        let mut text = String::new();
+        root.open_file("README.TXT").unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text.as_bytes(), README_TEXT);
+
+        let docs = root.open_dir("My Documents").unwrap();
+        let notes = docs.iter().map(|e| e.unwrap()).find(|e| e.is_file()).unwrap();
+        assert_eq!(notes.file_name(), "notes about fat32.md");
+        let mut content = Vec::new();
+        notes.to_file().read_to_end(&mut content).unwrap();
+        assert_eq!(content.len() as u64, notes.len());
+
+        // Seek depuis la fin puis lecture
+        let mut frag = root.open_file(FRAGMENTED_PATH).unwrap();
+        let expected = TestImage::default().fragmented_data();
+        assert_eq!(Seek::seek(&mut frag, SeekFrom::End(-10)).unwrap(), expected.len() as u64 - 10);
+        let mut tail = Vec::new();
+        frag.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, expected[expected.len() - 10..]);
+        assert!(Seek::seek(&mut frag, SeekFrom::Current(-100_000)).is_err());
+
+        assert_eq!(root.open_file("NOPE").map(|_| ()).unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(root.open_file("My Documents").map(|_| ()).unwrap_err().kind(), io::ErrorKind::IsADirectory);
+        assert_eq!(root.open_dir("README.TXT").map(|_| ()).unwrap_err().kind(), io::ErrorKind::NotADirectory);
+    }
+}
@@ -0,0 +1,195 @@
+//! Périphérique bloc: lecture et écriture par secteurs
+//!
+//! `Fat32` travaille sur une image en mémoire; ce trait décrit un support
+//! adressé par secteurs (carte SD, fichier, image en mémoire) et permet
+//! d'intercaler des enveloppes comme `testimg::faulty::FaultyDevice`.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::boot_sector::BootSector;
+use super::error::FsError;
+use super::fat::cluster_runs;
+
+/// Taille de secteur par défaut
+pub const SECTOR_SIZE: usize = 512;
+
+/// Support adressé par secteurs
+pub trait BlockDevice {
+    /// Taille d'un secteur en octets
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    /// Nombre de secteurs du support
+    fn sector_count(&self) -> u64;
+
+    /// Lit le secteur `sector` dans `buf` (`sector_size` octets)
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError>;
+
+    /// Lit les secteurs consécutifs à partir de `start` pour remplir `buf`
+    ///
+    /// `buf` doit contenir un nombre entier de secteurs. Par défaut, un appel à
+    /// `read_sector` par secteur; un pilote matériel peut le remplacer par une
+    /// seule commande multi-blocs.
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let size = self.sector_size();
+        if !buf.len().is_multiple_of(size) {
+            return Err(FsError::OutOfBounds);
+        }
+        for (i, chunk) in buf.chunks_mut(size).enumerate() {
+            self.read_sector(start + i as u64, chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Écrit `buf` (`sector_size` octets) dans le secteur `sector`
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError>;
+}
+
+/// Image en mémoire, découpée en secteurs de `SECTOR_SIZE` octets
+impl BlockDevice for [u8] {
+    fn sector_count(&self) -> u64 {
+        (self.len() / SECTOR_SIZE) as u64
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let range = sector_range(sector, self.len())?;
+        buf.get_mut(..SECTOR_SIZE).ok_or(FsError::OutOfBounds)?.copy_from_slice(&self[range]);
+        Ok(())
+    }
+
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        if !buf.len().is_multiple_of(SECTOR_SIZE) {
+            return Err(FsError::OutOfBounds);
+        }
+        let first = sector_range(start, self.len())?.start;
+        let end = first.checked_add(buf.len()).filter(|&end| end <= self.len()).ok_or(FsError::OutOfBounds)?;
+        buf.copy_from_slice(&self[first..end]);
+        Ok(())
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError> {
+        let range = sector_range(sector, self.len())?;
+        self[range].copy_from_slice(buf.get(..SECTOR_SIZE).ok_or(FsError::OutOfBounds)?);
+        Ok(())
+    }
+}
+
+impl BlockDevice for Vec<u8> {
+    fn sector_count(&self) -> u64 {
+        self.as_slice().sector_count()
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        self.as_mut_slice().read_sector(sector, buf)
+    }
+
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        self.as_mut_slice().read_sectors(start, buf)
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError> {
+        self.as_mut_slice().write_sector(sector, buf)
+    }
+}
+
+impl<D: BlockDevice + ?Sized> BlockDevice for &mut D {
+    fn sector_size(&self) -> usize {
+        (**self).sector_size()
+    }
+
+    fn sector_count(&self) -> u64 {
+        (**self).sector_count()
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        (**self).read_sector(sector, buf)
+    }
+
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        (**self).read_sectors(start, buf)
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError> {
+        (**self).write_sector(sector, buf)
+    }
+}
+
+/// Octets du secteur `sector` dans une image de `len` octets
+fn sector_range(sector: u64, len: usize) -> Result<core::ops::Range<usize>, FsError> {
+    let start = usize::try_from(sector).ok()
+        .and_then(|s| s.checked_mul(SECTOR_SIZE))
+        .ok_or(FsError::OutOfBounds)?;
+    let end = start.checked_add(SECTOR_SIZE).ok_or(FsError::OutOfBounds)?;
+    if end > len {
+        return Err(FsError::OutOfBounds);
+    }
+    Ok(start..end)
+}
+
+/// Lit depuis `device` le contenu des clusters de `chain`, dans l'ordre
+///
+/// Chaque plage de clusters contigus est lue en un seul `read_sectors`. La
+/// taille de secteur du périphérique doit être celle du boot sector.
+pub fn read_clusters<D: BlockDevice + ?Sized>(device: &mut D, boot_sector: &BootSector, chain: &[u32]) -> Result<Vec<u8>, FsError> {
+    if device.sector_size() != boot_sector.bytes_per_sector as usize {
+        return Err(FsError::InvalidGeometry);
+    }
+    let cluster_size = boot_sector.bytes_per_cluster() as usize;
+    let mut data = alloc::vec![0u8; chain.len() * cluster_size];
+    let mut pos = 0;
+    for (start, len) in cluster_runs(chain) {
+        if start < 2 {
+            return Err(FsError::OutOfBounds);
+        }
+        let end = pos + len as usize * cluster_size;
+        device.read_sectors(boot_sector.cluster_to_sector(start), &mut data[pos..end])?;
+        pos = end;
+    }
+    Ok(data)
+}
+
+/// Écrit sur `device`, dans l'ordre croissant, les secteurs qui diffèrent entre `before` et `after`
+///
+/// Retourne le nombre de secteurs écrits; une erreur du périphérique arrête la copie.
+pub fn write_changes<D: BlockDevice + ?Sized>(device: &mut D, before: &[u8], after: &[u8]) -> Result<usize, FsError> {
+    let size = device.sector_size();
+    let mut written = 0;
+    for (index, (old, new)) in before.chunks(size).zip(after.chunks(size)).enumerate() {
+        if old != new {
+            device.write_sector(index as u64, new)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::Fat32;
+    use crate::testimg::faulty::FaultyDevice;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH};
+
+    #[test]
+    fn test_read_clusters_by_runs() {
+        let mut image = TestImage::default().fragmented().build().unwrap();
+        let (boot_sector, chain, expected) = {
+            let fs = Fat32::new(&image).unwrap();
+            let entry = fs.resolve_path(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+            (fs.boot_sector().clone(), fs.cluster_chain(entry.cluster()), fs.read_cluster_chain(entry.cluster()))
+        };
+        let runs = cluster_runs(&chain).len();
+        assert!(runs > 1);
+
+        let mut device = FaultyDevice::new(&mut image);
+        assert_eq!(read_clusters(&mut device, &boot_sector, &chain).unwrap(), expected);
+        assert_eq!(device.operations(), runs);
+
+        let mut buf = [0u8; SECTOR_SIZE + 1];
+        assert_eq!(device.read_sectors(0, &mut buf), Err(FsError::OutOfBounds));
+        let sectors = device.sector_count();
+        assert_eq!(device.read_sectors(sectors - 1, &mut [0u8; 2 * SECTOR_SIZE]), Err(FsError::OutOfBounds));
+    }
+}
@@ -0,0 +1,111 @@
+//! Cache optionnel des noms de répertoires pour `find_entry`
+//!
+//! Chaque répertoire consulté est lu une fois et indexé dans un tableau trié
+//! par nom replié (noms longs et courts, voir `casefold`); les recherches suivantes
+//! dans le même répertoire sont des recherches dichotomiques, sans relire sa
+//! chaîne. Toute écriture via `Fat32` vide le cache.
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::sync::Lock;
+use super::{casefold, DirEntry};
+
+/// Noms d'un répertoire, longs et courts, repliés et triés
+type DirIndex = Vec<(String, DirEntry)>;
+
+/// Index construits à la demande, par cluster de répertoire
+#[derive(Default)]
+pub(crate) struct DirCache {
+    dirs: Lock<BTreeMap<u32, DirIndex>>,
+}
+
+/// Entrée de nom `folded` dans un index trié
+fn lookup(index: &DirIndex, folded: &str) -> Option<DirEntry> {
+    index.binary_search_by(|(name, _)| name.as_str().cmp(folded)).ok().map(|i| index[i].1.clone())
+}
+
+impl DirCache {
+    /// `Some(résultat)` si le répertoire est indexé, `None` s'il faut le lire
+    pub(crate) fn get(&self, dir_cluster: u32, folded: &str) -> Option<Option<DirEntry>> {
+        self.dirs.lock().get(&dir_cluster).map(|index| lookup(index, folded))
+    }
+
+    /// Indexe les entrées du répertoire et y cherche `folded`
+    ///
+    /// À nom égal, la première entrée gagne, comme pour une recherche séquentielle.
+    pub(crate) fn insert(&self, dir_cluster: u32, entries: Vec<(DirEntry, Option<String>)>, folded: &str) -> Option<DirEntry> {
+        let mut index = DirIndex::with_capacity(entries.len() * 2);
+        for (entry, long_name) in entries {
+            if let Some(long_name) = long_name {
+                index.push((casefold::fold(&long_name), entry.clone()));
+            }
+            index.push((casefold::fold(&entry.display_name()), entry));
+        }
+        // Tri stable: après dédoublonnage, il reste la première entrée de chaque nom
+        index.sort_by(|a, b| a.0.cmp(&b.0));
+        index.dedup_by(|later, first| later.0 == first.0);
+
+        let found = lookup(&index, folded);
+        self.dirs.lock().insert(dir_cluster, index);
+        found
+    }
+
+    /// Nombre de répertoires indexés
+    pub(crate) fn len(&self) -> usize {
+        self.dirs.lock().len()
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut dirs = self.dirs.lock();
+        if !dirs.is_empty() {
+            log_debug!("directory cache cleared ({} directories)", dirs.len());
+        }
+        dirs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fat32::Fat32;
+    use crate::testimg::{TestImage, LONG_NAME_PATHS};
+
+    #[test]
+    fn test_cache_lookups_and_invalidation() {
+        let mut image = TestImage::default().long_names().deep_tree(2).build().unwrap();
+        let mut fs = Fat32::new_mut(&mut image).unwrap().with_dir_cache();
+        let root = fs.root_cluster();
+
+        let notes = fs.resolve_path(LONG_NAME_PATHS[1], root).unwrap();
+        assert_eq!(fs.dir_cache_len(), 2);
+        assert_eq!(fs.resolve_path(LONG_NAME_PATHS[1], root).unwrap().cluster(), notes.cluster());
+        assert_eq!(fs.dir_cache_len(), 2);
+        assert!(fs.find_entry(root, "long file name.TXT").is_some());
+        assert!(fs.find_entry(root, "missing").is_none());
+
+        // Une écriture vide le cache: le nouveau fichier est trouvé
+        fs.write_file(root, "new file.txt", b"new").unwrap();
+        assert_eq!(fs.dir_cache_len(), 0);
+        assert!(fs.find_entry(root, "NEW FILE.TXT").is_some());
+        assert_eq!(fs.dir_cache_len(), 1);
+
+        // Répertoire de nombreux fichiers: chaque nom est retrouvé dans l'index trié
+        let dir = fs.create_dir(root, "ASSETS").unwrap().cluster();
+        for i in (0..60).rev() {
+            fs.write_file(dir, &alloc::format!("asset {:02}.bin", i), &[i as u8]).unwrap();
+        }
+        fs.reset_stats();
+        for i in 0..60 {
+            let entry = fs.find_entry(dir, &alloc::format!("ASSET {:02}.BIN", i)).unwrap();
+            assert_eq!(fs.read_file(&entry), [i as u8]);
+        }
+        assert!(fs.find_entry(dir, "asset 60.bin").is_none());
+        assert_eq!((fs.stats().cache_hits, fs.stats().cache_misses), (60, 1));
+
+        fs.clear_dir_cache();
+        assert_eq!(fs.dir_cache_len(), 0);
+        assert_eq!(Fat32::new(&image).unwrap().dir_cache_len(), 0);
+    }
+}
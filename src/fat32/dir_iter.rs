@@ -0,0 +1,291 @@
+//! Parcours paresseux d'un répertoire, cluster par cluster
+//!
+//! Contrairement à `read_directory_with_lfn`, la chaîne n'est pas chargée
+//! d'avance: une recherche s'arrête dès l'entrée trouvée.
+
+extern crate alloc;
+use alloc::string::String;
+
+use super::directory::{EntryParser, Parsed};
+use super::{casefold, DirEntry, Fat32, FatEntry, FsError};
+
+/// Itérateur sur les entrées d'un répertoire (avec noms longs)
+///
+/// Produit au plus une erreur (anomalie en mode strict, limite dépassée,
+/// annulation), puis s'arrête.
+pub struct DirIter<'f, 'a> {
+    fs: &'f Fat32<'a>,
+    /// Cluster suivant à lire
+    next: Option<u32>,
+    /// Données du cluster courant et position dans celui-ci
+    data: &'f [u8],
+    pos: usize,
+    /// Position du cluster courant dans le répertoire
+    base: usize,
+    /// Nombre de clusters lus
+    read: usize,
+    /// Détecteur de boucle, en avance sur la lecture
+    scout: LoopScout,
+    entries: usize,
+    parser: EntryParser,
+    done: bool,
+}
+
+impl<'f, 'a> DirIter<'f, 'a> {
+    /// Passe au cluster suivant; `Ok(false)` en fin de chaîne
+    fn next_cluster(&mut self) -> Result<bool, FsError> {
+        let cluster = match self.next {
+            Some(cluster) if cluster >= 2 => cluster,
+            _ => return Ok(false),
+        };
+        // Une chaîne qui reboucle s'arrête avant son premier cluster revisité
+        if self.scout.distinct(self.fs, self.read).is_some_and(|distinct| self.read >= distinct) {
+            return Ok(false);
+        }
+        if self.read >= self.fs.limits().max_chain_length {
+            return Err(FsError::LimitExceeded);
+        }
+        self.fs.check_cancel()?;
+
+        self.read += 1;
+        self.base += self.data.len();
+        self.data = self.fs.read_cluster(cluster);
+        self.pos = 0;
+        self.fs.stats.update(|s| s.fat_lookups += 1);
+        self.next = match self.fs.fat_table().get_entry(cluster) {
+            FatEntry::Data(next) => Some(next),
+            FatEntry::Invalid(next) => return Err(FsError::CorruptFat { cluster, next }),
+            _ => None,
+        };
+        Ok(!self.data.is_empty())
+    }
+
+    fn advance(&mut self) -> Result<Option<(DirEntry, Option<String>)>, FsError> {
+        loop {
+            if self.pos + 32 > self.data.len() && !self.next_cluster()? {
+                return Ok(None);
+            }
+            let chunk = &self.data[self.pos..self.pos + 32];
+            let offset = self.base + self.pos;
+            self.pos += 32;
+
+            match self.parser.push(chunk, offset, &self.fs.parse_options())? {
+                Parsed::End => return Ok(None),
+                Parsed::Skip => {}
+                Parsed::Entry(entry, long_name) => {
+                    self.entries += 1;
+                    if self.entries > self.fs.limits().max_dir_entries {
+                        return Err(FsError::LimitExceeded);
+                    }
+                    return Ok(Some((entry, long_name)));
+                }
+            }
+        }
+    }
+}
+
+/// Détection de boucle sans allocation (algorithme de Brent) sur la FAT seule
+///
+/// Brent détecte une boucle de `mu + lambda` clusters distincts au plus tard
+/// au pas `3 (mu + lambda) + 1`: en gardant cette avance sur la lecture, la
+/// boucle est connue avant que le premier cluster revisité ne soit lu.
+struct LoopScout {
+    start: u32,
+    /// Cluster d'indice `steps` de la chaîne
+    hare: Option<u32>,
+    steps: usize,
+    tortoise: Option<u32>,
+    power: usize,
+    lambda: usize,
+    /// Nombre de clusters distincts d'une chaîne qui reboucle
+    distinct: Option<usize>,
+}
+
+impl LoopScout {
+    fn new(start: u32) -> Self {
+        LoopScout { start, hare: Some(start), steps: 0, tortoise: None, power: 1, lambda: 0, distinct: None }
+    }
+
+    /// Nombre de clusters distincts si la chaîne reboucle, vérifié jusqu'au cluster d'indice `read`
+    fn distinct(&mut self, fs: &Fat32, read: usize) -> Option<usize> {
+        let ahead = read.saturating_mul(3).saturating_add(1);
+        while self.distinct.is_none() && self.steps <= ahead {
+            let current = match self.hare {
+                Some(cluster) if cluster >= 2 => cluster,
+                _ => break,
+            };
+            if self.tortoise == Some(current) {
+                self.distinct = Some(self.loop_start(fs) + self.lambda);
+                break;
+            }
+            if self.tortoise.is_none() || self.power == self.lambda {
+                self.tortoise = Some(current);
+                self.power *= 2;
+                self.lambda = 0;
+            }
+            self.lambda += 1;
+            self.hare = Self::link(fs, current);
+            self.steps += 1;
+        }
+        self.distinct
+    }
+
+    /// Indice `mu` du premier cluster de la boucle, de période `lambda`
+    fn loop_start(&self, fs: &Fat32) -> usize {
+        let mut slow = Some(self.start);
+        let mut fast = Some(self.start);
+        for _ in 0..self.lambda {
+            fast = fast.and_then(|c| Self::link(fs, c));
+        }
+        let mut mu = 0;
+        while slow != fast {
+            slow = slow.and_then(|c| Self::link(fs, c));
+            fast = fast.and_then(|c| Self::link(fs, c));
+            mu += 1;
+        }
+        mu
+    }
+
+    fn link(fs: &Fat32, cluster: u32) -> Option<u32> {
+        fs.stats.update(|s| s.fat_lookups += 1);
+        fs.fat_table().get_entry(cluster).next_cluster()
+    }
+}
+
+impl<'f, 'a> Iterator for DirIter<'f, 'a> {
+    type Item = Result<(DirEntry, Option<String>), FsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.advance() {
+            Ok(Some(item)) => Some(Ok(item)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl<'a> Fat32<'a> {
+    /// Parcourt le répertoire `cluster` sans charger sa chaîne entière
+    pub fn iter_directory(&self, cluster: u32) -> DirIter<'_, 'a> {
+        DirIter {
+            fs: self,
+            next: Some(cluster),
+            data: &[],
+            pos: 0,
+            base: 0,
+            read: 0,
+            scout: LoopScout::new(cluster),
+            entries: 0,
+            parser: EntryParser::default(),
+            done: false,
+        }
+    }
+
+    /// Cherche une entrée par nom (insensible à la casse), en s'arrêtant à la première trouvée
+    ///
+    /// Retourne l'erreur rencontrée avant de trouver l'entrée (anomalie, limite, annulation).
+    pub fn try_find_entry(&self, dir_cluster: u32, name: &str) -> Result<Option<DirEntry>, FsError> {
+        let folded = casefold::fold(name);
+        if let Some(cache) = &self.dir_cache {
+            if let Some(found) = cache.get(dir_cluster, &folded) {
+                self.stats.update(|s| s.cache_hits += 1);
+                return Ok(found);
+            }
+            self.stats.update(|s| s.cache_misses += 1);
+            // Un répertoire illisible en entier n'est pas indexé: recherche séquentielle
+            if let Ok(entries) = self.try_read_directory(dir_cluster) {
+                return Ok(cache.insert(dir_cluster, entries, &folded));
+            }
+        }
+        for item in self.iter_directory(dir_cluster) {
+            let (entry, long_name) = item?;
+            if Self::name_matches(&entry, long_name.as_deref(), &folded) {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Vrai si le nom long ou court de l'entrée est `folded` (déjà replié par `casefold::fold`)
+    pub(crate) fn name_matches(entry: &DirEntry, long_name: Option<&str>, folded: &str) -> bool {
+        long_name.is_some_and(|ln| casefold::matches_folded(ln, folded))
+            || casefold::matches_folded(&entry.display_name(), folded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use crate::fat32::{Limits, ParseOptions};
+    use crate::testimg::{corrupt, TestImage, LONG_NAME_PATHS};
+
+    #[test]
+    fn test_iter_matches_full_read() {
+        let test = TestImage::default().cluster_size(512).long_names().deep_tree(2);
+        let mut image = test.build().unwrap();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+        // Assez d'entrées pour occuper plusieurs clusters
+        let dir = fs.create_dir(root, "MANY").unwrap().cluster();
+        for i in 0..40 {
+            fs.write_file(dir, &alloc::format!("file number {}.txt", i), b"x").unwrap();
+        }
+
+        for cluster in [root, dir] {
+            let lazy: Vec<_> = fs.iter_directory(cluster).map(|r| r.unwrap()).collect();
+            let full = fs.read_directory_with_lfn(cluster);
+            assert_eq!(lazy.len(), full.len());
+            assert!(lazy.iter().zip(&full).all(|(a, b)| a.1 == b.1 && a.0.cluster() == b.0.cluster()));
+        }
+        assert!(fs.try_find_entry(dir, "FILE NUMBER 39.TXT").unwrap().is_some());
+        assert!(fs.try_find_entry(dir, "missing").unwrap().is_none());
+
+        // Une recherche qui aboutit tôt ne voit pas la limite des entrées suivantes
+        let fs = Fat32::new(&image).unwrap().with_limits(Limits { max_dir_entries: 5, ..Limits::default() });
+        let dir = fs.find_entry(root, "MANY").unwrap().cluster();
+        assert!(fs.try_find_entry(dir, "file number 1.txt").unwrap().is_some());
+        assert_eq!(fs.try_find_entry(dir, "file number 39.txt").unwrap_err(), FsError::LimitExceeded);
+    }
+
+    #[test]
+    fn test_iter_stops_at_chain_loop() {
+        let mut image = TestImage::default().cluster_size(512).build().unwrap();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let dir = fs.create_dir(fs.root_cluster(), "MANY").unwrap().cluster();
+        // 94 fichiers, `.` et `..`: six clusters pleins, sans marqueur de fin
+        for i in 0..94 {
+            fs.write_file(dir, &alloc::format!("F{}.TXT", i), b"x").unwrap();
+        }
+        let chain = fs.cluster_chain(dir);
+        let expected = fs.iter_directory(dir).count();
+        assert_eq!((chain.len(), expected), (6, 96));
+
+        // Le dernier cluster revient sur chacun des précédents: chaque entrée est vue une fois
+        for back in 0..chain.len() {
+            fs.write_fat_entry(chain[chain.len() - 1], chain[back]).unwrap();
+            let names: Vec<_> = fs.iter_directory(dir).map(|r| r.unwrap().0.display_name()).collect();
+            assert_eq!(names.len(), expected, "loop back to cluster {}", back);
+            assert!(fs.try_find_entry(dir, "MISSING").unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_iter_stops_on_anomaly() {
+        let mut image = TestImage::default().long_names().build().unwrap();
+        corrupt::break_lfn_checksum(&mut image, LONG_NAME_PATHS[0]).unwrap();
+        let fs = Fat32::new(&image).unwrap().with_options(ParseOptions::strict()).unwrap();
+        let items: Vec<_> = fs.iter_directory(fs.root_cluster()).collect();
+        assert!(matches!(items.last(), Some(Err(FsError::Anomaly(_)))));
+        assert_eq!(items.iter().filter(|r| r.is_err()).count(), 1);
+    }
+}
@@ -4,6 +4,9 @@ extern crate alloc;
 use alloc::string::String;
 use alloc::vec::Vec;
 
+use super::anomaly::{check_short_entry, Anomaly, ParseOptions, ATTR_RESERVED};
+use super::error::FsError;
+
 // Flags d'attributs des entrées
 pub const ATTR_READ_ONLY: u8 = 0x01;
 pub const ATTR_HIDDEN: u8 = 0x02;
@@ -13,8 +16,120 @@ pub const ATTR_DIRECTORY: u8 = 0x10;
 pub const ATTR_ARCHIVE: u8 = 0x20;
 pub const ATTR_LONG_NAME: u8 = 0x0F;
 
+/// Date et heure FAT décodées (heure locale, précision 2 secondes)
+///
+/// FAT ne stocke aucun fuseau: une date est l'heure locale de la machine qui
+/// l'a écrite. Les conversions depuis et vers le temps Unix prennent donc le
+/// décalage de cette heure locale sur UTC, en secondes (`3600` pour UTC+1);
+/// `from_unix_seconds` suppose UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FatDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl FatDateTime {
+    /// Décode un couple date/heure FAT (date: AAAAAAAMMMMJJJJJ, heure: HHHHHMMMMMMSSSSS)
+    pub fn from_raw(date: u16, time: u16) -> Self {
+        FatDateTime {
+            year: 1980 + (date >> 9),
+            month: ((date >> 5) & 0x0F) as u8,
+            day: (date & 0x1F) as u8,
+            hour: (time >> 11) as u8,
+            minute: ((time >> 5) & 0x3F) as u8,
+            second: ((time & 0x1F) * 2) as u8,
+        }
+    }
+
+    /// Encode en couple `(date, heure)` FAT, borné à la plage 1980-2107
+    pub fn to_raw(&self) -> (u16, u16) {
+        if self.year < 1980 {
+            return ((1 << 5) | 1, 0);
+        }
+        if self.year > 2107 {
+            return ((127 << 9) | (12 << 5) | 31, (23 << 11) | (59 << 5) | 29);
+        }
+        let date = ((self.year - 1980) << 9) | ((self.month as u16) << 5) | self.day as u16;
+        let time = ((self.hour as u16) << 11) | ((self.minute as u16) << 5) | (self.second as u16 / 2);
+        (date, time)
+    }
+
+    /// Date locale d'un instant Unix, pour une heure locale décalée de `utc_offset` secondes
+    pub fn from_unix_local(seconds: u64, utc_offset: i32) -> Self {
+        Self::from_unix_seconds(seconds.saturating_add_signed(utc_offset as i64))
+    }
+
+    /// Instant Unix de cette date locale, pour une heure locale décalée de `utc_offset` secondes
+    ///
+    /// `None` pour une date invalide (mois ou jour nul, comme une date jamais écrite).
+    pub fn to_unix_seconds(&self, utc_offset: i32) -> Option<i64> {
+        if !(1..=12).contains(&self.month) || self.day == 0 {
+            return None;
+        }
+
+        // Jours depuis 1970-01-01 (algorithme "days from civil" de H. Hinnant)
+        let (year, month) = (self.year as i64, self.month as i64);
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let mp = (month + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+
+        let local = days * 86_400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        Some(local - utc_offset as i64)
+    }
+
+    /// Date d'un nombre de secondes depuis 1970-01-01 (UTC)
+    pub fn from_unix_seconds(seconds: u64) -> Self {
+        // Algorithme "civil from days" de H. Hinnant
+        let days = (seconds / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let doe = days - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        let rest = seconds % 86_400;
+        FatDateTime {
+            year: year.clamp(0, u16::MAX as i64) as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (rest / 3600) as u8,
+            minute: (rest / 60 % 60) as u8,
+            second: (rest % 60) as u8,
+        }
+    }
+}
+
+/// Nom d'affichage d'une entrée, voir `DirEntry::display`
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayName<'e>(&'e DirEntry);
+
+impl core::fmt::Display for DisplayName<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.write_name(f)
+    }
+}
+
+impl core::fmt::Display for FatDateTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute)
+    }
+}
+
 /// Entrée de répertoire FAT32 (32 octets)
 #[derive(Clone, Debug)]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
 pub struct DirEntry {
     pub name: [u8; 8],
     pub ext: [u8; 3],
@@ -22,6 +137,8 @@ pub struct DirEntry {
     pub cluster_high: u16,
     pub cluster_low: u16,
     pub size: u32,
+    /// Complément de l'heure de création en centièmes de seconde (0 à 199, octet 13)
+    pub create_time_tenths: u8,
     pub create_time: u16,
     pub create_date: u16,
     pub access_date: u16,
@@ -50,6 +167,7 @@ impl DirEntry {
             name,
             ext,
             attr: data[11],
+            create_time_tenths: data[13],
             create_time: u16::from_le_bytes([data[14], data[15]]),
             create_date: u16::from_le_bytes([data[16], data[17]]),
             access_date: u16::from_le_bytes([data[18], data[19]]),
@@ -103,6 +221,44 @@ impl DirEntry {
         self.attr & ATTR_SYSTEM != 0
     }
 
+    /// Retourne la date de dernière modification
+    pub fn modified(&self) -> FatDateTime {
+        FatDateTime::from_raw(self.modify_date, self.modify_time)
+    }
+
+    /// Retourne la date de création, à la seconde (voir `created_millis`)
+    ///
+    /// L'heure sur 2 secondes est complétée par l'octet des centièmes.
+    pub fn created(&self) -> FatDateTime {
+        let mut time = FatDateTime::from_raw(self.create_date, self.create_time);
+        time.second = time.second.saturating_add(self.create_time_tenths.min(199) / 100);
+        time
+    }
+
+    /// Millisecondes de la création au-delà de la seconde de `created`
+    pub fn created_millis(&self) -> u16 {
+        (self.create_time_tenths.min(199) % 100) as u16 * 10
+    }
+
+    /// Retourne la date du dernier accès (sans heure: FAT n'en stocke pas)
+    pub fn accessed(&self) -> FatDateTime {
+        FatDateTime::from_raw(self.access_date, 0)
+    }
+
+    /// Retourne les attributs sous forme "drhsa" ('-' si absent)
+    pub fn attr_string(&self) -> String {
+        let flags = [
+            (ATTR_DIRECTORY, 'd'),
+            (ATTR_READ_ONLY, 'r'),
+            (ATTR_HIDDEN, 'h'),
+            (ATTR_SYSTEM, 's'),
+            (ATTR_ARCHIVE, 'a'),
+        ];
+        flags.iter()
+            .map(|&(bit, c)| if self.attr & bit != 0 { c } else { '-' })
+            .collect()
+    }
+
     /// Vérifie si c'est l'entrée "."
     pub fn is_dot(&self) -> bool {
         self.name[0] == b'.' && self.name[1] == b' '
@@ -115,28 +271,38 @@ impl DirEntry {
 
     /// Retourne le nom d'affichage (NAME.EXT)
     pub fn display_name(&self) -> String {
+        let mut name = String::new();
+        // L'écriture dans une String n'échoue pas
+        let _ = self.write_name(&mut name);
+        name
+    }
+
+    /// Nom d'affichage (NAME.EXT) utilisable dans `format!`, sans allocation
+    pub fn display(&self) -> DisplayName<'_> {
+        DisplayName(self)
+    }
+
+    /// Écrit le nom d'affichage (NAME.EXT) dans `out`
+    pub fn write_name<W: core::fmt::Write>(&self, out: &mut W) -> core::fmt::Result {
         if self.is_dot() {
-            return String::from(".");
+            return out.write_str(".");
         }
         if self.is_dotdot() {
-            return String::from("..");
+            return out.write_str("..");
         }
 
-        let name_part: String = self.name.iter()
-            .take_while(|&&b| b != 0x20 && b != 0x00)
-            .map(|&b| b as char)
-            .collect();
-
-        let ext_part: String = self.ext.iter()
-            .take_while(|&&b| b != 0x20 && b != 0x00)
-            .map(|&b| b as char)
-            .collect();
-
-        if ext_part.is_empty() {
-            name_part
-        } else {
-            alloc::format!("{}.{}", name_part, ext_part)
+        let part = |bytes: &[u8]| bytes.iter().take_while(|&&b| b != 0x20 && b != 0x00).count();
+        for &b in &self.name[..part(&self.name)] {
+            out.write_char(b as char)?;
         }
+        let ext_len = part(&self.ext);
+        if ext_len > 0 {
+            out.write_char('.')?;
+            for &b in &self.ext[..ext_len] {
+                out.write_char(b as char)?;
+            }
+        }
+        Ok(())
     }
 
     /// Retourne le nom court brut (format 8.3)
@@ -174,19 +340,19 @@ impl LfnEntry {
         let mut name2 = [0u16; 6];
         let mut name3 = [0u16; 2];
 
-        for i in 0..5 {
+        for (i, c) in name1.iter_mut().enumerate() {
             let offset = 1 + i * 2;
-            name1[i] = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            *c = u16::from_le_bytes([data[offset], data[offset + 1]]);
         }
 
-        for i in 0..6 {
+        for (i, c) in name2.iter_mut().enumerate() {
             let offset = 14 + i * 2;
-            name2[i] = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            *c = u16::from_le_bytes([data[offset], data[offset + 1]]);
         }
 
-        for i in 0..2 {
+        for (i, c) in name3.iter_mut().enumerate() {
             let offset = 28 + i * 2;
-            name3[i] = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            *c = u16::from_le_bytes([data[offset], data[offset + 1]]);
         }
 
         Some(LfnEntry {
@@ -210,39 +376,24 @@ impl LfnEntry {
 
     /// Extrait les caractères de cette entrée LFN
     pub fn get_chars(&self) -> Vec<char> {
-        let mut chars = Vec::new();
-
-        for &c in &self.name1 {
-            if c == 0x0000 || c == 0xFFFF {
-                return chars;
-            }
-            if let Some(ch) = char::from_u32(c as u32) {
-                chars.push(ch);
-            }
-        }
-
-        for &c in &self.name2 {
-            if c == 0x0000 || c == 0xFFFF {
-                return chars;
-            }
-            if let Some(ch) = char::from_u32(c as u32) {
-                chars.push(ch);
-            }
-        }
-
-        for &c in &self.name3 {
-            if c == 0x0000 || c == 0xFFFF {
-                return chars;
-            }
-            if let Some(ch) = char::from_u32(c as u32) {
-                chars.push(ch);
-            }
-        }
+        self.chars().collect()
+    }
 
-        chars
+    /// Caractères de cette entrée LFN, jusqu'au terminateur 0x0000 ou au bourrage 0xFFFF
+    pub(crate) fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.name1.iter().chain(&self.name2).chain(&self.name3)
+            .take_while(|&&c| c != 0x0000 && c != 0xFFFF)
+            .filter_map(|&c| char::from_u32(c as u32))
     }
 }
 
+/// Calcule la somme de contrôle LFN d'un nom court (11 octets NAME+EXT)
+pub fn lfn_checksum(short_name: &[u8; 11]) -> u8 {
+    short_name.iter().fold(0u8, |sum, &b| {
+        ((sum & 1) << 7).wrapping_add(sum >> 1).wrapping_add(b)
+    })
+}
+
 /// Parse toutes les entrées d'un répertoire
 pub fn parse_directory(data: &[u8]) -> Vec<DirEntry> {
     let mut entries = Vec::new();
@@ -264,45 +415,133 @@ pub fn parse_directory(data: &[u8]) -> Vec<DirEntry> {
 
 /// Parse le répertoire avec support des noms longs
 pub fn parse_directory_with_lfn(data: &[u8]) -> Vec<(DirEntry, Option<String>)> {
+    // Les options par défaut acceptent toutes les anomalies
+    parse_directory_checked(data, &ParseOptions::default()).unwrap_or_default()
+}
+
+/// Parse un répertoire avec noms longs en appliquant `options` aux anomalies
+///
+/// Le répertoire s'arrête à la première entrée libre (0x00), sauf avec
+/// `full_scan` qui parcourt tout `data`.
+///
+/// En mode strict, retourne la première anomalie (`FsError::Anomaly`,
+/// position dans `data`). En mode réparation, un nom long dont la somme de
+/// contrôle est fausse est ignoré, les bits d'attribut réservés sont masqués
+/// et la taille d'un répertoire est remise à zéro. Les entrées et les noms
+/// longs sont alloués par `try_reserve`: un heap plein donne `OutOfMemory`.
+pub fn parse_directory_checked(data: &[u8], options: &ParseOptions) -> Result<Vec<(DirEntry, Option<String>)>, FsError> {
     let mut entries = Vec::new();
-    let mut lfn_parts: Vec<(u8, Vec<char>)> = Vec::new();
+    let mut parser = EntryParser::default();
+
+    for (index, chunk) in data.chunks(32).enumerate() {
+        match parser.push(chunk, index * 32, options)? {
+            Parsed::End => break,
+            Parsed::Skip => {}
+            Parsed::Entry(entry, long_name) => {
+                entries.try_reserve(1).map_err(|_| FsError::OutOfMemory)?;
+                entries.push((entry, long_name));
+            }
+        }
+    }
 
-    for chunk in data.chunks(32) {
-        if chunk.len() < 32 || chunk[0] == 0x00 {
-            break;
+    Ok(entries)
+}
+
+/// Résultat de l'analyse d'une entrée de 32 octets
+pub(crate) enum Parsed {
+    /// Fin du répertoire (entrée libre finale ou données incomplètes)
+    End,
+    /// Entrée LFN, libre, supprimée ou label: rien à produire
+    Skip,
+    Entry(DirEntry, Option<String>),
+}
+
+/// Analyse entrée par entrée d'un répertoire, avec les noms longs en attente
+///
+/// Permet de parcourir un répertoire sans charger toute sa chaîne de clusters.
+#[derive(Default)]
+pub(crate) struct EntryParser {
+    lfn_parts: Vec<(u8, Vec<char>)>,
+    lfn_checksums: Vec<u8>,
+}
+
+impl EntryParser {
+    /// Analyse l'entrée `chunk` située à `offset` dans le répertoire
+    ///
+    /// Erreur sur une anomalie en mode strict, ou `OutOfMemory` si un nom long
+    /// ne peut pas être alloué.
+    pub(crate) fn push(&mut self, chunk: &[u8], offset: usize, options: &ParseOptions) -> Result<Parsed, FsError> {
+        if chunk.len() < 32 || (chunk[0] == 0x00 && !options.full_scan) {
+            return Ok(Parsed::End);
+        }
+        if chunk[0] == 0x00 {
+            self.lfn_parts.clear();
+            self.lfn_checksums.clear();
+            return Ok(Parsed::Skip);
         }
 
         if chunk[11] == ATTR_LONG_NAME && chunk[0] != 0xE5 {
             if let Some(lfn) = LfnEntry::from_bytes(chunk) {
-                lfn_parts.push((lfn.order(), lfn.get_chars()));
+                let mut chars = Vec::new();
+                chars.try_reserve_exact(13).map_err(|_| FsError::OutOfMemory)?;
+                chars.extend(lfn.chars());
+                self.lfn_checksums.try_reserve(1).map_err(|_| FsError::OutOfMemory)?;
+                self.lfn_parts.try_reserve(1).map_err(|_| FsError::OutOfMemory)?;
+                self.lfn_checksums.push(lfn.checksum);
+                self.lfn_parts.push((lfn.order(), chars));
             }
-            continue;
+            return Ok(Parsed::Skip);
         }
 
-        if let Some(entry) = DirEntry::from_bytes(chunk) {
-            if entry.is_volume_label() {
-                lfn_parts.clear();
-                continue;
+        let mut entry = match DirEntry::from_bytes(chunk) {
+            Some(entry) => entry,
+            None => {
+                if !self.lfn_parts.is_empty() {
+                    log_debug!("long name without a short entry dropped at offset {}", offset);
+                }
+                self.lfn_parts.clear();
+                self.lfn_checksums.clear();
+                return Ok(Parsed::Skip);
+            }
+        };
+
+        if let Some(anomaly) = check_short_entry(chunk, offset) {
+            if options.handle(anomaly).map_err(FsError::Anomaly)? {
+                entry.attr &= !ATTR_RESERVED;
+                if entry.attr & ATTR_DIRECTORY != 0 {
+                    entry.attr &= !ATTR_VOLUME_ID;
+                    entry.size = 0;
+                }
             }
+        }
 
-            let long_name = if !lfn_parts.is_empty() {
-                lfn_parts.sort_by_key(|(order, _)| *order);
-                let name: String = lfn_parts.iter()
-                    .flat_map(|(_, chars)| chars.iter())
-                    .collect();
-                lfn_parts.clear();
-                Some(name)
-            } else {
-                None
-            };
-
-            entries.push((entry, long_name));
-        } else {
-            lfn_parts.clear();
+        if entry.is_volume_label() {
+            self.lfn_parts.clear();
+            self.lfn_checksums.clear();
+            return Ok(Parsed::Skip);
         }
-    }
 
-    entries
+        let checksum = lfn_checksum(chunk[0..11].try_into().unwrap_or(&[0; 11]));
+        let checksum_ok = self.lfn_checksums.iter().all(|&c| c == checksum);
+        self.lfn_checksums.clear();
+        if !checksum_ok && options.handle(Anomaly::LfnChecksum(offset)).map_err(FsError::Anomaly)? {
+            self.lfn_parts.clear();
+        }
+
+        let long_name = if !self.lfn_parts.is_empty() {
+            self.lfn_parts.sort_by_key(|(order, _)| *order);
+            let mut name = String::new();
+            let len = self.lfn_parts.iter().flat_map(|(_, chars)| chars).map(|c| c.len_utf8()).sum();
+            name.try_reserve_exact(len).map_err(|_| FsError::OutOfMemory)?;
+            name.extend(self.lfn_parts.iter().flat_map(|(_, chars)| chars));
+            self.lfn_parts.clear();
+            Some(name)
+        } else {
+            None
+        };
+
+        Ok(Parsed::Entry(entry, long_name))
+    }
 }
 
 #[cfg(test)]
@@ -318,6 +557,7 @@ mod tests {
 
         let entry = DirEntry::from_bytes(&data).unwrap();
         assert_eq!(entry.display_name(), "TEST.TXT");
+        assert_eq!(alloc::format!("{}", entry.display()), "TEST.TXT");
         assert!(!entry.is_directory());
     }
 
@@ -362,6 +602,72 @@ mod tests {
         assert_eq!(entry.display_name(), "..");
     }
 
+    #[test]
+    fn test_datetime_and_attributes() {
+        let mut data = [0u8; 32];
+        data[0..8].copy_from_slice(b"FILE    ");
+        data[11] = ATTR_ARCHIVE | ATTR_READ_ONLY;
+        // 2024-03-15 13:45:30
+        let date: u16 = (44 << 9) | (3 << 5) | 15;
+        let time: u16 = (13 << 11) | (45 << 5) | 15;
+        data[22..24].copy_from_slice(&time.to_le_bytes());
+        data[24..26].copy_from_slice(&date.to_le_bytes());
+
+        let entry = DirEntry::from_bytes(&data).unwrap();
+        let dt = entry.modified();
+        assert_eq!((dt.year, dt.month, dt.day), (2024, 3, 15));
+        assert_eq!((dt.hour, dt.minute, dt.second), (13, 45, 30));
+        assert_eq!(alloc::format!("{}", dt), "2024-03-15 13:45");
+        assert_eq!(entry.attr_string(), "-r--a");
+        assert_eq!(dt.to_raw(), (date, time));
+    }
+
+    #[test]
+    fn test_datetime_conversions() {
+        let dt = FatDateTime::from_unix_seconds(1_709_209_811);
+        assert_eq!(alloc::format!("{}:{:02}", dt, dt.second), "2024-02-29 12:30:11");
+        // Les secondes FAT ont une précision de 2 secondes
+        assert_eq!(FatDateTime::from_raw(dt.to_raw().0, dt.to_raw().1).second, 10);
+
+        let epoch = FatDateTime::from_unix_seconds(0);
+        assert_eq!((epoch.year, epoch.month, epoch.day), (1970, 1, 1));
+        assert_eq!(FatDateTime::from_raw(epoch.to_raw().0, epoch.to_raw().1).year, 1980);
+        let far = FatDateTime::from_unix_seconds(5_000_000_000);
+        assert_eq!(FatDateTime::from_raw(far.to_raw().0, far.to_raw().1).year, 2107);
+
+        // Heure locale: 14:30:11 à UTC+2 est 12:30:11 UTC
+        let local = FatDateTime::from_unix_local(1_709_209_811, 7200);
+        assert_eq!((local.hour, local.minute, local.second), (14, 30, 11));
+        assert_eq!(local.to_unix_seconds(7200), Some(1_709_209_811));
+        assert_eq!(dt.to_unix_seconds(0), Some(1_709_209_811));
+        assert_eq!(FatDateTime::from_raw(0, 0).to_unix_seconds(0), None);
+    }
+
+    #[test]
+    fn test_creation_tenths() {
+        let mut data = [0u8; 32];
+        data[0..8].copy_from_slice(b"FILE    ");
+        // Création 2024-03-15 13:45:31.250, dernier accès le 2024-03-16
+        data[13] = 125;
+        data[14..16].copy_from_slice(&((13u16 << 11) | (45 << 5) | 15).to_le_bytes());
+        data[16..18].copy_from_slice(&((44u16 << 9) | (3 << 5) | 15).to_le_bytes());
+        data[18..20].copy_from_slice(&((44u16 << 9) | (3 << 5) | 16).to_le_bytes());
+
+        let entry = DirEntry::from_bytes(&data).unwrap();
+        assert_eq!(entry.create_time_tenths, 125);
+        let created = entry.created();
+        assert_eq!((created.hour, created.minute, created.second), (13, 45, 31));
+        assert_eq!(entry.created_millis(), 250);
+        let accessed = entry.accessed();
+        assert_eq!((accessed.day, accessed.hour, accessed.minute), (16, 0, 0));
+    }
+
+    #[test]
+    fn test_lfn_checksum() {
+        assert_eq!(lfn_checksum(b"README  TXT"), 0x73);
+        assert_eq!(lfn_checksum(b"           "), 0xF7);
+    }
+
     #[test]
     fn test_deleted_entry() {
         let mut data = [0u8; 32];
@@ -374,4 +680,20 @@ mod tests {
         let data = [0u8; 32];
         assert!(DirEntry::from_bytes(&data).is_none());
     }
+
+    #[test]
+    fn test_full_scan() {
+        // A.TXT, slot libre, B.TXT
+        let mut data = [0u8; 96];
+        data[0..11].copy_from_slice(b"A       TXT");
+        data[64..75].copy_from_slice(b"B       TXT");
+
+        let entries = parse_directory_checked(&data, &ParseOptions::default()).unwrap();
+        assert_eq!(entries.len(), 1);
+        let options = ParseOptions::strict().with_full_scan();
+        let entries = parse_directory_checked(&data, &options).unwrap();
+        let names: Vec<String> = entries.iter().map(|(e, _)| e.display_name()).collect();
+        assert_eq!(names, ["A.TXT", "B.TXT"]);
+        assert!(options.strict);
+    }
 }
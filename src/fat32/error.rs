@@ -0,0 +1,126 @@
+//! Erreurs du système de fichiers FAT32
+
+extern crate alloc;
+use alloc::string::String;
+use core::fmt;
+
+use super::anomaly::Anomaly;
+use super::write::NameError;
+
+/// Erreur retournée par les opérations du filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub enum FsError {
+    /// L'image a été montée en lecture seule
+    ReadOnly,
+    /// Entrée introuvable
+    NotFound,
+    /// Un cluster nécessaire est déjà alloué
+    ClusterInUse(u32),
+    /// Nom ou caractère invalide
+    InvalidName,
+    /// Nom long refusé par `validate_long_name`, avec la raison
+    InvalidLongName(NameError),
+    /// Accès en dehors de l'image
+    OutOfBounds,
+    /// Une entrée du même nom existe déjà
+    AlreadyExists,
+    /// Plus de cluster libre
+    NoSpace,
+    /// Géométrie invalide (taille de secteur, image trop petite...)
+    InvalidGeometry,
+    /// Nombre de secteurs par cluster qui n'est pas une puissance de deux de 1 à 128
+    InvalidClusterSize(u8),
+    /// Image plus petite que le volume décrit par le boot sector (tailles en octets)
+    TruncatedImage { expected: u64, actual: u64 },
+    /// Opération de fichier sur un répertoire
+    IsADirectory,
+    /// Limite de ressources dépassée (chaîne, répertoire, fichier ou chemin)
+    LimitExceeded,
+    /// Échec de lecture ou d'écriture du périphérique
+    Io,
+    /// Opération interrompue par le test d'annulation
+    Cancelled,
+    /// Le heap ne peut pas contenir le résultat (fichier, répertoire ou chaîne)
+    OutOfMemory,
+    /// Entrée de la FAT qui désigne un cluster hors du volume
+    ///
+    /// `cluster` vaut 0 quand c'est le premier cluster d'une entrée de
+    /// répertoire, et non un lien de la FAT, qui sort du volume.
+    CorruptFat { cluster: u32, next: u32 },
+    /// Structure sur disque rejetée en lecture stricte
+    Anomaly(Anomaly),
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsError::ReadOnly => write!(f, "Read-only filesystem"),
+            FsError::NotFound => write!(f, "Not found"),
+            FsError::ClusterInUse(c) => write!(f, "Cluster {} is already in use", c),
+            FsError::InvalidName => write!(f, "Invalid name"),
+            FsError::InvalidLongName(error) => write!(f, "Invalid name: {}", error),
+            FsError::OutOfBounds => write!(f, "Access outside of the image"),
+            FsError::AlreadyExists => write!(f, "File exists"),
+            FsError::NoSpace => write!(f, "No space left on device"),
+            FsError::InvalidGeometry => write!(f, "Invalid filesystem geometry"),
+            FsError::InvalidClusterSize(sectors) => {
+                write!(f, "Invalid cluster size: {} sectors per cluster (expected a power of two from 1 to 128)", sectors)
+            }
+            FsError::TruncatedImage { expected, actual } => {
+                write!(f, "Truncated image: the boot sector describes {} bytes, the image holds {}", expected, actual)
+            }
+            FsError::IsADirectory => write!(f, "Is a directory"),
+            FsError::LimitExceeded => write!(f, "Resource limit exceeded"),
+            FsError::Io => write!(f, "Input/output error"),
+            FsError::Cancelled => write!(f, "Operation cancelled"),
+            FsError::OutOfMemory => write!(f, "Out of memory"),
+            FsError::CorruptFat { cluster, next } => {
+                write!(f, "Corrupted FAT: cluster {} links to cluster {} outside the volume", cluster, next)
+            }
+            FsError::Anomaly(anomaly) => write!(f, "Corrupted filesystem: {}", anomaly),
+        }
+    }
+}
+
+/// Raison de l'échec sur un composant de chemin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// Le composant n'existe pas dans son répertoire
+    NotFound,
+    /// Le composant est un fichier alors que le chemin continue
+    NotADirectory,
+    /// Erreur du filesystem pendant la recherche (limite, anomalie, annulation...)
+    Fs(FsError),
+}
+
+/// Échec de résolution d'un chemin, sur le composant en cause
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    /// Position du composant parmi les composants non vides (0 pour le premier)
+    pub index: usize,
+    pub component: String,
+    /// Chemin jusqu'au composant inclus (`/DOCS/REPORTS`)
+    pub path: String,
+    pub kind: PathErrorKind,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PathErrorKind::NotFound => write!(f, "{}: not found", self.path),
+            PathErrorKind::NotADirectory => write!(f, "{}: not a directory", self.path),
+            PathErrorKind::Fs(error) => write!(f, "{}: {}", self.path, error),
+        }
+    }
+}
+
+/// `NotFound` pour un composant absent ou qui n'est pas un répertoire
+impl From<PathError> for FsError {
+    fn from(error: PathError) -> Self {
+        match error.kind {
+            PathErrorKind::NotFound | PathErrorKind::NotADirectory => FsError::NotFound,
+            PathErrorKind::Fs(error) => error,
+        }
+    }
+}
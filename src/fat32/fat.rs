@@ -3,6 +3,15 @@
 extern crate alloc;
 use alloc::vec::Vec;
 
+use super::error::FsError;
+use super::limits::Limits;
+
+/// Nombre de clusters parcourus entre deux tests d'annulation
+const CANCEL_INTERVAL: usize = 1024;
+
+/// Taille des blocs de FAT testés d'un coup par `try_count_free_clusters`
+const FAT_SECTOR_BYTES: usize = 512;
+
 /// Types d'entrées FAT
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FatEntry {
@@ -11,6 +20,8 @@ pub enum FatEntry {
     Data(u32),
     BadCluster,
     EndOfChain,
+    /// Lien vers un cluster qui n'existe pas sur le volume (FAT corrompue)
+    Invalid(u32),
 }
 
 impl FatEntry {
@@ -25,6 +36,17 @@ impl FatEntry {
         }
     }
 
+    /// Parse une entrée brute d'un volume de `cluster_count` clusters
+    ///
+    /// Les clusters de données vont de 2 à `cluster_count + 1`: un lien au-delà
+    /// mènerait dans la FAT ou hors du disque, il devient `Invalid`.
+    pub fn from_raw_checked(value: u32, cluster_count: u32) -> Self {
+        match Self::from_raw(value) {
+            FatEntry::Data(n) if n > cluster_count.saturating_add(1) => FatEntry::Invalid(n),
+            entry => entry,
+        }
+    }
+
     /// Vérifie si c'est la fin de chaîne
     #[inline]
     pub fn is_end(&self) -> bool {
@@ -50,12 +72,22 @@ impl FatEntry {
 /// Lecteur de table FAT
 pub struct FatTable<'a> {
     data: &'a [u8],
+    cluster_count: u32,
 }
 
 impl<'a> FatTable<'a> {
-    /// Crée un nouveau lecteur de table FAT
+    /// Crée un nouveau lecteur de table FAT, chaque entrée comptant pour un cluster
     pub fn new(data: &'a [u8]) -> Self {
-        FatTable { data }
+        let cluster_count = (data.len() / 4).saturating_sub(2).min(u32::MAX as usize) as u32;
+        FatTable { data, cluster_count }
+    }
+
+    /// Lecteur d'une FAT dont seuls `cluster_count` clusters existent sur le volume
+    ///
+    /// La FAT occupe des secteurs entiers et a souvent plus d'entrées que de
+    /// clusters: les liens vers les entrées en trop sont `Invalid`.
+    pub fn with_cluster_count(data: &'a [u8], cluster_count: u32) -> Self {
+        FatTable { data, cluster_count }
     }
 
     /// Récupère l'entrée FAT pour un cluster
@@ -72,54 +104,160 @@ impl<'a> FatTable<'a> {
             self.data[offset + 3],
         ]);
 
-        FatEntry::from_raw(value)
+        FatEntry::from_raw_checked(value, self.cluster_count)
     }
 
     /// Récupère la chaîne complète de clusters
+    ///
+    /// Une chaîne qui reboucle est coupée au premier cluster déjà visité, une
+    /// chaîne trop longue à `Limits::default().max_chain_length` clusters.
     pub fn get_cluster_chain(&self, start: u32) -> Vec<u32> {
+        self.walk_chain(start, Limits::default().max_chain_length, None).0
+    }
+
+    /// Récupère la chaîne de clusters, en erreur au-delà de `max_length` clusters
+    /// ou sur un lien hors du volume (`CorruptFat`)
+    pub fn try_cluster_chain(&self, start: u32, max_length: usize) -> Result<Vec<u32>, FsError> {
+        match self.walk_chain(start, max_length, None) {
+            (chain, None) => Ok(chain),
+            (_, Some(error)) => Err(error),
+        }
+    }
+
+    /// Parcourt au plus `max_length` clusters en consultant `cancel` régulièrement
+    ///
+    /// Retourne la chaîne lue et, si elle a été coupée, la raison (limite,
+    /// annulation, heap plein ou cluster hors du volume). Une boucle est
+    /// détectée sans allocation (algorithme de Brent) et la chaîne est coupée
+    /// avant le premier cluster revisité.
+    pub(crate) fn walk_chain(&self, start: u32, max_length: usize, cancel: Option<&dyn Fn() -> bool>) -> (Vec<u32>, Option<FsError>) {
         let mut chain = Vec::new();
+        if start > self.cluster_count.saturating_add(1) {
+            log_warn!("chain starts at cluster {}, outside the volume", start);
+            return (chain, Some(FsError::CorruptFat { cluster: 0, next: start }));
+        }
+
         let mut current = start;
-        const MAX_CHAIN_LENGTH: usize = 1_000_000;
+        // Brent: `tortoise` est replacé sur la chaîne à chaque puissance de 2
+        let mut tortoise = None;
+        let mut power = 1;
+        let mut lambda = 0;
 
         loop {
             if current < 2 {
                 break;
             }
-            if chain.len() >= MAX_CHAIN_LENGTH {
+            let period = if tortoise == Some(current) {
+                Some(lambda)
+            } else if chain.len() >= max_length {
+                // Boucle pas encore détectée à la limite: `current` déjà lu en est une
+                chain.iter().rposition(|&c| c == current).map(|last| chain.len() - last)
+            } else {
+                None
+            };
+            if let Some(period) = period {
+                chain.truncate(loop_end(&chain, current, period));
+                log_warn!("chain from cluster {} loops back to cluster {}, cut after {} clusters", start, chain[chain.len() - period], chain.len());
                 break;
             }
+            if chain.len() >= max_length {
+                log_warn!("chain from cluster {} cut at the limit of {} clusters", start, max_length);
+                return (chain, Some(FsError::LimitExceeded));
+            }
+            if tortoise.is_none() || power == lambda {
+                tortoise = Some(current);
+                power *= 2;
+                lambda = 0;
+            }
+            lambda += 1;
+
+            if chain.len().is_multiple_of(CANCEL_INTERVAL) && cancel.is_some_and(|check| check()) {
+                return (chain, Some(FsError::Cancelled));
+            }
 
+            if chain.try_reserve(1).is_err() {
+                return (chain, Some(FsError::OutOfMemory));
+            }
             chain.push(current);
 
             match self.get_entry(current) {
-                FatEntry::Data(next) => {
-                    if next == current {
-                        break;
-                    }
-                    current = next;
+                FatEntry::Data(next) => current = next,
+                FatEntry::EndOfChain => break,
+                FatEntry::Invalid(next) => {
+                    log_warn!("cluster {} links to cluster {}, outside the volume", current, next);
+                    return (chain, Some(FsError::CorruptFat { cluster: current, next }));
+                }
+                entry => {
+                    log_warn!("chain from cluster {} ends on a {:?} entry at cluster {}", start, entry, current);
+                    break;
                 }
-                _ => break,
             }
         }
 
-        chain
+        (chain, None)
     }
 
     /// Compte les clusters libres dans la FAT
     pub fn count_free_clusters(&self, total_clusters: u32) -> u32 {
+        self.try_count_free_clusters(total_clusters, None).unwrap_or(0)
+    }
+
+    /// Compte les clusters libres en consultant `cancel` régulièrement
+    ///
+    /// La FAT est lue par mots de 32 bits; un secteur entièrement à zéro,
+    /// courant sur un volume peu rempli, est compté d'un bloc.
+    pub fn try_count_free_clusters(&self, total_clusters: u32, cancel: Option<&dyn Fn() -> bool>) -> Result<u32, FsError> {
         let mut count = 0;
-        for cluster in 2..total_clusters + 2 {
-            if self.get_entry(cluster).is_free() {
-                count += 1;
+        // Les clusters au-delà de la FAT ne sont jamais libres
+        let end = total_clusters.saturating_add(2).min((self.data.len() / 4) as u32) as usize;
+        let entries = self.data.get(8..end * 4).unwrap_or(&[]);
+        for block in entries.chunks(CANCEL_INTERVAL * 4) {
+            if cancel.is_some_and(|check| check()) {
+                return Err(FsError::Cancelled);
+            }
+            for sector in block.chunks(FAT_SECTOR_BYTES) {
+                count += if sector.iter().all(|&b| b == 0) {
+                    sector.len() / 4
+                } else {
+                    sector.chunks_exact(4)
+                        .filter(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]) & 0x0FFFFFFF == 0)
+                        .count()
+                };
             }
         }
-        count
+        Ok(count as u32)
     }
 }
 
+/// Longueur de `chain` suivie de `next` jusqu'au premier cluster revisité
+///
+/// La suite boucle avec une période `lambda`: le premier indice `mu` tel que
+/// le cluster `mu` revient `lambda` pas plus loin ouvre la boucle, et les
+/// `mu + lambda` premiers clusters sont tous distincts.
+fn loop_end(chain: &[u32], next: u32, lambda: usize) -> usize {
+    let at = |i: usize| chain.get(i).copied().unwrap_or(next);
+    let mu = (0..chain.len()).find(|&i| chain[i] == at(i + lambda)).unwrap_or(0);
+    mu + lambda
+}
+
+/// Compresse une chaîne en plages contiguës (cluster de début, nombre de clusters)
+pub fn cluster_runs(chain: &[u32]) -> Vec<(u32, u32)> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+
+    for &cluster in chain {
+        match runs.last_mut() {
+            Some((start, len)) if *start + *len == cluster => *len += 1,
+            _ => runs.push((cluster, 1)),
+        }
+    }
+
+    runs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_fat_entry_types() {
@@ -129,6 +267,13 @@ mod tests {
         assert_eq!(FatEntry::from_raw(0x0FFFFFF7), FatEntry::BadCluster);
         assert_eq!(FatEntry::from_raw(0x0FFFFFF8), FatEntry::EndOfChain);
         assert_eq!(FatEntry::from_raw(0x0FFFFFFF), FatEntry::EndOfChain);
+
+        // Volume de 100 clusters: les données vont de 2 à 101
+        assert_eq!(FatEntry::from_raw_checked(101, 100), FatEntry::Data(101));
+        assert_eq!(FatEntry::from_raw_checked(102, 100), FatEntry::Invalid(102));
+        assert_eq!(FatEntry::from_raw_checked(0x0FFFFFF6, 100), FatEntry::Invalid(0x0FFFFFF6));
+        assert_eq!(FatEntry::from_raw_checked(0x0FFFFFF8, 100), FatEntry::EndOfChain);
+        assert_eq!(FatEntry::from_raw_checked(0, 100), FatEntry::Free);
     }
 
     #[test]
@@ -151,5 +296,105 @@ mod tests {
         let chain = fat.get_cluster_chain(2);
 
         assert_eq!(chain, vec![2, 3, 4]);
+
+        // 4 -> 3 reboucle: la chaîne s'arrête avant de revisiter 3
+        fat_data[16..20].copy_from_slice(&3u32.to_le_bytes());
+        assert_eq!(FatTable::new(&fat_data).get_cluster_chain(2), vec![2, 3, 4]);
+        // Volume de 6 clusters (2 à 7): un premier cluster au-delà est une corruption
+        assert_eq!(FatTable::new(&fat_data).get_cluster_chain(100), vec![]);
+        assert_eq!(FatTable::new(&fat_data).try_cluster_chain(8, 10), Err(FsError::CorruptFat { cluster: 0, next: 8 }));
+        assert_eq!(FatTable::new(&fat_data).try_cluster_chain(0, 10), Ok(vec![]));
+
+        // La boucle n'est pas un dépassement de limite
+        assert_eq!(FatTable::new(&fat_data).try_cluster_chain(2, 3), Ok(vec![2, 3, 4]));
+        assert_eq!(FatTable::new(&fat_data).try_cluster_chain(2, 2), Err(FsError::LimitExceeded));
+
+        // 3 -> 6: la FAT a 8 entrées mais le volume seulement 4 clusters (2 à 5)
+        fat_data[12..16].copy_from_slice(&6u32.to_le_bytes());
+        assert_eq!(FatTable::new(&fat_data).try_cluster_chain(2, 10), Ok(vec![2, 3, 6]));
+        let fat = FatTable::with_cluster_count(&fat_data, 4);
+        assert_eq!(fat.get_entry(3), FatEntry::Invalid(6));
+        assert_eq!(fat.get_cluster_chain(2), vec![2, 3]);
+        assert_eq!(fat.try_cluster_chain(2, 10), Err(FsError::CorruptFat { cluster: 3, next: 6 }));
+    }
+
+    #[test]
+    fn test_cluster_chain_loops() {
+        // 2 -> 3 -> ... -> last -> back, pour toutes les positions de retour
+        for last in 2u32..40 {
+            for back in 2..=last {
+                let mut fat_data = vec![0u8; 4 * 48];
+                for cluster in 2..last {
+                    fat_data[cluster as usize * 4..cluster as usize * 4 + 4].copy_from_slice(&(cluster + 1).to_le_bytes());
+                }
+                fat_data[last as usize * 4..last as usize * 4 + 4].copy_from_slice(&back.to_le_bytes());
+                let fat = FatTable::new(&fat_data);
+
+                let expected: Vec<u32> = (2..=last).collect();
+                assert_eq!(fat.get_cluster_chain(2), expected, "{} -> {}", last, back);
+                // Exactement à la limite: la boucle n'est pas un dépassement
+                assert_eq!(fat.try_cluster_chain(2, expected.len()), Ok(expected.clone()));
+                assert_eq!(fat.try_cluster_chain(2, expected.len() - 1), Err(FsError::LimitExceeded));
+            }
+        }
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_chain_anomalies_are_logged() {
+        use std::string::{String, ToString};
+        use std::sync::Mutex;
+
+        struct Capture(Mutex<Vec<String>>);
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+            fn flush(&self) {}
+        }
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        // 7 -> 8 -> 7 reboucle, 9 -> 10 mène à une entrée libre
+        let mut fat_data = vec![0u8; 64];
+        fat_data[28..32].copy_from_slice(&8u32.to_le_bytes());
+        fat_data[32..36].copy_from_slice(&7u32.to_le_bytes());
+        fat_data[36..40].copy_from_slice(&10u32.to_le_bytes());
+        let fat = FatTable::new(&fat_data);
+        fat.get_cluster_chain(7);
+        fat.get_cluster_chain(9);
+
+        let messages = CAPTURE.0.lock().unwrap();
+        assert!(messages.iter().any(|m| m == "chain from cluster 7 loops back to cluster 7, cut after 2 clusters"));
+        assert!(messages.iter().any(|m| m == "chain from cluster 9 ends on a Free entry at cluster 10"));
+    }
+
+    #[test]
+    fn test_count_free_clusters() {
+        // Plusieurs secteurs: un rempli, un vide, un mélangé (bits hauts ignorés)
+        let mut fat_data = vec![0u8; 3 * 512 + 40];
+        for entry in 2..128 {
+            fat_data[entry * 4..entry * 4 + 4].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
+        }
+        fat_data[300 * 4..300 * 4 + 4].copy_from_slice(&0xF0000000u32.to_le_bytes());
+        fat_data[301 * 4..301 * 4 + 4].copy_from_slice(&7u32.to_le_bytes());
+        let fat = FatTable::new(&fat_data);
+
+        let naive = |total: u32| (2..(total + 2).min(fat_data.len() as u32 / 4)).filter(|&c| fat.get_entry(c).is_free()).count() as u32;
+        for total in [0, 1, 126, 127, 200, 390, 1000] {
+            assert_eq!(fat.count_free_clusters(total), naive(total));
+        }
+        assert_eq!(fat.try_count_free_clusters(1000, Some(&|| true)), Err(FsError::Cancelled));
+    }
+
+    #[test]
+    fn test_cluster_runs() {
+        assert_eq!(cluster_runs(&[]), vec![]);
+        assert_eq!(cluster_runs(&[2, 3, 4]), vec![(2, 3)]);
+        assert_eq!(cluster_runs(&[5, 6, 10, 3, 4]), vec![(5, 2), (10, 1), (3, 2)]);
     }
 }
@@ -0,0 +1,161 @@
+//! Fichier ouvert: lecture à une position quelconque
+//!
+//! `Fat32::open` suit la chaîne de clusters une seule fois et la garde sous
+//! forme de plages contiguës (extents). Les lectures et déplacements suivants
+//! calculent le cluster visé à partir de ces plages, sans relire la FAT.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{cluster_runs, DirEntry, Fat32, FsError};
+
+/// Fichier ouvert en lecture, avec sa position courante
+pub struct File<'f, 'a> {
+    fs: &'f Fat32<'a>,
+    entry: DirEntry,
+    /// Plages (premier cluster, nombre de clusters) de la chaîne
+    extents: Vec<(u32, u32)>,
+    /// Octets lisibles: taille de l'entrée, bornée par la chaîne
+    len: u64,
+    pos: u64,
+}
+
+impl<'a> Fat32<'a> {
+    /// Ouvre un fichier; sa chaîne est lue une fois, dans les limites de ressources
+    pub fn open(&self, entry: &DirEntry) -> Result<File<'_, 'a>, FsError> {
+        if entry.is_directory() {
+            return Err(FsError::IsADirectory);
+        }
+        if entry.size as u64 > self.limits.max_file_size {
+            return Err(FsError::LimitExceeded);
+        }
+
+        let (chain, len) = self.chain_for(entry.cluster(), Some(entry.size as usize))?;
+        Ok(File { fs: self, entry: entry.clone(), extents: cluster_runs(&chain), len: len as u64, pos: 0 })
+    }
+}
+
+impl<'f, 'a> File<'f, 'a> {
+    /// Entrée de répertoire du fichier
+    pub fn entry(&self) -> &DirEntry {
+        &self.entry
+    }
+
+    /// Nombre d'octets lisibles
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Plages de clusters (premier cluster, nombre de clusters)
+    pub fn extents(&self) -> &[(u32, u32)] {
+        &self.extents
+    }
+
+    /// Position courante
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Place la position à `pos`, bornée par la fin du fichier; retourne la nouvelle position
+    pub fn seek(&mut self, pos: u64) -> u64 {
+        self.pos = pos.min(self.len);
+        self.pos
+    }
+
+    /// Lit à partir de la position courante et l'avance; 0 en fin de fichier
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let read = self.read_at(self.pos, buf);
+        self.pos += read as u64;
+        read
+    }
+
+    /// Lit à partir de `offset` sans changer la position; 0 en fin de fichier
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> usize {
+        enter_span!(span = "file_read_at", cluster = self.entry.cluster(), offset = offset; bytes);
+        let cluster_size = self.fs.bytes_per_cluster() as u64;
+        let mut done = 0;
+
+        while done < buf.len() {
+            let at = offset + done as u64;
+            if at >= self.len {
+                break;
+            }
+            let cluster = match self.cluster_at(at / cluster_size) {
+                Some(cluster) => cluster,
+                None => break,
+            };
+            let bytes = self.fs.read_cluster(cluster);
+            let start = (at % cluster_size) as usize;
+            let take = (bytes.len().saturating_sub(start))
+                .min(buf.len() - done)
+                .min((self.len - at) as usize);
+            if take == 0 {
+                break;
+            }
+            buf[done..done + take].copy_from_slice(&bytes[start..start + take]);
+            done += take;
+        }
+
+        self.fs.stats.update(|s| s.bytes_copied += done as u64);
+        span_record!(span, bytes = done);
+        done
+    }
+
+    /// Numéro du `index`-ième cluster du fichier, retrouvé dans les plages
+    fn cluster_at(&self, mut index: u64) -> Option<u32> {
+        for &(start, count) in &self.extents {
+            if index < count as u64 {
+                return Some(start + index as u32);
+            }
+            index -= count as u64;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH, README_PATH};
+
+    #[test]
+    fn test_read_at_and_seek() {
+        let image = TestImage::default().cluster_size(512).fragmented().deep_tree(1).build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let entry = fs.resolve_path(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+        let expected = fs.read_file(&entry);
+
+        let mut file = fs.open(&entry).unwrap();
+        assert!(file.extents().len() > 1);
+        assert_eq!(file.len(), expected.len() as u64);
+
+        for offset in [0, 1, 511, 512, 1000, expected.len() as u64 - 3] {
+            let mut buf = [0u8; 700];
+            let read = file.read_at(offset, &mut buf);
+            let end = (offset as usize + 700).min(expected.len());
+            assert_eq!(&buf[..read], &expected[offset as usize..end]);
+        }
+
+        let mut data = Vec::new();
+        let mut buf = [0u8; 300];
+        loop {
+            let read = file.read(&mut buf);
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..read]);
+        }
+        assert_eq!(data, expected);
+        assert_eq!(file.seek(u64::MAX), file.len());
+        assert_eq!(file.read(&mut buf), 0);
+
+        let readme = fs.resolve_path(README_PATH, fs.root_cluster()).unwrap();
+        assert_eq!(fs.open(&readme).unwrap().extents().len(), 1);
+        let dir = fs.read_directory(fs.root_cluster()).into_iter().find(|e| e.is_directory()).unwrap();
+        assert_eq!(fs.open(&dir).err(), Some(FsError::IsADirectory));
+    }
+}
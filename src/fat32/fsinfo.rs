@@ -0,0 +1,80 @@
+//! Secteur FSInfo FAT32 - Compteur de clusters libres et indice d'allocation
+
+/// Valeur indiquant un champ FSInfo inconnu
+pub const FSINFO_UNKNOWN: u32 = 0xFFFFFFFF;
+
+/// Signature en tête du secteur ("RRaA")
+pub const FSINFO_LEAD_SIGNATURE: u32 = 0x41615252;
+/// Signature avant le compteur de clusters libres ("rrAa")
+pub const FSINFO_STRUCT_SIGNATURE: u32 = 0x61417272;
+/// Signature en fin de secteur
+pub const FSINFO_TRAIL_SIGNATURE: u32 = 0xAA550000;
+
+/// Structure du secteur FSInfo
+#[derive(Debug, Clone)]
+pub struct FsInfo {
+    pub lead_signature: u32,
+    pub struct_signature: u32,
+    pub free_count: u32,
+    pub next_free: u32,
+    pub trail_signature: u32,
+}
+
+impl FsInfo {
+    /// Parse le secteur FSInfo depuis 512 octets bruts
+    pub fn from_bytes(data: &[u8; 512]) -> Self {
+        FsInfo {
+            lead_signature: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            struct_signature: u32::from_le_bytes([data[484], data[485], data[486], data[487]]),
+            free_count: u32::from_le_bytes([data[488], data[489], data[490], data[491]]),
+            next_free: u32::from_le_bytes([data[492], data[493], data[494], data[495]]),
+            trail_signature: u32::from_le_bytes([data[508], data[509], data[510], data[511]]),
+        }
+    }
+
+    /// Vérifie les trois signatures du secteur
+    pub fn is_valid(&self) -> bool {
+        self.lead_signature == FSINFO_LEAD_SIGNATURE
+            && self.struct_signature == FSINFO_STRUCT_SIGNATURE
+            && self.trail_signature == FSINFO_TRAIL_SIGNATURE
+    }
+
+    /// Compteur de clusters libres, s'il est connu et plausible pour `cluster_count` clusters
+    pub fn free_clusters(&self, cluster_count: u32) -> Option<u32> {
+        if !self.is_valid() || self.free_count == FSINFO_UNKNOWN || self.free_count > cluster_count {
+            return None;
+        }
+        Some(self.free_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fsinfo_parsing() {
+        let mut data = [0u8; 512];
+        data[0..4].copy_from_slice(&0x41615252u32.to_le_bytes());
+        data[484..488].copy_from_slice(&0x61417272u32.to_le_bytes());
+        data[488..492].copy_from_slice(&1000u32.to_le_bytes());
+        data[492..496].copy_from_slice(&FSINFO_UNKNOWN.to_le_bytes());
+        data[508..512].copy_from_slice(&0xAA550000u32.to_le_bytes());
+
+        let info = FsInfo::from_bytes(&data);
+        assert_eq!(info.lead_signature, 0x41615252);
+        assert_eq!(info.struct_signature, 0x61417272);
+        assert_eq!(info.free_count, 1000);
+        assert_eq!(info.next_free, FSINFO_UNKNOWN);
+        assert_eq!(info.trail_signature, 0xAA550000);
+        assert_eq!(info.free_clusters(1000), Some(1000));
+        assert_eq!(info.free_clusters(999), None);
+
+        data[488..492].copy_from_slice(&FSINFO_UNKNOWN.to_le_bytes());
+        assert_eq!(FsInfo::from_bytes(&data).free_clusters(1000), None);
+        data[488..492].copy_from_slice(&10u32.to_le_bytes());
+        data[510] = 0;
+        assert!(!FsInfo::from_bytes(&data).is_valid());
+        assert_eq!(FsInfo::from_bytes(&data).free_clusters(1000), None);
+    }
+}
@@ -0,0 +1,241 @@
+//! Montage FUSE en lecture seule (feature fuse, Linux et macOS)
+//!
+//! `FuseImage` garde l'image en mémoire et répond aux requêtes du noyau avec
+//! l'API par inodes (`node_lookup`, `node_children`, `node_read`): les
+//! numéros d'inode sont les siens, `ROOT_INODE` valant celui de la racine
+//! FUSE. Le montage est en lecture seule, quelle que soit l'image.
+
+extern crate alloc;
+extern crate std;
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fuser::{FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, ReplyStatfs, Request};
+use libc::{EIO, EISDIR, ENOENT, ENOTDIR};
+
+use super::inode::{NodeAttr, NodeKind, ROOT_INODE};
+use super::{Fat32, FatDateTime, FsError};
+
+/// Durée pendant laquelle le noyau garde attributs et entrées: l'image ne change pas
+const TTL: Duration = Duration::from_secs(60);
+
+/// Image FAT32 prête à être montée avec `mount`
+pub struct FuseImage {
+    data: Vec<u8>,
+    /// Décalage de l'heure locale des dates FAT sur UTC, en secondes
+    utc_offset: i32,
+    uid: u32,
+    gid: u32,
+}
+
+impl FuseImage {
+    /// Image à monter; erreur si ce n'est pas du FAT32
+    pub fn new(data: Vec<u8>) -> Result<Self, FsError> {
+        Fat32::try_new(&data)?;
+        Ok(FuseImage { data, utc_offset: 0, uid: 0, gid: 0 })
+    }
+
+    /// Dates FAT écrites à l'heure locale décalée de `seconds` sur UTC
+    pub fn with_utc_offset(mut self, seconds: i32) -> Self {
+        self.utc_offset = seconds;
+        self
+    }
+
+    /// Propriétaire affiché pour tous les fichiers (FAT n'en a pas)
+    pub fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
+    }
+
+    /// Filesystem sur l'image, déjà validée par `new`
+    fn fs(&self) -> Option<Fat32<'_>> {
+        Fat32::new(&self.data)
+    }
+
+    /// Instant d'une date FAT; l'époque Unix pour une date jamais écrite
+    fn time(&self, date: FatDateTime, millis: u16) -> SystemTime {
+        match date.to_unix_seconds(self.utc_offset).and_then(|s| u64::try_from(s).ok()) {
+            Some(seconds) => UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_millis(millis as u64),
+            None => UNIX_EPOCH,
+        }
+    }
+
+    /// Attributs FUSE d'un nœud
+    fn file_attr(&self, attr: &NodeAttr, block_size: u32) -> FileAttr {
+        let (kind, perm, nlink) = match attr.kind {
+            NodeKind::Directory => (FileType::Directory, 0o555, 2),
+            NodeKind::File => (FileType::RegularFile, 0o444, 1),
+        };
+        let modified = self.time(attr.modified, 0);
+        FileAttr {
+            ino: attr.ino,
+            size: attr.size,
+            blocks: attr.size.div_ceil(512),
+            atime: self.time(attr.accessed, 0),
+            mtime: modified,
+            ctime: modified,
+            crtime: self.time(attr.created, attr.created_millis),
+            kind,
+            perm,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: block_size,
+            flags: 0,
+        }
+    }
+
+    /// Entrées du répertoire `ino` pour `readdir`, `.` et `..` en tête
+    fn dir_entries(&self, fs: &Fat32<'_>, ino: u64) -> Result<Vec<(u64, FileType, String)>, i32> {
+        let children = match fs.node_children(ino) {
+            Some(children) => children,
+            None if fs.node_attr(ino).is_some() => return Err(ENOTDIR),
+            None => return Err(ENOENT),
+        };
+        let parent = fs.node_lookup(ino, "..").map_or(ROOT_INODE, |attr| attr.ino);
+
+        let mut entries = Vec::with_capacity(children.len() + 2);
+        entries.push((ino, FileType::Directory, String::from(".")));
+        entries.push((parent, FileType::Directory, String::from("..")));
+        for (attr, name) in children {
+            let kind = match attr.kind {
+                NodeKind::Directory => FileType::Directory,
+                NodeKind::File => FileType::RegularFile,
+            };
+            entries.push((attr.ino, kind, name));
+        }
+        Ok(entries)
+    }
+}
+
+impl Filesystem for FuseImage {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let fs = match self.fs() {
+            Some(fs) => fs,
+            None => return reply.error(EIO),
+        };
+        match name.to_str().and_then(|name| fs.node_lookup(parent, name)) {
+            Some(attr) => reply.entry(&TTL, &self.file_attr(&attr, fs.bytes_per_cluster()), 0),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        let fs = match self.fs() {
+            Some(fs) => fs,
+            None => return reply.error(EIO),
+        };
+        match fs.node_attr(ino) {
+            Some(attr) => reply.attr(&TTL, &self.file_attr(&attr, fs.bytes_per_cluster())),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let fs = match self.fs() {
+            Some(fs) => fs,
+            None => return reply.error(EIO),
+        };
+        match fs.node_read(ino, offset.max(0) as u64, size as usize) {
+            Some(data) => reply.data(&data),
+            None if fs.node_attr(ino).is_some() => reply.error(EISDIR),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn readdir(&mut self, _req: &Request<'_>, ino: u64, _fh: u64, offset: i64, mut reply: ReplyDirectory) {
+        let entries = match self.fs() {
+            Some(fs) => self.dir_entries(&fs, ino),
+            None => Err(EIO),
+        };
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(errno) => return reply.error(errno),
+        };
+
+        // `offset` est celui de la dernière entrée déjà rendue
+        for (i, (ino, kind, name)) in entries.into_iter().enumerate().skip(offset.max(0) as usize) {
+            if reply.add(ino, i as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn statfs(&mut self, _req: &Request<'_>, _ino: u64, reply: ReplyStatfs) {
+        let fs = match self.fs() {
+            Some(fs) => fs,
+            None => return reply.error(EIO),
+        };
+        let cluster_size = fs.bytes_per_cluster();
+        let free = fs.free_space() / cluster_size as u64;
+        reply.statfs(fs.cluster_count() as u64, free, free, 0, 0, cluster_size, 255, cluster_size);
+    }
+}
+
+/// Monte `image` en lecture seule sur `mountpoint`, jusqu'au démontage (`fusermount -u`)
+pub fn mount(image: FuseImage, mountpoint: &Path) -> io::Result<()> {
+    let options = [
+        MountOption::RO,
+        MountOption::FSName(String::from("fat32")),
+        MountOption::Subtype(String::from("fat32")),
+        MountOption::DefaultPermissions,
+    ];
+    fuser::mount2(image, mountpoint, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, README_PATH, README_TEXT};
+
+    #[test]
+    fn test_attrs_and_listing() {
+        let image = FuseImage::new(TestImage::default().long_names().build().unwrap()).unwrap().with_owner(1000, 100);
+        let fs = image.fs().unwrap();
+
+        let root = image.dir_entries(&fs, ROOT_INODE).unwrap();
+        let names: Vec<&str> = root.iter().map(|(_, _, name)| name.as_str()).collect();
+        assert_eq!(names, [".", "..", "README.TXT", "Long File Name.txt", "My Documents"]);
+        assert_eq!((root[0].0, root[1].0), (ROOT_INODE, ROOT_INODE));
+
+        let docs = fs.node_lookup(ROOT_INODE, "My Documents").unwrap();
+        let listing = image.dir_entries(&fs, docs.ino).unwrap();
+        assert_eq!((listing[0].0, listing[1].0), (docs.ino, ROOT_INODE));
+        assert_eq!(listing[2].2, "notes about fat32.md");
+
+        let readme = fs.node_lookup(ROOT_INODE, &README_PATH[1..]).unwrap();
+        let attr = image.file_attr(&readme, fs.bytes_per_cluster());
+        assert_eq!((attr.kind, attr.perm, attr.nlink), (FileType::RegularFile, 0o444, 1));
+        assert_eq!((attr.size, attr.uid, attr.gid), (README_TEXT.len() as u64, 1000, 100));
+        assert_eq!(image.dir_entries(&fs, readme.ino), Err(ENOTDIR));
+        assert_eq!(image.dir_entries(&fs, readme.ino + 1), Err(ENOENT));
+
+        assert!(FuseImage::new(alloc::vec![0u8; 4096]).is_err());
+    }
+
+    #[test]
+    fn test_fat_dates_to_system_time() {
+        let image = FuseImage::new(TestImage::default().build().unwrap()).unwrap().with_utc_offset(3600);
+        let date = FatDateTime { year: 2024, month: 3, day: 1, hour: 13, minute: 0, second: 0 };
+        // 2024-03-01 13:00 à UTC+1 = 12:00 UTC
+        assert_eq!(image.time(date, 250), UNIX_EPOCH + Duration::from_millis(1_709_294_400_250));
+        assert_eq!(image.time(FatDateTime::from_raw(0, 0), 0), UNIX_EPOCH);
+    }
+}
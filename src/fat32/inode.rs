@@ -0,0 +1,217 @@
+//! Accès par numéros d'inode, la forme attendue par un montage FUSE
+//!
+//! FAT32 n'a pas d'inodes: le numéro d'une entrée est la position de son
+//! entrée courte dans l'image, stable tant que l'entrée n'est pas déplacée.
+//! La racine, qui n'a pas d'entrée, porte le numéro `ROOT_INODE`.
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{DirEntry, Fat32, FatDateTime};
+
+/// Numéro d'inode de la racine (celui de FUSE)
+pub const ROOT_INODE: u64 = 1;
+
+/// Type d'un nœud
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    File,
+    Directory,
+}
+
+/// Attributs d'un nœud (équivalent de `stat`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeAttr {
+    pub ino: u64,
+    pub kind: NodeKind,
+    pub size: u64,
+    /// Premier cluster (celui du répertoire racine pour la racine)
+    pub cluster: u32,
+    pub modified: FatDateTime,
+    pub created: FatDateTime,
+    /// Millisecondes de la création au-delà de `created`
+    pub created_millis: u16,
+    /// Date du dernier accès, à minuit
+    pub accessed: FatDateTime,
+    pub read_only: bool,
+}
+
+impl<'a> Fat32<'a> {
+    /// Attributs de l'inode `ino`
+    pub fn node_attr(&self, ino: u64) -> Option<NodeAttr> {
+        if ino == ROOT_INODE {
+            return Some(self.root_attr());
+        }
+
+        // Les entrées sont alignées sur 32 octets
+        if !ino.is_multiple_of(32) {
+            return None;
+        }
+        let start = usize::try_from(ino).ok()?;
+        let raw = self.disk_data().get(start..start.checked_add(32)?)?;
+        let entry = DirEntry::from_bytes(raw)?;
+        // Un numéro qui ne désigne pas une entrée valide est refusé
+        if raw[0] == 0x00 || raw[0] == super::undelete::DELETED_MARKER || entry.is_long_name() {
+            return None;
+        }
+        Some(self.entry_attr(ino, &entry))
+    }
+
+    /// Cherche `name` dans le répertoire `parent` (insensible à la casse)
+    pub fn node_lookup(&self, parent: u64, name: &str) -> Option<NodeAttr> {
+        let dir = self.node_dir_cluster(parent)?;
+        match name {
+            "." => self.node_attr(parent),
+            ".." => self.node_parent(dir),
+            _ => {
+                let location = self.locate_entry(dir, name)?;
+                Some(self.entry_attr(location.short_offset(), &location.entry))
+            }
+        }
+    }
+
+    /// Contenu du répertoire `ino` avec les noms affichés (longs si présents), sans `.` ni `..`
+    pub fn node_children(&self, ino: u64) -> Option<Vec<(NodeAttr, String)>> {
+        let dir = self.node_dir_cluster(ino)?;
+        let children = self.scan_directory(dir)
+            .into_iter()
+            .filter(|(location, _)| !location.entry.is_dot() && !location.entry.is_dotdot())
+            .map(|(location, long_name)| {
+                let name = if long_name.is_empty() { location.entry.display_name() } else { long_name };
+                (self.entry_attr(location.short_offset(), &location.entry), name)
+            })
+            .collect();
+        Some(children)
+    }
+
+    /// Lit au plus `len` octets du fichier `ino` à partir de `offset`
+    ///
+    /// Seuls les clusters couvrant la plage demandée sont copiés.
+    pub fn node_read(&self, ino: u64, offset: u64, len: usize) -> Option<Vec<u8>> {
+        let attr = self.node_attr(ino)?;
+        if attr.kind != NodeKind::File {
+            return None;
+        }
+        if offset >= attr.size || len == 0 {
+            return Some(Vec::new());
+        }
+
+        let end = attr.size.min(offset.saturating_add(len as u64));
+        let bytes_per_cluster = self.bytes_per_cluster() as u64;
+        let first = (offset / bytes_per_cluster) as usize;
+        let last = ((end - 1) / bytes_per_cluster) as usize;
+
+        let mut data = Vec::with_capacity((end - offset) as usize);
+        for (i, cluster) in self.cluster_chain(attr.cluster).into_iter().enumerate().take(last + 1).skip(first) {
+            let base = i as u64 * bytes_per_cluster;
+            let from = offset.saturating_sub(base) as usize;
+            let to = (end - base).min(bytes_per_cluster) as usize;
+            data.extend_from_slice(self.read_cluster(cluster).get(from..to)?);
+        }
+        Some(data)
+    }
+
+    fn root_attr(&self) -> NodeAttr {
+        NodeAttr {
+            ino: ROOT_INODE,
+            kind: NodeKind::Directory,
+            size: 0,
+            cluster: self.root_cluster(),
+            modified: FatDateTime::from_raw(0, 0),
+            created: FatDateTime::from_raw(0, 0),
+            created_millis: 0,
+            accessed: FatDateTime::from_raw(0, 0),
+            read_only: self.is_read_only(),
+        }
+    }
+
+    fn entry_attr(&self, ino: u64, entry: &DirEntry) -> NodeAttr {
+        // `..` vers la racine et les répertoires à cluster 0 désignent la racine
+        if entry.is_directory() && entry.cluster() == 0 {
+            return self.root_attr();
+        }
+        NodeAttr {
+            ino,
+            kind: if entry.is_directory() { NodeKind::Directory } else { NodeKind::File },
+            size: if entry.is_directory() { 0 } else { entry.size as u64 },
+            cluster: entry.cluster(),
+            modified: entry.modified(),
+            created: entry.created(),
+            created_millis: entry.created_millis(),
+            accessed: entry.accessed(),
+            read_only: self.is_read_only() || entry.is_read_only(),
+        }
+    }
+
+    /// Cluster du répertoire `ino`
+    fn node_dir_cluster(&self, ino: u64) -> Option<u32> {
+        let attr = self.node_attr(ino)?;
+        (attr.kind == NodeKind::Directory).then_some(attr.cluster)
+    }
+
+    /// Nœud parent du répertoire `dir`, retrouvé via son entrée `..`
+    fn node_parent(&self, dir: u32) -> Option<NodeAttr> {
+        if dir == self.root_cluster() {
+            return Some(self.root_attr());
+        }
+        let dotdot = self.read_directory(dir).into_iter().find(|e| e.is_dotdot())?;
+        let parent = if dotdot.cluster() == 0 { self.root_cluster() } else { dotdot.cluster() };
+        if parent == self.root_cluster() {
+            return Some(self.root_attr());
+        }
+
+        // L'inode du parent est la position de son entrée dans le grand-parent
+        let grandparent = self.read_directory(parent).into_iter().find(|e| e.is_dotdot())?;
+        let grandparent = if grandparent.cluster() == 0 { self.root_cluster() } else { grandparent.cluster() };
+        self.scan_directory(grandparent)
+            .into_iter()
+            .find(|(location, _)| !location.entry.is_dot() && location.entry.cluster() == parent)
+            .map(|(location, _)| self.entry_attr(location.short_offset(), &location.entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH};
+
+    #[test]
+    fn test_lookup_and_children() {
+        let image = TestImage::default().long_names().deep_tree(2).build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+
+        let names: Vec<String> = fs.node_children(ROOT_INODE).unwrap().into_iter().map(|(_, n)| n).collect();
+        assert_eq!(names, ["README.TXT", "Long File Name.txt", "My Documents", "DEEP"]);
+
+        let docs = fs.node_lookup(ROOT_INODE, "my documents").unwrap();
+        assert_eq!(docs.kind, NodeKind::Directory);
+        assert_eq!(fs.node_attr(docs.ino), Some(docs.clone()));
+        let notes = fs.node_lookup(docs.ino, "notes about fat32.md").unwrap();
+        assert_eq!(notes.size, 8);
+        assert_eq!(fs.node_lookup(docs.ino, "..").unwrap().ino, ROOT_INODE);
+
+        let d1 = fs.node_lookup(fs.node_lookup(ROOT_INODE, "DEEP").unwrap().ino, "D1").unwrap();
+        let d2 = fs.node_lookup(d1.ino, "D2").unwrap();
+        assert_eq!(fs.node_lookup(d2.ino, "..").unwrap(), d1);
+
+        assert!(fs.node_lookup(ROOT_INODE, "MISSING").is_none());
+        assert!(fs.node_children(notes.ino).is_none());
+        assert!(fs.node_attr(notes.ino + 1).is_none());
+    }
+
+    #[test]
+    fn test_read_ranges() {
+        let spec = TestImage::default().fragmented();
+        let image = spec.build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let expected = spec.fragmented_data();
+
+        let frag = fs.node_lookup(ROOT_INODE, &FRAGMENTED_PATH[1..]).unwrap();
+        assert_eq!(fs.node_read(frag.ino, 0, usize::MAX / 2).unwrap(), expected);
+        assert_eq!(fs.node_read(frag.ino, 500, 100).unwrap(), expected[500..600]);
+        assert_eq!(fs.node_read(frag.ino, 1020, 2000).unwrap(), expected[1020..]);
+        assert!(fs.node_read(frag.ino, 5000, 10).unwrap().is_empty());
+        assert!(fs.node_read(ROOT_INODE, 0, 10).is_none());
+    }
+}
@@ -0,0 +1,69 @@
+//! Limites de ressources appliquées à la lecture d'une image
+//!
+//! Une image corrompue ou malveillante peut décrire des chaînes de clusters
+//! immenses, des répertoires sans fin ou des chemins très profonds. Ces
+//! limites bornent le travail et la mémoire d'une lecture; un dépassement
+//! est signalé par `FsError::LimitExceeded`.
+
+/// Bornes consultées lors du parcours des chaînes, répertoires et chemins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Nombre maximal de clusters d'une chaîne
+    pub max_chain_length: usize,
+    /// Nombre maximal d'entrées lues dans un répertoire
+    pub max_dir_entries: usize,
+    /// Taille maximale d'un fichier chargé en mémoire
+    pub max_file_size: u64,
+    /// Nombre maximal de composants d'un chemin
+    pub max_path_depth: usize,
+}
+
+impl Default for Limits {
+    /// Limites larges: aucune image conforme ne les atteint
+    fn default() -> Self {
+        Limits {
+            max_chain_length: 1_000_000,
+            // Maximum de la spécification FAT
+            max_dir_entries: 65_536,
+            max_file_size: u32::MAX as u64,
+            max_path_depth: 256,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::{Fat32, FsError};
+    use crate::testimg::{TestImage, FRAGMENTED_CLUSTERS, FRAGMENTED_PATH, README_PATH};
+
+    #[test]
+    fn test_limits_exceeded() {
+        let test = TestImage::default().fragmented().deep_tree(4);
+        let image = test.build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let root = fs.root_cluster();
+        let frag = fs.resolve_path(FRAGMENTED_PATH, root).unwrap();
+        let leaf = test.leaf_path();
+        assert!(fs.try_resolve_path(&leaf, root).is_ok());
+
+        let limits = Limits {
+            max_chain_length: FRAGMENTED_CLUSTERS - 1,
+            max_dir_entries: 2,
+            max_file_size: 8,
+            max_path_depth: 3,
+        };
+        let fs = fs.with_limits(limits);
+        assert_eq!(fs.try_cluster_chain(frag.cluster()), Err(FsError::LimitExceeded));
+        assert_eq!(fs.cluster_chain(frag.cluster()).len(), FRAGMENTED_CLUSTERS - 1);
+        assert_eq!(fs.try_read_directory(root).unwrap_err(), FsError::LimitExceeded);
+        assert_eq!(fs.try_resolve_path(&leaf, root).unwrap_err(), FsError::LimitExceeded);
+        assert!(fs.resolve_path(&leaf, root).is_none());
+
+        let fs = fs.with_limits(Limits { max_file_size: 8, ..Limits::default() });
+        let readme = fs.resolve_path(README_PATH, root).unwrap();
+        assert_eq!(fs.try_read_file(&readme), Err(FsError::LimitExceeded));
+        assert!(fs.read_file(&readme).is_empty());
+        assert_eq!(fs.try_resolve_path("/MISSING.TXT", root).unwrap_err(), FsError::NotFound);
+    }
+}
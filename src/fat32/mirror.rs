@@ -0,0 +1,114 @@
+//! Comparaison des copies de la FAT
+//!
+//! Les écritures passent par toutes les copies (`write_fat_entry`) mais les
+//! lectures ne consultent que la première: une seconde copie qui diverge
+//! reste invisible. Une écriture interrompue ou un outil qui n'a mis à jour
+//! qu'une copie laisse ce genre de trace, souvent le premier signe d'une
+//! corruption.
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{byte_range, Fat32, FsError};
+
+/// Bits utiles d'une entrée FAT32 (les 4 bits de poids fort sont réservés)
+const ENTRY_MASK: u32 = 0x0FFFFFFF;
+
+/// Entrée qui diffère entre la première FAT et une autre copie
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FatMismatch {
+    /// Numéro de la copie comparée à la FAT #0 (1 pour la seconde)
+    pub copy: u8,
+    pub cluster: u32,
+    /// Valeur dans la FAT #0
+    pub primary: u32,
+    /// Valeur dans la copie `copy`
+    pub mirror: u32,
+}
+
+impl fmt::Display for FatMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cluster {}: FAT #0 has 0x{:08X}, FAT #{} has 0x{:08X}",
+            self.cluster, self.primary, self.copy, self.mirror
+        )
+    }
+}
+
+impl<'a> Fat32<'a> {
+    /// Compare la FAT #0 à chacune des autres copies, entrée par entrée
+    ///
+    /// Seules les entrées des clusters 2 à `cluster_count + 1` sont comparées
+    /// (les entrées 0 et 1 portent le type de média et des drapeaux d'état),
+    /// sur leurs 28 bits utiles. Liste vide si les copies concordent ou s'il
+    /// n'y a qu'une FAT; `OutOfBounds` si une copie dépasse l'image.
+    pub fn verify_fat_mirrors(&self) -> Result<Vec<FatMismatch>, FsError> {
+        let bytes_per_sector = self.boot_sector.bytes_per_sector as usize;
+        let entries = (self.cluster_count() as u64 + 2).min(self.boot_sector.fat_bytes() / 4);
+        let data = self.disk_data();
+
+        let copy_at = |copy: u8| {
+            let range = byte_range(self.boot_sector.fat_offset(copy), entries * 4);
+            range.and_then(|range| data.get(range)).ok_or(FsError::OutOfBounds)
+        };
+        let primary = copy_at(0)?;
+
+        let mut mismatches = Vec::new();
+        for copy in 1..self.boot_sector.fat_count {
+            let mirror = copy_at(copy)?;
+            // Comparaison par secteur, entrée par entrée seulement s'il diffère
+            let sectors = primary.chunks(bytes_per_sector).zip(mirror.chunks(bytes_per_sector));
+            for (index, (a, b)) in sectors.enumerate() {
+                self.check_cancel()?;
+                if a == b {
+                    continue;
+                }
+                let first = index * bytes_per_sector / 4;
+                for (i, (a, b)) in a.chunks_exact(4).zip(b.chunks_exact(4)).enumerate() {
+                    let cluster = (first + i) as u32;
+                    let primary = u32::from_le_bytes([a[0], a[1], a[2], a[3]]) & ENTRY_MASK;
+                    let mirror = u32::from_le_bytes([b[0], b[1], b[2], b[3]]) & ENTRY_MASK;
+                    if cluster >= 2 && primary != mirror {
+                        mismatches.push(FatMismatch { copy, cluster, primary, mirror });
+                    }
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, README_PATH};
+
+    #[test]
+    fn test_verify_fat_mirrors() {
+        let mut image = TestImage::default().fragmented().build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        assert_eq!(fs.verify_fat_mirrors().unwrap(), Vec::new());
+        let cluster = fs.resolve_path(README_PATH, fs.root_cluster()).unwrap().cluster();
+        let bs = fs.boot_sector().clone();
+
+        // Seconde copie modifiée à la main: libre au lieu de fin de chaîne
+        let second = bs.fat_offset(1) as usize;
+        let at = second + cluster as usize * 4;
+        image[at..at + 4].copy_from_slice(&0u32.to_le_bytes());
+        // Bits réservés et entrée 1 ignorés
+        image[second + 7] ^= 0x40;
+        image[second + 3 * 4 + 3] ^= 0x10;
+
+        let fs = Fat32::new(&image).unwrap();
+        let mismatches = fs.verify_fat_mirrors().unwrap();
+        assert_eq!(mismatches, [FatMismatch { copy: 1, cluster, primary: 0x0FFFFFFF, mirror: 0 }]);
+        assert_eq!(
+            mismatches[0].to_string(),
+            alloc::format!("cluster {}: FAT #0 has 0x0FFFFFFF, FAT #1 has 0x00000000", cluster)
+        );
+        // Les lectures ne voient que la première copie
+        assert!(fs.resolve_path(README_PATH, fs.root_cluster()).is_some());
+    }
+}
@@ -0,0 +1,238 @@
+//! Formatage d'une image FAT32 (équivalent minimal de mkfs.fat)
+
+use super::{Fat32, FsError, BootSector, ATTR_VOLUME_ID};
+use super::fsinfo::{FSINFO_LEAD_SIGNATURE, FSINFO_STRUCT_SIGNATURE, FSINFO_TRAIL_SIGNATURE};
+
+/// Taille de secteur utilisée pour le formatage
+const SECTOR_SIZE: usize = 512;
+/// Secteurs réservés (boot sector, FSInfo, copies de secours)
+const RESERVED_SECTORS: u16 = 32;
+/// Secteur de la copie de secours du boot sector
+const BACKUP_BOOT_SECTOR: u16 = 6;
+/// Label d'un volume sans nom
+const NO_NAME: [u8; 11] = *b"NO NAME    ";
+
+/// Options de formatage
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Nombre de secteurs par cluster (puissance de deux, 1..=128)
+    pub sectors_per_cluster: u8,
+    /// Nombre de copies de la FAT
+    pub fat_count: u8,
+    /// Label du volume (11 caractères max, vide = "NO NAME")
+    pub label: [u8; 11],
+    /// Numéro de série du volume
+    pub volume_serial: u32,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            sectors_per_cluster: 1,
+            fat_count: 2,
+            label: NO_NAME,
+            volume_serial: 0,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Définit la taille de cluster en octets
+    pub fn with_cluster_size(mut self, bytes: u32) -> Result<Self, FsError> {
+        let sectors = bytes / SECTOR_SIZE as u32;
+        if !bytes.is_multiple_of(SECTOR_SIZE as u32) || !sectors.is_power_of_two() || sectors > 128 {
+            return Err(FsError::InvalidGeometry);
+        }
+        self.sectors_per_cluster = sectors as u8;
+        Ok(self)
+    }
+
+    /// Définit le label du volume (converti en majuscules, complété par des espaces)
+    ///
+    /// Un label vide ou fait d'espaces donne "NO NAME".
+    pub fn with_label(mut self, label: &str) -> Result<Self, FsError> {
+        if label.len() > 11 || !label.bytes().all(|b| b.is_ascii_graphic() || b == b' ') {
+            return Err(FsError::InvalidName);
+        }
+        if label.trim().is_empty() {
+            self.label = NO_NAME;
+            return Ok(self);
+        }
+        self.label = [b' '; 11];
+        for (dst, src) in self.label.iter_mut().zip(label.bytes()) {
+            *dst = src.to_ascii_uppercase();
+        }
+        Ok(self)
+    }
+}
+
+/// Calcule le nombre de secteurs par FAT (formule de la spécification Microsoft)
+fn sectors_per_fat(total_sectors: u32, options: &FormatOptions) -> u32 {
+    let tmp1 = total_sectors - RESERVED_SECTORS as u32;
+    let tmp2 = (256 * options.sectors_per_cluster as u32 + options.fat_count as u32) / 2;
+    tmp1.div_ceil(tmp2)
+}
+
+/// Secteurs par FAT, premier secteur de données et nombre de clusters d'un volume
+fn layout(total_sectors: u32, options: &FormatOptions) -> Result<(u32, u32, u32), FsError> {
+    let spc = options.sectors_per_cluster;
+    if spc == 0 || !spc.is_power_of_two() || spc > 128 || options.fat_count == 0 {
+        return Err(FsError::InvalidGeometry);
+    }
+    if total_sectors <= RESERVED_SECTORS as u32 {
+        return Err(FsError::InvalidGeometry);
+    }
+
+    let fat_sectors = sectors_per_fat(total_sectors, options);
+    let data_start = (options.fat_count as u32)
+        .checked_mul(fat_sectors)
+        .and_then(|fats| fats.checked_add(RESERVED_SECTORS as u32))
+        .ok_or(FsError::InvalidGeometry)?;
+    let clusters = total_sectors.saturating_sub(data_start) / spc as u32;
+    if clusters < 1 {
+        return Err(FsError::InvalidGeometry);
+    }
+    Ok((fat_sectors, data_start, clusters))
+}
+
+/// Formate une image brute en FAT32 (formatage rapide, les données ne sont pas effacées)
+pub fn format(image: &mut [u8], options: &FormatOptions) -> Result<(), FsError> {
+    let total_sectors = u32::try_from(image.len() / SECTOR_SIZE)
+        .map_err(|_| FsError::InvalidGeometry)?;
+    let (fat_sectors, data_start, clusters) = layout(total_sectors, options)?;
+    let spc = options.sectors_per_cluster;
+    let label = match options.label {
+        label if label.iter().all(|&b| b == b' ') => NO_NAME,
+        label => label,
+    };
+
+    // Zone réservée + FATs + cluster racine remis à zéro
+    let root_end = (data_start as usize + spc as usize) * SECTOR_SIZE;
+    image[..root_end].fill(0);
+
+    let boot = &mut image[..SECTOR_SIZE];
+    boot[0..3].copy_from_slice(&[0xEB, 0x58, 0x90]);
+    boot[3..11].copy_from_slice(b"MSWIN4.1");
+    boot[11..13].copy_from_slice(&(SECTOR_SIZE as u16).to_le_bytes());
+    boot[13] = spc;
+    boot[14..16].copy_from_slice(&RESERVED_SECTORS.to_le_bytes());
+    boot[16] = options.fat_count;
+    boot[21] = 0xF8;
+    boot[24..26].copy_from_slice(&63u16.to_le_bytes());
+    boot[26..28].copy_from_slice(&255u16.to_le_bytes());
+    boot[32..36].copy_from_slice(&total_sectors.to_le_bytes());
+    boot[36..40].copy_from_slice(&fat_sectors.to_le_bytes());
+    boot[44..48].copy_from_slice(&2u32.to_le_bytes());
+    boot[48..50].copy_from_slice(&1u16.to_le_bytes());
+    boot[50..52].copy_from_slice(&BACKUP_BOOT_SECTOR.to_le_bytes());
+    boot[64] = 0x80;
+    boot[66] = 0x29;
+    boot[67..71].copy_from_slice(&options.volume_serial.to_le_bytes());
+    boot[71..82].copy_from_slice(&label);
+    boot[82..90].copy_from_slice(b"FAT32   ");
+    boot[510] = 0x55;
+    boot[511] = 0xAA;
+
+    // FSInfo: le cluster 2 (racine) est alloué, le prochain libre est le 3
+    let fsinfo = &mut image[SECTOR_SIZE..2 * SECTOR_SIZE];
+    fsinfo[0..4].copy_from_slice(&FSINFO_LEAD_SIGNATURE.to_le_bytes());
+    fsinfo[484..488].copy_from_slice(&FSINFO_STRUCT_SIGNATURE.to_le_bytes());
+    fsinfo[488..492].copy_from_slice(&(clusters - 1).to_le_bytes());
+    fsinfo[492..496].copy_from_slice(&3u32.to_le_bytes());
+    fsinfo[508..512].copy_from_slice(&FSINFO_TRAIL_SIGNATURE.to_le_bytes());
+
+    // Copies de secours du boot sector et du FSInfo
+    let backup = BACKUP_BOOT_SECTOR as usize * SECTOR_SIZE;
+    image.copy_within(0..2 * SECTOR_SIZE, backup);
+
+    for i in 0..options.fat_count as usize {
+        let fat = (RESERVED_SECTORS as usize + i * fat_sectors as usize) * SECTOR_SIZE;
+        image[fat..fat + 4].copy_from_slice(&0x0FFFFFF8u32.to_le_bytes());
+        image[fat + 4..fat + 8].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
+        image[fat + 8..fat + 12].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
+    }
+
+    if label != NO_NAME {
+        let root = data_start as usize * SECTOR_SIZE;
+        image[root..root + 11].copy_from_slice(&label);
+        image[root + 11] = ATTR_VOLUME_ID;
+    }
+
+    Ok(())
+}
+
+impl<'a> Fat32<'a> {
+    /// Reformate le filesystem monté en écriture
+    pub fn format(&mut self, options: &FormatOptions) -> Result<(), FsError> {
+        let data = self.disk_data_mut()?;
+        format(data, options)?;
+
+        let boot_bytes: [u8; 512] = data[0..512].try_into().map_err(|_| FsError::OutOfBounds)?;
+        self.boot_sector = BootSector::from_bytes(&boot_bytes).ok_or(FsError::InvalidGeometry)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_format_produces_mountable_image() {
+        let mut image = vec![0xAAu8; 1024 * 1024];
+        let options = FormatOptions::default().with_label("TESTVOL").unwrap();
+        format(&mut image, &options).unwrap();
+
+        let fs = Fat32::new(&image).unwrap();
+        assert_eq!(fs.root_cluster(), 2);
+        assert_eq!(fs.boot_sector().total_sectors, 2048);
+        assert_eq!(fs.boot_sector().label(), "TESTVOL");
+        assert!(fs.read_directory(fs.root_cluster()).is_empty());
+        assert_eq!(fs.fs_info().unwrap().free_count, fs.cluster_count() - 1);
+        assert_eq!(fs.free_space(), (fs.cluster_count() as u64 - 1) * 512);
+    }
+
+    #[test]
+    fn test_format_options_validation() {
+        assert!(FormatOptions::default().with_cluster_size(4096).is_ok());
+        assert_eq!(FormatOptions::default().with_cluster_size(3000).unwrap_err(),
+                   FsError::InvalidGeometry);
+        assert_eq!(FormatOptions::default().with_label("WAY TOO LONG").unwrap_err(),
+                   FsError::InvalidName);
+
+        let mut tiny = vec![0u8; 8 * 512];
+        assert_eq!(format(&mut tiny, &FormatOptions::default()), Err(FsError::InvalidGeometry));
+
+        // 255 FATs d'un volume de 2 To: la zone des FATs dépasse un u32
+        let many_fats = FormatOptions { fat_count: 255, ..FormatOptions::default() };
+        assert_eq!(layout(u32::MAX, &many_fats), Err(FsError::InvalidGeometry));
+        assert!(layout(u32::MAX, &FormatOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_blank_label_is_no_name() {
+        for blank in ["", "   "] {
+            let options = FormatOptions::default().with_label(blank).unwrap();
+            assert_eq!(options.label, NO_NAME);
+        }
+
+        // Label rempli d'espaces à la main: pas d'entrée de volume vide à la racine
+        let mut image = vec![0xAAu8; 1024 * 1024];
+        let options = FormatOptions { label: [b' '; 11], ..FormatOptions::default() };
+        format(&mut image, &options).unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        assert_eq!(fs.boot_sector().label(), "NO NAME");
+        let root = fs.cluster_offset(fs.root_cluster()) as usize;
+        assert_eq!(image[root], 0);
+    }
+
+    #[test]
+    fn test_reformat_mounted_filesystem() {
+        let mut image = crate::builder::FsBuilder::new(crate::builder::MB).build().unwrap();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let options = FormatOptions::default().with_cluster_size(2048).unwrap();
+        fs.format(&options).unwrap();
+        assert_eq!(fs.bytes_per_cluster(), 2048);
+    }
+}
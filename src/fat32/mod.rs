@@ -1,40 +1,315 @@
-//! Implémentation du système de fichiers FAT32 (lecture seule, compatible no_std)
+//! Implémentation du système de fichiers FAT32 (compatible no_std)
 
+pub mod error;
 pub mod boot_sector;
+pub mod fsinfo;
 pub mod fat;
+pub mod mirror;
 pub mod directory;
+pub mod undelete;
+pub mod mkfs;
+pub mod write;
+pub mod inode;
+pub mod partition;
+pub mod anomaly;
+pub mod limits;
+pub mod device;
+pub mod snapshot;
+pub mod dir_iter;
+pub mod readahead;
+pub mod file;
+pub mod stats;
+pub mod trace;
+pub mod noalloc;
+pub mod vfs;
+pub mod casefold;
+#[cfg(feature = "std")]
+pub mod parallel;
+#[cfg(feature = "async")]
+pub mod async_fs;
+#[cfg(feature = "sdcard")]
+pub mod sdcard;
+#[cfg(feature = "fatfs-compat")]
+pub mod compat;
+#[cfg(all(feature = "fuse", unix))]
+pub mod fuse;
+mod dir_cache;
+mod sync;
 
+pub use error::{FsError, PathError, PathErrorKind};
 pub use boot_sector::BootSector;
-pub use fat::{FatTable, FatEntry};
-pub use directory::{DirEntry, parse_directory, parse_directory_with_lfn};
+pub use fsinfo::{FsInfo, FSINFO_UNKNOWN};
+pub use fat::{FatTable, FatEntry, cluster_runs};
+pub use mirror::FatMismatch;
+pub use directory::{DirEntry, DisplayName, FatDateTime, parse_directory, parse_directory_with_lfn, parse_directory_checked, lfn_checksum};
+pub use undelete::DeletedEntry;
+pub use mkfs::FormatOptions;
+pub use write::{EntryLocation, NameError, LFN_MAX_UNITS, is_long_name_char, validate_long_name};
+pub use inode::{NodeAttr, NodeKind, ROOT_INODE};
+pub use partition::{Partition, PartitionType, read_partitions};
+pub use anomaly::{Anomaly, ParseOptions};
+pub use limits::Limits;
+pub use device::{BlockDevice, read_clusters};
+pub use readahead::ReadAhead;
+pub use file::File;
+pub use stats::IoStats;
+pub use trace::TraceEvent;
+pub use noalloc::{DirEntryRaw, NOALLOC_MAX_DEPTH};
+pub use vfs::FileSystem;
+#[cfg(feature = "std")]
+pub use parallel::FileHash;
+#[cfg(feature = "async")]
+pub use async_fs::{AsyncBlockDevice, AsyncFat32, AsyncFile, Blocking};
+#[cfg(feature = "sdcard")]
+pub use sdcard::{SdCard, SpiTransport};
+#[cfg(all(feature = "fuse", unix))]
+pub use fuse::FuseImage;
+pub use snapshot::{Change, ChangeKind, FileState, Snapshot};
+pub use dir_iter::DirIter;
 pub use directory::{ATTR_READ_ONLY, ATTR_HIDDEN, ATTR_SYSTEM, ATTR_VOLUME_ID,
                    ATTR_DIRECTORY, ATTR_ARCHIVE, ATTR_LONG_NAME};
 
 extern crate alloc;
 use alloc::vec::Vec;
 use alloc::string::String;
+use alloc::borrow::Cow;
+use core::ops::Range;
+
+use crate::checksum::Checksum;
+
+/// Plage de `len` octets à `offset` dans l'image, `None` si elle ne tient pas dans un `usize`
+///
+/// Les positions sont calculées en u64: sur une cible 32 bits, un volume de
+/// plus de 4 Gio a des offsets qu'un `usize` tronquerait.
+pub(crate) fn byte_range(offset: u64, len: u64) -> Option<Range<usize>> {
+    let start = usize::try_from(offset).ok()?;
+    let end = usize::try_from(offset.checked_add(len)?).ok()?;
+    Some(start..end)
+}
+
+/// Données du disque, empruntées en lecture seule ou en écriture
+enum Storage<'a> {
+    ReadOnly(&'a [u8]),
+    ReadWrite(&'a mut [u8]),
+}
 
 /// Interface du système de fichiers FAT32
+///
+/// `Fat32` est `Send` et `Sync` sur les cibles qui ont des atomiques de
+/// comparaison-échange (voir le module `sync`): plusieurs threads peuvent
+/// lire à travers une même référence partagée.
 pub struct Fat32<'a> {
-    disk_data: &'a [u8],
+    storage: Storage<'a>,
     boot_sector: BootSector,
+    options: ParseOptions,
+    limits: Limits,
+    cancel: Option<&'a (dyn Fn() -> bool + Sync)>,
+    trace: Option<&'a (dyn Fn(&TraceEvent) + Sync)>,
+    dir_cache: Option<dir_cache::DirCache>,
+    stats: stats::Counters,
 }
 
 impl<'a> Fat32<'a> {
-    /// Crée un nouveau filesystem FAT32 depuis les données brutes du disque
+    /// Crée un nouveau filesystem FAT32 (lecture seule) depuis les données brutes du disque
     pub fn new(disk_data: &'a [u8]) -> Option<Self> {
-        if disk_data.len() < 512 {
-            return None;
+        Self::try_new(disk_data).ok()
+    }
+
+    /// Crée un filesystem FAT32 modifiable depuis les données brutes du disque
+    pub fn new_mut(disk_data: &'a mut [u8]) -> Option<Self> {
+        Self::try_new_mut(disk_data).ok()
+    }
+
+    /// Comme `new`, avec la raison du refus
+    ///
+    /// `InvalidClusterSize` pour un nombre de secteurs par cluster illégal,
+    /// `TruncatedImage` si le volume décrit dépasse `disk_data`,
+    /// `InvalidGeometry` pour tout autre boot sector inutilisable.
+    pub fn try_new(disk_data: &'a [u8]) -> Result<Self, FsError> {
+        let boot_sector = Self::parse_boot_sector(disk_data)?;
+        Ok(Self::from_storage(Storage::ReadOnly(disk_data), boot_sector))
+    }
+
+    /// Comme `new_mut`, avec la raison du refus (voir `try_new`)
+    pub fn try_new_mut(disk_data: &'a mut [u8]) -> Result<Self, FsError> {
+        let boot_sector = Self::parse_boot_sector(disk_data)?;
+        Ok(Self::from_storage(Storage::ReadWrite(disk_data), boot_sector))
+    }
+
+    fn from_storage(storage: Storage<'a>, boot_sector: BootSector) -> Self {
+        Fat32 {
+            storage,
+            boot_sector,
+            options: ParseOptions::default(),
+            limits: Limits::default(),
+            cancel: None,
+            trace: None,
+            dir_cache: None,
+            stats: stats::Counters::default(),
         }
+    }
 
-        let boot_bytes: [u8; 512] = disk_data[0..512].try_into().ok()?;
-        let boot_sector = BootSector::from_bytes(&boot_bytes)?;
+    /// Applique une politique de lecture des anomalies
+    ///
+    /// En mode strict, les champs réservés du boot sector sont vérifiés immédiatement.
+    pub fn with_options(mut self, options: ParseOptions) -> Result<Self, FsError> {
+        if options.strict {
+            let boot_bytes: &[u8; 512] = self.disk_data()[0..512].try_into().map_err(|_| FsError::OutOfBounds)?;
+            anomaly::check_boot_sector(boot_bytes).map_err(FsError::Anomaly)?;
+        }
+        self.options = options;
+        Ok(self)
+    }
 
-        if boot_sector.bytes_per_sector == 0 || boot_sector.sectors_per_cluster == 0 {
-            return None;
+    /// Politique de lecture des anomalies en vigueur
+    pub fn parse_options(&self) -> ParseOptions {
+        self.options
+    }
+
+    /// Remplace les limites de ressources appliquées aux lectures
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Limites de ressources en vigueur
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Installe un test d'annulation consulté pendant les parcours longs
+    ///
+    /// Quand `check` retourne vrai, les chaînes, répertoires, chemins et
+    /// comptages en cours s'arrêtent avec `FsError::Cancelled`. Les écritures
+    /// ne sont jamais interrompues: le filesystem reste cohérent.
+    pub fn with_cancel(mut self, check: &'a (dyn Fn() -> bool + Sync)) -> Self {
+        self.cancel = Some(check);
+        self
+    }
+
+    /// Installe une fonction appelée à chaque point de trace (voir le module `trace`)
+    ///
+    /// L'événement `Mounted` est émis tout de suite avec la géométrie du volume.
+    pub fn with_trace(mut self, trace: &'a (dyn Fn(&TraceEvent) + Sync)) -> Self {
+        self.trace = Some(trace);
+        self.trace(TraceEvent::Mounted {
+            bytes_per_sector: self.boot_sector.bytes_per_sector,
+            sectors_per_cluster: self.boot_sector.sectors_per_cluster,
+            root_cluster: self.root_cluster(),
+            cluster_count: self.cluster_count(),
+        });
+        self
+    }
+
+    /// Active le cache des noms de répertoires utilisé par `find_entry` et `resolve_path`
+    ///
+    /// Chaque répertoire est lu une fois puis trié par nom: les recherches
+    /// suivantes y sont dichotomiques.
+    pub fn with_dir_cache(mut self) -> Self {
+        self.dir_cache = Some(dir_cache::DirCache::default());
+        self
+    }
+
+    /// Vide le cache des noms de répertoires
+    pub fn clear_dir_cache(&self) {
+        if let Some(cache) = &self.dir_cache {
+            cache.clear();
         }
+    }
 
-        Some(Fat32 { disk_data, boot_sector })
+    /// Nombre de répertoires dans le cache des noms (0 sans cache)
+    pub fn dir_cache_len(&self) -> usize {
+        self.dir_cache.as_ref().map_or(0, |cache| cache.len())
+    }
+
+    /// Relevé des compteurs d'E/S et de cache depuis l'ouverture ou `reset_stats`
+    pub fn stats(&self) -> IoStats {
+        self.stats.get()
+    }
+
+    /// Remet les compteurs d'E/S et de cache à zéro
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    /// Test d'annulation sous la forme attendue par `FatTable`
+    fn cancel_check(&self) -> Option<&dyn Fn() -> bool> {
+        self.cancel.map(|check| check as &dyn Fn() -> bool)
+    }
+
+    /// Remonte `event` à la fonction de trace, s'il y en a une, et à defmt
+    fn trace(&self, event: TraceEvent) {
+        log_trace!("{}", event);
+        #[cfg(all(feature = "defmt", target_os = "none"))]
+        defmt::trace!("{}", event);
+        if let Some(trace) = self.trace {
+            trace(&event);
+        }
+    }
+
+    /// Erreur `Cancelled` si l'annulation a été demandée
+    fn check_cancel(&self) -> Result<(), FsError> {
+        match self.cancel {
+            Some(check) if check() => Err(FsError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parse et valide le boot sector en tête de l'image
+    fn parse_boot_sector(disk_data: &[u8]) -> Result<BootSector, FsError> {
+        let boot_bytes: &[u8; 512] = match disk_data.get(..512).and_then(|b| b.try_into().ok()) {
+            Some(boot_bytes) => boot_bytes,
+            None => {
+                log_warn!("image too small for a boot sector ({} bytes)", disk_data.len());
+                return Err(FsError::InvalidGeometry);
+            }
+        };
+        let boot_sector = match BootSector::from_bytes(boot_bytes) {
+            Some(boot_sector) => boot_sector,
+            None => {
+                log_warn!("no FAT32 boot sector (missing signature or sector count)");
+                return Err(FsError::InvalidGeometry);
+            }
+        };
+
+        if let Err(error) = boot_sector.check_geometry() {
+            log_warn!(
+                "invalid boot sector geometry: {} bytes/sector, {} sectors/cluster",
+                boot_sector.bytes_per_sector, boot_sector.sectors_per_cluster
+            );
+            return Err(error);
+        }
+        if let Err(error) = boot_sector.check_image_size(disk_data.len() as u64) {
+            log_warn!("{}", error);
+            return Err(error);
+        }
+
+        Ok(boot_sector)
+    }
+
+    /// Retourne les données brutes du disque
+    #[inline]
+    fn disk_data(&self) -> &[u8] {
+        match &self.storage {
+            Storage::ReadOnly(data) => data,
+            Storage::ReadWrite(data) => data,
+        }
+    }
+
+    /// Retourne les données du disque en écriture (erreur si monté en lecture seule)
+    fn disk_data_mut(&mut self) -> Result<&mut [u8], FsError> {
+        // Toute écriture peut changer un répertoire indexé
+        self.clear_dir_cache();
+        match &mut self.storage {
+            Storage::ReadOnly(_) => Err(FsError::ReadOnly),
+            Storage::ReadWrite(data) => Ok(data),
+        }
+    }
+
+    /// Vérifie si le filesystem est monté en lecture seule
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.storage, Storage::ReadOnly(_))
     }
 
     /// Retourne les informations du boot sector
@@ -43,6 +318,31 @@ impl<'a> Fat32<'a> {
         &self.boot_sector
     }
 
+    /// Lit le secteur FSInfo s'il est déclaré dans le boot sector
+    pub fn fs_info(&self) -> Option<FsInfo> {
+        let sector = self.boot_sector.fs_info_sector;
+        if sector == 0 || sector >= self.boot_sector.reserved_sectors {
+            return None;
+        }
+
+        let range = byte_range(self.boot_sector.sector_offset(sector as u64), 512)?;
+        let bytes: &[u8; 512] = self.disk_data().get(range)?.try_into().ok()?;
+        Some(FsInfo::from_bytes(bytes))
+    }
+
+    /// Marque le compteur de clusters libres FSInfo comme inconnu après une écriture
+    pub(crate) fn invalidate_free_count(&mut self) -> Result<(), FsError> {
+        if self.fs_info().is_none() {
+            return Ok(());
+        }
+
+        let start = self.boot_sector.sector_offset(self.boot_sector.fs_info_sector as u64);
+        let range = byte_range(start + 488, 4).ok_or(FsError::OutOfBounds)?;
+        let data = self.disk_data_mut()?;
+        data[range].copy_from_slice(&FSINFO_UNKNOWN.to_le_bytes());
+        Ok(())
+    }
+
     /// Retourne le numéro de cluster du répertoire racine
     #[inline]
     pub fn root_cluster(&self) -> u32 {
@@ -63,130 +363,438 @@ impl<'a> Fat32<'a> {
 
     /// Retourne le lecteur de table FAT
     fn fat_table(&self) -> FatTable<'_> {
-        let start = self.boot_sector.fat_start_sector() as usize
-            * self.boot_sector.bytes_per_sector as usize;
-        let size = self.boot_sector.sectors_per_fat as usize
-            * self.boot_sector.bytes_per_sector as usize;
+        let data = self.disk_data();
+        let len = data.len() as u64;
+        let start = self.boot_sector.fat_offset(0).min(len);
+        let end = (start + self.boot_sector.fat_bytes()).min(len);
+        // Bornes réduites à la taille de l'image: la conversion ne peut pas échouer
+        let range = byte_range(start, end - start).unwrap_or(0..0);
+        FatTable::with_cluster_count(&data[range], self.cluster_count())
+    }
+
+    /// Écrit une entrée dans toutes les copies de la FAT
+    pub(crate) fn write_fat_entry(&mut self, cluster: u32, value: u32) -> Result<(), FsError> {
+        let offset = cluster as u64 * 4;
+        if offset + 4 > self.boot_sector.fat_bytes() {
+            return Err(FsError::OutOfBounds);
+        }
+
+        for copy in 0..self.boot_sector.fat_count {
+            let range = byte_range(self.boot_sector.fat_offset(copy) + offset, 4).ok_or(FsError::OutOfBounds)?;
+            let data = self.disk_data_mut()?;
+            let slot = data.get_mut(range).ok_or(FsError::OutOfBounds)?;
+            let old = u32::from_le_bytes([slot[0], slot[1], slot[2], slot[3]]);
+            let new = (old & 0xF0000000) | (value & 0x0FFFFFFF);
+            slot.copy_from_slice(&new.to_le_bytes());
+        }
+
+        Ok(())
+    }
 
-        let end = (start + size).min(self.disk_data.len());
-        FatTable::new(&self.disk_data[start..end])
+    /// Retourne le nombre de clusters de la région de données
+    pub fn cluster_count(&self) -> u32 {
+        self.boot_sector.cluster_count()
     }
 
-    /// Lit un seul cluster
-    fn read_cluster(&self, cluster: u32) -> &[u8] {
+    /// Lit un seul cluster (vide si hors de l'image)
+    pub fn read_cluster(&self, cluster: u32) -> &[u8] {
         if cluster < 2 {
             return &[];
         }
 
-        let sector = self.boot_sector.cluster_to_sector(cluster);
-        let bytes_per_cluster = self.boot_sector.bytes_per_cluster() as usize;
-        let start = sector as usize * self.boot_sector.bytes_per_sector as usize;
-        let end = start + bytes_per_cluster;
+        let range = byte_range(self.cluster_offset(cluster), self.bytes_per_cluster() as u64);
+        let data = match range.and_then(|range| self.disk_data().get(range)) {
+            Some(data) => data,
+            None => return &[],
+        };
+
+        let sectors = self.boot_sector.sectors_per_cluster as u64;
+        self.stats.update(|s| s.sectors_read += sectors);
+        self.trace(TraceEvent::ClusterRead { cluster });
+        data
+    }
 
-        if end > self.disk_data.len() {
-            return &[];
+    /// Retourne la liste des clusters d'une chaîne, coupée à `max_chain_length`
+    pub fn cluster_chain(&self, start: u32) -> Vec<u32> {
+        self.walk_chain(start, self.limits.max_chain_length).0
+    }
+
+    /// Retourne la liste des clusters d'une chaîne, en erreur au-delà de `max_chain_length`
+    pub fn try_cluster_chain(&self, start: u32) -> Result<Vec<u32>, FsError> {
+        match self.walk_chain(start, self.limits.max_chain_length) {
+            (chain, None) => Ok(chain),
+            (_, Some(error)) => Err(error),
         }
+    }
+
+    /// Suit une chaîne dans la FAT en comptant les entrées consultées
+    fn walk_chain(&self, start: u32, max_length: usize) -> (Vec<u32>, Option<FsError>) {
+        let (chain, error) = self.fat_table().walk_chain(start, max_length, self.cancel_check());
+        let lookups = chain.len() as u64;
+        self.stats.update(|s| s.fat_lookups += lookups);
+        self.trace(TraceEvent::ChainWalked { start, length: chain.len(), error });
+        (chain, error)
+    }
+
+    /// Retourne l'offset en octets d'un cluster dans l'image
+    pub fn cluster_offset(&self, cluster: u32) -> u64 {
+        self.boot_sector.sector_offset(self.boot_sector.cluster_to_sector(cluster))
+    }
 
-        &self.disk_data[start..end]
+    /// Secteur de début d'un cluster sur le disque entier (`hidden_sectors` compris)
+    pub fn cluster_lba(&self, cluster: u32) -> u64 {
+        self.boot_sector.absolute_sector(self.boot_sector.cluster_to_sector(cluster))
     }
 
     /// Lit une chaîne complète de clusters
     pub fn read_cluster_chain(&self, start: u32) -> Vec<u8> {
-        let fat = self.fat_table();
-        let chain = fat.get_cluster_chain(start);
-        let mut data = Vec::new();
+        let chain = self.cluster_chain(start);
+        let mut data = Vec::with_capacity(chain.len().saturating_mul(self.bytes_per_cluster() as usize));
 
         for cluster in chain {
             data.extend_from_slice(self.read_cluster(cluster));
         }
 
+        let copied = data.len() as u64;
+        self.stats.update(|s| s.bytes_copied += copied);
         data
     }
 
+    /// Lit une chaîne complète de clusters, en erreur au-delà de `max_chain_length`
+    pub fn try_read_cluster_chain(&self, start: u32) -> Result<Vec<u8>, FsError> {
+        self.read_chain(start, None)
+    }
+
+    /// Lit une chaîne en une seule allocation, en s'arrêtant après `size` octets
+    ///
+    /// Sans `size`, la chaîne est lue entièrement. Avec `size`, seuls les
+    /// clusters utiles sont parcourus: une chaîne plus longue que le fichier
+    /// n'est pas une erreur.
+    fn read_chain(&self, start: u32, size: Option<usize>) -> Result<Vec<u8>, FsError> {
+        let (chain, len) = self.chain_for(start, size)?;
+        self.copy_chain(&chain, len)
+    }
+
+    /// Clusters utiles d'une chaîne et nombre d'octets à en lire (voir `read_chain`)
+    ///
+    /// Une taille incohérente avec la chaîne est une erreur en mode strict;
+    /// sinon elle est signalée et seuls les octets présents sont lus.
+    fn chain_for(&self, start: u32, size: Option<usize>) -> Result<(Vec<u32>, usize), FsError> {
+        let (chain, len, mismatch) = self.chain_checked(start, size)?;
+        if let Some(anomaly) = mismatch {
+            if self.options.strict {
+                return Err(FsError::Anomaly(anomaly));
+            }
+            log_warn!("{}, reading {} bytes", anomaly, len);
+        }
+        Ok((chain, len))
+    }
+
+    /// Comme `chain_for`, en retournant l'incohérence entre la taille et la chaîne
+    fn chain_checked(&self, start: u32, size: Option<usize>) -> Result<(Vec<u32>, usize, Option<Anomaly>), FsError> {
+        let cluster_size = self.bytes_per_cluster() as usize;
+        let max_length = self.limits.max_chain_length;
+        let needed = size.map_or(max_length, |size| size.div_ceil(cluster_size));
+        // Un cluster de plus pour savoir si la chaîne continue après le fichier
+        let walk = needed.saturating_add(size.is_some() as usize).min(max_length);
+        let mut chain = match self.walk_chain(start, walk) {
+            (chain, None) => chain,
+            (chain, Some(FsError::LimitExceeded)) if size.is_some() && needed <= max_length => chain,
+            (_, Some(error)) => return Err(error),
+        };
+
+        let mut mismatch = None;
+        if let Some(size) = size.filter(|_| chain.len() > needed) {
+            chain.truncate(needed);
+            mismatch = Some(Anomaly::LongChain { cluster: start, size: size as u32 });
+        }
+
+        // La longueur ne dépend que de la chaîne lue, pas d'une taille d'entrée corrompue
+        let len = chain.len().saturating_mul(cluster_size).min(size.unwrap_or(usize::MAX));
+        if let Some(size) = size.filter(|&size| size > len) {
+            mismatch = Some(Anomaly::ShortChain { cluster: start, size: size as u32, available: len as u64 });
+        }
+        Ok((chain, len, mismatch))
+    }
+
+    /// Copie les `len` premiers octets des clusters `chain` en une seule allocation
+    ///
+    /// L'allocation passe par `try_reserve_exact`: un fichier plus gros que le
+    /// heap donne `OutOfMemory` au lieu d'appeler le gestionnaire d'erreur
+    /// d'allocation, qui bloque l'appareil.
+    fn copy_chain(&self, chain: &[u32], len: usize) -> Result<Vec<u8>, FsError> {
+        let mut data = Vec::new();
+        data.try_reserve_exact(len).map_err(|_| FsError::OutOfMemory)?;
+        for &cluster in chain {
+            self.check_cancel()?;
+            let bytes = self.read_cluster(cluster);
+            let take = bytes.len().min(len - data.len());
+            data.extend_from_slice(&bytes[..take]);
+        }
+
+        let copied = data.len() as u64;
+        self.stats.update(|s| s.bytes_copied += copied);
+        Ok(data)
+    }
+
     /// Lit les entrées d'un répertoire
+    ///
+    /// Un répertoire qui dépasse les limites de ressources est lu comme vide.
     pub fn read_directory(&self, cluster: u32) -> Vec<DirEntry> {
-        let data = self.read_cluster_chain(cluster);
-        parse_directory(&data)
+        let entries = match self.try_read_cluster_chain(cluster) {
+            Ok(data) => parse_directory(&data),
+            Err(_) => return Vec::new(),
+        };
+        if entries.len() > self.limits.max_dir_entries {
+            return Vec::new();
+        }
+        entries
     }
 
     /// Lit les entrées d'un répertoire avec support des noms longs
+    ///
+    /// En mode strict, un répertoire contenant une anomalie est lu comme vide,
+    /// de même qu'un répertoire qui dépasse les limites de ressources;
+    /// `try_read_directory` retourne l'erreur.
     pub fn read_directory_with_lfn(&self, cluster: u32) -> Vec<(DirEntry, Option<String>)> {
-        let data = self.read_cluster_chain(cluster);
-        parse_directory_with_lfn(&data)
+        self.try_read_directory(cluster).unwrap_or_default()
+    }
+
+    /// Lit les entrées d'un répertoire selon la politique de lecture des anomalies
+    pub fn try_read_directory(&self, cluster: u32) -> Result<Vec<(DirEntry, Option<String>)>, FsError> {
+        enter_span!(span = "read_directory", cluster = cluster; bytes, entries);
+        let data = self.try_read_cluster_chain(cluster)?;
+        span_record!(span, bytes = data.len());
+        let entries = parse_directory_checked(&data, &self.options)?;
+        span_record!(span, entries = entries.len());
+        if entries.len() > self.limits.max_dir_entries {
+            return Err(FsError::LimitExceeded);
+        }
+        Ok(entries)
     }
 
     /// Cherche une entrée par nom dans un répertoire (insensible à la casse)
+    ///
+    /// Le répertoire est parcouru jusqu'à l'entrée trouvée seulement; une
+    /// erreur rencontrée avant (anomalie en mode strict, limite) donne `None`.
     pub fn find_entry(&self, dir_cluster: u32, name: &str) -> Option<DirEntry> {
-        let entries = self.read_directory_with_lfn(dir_cluster);
-        let name_upper = name.to_ascii_uppercase();
+        self.try_find_entry(dir_cluster, name).ok().flatten()
+    }
 
-        for (entry, long_name) in entries {
-            if let Some(ref ln) = long_name {
-                if ln.to_ascii_uppercase() == name_upper {
-                    return Some(entry);
-                }
-            }
+    /// Lit le contenu d'un fichier
+    ///
+    /// Un répertoire, ou un fichier qui dépasse les limites de ressources, est lu comme vide.
+    pub fn read_file(&self, entry: &DirEntry) -> Vec<u8> {
+        self.try_read_file(entry).unwrap_or_default()
+    }
+
+    /// Lit le contenu d'un fichier, en erreur au-delà de `max_file_size` ou `max_chain_length`
+    pub fn try_read_file(&self, entry: &DirEntry) -> Result<Vec<u8>, FsError> {
+        if entry.is_directory() {
+            return Err(FsError::IsADirectory);
+        }
+        if entry.size as u64 > self.limits.max_file_size {
+            return Err(FsError::LimitExceeded);
+        }
+
+        enter_span!(span = "read_file", cluster = entry.cluster(), size = entry.size; bytes);
+        let data = self.read_chain(entry.cluster(), Some(entry.size as usize))?;
+        span_record!(span, bytes = data.len());
+        Ok(data)
+    }
+
+    /// Lit un fichier en signalant une taille incohérente avec sa chaîne
+    ///
+    /// L'incohérence (`ShortChain`, `LongChain`) n'est pas une erreur, même en
+    /// mode strict: les octets présents dans la chaîne sont retournés avec elle.
+    pub fn read_file_checked(&self, entry: &DirEntry) -> Result<(Vec<u8>, Option<Anomaly>), FsError> {
+        if entry.is_directory() {
+            return Err(FsError::IsADirectory);
+        }
+        if entry.size as u64 > self.limits.max_file_size {
+            return Err(FsError::LimitExceeded);
+        }
+
+        let (chain, len, mismatch) = self.chain_checked(entry.cluster(), Some(entry.size as usize))?;
+        Ok((self.copy_chain(&chain, len)?, mismatch))
+    }
 
-            if entry.display_name().to_ascii_uppercase() == name_upper {
-                return Some(entry);
+    /// Lit le contenu d'un fichier sans copie quand ses clusters sont contigus
+    ///
+    /// Un fichier fragmenté est copié comme avec `read_file`; un répertoire,
+    /// ou un fichier qui dépasse les limites de ressources, est lu comme vide.
+    pub fn read_file_cow(&self, entry: &DirEntry) -> Cow<'_, [u8]> {
+        self.try_read_file_cow(entry).unwrap_or_default()
+    }
+
+    /// Lit le contenu d'un fichier, emprunté dans l'image si ses clusters sont contigus
+    pub fn try_read_file_cow(&self, entry: &DirEntry) -> Result<Cow<'_, [u8]>, FsError> {
+        if entry.is_directory() {
+            return Err(FsError::IsADirectory);
+        }
+        if entry.size as u64 > self.limits.max_file_size {
+            return Err(FsError::LimitExceeded);
+        }
+
+        enter_span!(span = "read_file_cow", cluster = entry.cluster(), size = entry.size; bytes);
+        let (chain, len) = self.chain_for(entry.cluster(), Some(entry.size as usize))?;
+        span_record!(span, bytes = len);
+        let contiguous = chain.windows(2).all(|pair| pair[1] == pair[0].wrapping_add(1));
+        if let (true, Some(&first)) = (contiguous, chain.first()) {
+            if let Some(data) = byte_range(self.cluster_offset(first), len as u64).and_then(|r| self.disk_data().get(r)) {
+                return Ok(Cow::Borrowed(data));
             }
         }
+        if chain.is_empty() {
+            return Ok(Cow::Borrowed(&[]));
+        }
+        self.copy_chain(&chain, len).map(Cow::Owned)
+    }
 
-        None
+    /// Parcourt le contenu d'un fichier cluster par cluster sans le charger en entier
+    ///
+    /// Le parcours s'arrête en silence sur annulation; voir `try_for_each_file_chunk`.
+    pub fn for_each_file_chunk<F: FnMut(&[u8])>(&self, entry: &DirEntry, f: F) {
+        let _ = self.try_for_each_file_chunk(entry, f);
     }
 
-    /// Lit le contenu d'un fichier
-    pub fn read_file(&self, entry: &DirEntry) -> Vec<u8> {
+    /// Parcourt le contenu d'un fichier cluster par cluster, en erreur sur annulation
+    pub fn try_for_each_file_chunk<F: FnMut(&[u8])>(&self, entry: &DirEntry, mut f: F) -> Result<(), FsError> {
         if entry.is_directory() {
-            return Vec::new();
+            return Ok(());
         }
 
-        let mut data = self.read_cluster_chain(entry.cluster());
-        let actual_size = entry.size as usize;
+        let mut remaining = entry.size as usize;
+        for cluster in self.try_cluster_chain(entry.cluster())? {
+            if remaining == 0 {
+                break;
+            }
+            self.check_cancel()?;
+            let data = self.read_cluster(cluster);
+            let take = data.len().min(remaining);
+            f(&data[..take]);
+            remaining -= take;
+        }
+        Ok(())
+    }
+
+    /// Compare deux fichiers cluster par cluster
+    ///
+    /// Retourne l'offset du premier octet différent, ou `None` si les fichiers
+    /// sont identiques. Si l'un est un préfixe de l'autre, l'offset est la
+    /// taille du plus court.
+    pub fn compare_files(&self, a: &DirEntry, b: &DirEntry) -> Option<u64> {
+        let chain_a = self.cluster_chain(a.cluster());
+        let chain_b = self.cluster_chain(b.cluster());
+        let common = a.size.min(b.size) as usize;
 
-        if data.len() > actual_size {
-            data.truncate(actual_size);
+        let mut chunk_a: &[u8] = &[];
+        let mut chunk_b: &[u8] = &[];
+        let (mut next_a, mut next_b) = (0, 0);
+        let mut offset = 0usize;
+
+        while offset < common {
+            if chunk_a.is_empty() {
+                chunk_a = chain_a.get(next_a).map_or(&[], |&c| self.read_cluster(c));
+                next_a += 1;
+            }
+            if chunk_b.is_empty() {
+                chunk_b = chain_b.get(next_b).map_or(&[], |&c| self.read_cluster(c));
+                next_b += 1;
+            }
+            if chunk_a.is_empty() || chunk_b.is_empty() {
+                return Some(offset as u64);
+            }
+
+            let len = chunk_a.len().min(chunk_b.len()).min(common - offset);
+            if let Some(i) = chunk_a[..len].iter().zip(&chunk_b[..len]).position(|(x, y)| x != y) {
+                return Some((offset + i) as u64);
+            }
+
+            chunk_a = &chunk_a[len..];
+            chunk_b = &chunk_b[len..];
+            offset += len;
         }
 
-        data
+        if a.size == b.size {
+            None
+        } else {
+            Some(common as u64)
+        }
+    }
+
+    /// Calcule l'empreinte d'un fichier avec l'algorithme donné
+    ///
+    /// Retourne une empreinte vide si le parcours est annulé ou dépasse les limites.
+    pub fn checksum_file<H: Checksum>(&self, entry: &DirEntry, mut hasher: H) -> Vec<u8> {
+        match self.try_for_each_file_chunk(entry, |chunk| hasher.update(chunk)) {
+            Ok(()) => hasher.finish(),
+            Err(_) => Vec::new(),
+        }
     }
 
     /// Résout un chemin et retourne l'entrée correspondante
     pub fn resolve_path(&self, path: &str, current_cluster: u32) -> Option<DirEntry> {
-        let path = path.trim();
+        self.try_resolve_path(path, current_cluster).ok()
+    }
 
-        if path.is_empty() || path == "/" {
-            return None;
-        }
+    /// Résout un chemin, en distinguant une entrée absente d'une limite ou d'une anomalie
+    pub fn try_resolve_path(&self, path: &str, current_cluster: u32) -> Result<DirEntry, FsError> {
+        self.resolve_path_detailed(path, current_cluster).map_err(FsError::from)
+    }
 
-        let (start_cluster, path_str) = if path.starts_with('/') {
-            (self.root_cluster(), &path[1..])
-        } else {
-            (current_cluster, path)
+    /// Résout un chemin; l'erreur désigne le composant en cause
+    ///
+    /// `/DOCS/REPORTS/a.txt` sans `REPORTS` échoue sur le composant 1 avec
+    /// `NotFound`; un fichier au milieu du chemin donne `NotADirectory`.
+    pub fn resolve_path_detailed(&self, path: &str, current_cluster: u32) -> Result<DirEntry, PathError> {
+        let path = path.trim();
+        enter_span!(_span = "resolve_path", path = path, cluster = current_cluster);
+
+        let (start_cluster, path_str) = match path.strip_prefix('/') {
+            Some(rest) => (self.root_cluster(), rest),
+            None => (current_cluster, path),
         };
 
-        let components: Vec<&str> = path_str
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .collect();
+        // Composants parcourus sans être collectés dans un Vec; le chemin de
+        // l'erreur n'est construit qu'en cas d'échec
+        let components = || path_str.split('/').filter(|s| !s.is_empty());
+        let fail = |index: usize, kind: PathErrorKind| {
+            let mut failed = String::from(&path[..path.len() - path_str.len()]);
+            for (i, component) in components().take(index + 1).enumerate() {
+                if i > 0 {
+                    failed.push('/');
+                }
+                failed.push_str(component);
+            }
+            let component = components().nth(index).unwrap_or("").into();
+            PathError { index, component, path: failed, kind }
+        };
 
-        if components.is_empty() {
-            return None;
+        let depth = components().count();
+        if depth > self.limits.max_path_depth {
+            return Err(fail(self.limits.max_path_depth, PathErrorKind::Fs(FsError::LimitExceeded)));
         }
 
         let mut cluster = start_cluster;
-
-        for component in &components[..components.len() - 1] {
-            match self.find_entry(cluster, component) {
-                Some(entry) if entry.is_directory() => {
-                    cluster = entry.cluster();
-                }
-                _ => return None,
+        for (index, component) in components().enumerate() {
+            self.check_cancel().map_err(|e| fail(index, PathErrorKind::Fs(e)))?;
+            let entry = match self.try_find_entry(cluster, component) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => return Err(fail(index, PathErrorKind::NotFound)),
+                Err(e) => return Err(fail(index, PathErrorKind::Fs(e))),
+            };
+            if index + 1 == depth {
+                return Ok(entry);
             }
+            if !entry.is_directory() {
+                return Err(fail(index, PathErrorKind::NotADirectory));
+            }
+            cluster = entry.cluster();
         }
 
-        let final_name = components.last()?;
-        self.find_entry(cluster, final_name)
+        // Chemin vide ou racine: aucune entrée à retourner
+        Err(fail(0, PathErrorKind::NotFound))
     }
 
     /// Retourne la taille totale du filesystem en octets
@@ -194,15 +802,28 @@ impl<'a> Fat32<'a> {
         self.boot_sector.total_sectors as u64 * self.boot_sector.bytes_per_sector as u64
     }
 
-    /// Calcule l'espace libre (opération coûteuse)
+    /// Calcule l'espace libre
+    ///
+    /// Retourne 0 si le comptage est annulé; voir `try_free_space`.
     pub fn free_space(&self) -> u64 {
-        let fat = self.fat_table();
-        let data_clusters = (self.boot_sector.total_sectors
-            - self.boot_sector.data_start_sector())
-            / self.boot_sector.sectors_per_cluster as u32;
+        self.try_free_space().unwrap_or(0)
+    }
 
-        let free_clusters = fat.count_free_clusters(data_clusters);
-        free_clusters as u64 * self.boot_sector.bytes_per_cluster() as u64
+    /// Calcule l'espace libre depuis FSInfo, ou en parcourant la FAT si le compteur est invalide
+    pub fn try_free_space(&self) -> Result<u64, FsError> {
+        match self.fs_info().and_then(|info| info.free_clusters(self.cluster_count())) {
+            Some(free) => Ok(free as u64 * self.boot_sector.bytes_per_cluster() as u64),
+            None => self.count_free_space(),
+        }
+    }
+
+    /// Compte l'espace libre en parcourant toute la FAT (opération coûteuse), en erreur sur annulation
+    pub fn count_free_space(&self) -> Result<u64, FsError> {
+        let fat = self.fat_table();
+        let free_clusters = fat.try_count_free_clusters(self.cluster_count(), self.cancel_check())?;
+        let lookups = self.cluster_count() as u64;
+        self.stats.update(|s| s.fat_lookups += lookups);
+        Ok(free_clusters as u64 * self.boot_sector.bytes_per_cluster() as u64)
     }
 }
 
@@ -210,32 +831,9 @@ impl<'a> Fat32<'a> {
 mod tests {
     use super::*;
 
+    /// Image de 1 Mio contenant seulement TEST.TXT (100 octets)
     fn create_minimal_fat32_image() -> Vec<u8> {
-        let mut data = vec![0u8; 1024 * 1024];
-
-        data[11] = 0x00;
-        data[12] = 0x02;
-        data[13] = 1;
-        data[14] = 32;
-        data[15] = 0;
-        data[16] = 2;
-        let total_sectors: u32 = 2048;
-        data[32..36].copy_from_slice(&total_sectors.to_le_bytes());
-        data[36..40].copy_from_slice(&16u32.to_le_bytes());
-        data[44..48].copy_from_slice(&2u32.to_le_bytes());
-        data[510] = 0x55;
-        data[511] = 0xAA;
-
-        let fat_start = 32 * 512;
-        data[fat_start + 8..fat_start + 12].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-        let root_dir = 64 * 512;
-        data[root_dir..root_dir + 8].copy_from_slice(b"TEST    ");
-        data[root_dir + 8..root_dir + 11].copy_from_slice(b"TXT");
-        data[root_dir + 11] = ATTR_ARCHIVE;
-        data[root_dir + 28..root_dir + 32].copy_from_slice(&100u32.to_le_bytes());
-
-        data
+        crate::builder::FsBuilder::new(crate::builder::MB).file("TEST.TXT", &[0; 100]).build().unwrap()
     }
 
     #[test]
@@ -280,4 +878,172 @@ mod tests {
         let data = vec![0u8; 100];
         assert!(Fat32::new(&data).is_none());
     }
+
+    #[test]
+    fn test_invalid_cluster_size() {
+        for sectors_per_cluster in [0u8, 3, 255] {
+            let mut data = create_minimal_fat32_image();
+            data[13] = sectors_per_cluster;
+            assert!(Fat32::new(&data).is_none());
+            assert_eq!(Fat32::try_new(&data).err(), Some(FsError::InvalidClusterSize(sectors_per_cluster)));
+        }
+        let mut data = vec![0u8; 100];
+        assert_eq!(Fat32::try_new_mut(&mut data).err(), Some(FsError::InvalidGeometry));
+    }
+
+    #[test]
+    fn test_cancel() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use crate::testimg::{TestImage, FRAGMENTED_PATH, README_PATH};
+
+        let image = TestImage::default().fragmented().build().unwrap();
+        let checks = AtomicUsize::new(0);
+        // Annulation demandée à partir du troisième test
+        let cancel = || checks.fetch_add(1, Ordering::Relaxed) + 1 > 2;
+        let fs = Fat32::new(&image).unwrap().with_cancel(&cancel);
+        let root = fs.root_cluster();
+        let frag = Fat32::new(&image).unwrap().resolve_path(FRAGMENTED_PATH, root).unwrap();
+
+        assert_eq!(fs.try_read_file(&frag), Err(FsError::Cancelled));
+        assert!(checks.load(Ordering::Relaxed) > 2);
+        assert_eq!(fs.count_free_space(), Err(FsError::Cancelled));
+        assert_eq!(fs.try_resolve_path(README_PATH, root).unwrap_err(), FsError::Cancelled);
+        assert!(fs.checksum_file(&frag, crate::checksum::Crc32::new()).is_empty());
+
+        // Sans annulation, les mêmes lectures aboutissent
+        let never = || false;
+        let fs = Fat32::new(&image).unwrap().with_cancel(&never);
+        assert!(fs.try_read_file(&frag).is_ok());
+        assert!(fs.count_free_space().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_read_file_stops_at_size() {
+        use crate::testimg::{TestImage, FRAGMENTED_PATH};
+
+        let test = TestImage::default().fragmented();
+        let mut image = test.build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let short = fs.locate_entry(fs.root_cluster(), "FRAG.BIN").unwrap().short_offset() as usize;
+        // La chaîne de 4 clusters décrit désormais un fichier de 700 octets
+        image[short + 28..short + 32].copy_from_slice(&700u32.to_le_bytes());
+
+        let fs = Fat32::new(&image).unwrap().with_limits(Limits { max_chain_length: 2, ..Limits::default() });
+        let entry = fs.resolve_path(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+        let data = fs.try_read_file(&entry).unwrap();
+        assert_eq!(data, test.fragmented_data()[..700]);
+        assert_eq!(data.capacity(), 700);
+        assert_eq!(fs.try_read_cluster_chain(entry.cluster()), Err(FsError::LimitExceeded));
+    }
+
+    #[test]
+    fn test_free_space_from_fsinfo() {
+        let mut image = crate::builder::FsBuilder::new(1024 * 1024).build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let scanned = fs.count_free_space().unwrap();
+        assert_eq!(fs.free_space(), scanned);
+
+        // Un compteur plausible est cru sans parcourir la FAT
+        let fsinfo = fs.boot_sector().fs_info_sector as usize * 512;
+        image[fsinfo + 488..fsinfo + 492].copy_from_slice(&10u32.to_le_bytes());
+        assert_eq!(Fat32::new(&image).unwrap().free_space(), 10 * 512);
+
+        // Compteur impossible ou signature abîmée: parcours de la FAT
+        image[fsinfo + 488..fsinfo + 492].copy_from_slice(&u32::MAX.wrapping_sub(1).to_le_bytes());
+        assert_eq!(Fat32::new(&image).unwrap().free_space(), scanned);
+        image[fsinfo + 488..fsinfo + 492].copy_from_slice(&10u32.to_le_bytes());
+        image[fsinfo] = 0;
+        assert_eq!(Fat32::new(&image).unwrap().free_space(), scanned);
+    }
+
+    #[test]
+    fn test_read_file_cow() {
+        use crate::testimg::{TestImage, FRAGMENTED_PATH, README_PATH, README_TEXT};
+
+        let test = TestImage::default().long_names().fragmented();
+        let image = test.build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let root = fs.root_cluster();
+
+        let readme = fs.resolve_path(README_PATH, root).unwrap();
+        assert!(matches!(fs.read_file_cow(&readme), Cow::Borrowed(data) if data == README_TEXT));
+
+        let frag = fs.resolve_path(FRAGMENTED_PATH, root).unwrap();
+        let data = fs.read_file_cow(&frag);
+        assert!(matches!(data, Cow::Owned(_)));
+        assert_eq!(data, test.fragmented_data());
+        assert_eq!(fs.try_read_file_cow(&fs.resolve_path("/My Documents", root).unwrap()), Err(FsError::IsADirectory));
+    }
+
+    #[test]
+    fn test_stats() {
+        use crate::testimg::{TestImage, FRAGMENTED_CLUSTERS, FRAGMENTED_PATH};
+
+        let image = TestImage::default().fragmented().build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let frag = fs.resolve_path(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+
+        fs.reset_stats();
+        let data = fs.read_file(&frag);
+        let stats = fs.stats();
+        assert_eq!(stats.bytes_copied, data.len() as u64);
+        assert_eq!(stats.fat_lookups, FRAGMENTED_CLUSTERS as u64);
+        assert_eq!(stats.sectors_read, (FRAGMENTED_CLUSTERS * fs.boot_sector().sectors_per_cluster as usize) as u64);
+        assert_eq!(stats.cache_hits + stats.cache_misses, 0);
+
+        fs.reset_stats();
+        assert_eq!(fs.stats(), IoStats::default());
+    }
+
+    #[test]
+    fn test_trace() {
+        use std::sync::Mutex;
+        use crate::testimg::{TestImage, FRAGMENTED_CLUSTERS, FRAGMENTED_PATH};
+
+        let image = TestImage::default().fragmented().build().unwrap();
+        let events = Mutex::new(Vec::new());
+        let record = |event: &TraceEvent| events.lock().unwrap().push(*event);
+        let fs = Fat32::new(&image).unwrap().with_trace(&record);
+        let frag = Fat32::new(&image).unwrap().resolve_path(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+
+        fs.read_file(&frag);
+        let limited = Fat32::new(&image).unwrap().with_limits(Limits { max_chain_length: 2, ..Limits::default() });
+        let limited = limited.with_trace(&record);
+        assert!(limited.try_cluster_chain(frag.cluster()).is_err());
+
+        let events = events.into_inner().unwrap();
+        assert!(matches!(events[0], TraceEvent::Mounted { root_cluster: 2, .. }));
+        assert_eq!(events[1], TraceEvent::ChainWalked { start: frag.cluster(), length: FRAGMENTED_CLUSTERS, error: None });
+        let reads = events.iter().filter(|event| matches!(event, TraceEvent::ClusterRead { .. })).count();
+        assert_eq!(reads, FRAGMENTED_CLUSTERS);
+        assert_eq!(
+            events.last(),
+            Some(&TraceEvent::ChainWalked { start: frag.cluster(), length: 2, error: Some(FsError::LimitExceeded) })
+        );
+        assert_eq!(events[1].to_string(), format!("chain from cluster {}: {} clusters", frag.cluster(), FRAGMENTED_CLUSTERS));
+    }
+
+    #[test]
+    fn test_out_of_memory() {
+        let image = create_minimal_fat32_image();
+        let fs = Fat32::new(&image).unwrap();
+
+        // Une réservation impossible devient une erreur, sans gestionnaire d'allocation
+        assert_eq!(fs.copy_chain(&[], usize::MAX), Err(FsError::OutOfMemory));
+        assert_eq!(FsError::OutOfMemory.to_string(), "Out of memory");
+    }
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(byte_range(512, 32), Some(512..544));
+        assert_eq!(byte_range(u64::MAX, 1), None);
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(byte_range(1 << 32, 512), None);
+
+        // Cluster hors de l'image: rien plutôt qu'un offset tronqué
+        let image = create_minimal_fat32_image();
+        let fs = Fat32::new(&image).unwrap();
+        assert!(fs.cluster_offset(0x0FFFFFF5) > u32::MAX as u64);
+        assert!(fs.read_cluster(0x0FFFFFF5).is_empty());
+    }
 }
@@ -0,0 +1,352 @@
+//! Lecture sans allocation, dans des tampons fournis par l'appelant
+//!
+//! Pour les microcontrôleurs où le tas n'est pas acceptable: les chaînes de
+//! clusters sont suivies entrée par entrée dans la FAT au lieu d'être
+//! collectées, un répertoire est copié en entrées brutes dans un tableau de
+//! l'appelant, un chemin est résolu avec une pile de profondeur fixe et les
+//! noms longs sont comparés en UTF-16 sans être reconstitués en `String`.
+//! Une boucle dans la FAT s'arrête sur `max_chain_length`.
+
+use core::fmt;
+use core::ops::ControlFlow;
+
+use super::directory::{lfn_checksum, ATTR_LONG_NAME};
+use super::{casefold, DirEntry, Fat32, FatEntry, FsError};
+
+/// Profondeur maximale d'un chemin pour `resolve_path_in`
+pub const NOALLOC_MAX_DEPTH: usize = 16;
+/// Unités UTF-16 d'un nom long (20 entrées LFN de 13 caractères)
+const LFN_UNITS: usize = 260;
+/// Position des 13 caractères dans une entrée LFN
+const LFN_OFFSETS: [usize; 13] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+
+/// Entrée de répertoire telle qu'elle est sur le disque (32 octets)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirEntryRaw(pub [u8; 32]);
+
+impl DirEntryRaw {
+    /// Entrée décodée
+    pub fn entry(&self) -> Option<DirEntry> {
+        DirEntry::from_bytes(&self.0)
+    }
+}
+
+/// Nom long en cours de lecture, dans l'ordre des entrées LFN
+struct LongName {
+    units: [u16; LFN_UNITS],
+    len: usize,
+    checksum: Option<u8>,
+}
+
+impl LongName {
+    fn new() -> Self {
+        LongName { units: [0; LFN_UNITS], len: 0, checksum: None }
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.checksum = None;
+    }
+
+    /// Ajoute une entrée LFN; une séquence incohérente efface le nom
+    fn push(&mut self, raw: &[u8; 32]) {
+        let last = raw[0] & 0x40 != 0;
+        if last {
+            self.clear();
+        }
+        let order = (raw[0] & 0x1F) as usize;
+        if order == 0 || order * 13 > LFN_UNITS || self.checksum.is_some_and(|c| c != raw[13]) {
+            self.clear();
+            return;
+        }
+        let base = (order - 1) * 13;
+        for (unit, &at) in self.units[base..base + 13].iter_mut().zip(&LFN_OFFSETS) {
+            *unit = u16::from_le_bytes([raw[at], raw[at + 1]]);
+        }
+        if last {
+            // La dernière entrée du nom vient en premier et en fixe la longueur
+            self.len = self.units[base..base + 13].iter().position(|&u| u == 0).map_or(base + 13, |i| base + i);
+        }
+        self.checksum = Some(raw[13]);
+    }
+
+    /// Compare à `name` sans tenir compte de la casse (voir `casefold`)
+    fn matches(&self, name: &str) -> bool {
+        char::decode_utf16(self.units[..self.len].iter().copied())
+            .map(|c| casefold::fold_char(c.unwrap_or(char::REPLACEMENT_CHARACTER)))
+            .eq(name.chars().map(casefold::fold_char))
+    }
+}
+
+/// Nom court affiché (NAME.EXT) dans un tampon fixe
+struct ShortName {
+    buf: [u8; 24],
+    len: usize,
+}
+
+impl fmt::Write for ShortName {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buf.get_mut(self.len..end).ok_or(fmt::Error)?.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+fn short_name_matches(entry: &DirEntry, name: &str) -> bool {
+    let mut short = ShortName { buf: [0; 24], len: 0 };
+    entry.write_name(&mut short).is_ok()
+        && core::str::from_utf8(&short.buf[..short.len]).is_ok_and(|short| casefold::eq_ignore_case(short, name))
+}
+
+impl<'a> Fat32<'a> {
+    /// Appelle `f` sur chaque cluster de la chaîne, sans la collecter
+    fn for_each_cluster(&self, start: u32, mut f: impl FnMut(&[u8]) -> ControlFlow<()>) -> Result<(), FsError> {
+        let fat = self.fat_table();
+        let mut current = start;
+        let mut visited = 0;
+        while current >= 2 {
+            if visited >= self.limits.max_chain_length {
+                return Err(FsError::LimitExceeded);
+            }
+            self.check_cancel()?;
+            visited += 1;
+            self.stats.update(|s| s.fat_lookups += 1);
+            if f(self.read_cluster(current)).is_break() {
+                break;
+            }
+            current = match fat.get_entry(current) {
+                FatEntry::Data(next) => next,
+                FatEntry::Invalid(next) => return Err(FsError::CorruptFat { cluster: current, next }),
+                _ => break,
+            };
+        }
+        Ok(())
+    }
+
+    /// Appelle `f` sur chaque entrée de 32 octets d'un répertoire, jusqu'à l'entrée de fin
+    ///
+    /// Avec `full_scan`, toute la chaîne est parcourue et les entrées libres sont sautées.
+    fn for_each_raw_entry(&self, cluster: u32, mut f: impl FnMut(&[u8; 32]) -> ControlFlow<()>) -> Result<(), FsError> {
+        let cluster = if cluster == 0 { self.root_cluster() } else { cluster };
+        self.for_each_cluster(cluster, |data| {
+            for chunk in data.chunks_exact(32) {
+                let raw: &[u8; 32] = match chunk.try_into() {
+                    Ok(raw) => raw,
+                    Err(_) => break,
+                };
+                if raw[0] == 0x00 {
+                    if !self.options.full_scan {
+                        return ControlFlow::Break(());
+                    }
+                    continue;
+                }
+                f(raw)?;
+            }
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Copie les entrées visibles du répertoire `cluster` dans `out`
+    ///
+    /// Les noms longs, entrées supprimées et label sont omis. Retourne le
+    /// nombre total d'entrées: s'il dépasse `out.len()`, seules les premières
+    /// ont été copiées.
+    pub fn read_dir_into(&self, cluster: u32, out: &mut [DirEntryRaw]) -> Result<usize, FsError> {
+        let mut count = 0;
+        self.for_each_raw_entry(cluster, |raw| {
+            let visible = raw[11] != ATTR_LONG_NAME && DirEntry::from_bytes(raw).is_some_and(|e| !e.is_volume_label());
+            if visible {
+                if let Some(slot) = out.get_mut(count) {
+                    *slot = DirEntryRaw(*raw);
+                }
+                count += 1;
+            }
+            ControlFlow::Continue(())
+        })?;
+        if count > self.limits.max_dir_entries {
+            return Err(FsError::LimitExceeded);
+        }
+        Ok(count)
+    }
+
+    /// Cherche `name` (nom long ou court, insensible à la casse) dans un répertoire
+    pub fn find_entry_in(&self, dir_cluster: u32, name: &str) -> Result<Option<DirEntry>, FsError> {
+        let mut long_name = LongName::new();
+        let mut found = None;
+        self.for_each_raw_entry(dir_cluster, |raw| {
+            if raw[11] == ATTR_LONG_NAME && raw[0] != 0xE5 {
+                long_name.push(raw);
+                return ControlFlow::Continue(());
+            }
+            let entry = DirEntry::from_bytes(raw).filter(|e| !e.is_volume_label());
+            let short: &[u8; 11] = match raw[..11].try_into() {
+                Ok(short) => short,
+                Err(_) => return ControlFlow::Continue(()),
+            };
+            let long_ok = long_name.len > 0 && long_name.checksum == Some(lfn_checksum(short));
+            if let Some(entry) = entry {
+                if (long_ok && long_name.matches(name)) || short_name_matches(&entry, name) {
+                    found = Some(entry);
+                    return ControlFlow::Break(());
+                }
+            }
+            long_name.clear();
+            ControlFlow::Continue(())
+        })?;
+        Ok(found)
+    }
+
+    /// Résout un chemin avec une pile fixe de `NOALLOC_MAX_DEPTH` entrées
+    ///
+    /// `..` remonte dans la pile quand c'est possible, sinon il est cherché
+    /// sur le disque comme avec `try_resolve_path`.
+    pub fn resolve_path_in(&self, path: &str, current_cluster: u32) -> Result<DirEntry, FsError> {
+        let path = path.trim();
+        let (start, rest) = match path.strip_prefix('/') {
+            Some(rest) => (self.root_cluster(), rest),
+            None => (current_cluster, path),
+        };
+        let max_depth = NOALLOC_MAX_DEPTH.min(self.limits.max_path_depth);
+
+        let mut stack: [Option<DirEntry>; NOALLOC_MAX_DEPTH] = [const { None }; NOALLOC_MAX_DEPTH];
+        let mut depth = 0;
+        for component in rest.split('/').filter(|c| !c.is_empty()) {
+            match component {
+                "." => continue,
+                ".." if depth > 0 => {
+                    depth -= 1;
+                    continue;
+                }
+                _ => {}
+            }
+            if depth == max_depth {
+                return Err(FsError::LimitExceeded);
+            }
+            let dir = match depth.checked_sub(1).and_then(|top| stack[top].as_ref()) {
+                None => start,
+                Some(parent) if parent.is_directory() => parent.cluster(),
+                Some(_) => return Err(FsError::NotFound),
+            };
+            stack[depth] = Some(self.find_entry_in(dir, component)?.ok_or(FsError::NotFound)?);
+            depth += 1;
+        }
+        match depth.checked_sub(1) {
+            Some(top) => stack[top].take().ok_or(FsError::NotFound),
+            None => Err(FsError::NotFound),
+        }
+    }
+
+    /// Lit le fichier `entry` à partir de `offset` dans `buf`; 0 en fin de fichier
+    ///
+    /// La chaîne est suivie depuis le début à chaque appel: des lectures
+    /// séquentielles par grands tampons sont plus rapides que des petites.
+    pub fn read_file_into(&self, entry: &DirEntry, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        if entry.is_directory() {
+            return Err(FsError::IsADirectory);
+        }
+        self.read_chain_into(entry.cluster(), entry.size as u64, offset, buf)
+    }
+
+    /// Lit les `size` premiers octets de la chaîne `start` à partir de `offset`, sans allocation
+    pub(crate) fn read_chain_into(&self, start: u32, size: u64, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        if offset >= size || buf.is_empty() {
+            return Ok(0);
+        }
+        let cluster_size = self.bytes_per_cluster() as u64;
+        let skip = offset / cluster_size;
+        let mut index = 0;
+        let mut done = 0;
+        self.for_each_cluster(start, |data| {
+            index += 1;
+            if index <= skip {
+                return ControlFlow::Continue(());
+            }
+            let at = offset + done as u64;
+            let start = (at % cluster_size) as usize;
+            let take = data.len().saturating_sub(start).min(buf.len() - done).min((size - at) as usize);
+            buf[done..done + take].copy_from_slice(&data[start..start + take]);
+            done += take;
+            if take == 0 || done == buf.len() || at + take as u64 >= size {
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        })?;
+        self.stats.update(|s| s.bytes_copied += done as u64);
+        Ok(done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::Limits;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH, LONG_NAME_PATHS, README_PATH};
+
+    #[test]
+    fn test_read_dir_into() {
+        let image = TestImage::default().long_names().fragmented().build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let root = fs.root_cluster();
+        let expected = fs.read_directory(root);
+
+        let mut entries = [DirEntryRaw::default(); 32];
+        let count = fs.read_dir_into(root, &mut entries).unwrap();
+        assert_eq!(count, expected.len());
+        for (raw, entry) in entries[..count].iter().zip(&expected) {
+            assert_eq!(raw.entry().unwrap().display_name(), entry.display_name());
+        }
+
+        // Tableau trop petit: le total est retourné, le début copié
+        let mut few = [DirEntryRaw::default(); 1];
+        assert_eq!(fs.read_dir_into(root, &mut few).unwrap(), count);
+        assert_eq!(few[0], entries[0]);
+    }
+
+    #[test]
+    fn test_resolve_path_in() {
+        let builder = TestImage::default().long_names().deep_tree(4);
+        let image = builder.build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let root = fs.root_cluster();
+        let leaf_path = builder.leaf_path();
+
+        for path in [README_PATH, LONG_NAME_PATHS[0], "/my documents/NOTES ABOUT FAT32.MD", &leaf_path] {
+            let expected = fs.resolve_path(path, root).unwrap();
+            assert_eq!(fs.resolve_path_in(path, root).unwrap().cluster(), expected.cluster(), "{}", path);
+        }
+        let leaf = fs.resolve_path_in("/DEEP/D1/./D2/../D2/D3/D4/LEAF.TXT", root).unwrap();
+        assert_eq!(leaf.display_name(), "LEAF.TXT");
+
+        assert_eq!(fs.resolve_path_in("/NOPE", root).unwrap_err(), FsError::NotFound);
+        assert_eq!(fs.resolve_path_in("/README.TXT/X", root).unwrap_err(), FsError::NotFound);
+        let fs = fs.with_limits(Limits { max_path_depth: 2, ..Limits::default() });
+        assert_eq!(fs.resolve_path_in("/DEEP/D1/D2", root).unwrap_err(), FsError::LimitExceeded);
+        assert!(fs.resolve_path_in("/DEEP/D1/../D1", root).is_ok());
+    }
+
+    #[test]
+    fn test_read_file_into() {
+        let image = TestImage::default().fragmented().build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let entry = fs.resolve_path_in(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+        let expected = fs.read_file(&entry);
+
+        // Lecture par morceaux qui ne tombent pas sur les limites de clusters
+        let mut data = [0u8; 8192];
+        let mut chunk = [0u8; 700];
+        let mut offset = 0;
+        loop {
+            let read = fs.read_file_into(&entry, offset as u64, &mut chunk).unwrap();
+            if read == 0 {
+                break;
+            }
+            data[offset..offset + read].copy_from_slice(&chunk[..read]);
+            offset += read;
+        }
+        assert_eq!(&data[..offset], &expected[..]);
+
+        let dir = fs.resolve_path_in("/", fs.root_cluster());
+        assert_eq!(dir.unwrap_err(), FsError::NotFound);
+    }
+}
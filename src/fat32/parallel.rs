@@ -0,0 +1,120 @@
+//! Opérations sur toute l'image réparties sur plusieurs threads (feature std)
+//!
+//! Les fichiers ont des chaînes indépendantes: empreintes, vérifications de
+//! surface et recherche de doublons se répartissent sans coordination. Les
+//! threads partagent le même `Fat32` (voir le module `sync`), avec son cache,
+//! ses compteurs et son test d'annulation.
+
+extern crate std;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{DirEntry, Fat32};
+use crate::checksum::Sha256;
+
+/// Empreinte d'un fichier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHash {
+    /// Chemin absolu, avec les noms longs
+    pub path: String,
+    pub size: u32,
+    /// SHA-256 du contenu (vide si la lecture a échoué)
+    pub sha256: Vec<u8>,
+}
+
+impl<'a> Fat32<'a> {
+    /// Applique `f` à chaque fichier de l'arborescence sur `threads` threads
+    ///
+    /// Les résultats sont rendus dans l'ordre du parcours, avec le chemin de
+    /// chaque fichier. Une panique dans `f` est propagée à l'appelant.
+    pub fn map_files_parallel<T, F>(&self, threads: usize, f: F) -> Vec<(String, T)>
+    where
+        T: Send,
+        F: Fn(&Fat32<'a>, &DirEntry) -> T + Sync,
+    {
+        let files: Vec<(String, DirEntry)> = self.walk_tree().into_iter().filter(|(_, e)| !e.is_directory()).collect();
+        let next = AtomicUsize::new(0);
+
+        let mut results: Vec<(usize, T)> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.clamp(1, files.len().max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            match files.get(index) {
+                                Some((_, entry)) => done.push((index, f(self, entry))),
+                                None => return done,
+                            }
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+                .collect()
+        });
+
+        results.sort_by_key(|(index, _)| *index);
+        files.into_iter().zip(results).map(|((path, _), (_, value))| (path, value)).collect()
+    }
+
+    /// SHA-256 de tous les fichiers, calculés sur `threads` threads et triés par chemin
+    pub fn hash_files_parallel(&self, threads: usize) -> Vec<FileHash> {
+        let mut hashes: Vec<FileHash> = self
+            .map_files_parallel(threads, |fs, entry| (entry.size, fs.checksum_file(entry, Sha256::new())))
+            .into_iter()
+            .map(|(path, (size, sha256))| FileHash { path, size, sha256 })
+            .collect();
+        hashes.sort_by(|a, b| a.path.cmp(&b.path));
+        hashes
+    }
+
+    /// Groupes de fichiers non vides au contenu identique (même taille et même SHA-256)
+    ///
+    /// Chaque groupe est trié par chemin, les groupes par leur premier chemin.
+    pub fn find_duplicates_parallel(&self, threads: usize) -> Vec<Vec<String>> {
+        let mut groups: BTreeMap<(u32, Vec<u8>), Vec<String>> = BTreeMap::new();
+        for hash in self.hash_files_parallel(threads) {
+            if hash.size > 0 && !hash.sha256.is_empty() {
+                groups.entry((hash.size, hash.sha256)).or_default().push(hash.path);
+            }
+        }
+        let mut duplicates: Vec<Vec<String>> = groups.into_values().filter(|paths| paths.len() > 1).collect();
+        duplicates.sort();
+        duplicates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, LONG_NAME_PATHS, README_PATH};
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let mut image = TestImage::default().long_names().fragmented().deep_tree(3).build().unwrap();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+        let docs = fs.resolve_path("/My Documents", root).unwrap().cluster();
+        fs.write_file(docs, "copy of readme.txt", crate::testimg::README_TEXT).unwrap();
+        fs.write_file(root, "EMPTY1", b"").unwrap();
+        fs.write_file(root, "EMPTY2", b"").unwrap();
+
+        let hashes = fs.hash_files_parallel(4);
+        assert!(hashes.len() > 4);
+        for hash in &hashes {
+            let entry = fs.resolve_path(&hash.path, root).unwrap();
+            assert_eq!(hash.sha256, fs.checksum_file(&entry, Sha256::new()));
+        }
+        assert_eq!(hashes, fs.hash_files_parallel(1));
+        assert!(hashes.iter().any(|h| h.path == LONG_NAME_PATHS[1]));
+
+        let duplicates = fs.find_duplicates_parallel(3);
+        assert_eq!(duplicates, [[String::from("/My Documents/copy of readme.txt"), String::from(README_PATH)]]);
+    }
+}
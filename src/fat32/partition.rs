@@ -0,0 +1,321 @@
+//! Tables de partitions MBR et GPT, pour ouvrir une image de disque entier
+//!
+//! Les positions sont comptées en secteurs de 512 octets, la taille logique
+//! des images de disque et des cartes SD.
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use super::Fat32;
+
+/// Taille d'un secteur de la table de partitions
+const SECTOR: u64 = 512;
+
+/// Type MBR d'une table GPT (MBR protecteur)
+const MBR_PROTECTIVE: u8 = 0xEE;
+
+/// Nombre maximal de partitions logiques suivies dans une partition étendue
+const MAX_LOGICAL: usize = 128;
+
+/// Nombre maximal d'entrées GPT lues
+const MAX_GPT_ENTRIES: u32 = 1024;
+
+/// Taille maximale acceptée pour une entrée GPT (128 octets en pratique)
+const MAX_GPT_ENTRY_SIZE: usize = 4096;
+
+/// Type d'une partition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionType {
+    /// Octet de type d'une entrée MBR
+    Mbr(u8),
+    /// GUID de type d'une entrée GPT (octets tels que stockés sur le disque)
+    Gpt([u8; 16]),
+}
+
+impl PartitionType {
+    /// Nom usuel du type, s'il est connu
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            PartitionType::Mbr(kind) => match kind {
+                0x01 => Some("FAT12"),
+                0x04 | 0x06 | 0x0E => Some("FAT16"),
+                0x05 | 0x0F | 0x85 => Some("Extended"),
+                0x07 => Some("NTFS/exFAT"),
+                0x0B | 0x0C => Some("FAT32"),
+                0x82 => Some("Linux swap"),
+                0x83 => Some("Linux"),
+                0xEF => Some("EFI System"),
+                _ => None,
+            },
+            PartitionType::Gpt(_) => match format!("{}", self).as_str() {
+                "C12A7328-F81F-11D2-BA4B-00A0C93EC93B" => Some("EFI System"),
+                "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7" => Some("Microsoft basic data"),
+                "0FC63DAF-8483-4772-8E79-3D69D8477DE4" => Some("Linux filesystem"),
+                "0657FD6D-A4AB-43C4-84E5-0933C84B4F4F" => Some("Linux swap"),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for PartitionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionType::Mbr(kind) => write!(f, "0x{:02X}", kind),
+            PartitionType::Gpt(g) => write!(
+                f,
+                // Les trois premiers champs du GUID sont en little-endian
+                "{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+                g[3], g[2], g[1], g[0], g[5], g[4], g[7], g[6],
+                g[8], g[9], g[10], g[11], g[12], g[13], g[14], g[15],
+            ),
+        }
+    }
+}
+
+/// Partition d'une image de disque
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// Numéro de la partition (1 à 4 pour les primaires MBR, 5+ pour les logiques)
+    pub index: usize,
+    pub kind: PartitionType,
+    /// Position du début de la partition, en octets
+    pub offset: u64,
+    /// Taille en octets
+    pub size: u64,
+    /// Nom de la partition (GPT uniquement)
+    pub name: String,
+}
+
+impl Partition {
+    /// Plage d'octets de la partition dans `disk`, si elle tient dans l'image
+    pub fn range(&self, disk: &[u8]) -> Option<Range<usize>> {
+        let start = usize::try_from(self.offset).ok()?;
+        let end = start.checked_add(usize::try_from(self.size).ok()?)?;
+        (end <= disk.len()).then_some(start..end)
+    }
+}
+
+/// Lit la table de partitions de `disk`
+///
+/// Retourne `None` si l'image n'a pas de table: pas de signature, ou un
+/// filesystem FAT32 directement en tête (image de partition seule).
+pub fn read_partitions(disk: &[u8]) -> Option<Vec<Partition>> {
+    let mbr = disk.get(..SECTOR as usize)?;
+    if mbr[510] != 0x55 || mbr[511] != 0xAA || Fat32::parse_boot_sector(disk).is_ok() {
+        return None;
+    }
+
+    let entries = mbr_entries(mbr)?;
+    if entries.iter().any(|(kind, _, _)| *kind == MBR_PROTECTIVE) {
+        return gpt_partitions(disk);
+    }
+
+    let mut partitions = Vec::new();
+    for (i, &(kind, start, sectors)) in entries.iter().enumerate() {
+        if kind == 0 {
+            continue;
+        }
+        partitions.push(mbr_partition(i + 1, kind, start as u64, sectors));
+        if matches!(kind, 0x05 | 0x0F | 0x85) {
+            logical_partitions(disk, start, &mut partitions);
+        }
+    }
+    Some(partitions)
+}
+
+/// Entrées `(type, premier secteur, nombre de secteurs)` d'un secteur MBR ou EBR
+///
+/// Un indicateur d'amorçage autre que 0x00/0x80 signale que ce n'est pas une table.
+fn mbr_entries(sector: &[u8]) -> Option<[(u8, u32, u32); 4]> {
+    let mut entries = [(0, 0, 0); 4];
+    for (i, entry) in entries.iter_mut().enumerate() {
+        let raw = &sector[446 + i * 16..462 + i * 16];
+        if raw[0] != 0x00 && raw[0] != 0x80 {
+            return None;
+        }
+        let start = u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]);
+        let sectors = u32::from_le_bytes([raw[12], raw[13], raw[14], raw[15]]);
+        *entry = (raw[4], start, sectors);
+    }
+    Some(entries)
+}
+
+fn mbr_partition(index: usize, kind: u8, start: u64, sectors: u32) -> Partition {
+    Partition {
+        index,
+        kind: PartitionType::Mbr(kind),
+        offset: start * SECTOR,
+        size: sectors as u64 * SECTOR,
+        name: String::new(),
+    }
+}
+
+/// Suit la chaîne des EBR d'une partition étendue commençant au secteur `extended`
+fn logical_partitions(disk: &[u8], extended: u32, partitions: &mut Vec<Partition>) {
+    let mut ebr = extended as u64;
+    for index in 5..5 + MAX_LOGICAL {
+        let sector = match disk.get((ebr * SECTOR) as usize..((ebr + 1) * SECTOR) as usize) {
+            Some(sector) if sector[510] == 0x55 && sector[511] == 0xAA => sector,
+            _ => return,
+        };
+        let entries = match mbr_entries(sector) {
+            Some(entries) => entries,
+            None => return,
+        };
+
+        // Première entrée: la partition, relative à l'EBR; seconde: l'EBR suivant, relatif à l'étendue
+        let (kind, start, sectors) = entries[0];
+        if kind != 0 {
+            partitions.push(mbr_partition(index, kind, ebr + start as u64, sectors));
+        }
+        let (next_kind, next, _) = entries[1];
+        if next_kind == 0 || next == 0 {
+            return;
+        }
+        ebr = extended as u64 + next as u64;
+    }
+}
+
+/// Partitions d'une table GPT (en-tête au secteur 1)
+fn gpt_partitions(disk: &[u8]) -> Option<Vec<Partition>> {
+    let header = disk.get(SECTOR as usize..2 * SECTOR as usize)?;
+    if &header[0..8] != b"EFI PART" {
+        return None;
+    }
+
+    let read_u32 = |at: usize| u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]]);
+    let entries_lba = u64::from_le_bytes(header[72..80].try_into().ok()?);
+    let count = read_u32(80).min(MAX_GPT_ENTRIES);
+    let entry_size = read_u32(84) as usize;
+    if !(128..=MAX_GPT_ENTRY_SIZE).contains(&entry_size) {
+        return None;
+    }
+
+    let mut partitions = Vec::new();
+    for i in 0..count as usize {
+        let start = usize::try_from(entries_lba.checked_mul(SECTOR)?).ok()?.checked_add(i.checked_mul(entry_size)?)?;
+        let raw = match disk.get(start..start.saturating_add(128)) {
+            Some(raw) => raw,
+            None => break,
+        };
+        let kind: [u8; 16] = raw[0..16].try_into().ok()?;
+        if kind == [0; 16] {
+            continue;
+        }
+
+        let first = u64::from_le_bytes(raw[32..40].try_into().ok()?);
+        let last = u64::from_le_bytes(raw[40..48].try_into().ok()?);
+        let units = raw[56..128].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).take_while(|&u| u != 0);
+        partitions.push(Partition {
+            index: i + 1,
+            kind: PartitionType::Gpt(kind),
+            offset: first.saturating_mul(SECTOR),
+            size: last.saturating_sub(first).saturating_add(1).saturating_mul(SECTOR),
+            name: char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect(),
+        });
+    }
+    Some(partitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::{mkfs, FormatOptions};
+
+    /// Écrit une entrée MBR (ou EBR) à la position `slot`
+    fn set_entry(sector: &mut [u8], slot: usize, kind: u8, start: u32, sectors: u32) {
+        let raw = &mut sector[446 + slot * 16..462 + slot * 16];
+        raw[4] = kind;
+        raw[8..12].copy_from_slice(&start.to_le_bytes());
+        raw[12..16].copy_from_slice(&sectors.to_le_bytes());
+    }
+
+    fn disk_with_mbr(size: usize) -> Vec<u8> {
+        let mut disk = alloc::vec![0u8; size];
+        disk[510] = 0x55;
+        disk[511] = 0xAA;
+        disk
+    }
+
+    #[test]
+    fn test_mbr_partitions() {
+        let mut disk = disk_with_mbr(4 * 1024 * 1024);
+        set_entry(&mut disk, 0, 0x0C, 2048, 4096);
+        set_entry(&mut disk, 1, 0x05, 6144, 64);
+        // Deux partitions logiques dans l'étendue
+        let ebr = 6144 * 512;
+        disk[ebr + 510] = 0x55;
+        disk[ebr + 511] = 0xAA;
+        set_entry(&mut disk[ebr..], 0, 0x83, 1, 8);
+        set_entry(&mut disk[ebr..], 1, 0x05, 16, 32);
+        let ebr2 = (6144 + 16) * 512;
+        disk[ebr2 + 510] = 0x55;
+        disk[ebr2 + 511] = 0xAA;
+        set_entry(&mut disk[ebr2..], 0, 0x0B, 1, 4);
+
+        mkfs::format(&mut disk[2048 * 512..6144 * 512], &FormatOptions::default()).unwrap();
+        let partitions = read_partitions(&disk).unwrap();
+        let summary: Vec<(usize, Option<&str>, u64, u64)> = partitions.iter()
+            .map(|p| (p.index, p.kind.name(), p.offset / 512, p.size / 512))
+            .collect();
+        assert_eq!(summary, [
+            (1, Some("FAT32"), 2048, 4096),
+            (2, Some("Extended"), 6144, 64),
+            (5, Some("Linux"), 6145, 8),
+            (6, Some("FAT32"), 6161, 4),
+        ]);
+
+        let range = partitions[0].range(&disk).unwrap();
+        assert!(Fat32::new(&disk[range]).is_some());
+        assert!(read_partitions(&disk[2048 * 512..]).is_none());
+    }
+
+    #[test]
+    fn test_gpt_partitions() {
+        let mut disk = disk_with_mbr(64 * 1024);
+        set_entry(&mut disk, 0, MBR_PROTECTIVE, 1, 127);
+        disk[512..520].copy_from_slice(b"EFI PART");
+        disk[512 + 72..512 + 80].copy_from_slice(&2u64.to_le_bytes());
+        disk[512 + 80..512 + 84].copy_from_slice(&4u32.to_le_bytes());
+        disk[512 + 84..512 + 88].copy_from_slice(&128u32.to_le_bytes());
+
+        // Microsoft basic data, secteurs 34 à 99, nommée "DATA"
+        let entry = &mut disk[1024 + 128..1024 + 256];
+        entry[0..16].copy_from_slice(&[
+            0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7,
+        ]);
+        entry[32..40].copy_from_slice(&34u64.to_le_bytes());
+        entry[40..48].copy_from_slice(&99u64.to_le_bytes());
+        for (i, c) in "DATA".encode_utf16().enumerate() {
+            entry[56 + i * 2..58 + i * 2].copy_from_slice(&c.to_le_bytes());
+        }
+
+        let partitions = read_partitions(&disk).unwrap();
+        assert_eq!(partitions.len(), 1);
+        let partition = &partitions[0];
+        assert_eq!((partition.index, partition.offset, partition.size), (2, 34 * 512, 66 * 512));
+        assert_eq!(partition.name, "DATA");
+        assert_eq!(format!("{}", partition.kind), "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7");
+        assert_eq!(partition.kind.name(), Some("Microsoft basic data"));
+        assert!(partition.range(&disk[..40 * 512]).is_none());
+
+        // Taille d'entrée hostile: refusée plutôt qu'un débordement sur 32 bits
+        disk[512 + 84..512 + 88].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read_partitions(&disk).is_none());
+    }
+
+    #[test]
+    fn test_no_partition_table() {
+        assert!(read_partitions(&[0u8; 100]).is_none());
+        assert!(read_partitions(&alloc::vec![0u8; 1024]).is_none());
+
+        let mut disk = disk_with_mbr(1024);
+        disk[446] = 0x12;
+        assert!(read_partitions(&disk).is_none());
+    }
+}
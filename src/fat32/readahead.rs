@@ -0,0 +1,163 @@
+//! Lecture anticipée pour les accès séquentiels à un `BlockDevice`
+//!
+//! Dès que deux secteurs consécutifs sont demandés, `ReadAhead` charge les
+//! secteurs suivants dans un tampon, en un seul `read_sectors`: la lecture
+//! d'un fichier contigu (audio, firmware) ne paie plus un aller-retour vers
+//! la carte par secteur. Les accès aléatoires passent directement au
+//! périphérique.
+
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::device::BlockDevice;
+use super::error::FsError;
+use super::stats::IoStats;
+
+/// Enveloppe de `D` avec un tampon de lecture anticipée de `window` secteurs
+#[derive(Debug)]
+pub struct ReadAhead<D> {
+    inner: D,
+    window: usize,
+    buffer: Vec<u8>,
+    /// Premier secteur du tampon et nombre de secteurs valides
+    start: u64,
+    len: usize,
+    /// Dernier secteur demandé, pour détecter un accès séquentiel
+    last: Option<u64>,
+    stats: IoStats,
+}
+
+impl<D: BlockDevice> ReadAhead<D> {
+    /// Anticipe `window` secteurs (au moins un) après deux lectures consécutives
+    pub fn new(inner: D, window: usize) -> Self {
+        let window = window.max(1);
+        let buffer = vec![0u8; window * inner.sector_size()];
+        ReadAhead { inner, window, buffer, start: 0, len: 0, last: None, stats: IoStats::default() }
+    }
+
+    /// Secteurs lus sur le périphérique et lectures servies ou non par le tampon
+    pub fn stats(&self) -> IoStats {
+        self.stats
+    }
+
+    /// Remet les compteurs à zéro
+    pub fn reset_stats(&mut self) {
+        self.stats = IoStats::default();
+    }
+
+    /// Rend le périphérique enveloppé
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// Position de `sector` dans le tampon, s'il y est
+    fn buffered(&self, sector: u64) -> Option<usize> {
+        let index = sector.checked_sub(self.start)?;
+        (index < self.len as u64).then(|| index as usize * self.inner.sector_size())
+    }
+
+    /// Remplit le tampon à partir de `sector`, sans dépasser la fin du périphérique
+    fn fill(&mut self, sector: u64) -> Result<(), FsError> {
+        let size = self.inner.sector_size();
+        let count = self.inner.sector_count().saturating_sub(sector).min(self.window as u64) as usize;
+        if self.len > 0 {
+            log_trace!("read-ahead window moved from sector {} to {}", self.start, sector);
+        }
+        self.len = 0;
+        self.inner.read_sectors(sector, &mut self.buffer[..count * size])?;
+        self.stats.sectors_read += count as u64;
+        self.start = sector;
+        self.len = count;
+        Ok(())
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for ReadAhead<D> {
+    fn sector_size(&self) -> usize {
+        self.inner.sector_size()
+    }
+
+    fn sector_count(&self) -> u64 {
+        self.inner.sector_count()
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let sequential = self.last.is_some_and(|last| last.checked_add(1) == Some(sector));
+        self.last = Some(sector);
+
+        if self.buffered(sector).is_some() {
+            self.stats.cache_hits += 1;
+        } else {
+            self.stats.cache_misses += 1;
+            if !sequential {
+                self.inner.read_sector(sector, buf)?;
+                self.stats.sectors_read += 1;
+                return Ok(());
+            }
+            self.fill(sector)?;
+        }
+
+        let size = self.inner.sector_size();
+        let at = self.buffered(sector).ok_or(FsError::OutOfBounds)?;
+        buf.get_mut(..size).ok_or(FsError::OutOfBounds)?.copy_from_slice(&self.buffer[at..at + size]);
+        self.stats.bytes_copied += size as u64;
+        Ok(())
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError> {
+        self.inner.write_sector(sector, buf)?;
+        // Le tampon reste cohérent avec le périphérique
+        if let Some(at) = self.buffered(sector) {
+            let size = self.inner.sector_size();
+            self.buffer[at..at + size].copy_from_slice(&buf[..size]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::device::SECTOR_SIZE;
+    use crate::testimg::faulty::FaultyDevice;
+
+    fn disk(sectors: usize) -> Vec<u8> {
+        (0..sectors * SECTOR_SIZE).map(|i| (i / SECTOR_SIZE) as u8).collect()
+    }
+
+    #[test]
+    fn test_sequential_reads_hit_the_buffer() {
+        let mut data = disk(20);
+        let mut device = ReadAhead::new(FaultyDevice::new(&mut data), 8);
+        let mut buf = [0u8; SECTOR_SIZE];
+        for sector in 0..20 {
+            device.read_sector(sector, &mut buf).unwrap();
+            assert_eq!(buf, [sector as u8; SECTOR_SIZE]);
+        }
+        // Secteur 0 lu directement, puis 1, 9 et 17 remplissent le tampon
+        assert_eq!((device.stats().cache_hits, device.stats().cache_misses), (16, 4));
+        assert_eq!(device.stats().sectors_read, 20);
+        assert_eq!(device.inner.operations(), 4);
+        device.reset_stats();
+        assert_eq!(device.stats(), IoStats::default());
+
+        device.write_sector(18, &[0xAA; SECTOR_SIZE]).unwrap();
+        device.read_sector(18, &mut buf).unwrap();
+        assert_eq!(buf, [0xAA; SECTOR_SIZE]);
+        assert_eq!(device.read_sector(20, &mut buf), Err(FsError::OutOfBounds));
+    }
+
+    #[test]
+    fn test_random_reads_skip_prefetch() {
+        let mut data = disk(20);
+        let mut device = ReadAhead::new(FaultyDevice::new(&mut data), 8);
+        let mut buf = [0u8; SECTOR_SIZE];
+        for sector in [5, 2, 17, 9, 0] {
+            device.read_sector(sector, &mut buf).unwrap();
+            assert_eq!(buf, [sector as u8; SECTOR_SIZE]);
+        }
+        assert_eq!(device.stats().cache_hits, 0);
+        assert_eq!(device.into_inner().operations(), 5);
+    }
+}
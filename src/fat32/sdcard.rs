@@ -0,0 +1,528 @@
+//! Carte SD en mode SPI vue comme un `BlockDevice` (feature sdcard)
+//!
+//! Le bus est décrit par `SpiTransport`: un octet échangé et la sélection de
+//! la carte. Avec la feature embedded-hal, le couple `(SpiDevice, OutputPin)`
+//! l'implémente déjà: `SdCard::init((spi, cs))` suffit. Une lecture de
+//! plusieurs secteurs est une seule commande CMD18; les lectures isolées
+//! utilisent CMD17 et les écritures CMD24.
+//!
+//! `SdCard::init` fait la séquence de démarrage d'une vraie carte (CMD0,
+//! CMD8, ACMD41, CMD58, puis CMD9 pour la capacité). L'horloge SPI doit être
+//! entre 100 et 400 kHz pendant `init`; elle peut ensuite monter à 25 MHz.
+//! Sans embedded-hal (RP2040 ou autre carte de développement), il suffit
+//! d'implémenter `SpiTransport` pour le périphérique SPI et la broche CS:
+//!
+//! ```no_run
+//! use fat32_exam::fat32::{BlockDevice, BootSector, FsError, SdCard, SpiTransport};
+//!
+//! /// Bus de la carte: registres SPI et broche CS de la carte cible
+//! struct Board;
+//!
+//! impl SpiTransport for Board {
+//!     fn transfer(&mut self, byte: u8) -> Result<u8, FsError> {
+//!         // Écrire `byte` dans le registre de données, attendre la fin de
+//!         // l'échange et retourner l'octet reçu
+//!         Ok(byte)
+//!     }
+//!
+//!     fn select(&mut self, _selected: bool) -> Result<(), FsError> {
+//!         // CS bas quand la carte est sélectionnée
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut card = SdCard::init(Board)?;
+//! let mut sector = [0u8; 512];
+//! card.read_sector(0, &mut sector)?;
+//! let boot_sector = BootSector::from_bytes(&sector).ok_or(FsError::InvalidGeometry)?;
+//! # Ok::<(), FsError>(())
+//! ```
+//!
+//! Avec la feature async, la carte se monte directement:
+//! `AsyncFat32::mount(Blocking(SdCard::init(spi)?))`.
+
+use super::device::{BlockDevice, SECTOR_SIZE};
+use super::error::FsError;
+
+/// Liaison SPI avec la carte
+pub trait SpiTransport {
+    /// Envoie `byte` et retourne l'octet reçu pendant l'échange
+    fn transfer(&mut self, byte: u8) -> Result<u8, FsError>;
+
+    /// Sélectionne (CS bas) ou libère (CS haut) la carte
+    fn select(&mut self, selected: bool) -> Result<(), FsError>;
+}
+
+/// Bus embedded-hal et broche CS de la carte (feature embedded-hal)
+///
+/// La carte doit rester sélectionnée sur plusieurs échanges: `cs` est piloté
+/// ici, et `spi` ne doit pas avoir sa propre broche CS (par exemple un
+/// `ExclusiveDevice` d'embedded-hal-bus construit avec `new_no_delay` sur une
+/// broche factice).
+#[cfg(feature = "embedded-hal")]
+impl<S, P> SpiTransport for (S, P)
+where
+    S: embedded_hal::spi::SpiDevice,
+    P: embedded_hal::digital::OutputPin,
+{
+    fn transfer(&mut self, byte: u8) -> Result<u8, FsError> {
+        let mut buf = [byte];
+        self.0.transfer_in_place(&mut buf).map_err(|_| FsError::Io)?;
+        Ok(buf[0])
+    }
+
+    fn select(&mut self, selected: bool) -> Result<(), FsError> {
+        if selected { self.1.set_low() } else { self.1.set_high() }.map_err(|_| FsError::Io)
+    }
+}
+
+const CMD_GO_IDLE_STATE: u8 = 0;
+const CMD_SEND_IF_COND: u8 = 8;
+const CMD_SEND_CSD: u8 = 9;
+const CMD_STOP_TRANSMISSION: u8 = 12;
+const CMD_SET_BLOCKLEN: u8 = 16;
+const CMD_READ_SINGLE_BLOCK: u8 = 17;
+const CMD_READ_MULTIPLE_BLOCK: u8 = 18;
+const CMD_WRITE_BLOCK: u8 = 24;
+const CMD_APP_CMD: u8 = 55;
+const CMD_READ_OCR: u8 = 58;
+/// Commande applicative (précédée de CMD55)
+const ACMD_SD_SEND_OP_COND: u8 = 41;
+
+/// R1: carte en cours d'initialisation
+const R1_IDLE: u8 = 0x01;
+/// R1: commande inconnue (CMD8 sur une carte v1)
+const R1_ILLEGAL_COMMAND: u8 = 0x04;
+/// Argument de CMD8: tension 2,7-3,6 V et motif renvoyé par la carte
+const IF_COND: u32 = 0x1AA;
+/// HCS dans ACMD41, CCS dans l'OCR: carte haute capacité
+const HIGH_CAPACITY: u32 = 1 << 30;
+/// Essais de ACMD41 avant d'abandonner
+const INIT_ATTEMPTS: usize = 1000;
+
+/// Jeton qui précède un bloc de données
+const DATA_TOKEN: u8 = 0xFE;
+/// Réponse d'écriture acceptée (bits 0-4 de la réponse de données)
+const DATA_ACCEPTED: u8 = 0x05;
+/// Octets échangés au plus en attendant une réponse, un jeton ou la fin d'une écriture
+const MAX_POLLS: usize = 50_000;
+
+/// Carte SD initialisée, adressée par secteurs de 512 octets
+#[derive(Debug)]
+pub struct SdCard<S> {
+    spi: S,
+    sectors: u64,
+    /// Cartes SDHC/SDXC: adresses en secteurs; SDSC: adresses en octets
+    block_addressing: bool,
+}
+
+impl<S: SpiTransport> SdCard<S> {
+    /// Carte déjà initialisée, de `sectors` secteurs
+    pub fn new(spi: S, sectors: u64, block_addressing: bool) -> Self {
+        SdCard { spi, sectors, block_addressing }
+    }
+
+    /// Initialise une carte qui vient d'être alimentée et lit sa capacité
+    pub fn init(mut spi: S) -> Result<Self, FsError> {
+        // Au moins 74 cycles d'horloge, carte libérée, pour passer en mode SPI
+        spi.select(false)?;
+        for _ in 0..10 {
+            spi.transfer(0xFF)?;
+        }
+        let mut card = SdCard { spi, sectors: 0, block_addressing: false };
+
+        if card.selected(|card| card.command(CMD_GO_IDLE_STATE, 0))? != R1_IDLE {
+            return Err(FsError::Io);
+        }
+        // Une carte v1 ne connaît pas CMD8; une carte v2 renvoie le motif
+        let version2 = card.selected(|card| {
+            if card.command(CMD_SEND_IF_COND, IF_COND)? & R1_ILLEGAL_COMMAND != 0 {
+                return Ok(false);
+            }
+            match card.receive_u32()? & 0xFFF {
+                IF_COND => Ok(true),
+                _ => Err(FsError::Io),
+            }
+        })?;
+
+        let op_cond = if version2 { HIGH_CAPACITY } else { 0 };
+        let mut ready = false;
+        for _ in 0..INIT_ATTEMPTS {
+            let r1 = card.selected(|card| {
+                if card.command(CMD_APP_CMD, 0)? & !R1_IDLE != 0 {
+                    return Err(FsError::Io);
+                }
+                card.command(ACMD_SD_SEND_OP_COND, op_cond)
+            })?;
+            match r1 {
+                0 => {
+                    ready = true;
+                    break;
+                }
+                R1_IDLE => {}
+                _ => return Err(FsError::Io),
+            }
+        }
+        if !ready {
+            return Err(FsError::Io);
+        }
+
+        if version2 {
+            let ocr = card.selected(|card| {
+                if card.command(CMD_READ_OCR, 0)? != 0 {
+                    return Err(FsError::Io);
+                }
+                card.receive_u32()
+            })?;
+            card.block_addressing = ocr & HIGH_CAPACITY != 0;
+        }
+        if !card.block_addressing && card.selected(|card| card.command(CMD_SET_BLOCKLEN, SECTOR_SIZE as u32))? != 0 {
+            return Err(FsError::Io);
+        }
+
+        let mut csd = [0u8; 16];
+        card.selected(|card| {
+            if card.command(CMD_SEND_CSD, 0)? != 0 {
+                return Err(FsError::Io);
+            }
+            card.receive_block(&mut csd)
+        })?;
+        card.sectors = match csd_sectors(&csd) {
+            Some(sectors) if sectors > 0 => sectors,
+            _ => return Err(FsError::InvalidGeometry),
+        };
+        Ok(card)
+    }
+
+    /// Adresses en secteurs (SDHC/SDXC) plutôt qu'en octets (SDSC)
+    pub fn block_addressing(&self) -> bool {
+        self.block_addressing
+    }
+
+    /// Rend la liaison SPI
+    pub fn into_inner(self) -> S {
+        self.spi
+    }
+
+    /// Argument des commandes de lecture et d'écriture pour `sector`
+    fn address(&self, sector: u64) -> Result<u32, FsError> {
+        if sector >= self.sectors {
+            return Err(FsError::OutOfBounds);
+        }
+        let address = if self.block_addressing { sector } else { sector * SECTOR_SIZE as u64 };
+        u32::try_from(address).map_err(|_| FsError::OutOfBounds)
+    }
+
+    /// Envoie une commande et retourne sa réponse R1
+    fn command(&mut self, index: u8, arg: u32) -> Result<u8, FsError> {
+        // Seules CMD0 et CMD8 sont vérifiées par CRC en mode SPI
+        let crc = match index {
+            0 => 0x95,
+            8 => 0x87,
+            _ => 0x01,
+        };
+        let [a, b, c, d] = arg.to_be_bytes();
+        for byte in [0x40 | index, a, b, c, d, crc] {
+            self.spi.transfer(byte)?;
+        }
+        if index == CMD_STOP_TRANSMISSION {
+            // Octet de bourrage avant la réponse
+            self.spi.transfer(0xFF)?;
+        }
+        self.response()
+    }
+
+    /// Attend une réponse R1 (bit 7 à zéro)
+    fn response(&mut self) -> Result<u8, FsError> {
+        for _ in 0..MAX_POLLS {
+            let r1 = self.spi.transfer(0xFF)?;
+            if r1 & 0x80 == 0 {
+                return Ok(r1);
+            }
+        }
+        Err(FsError::Io)
+    }
+
+    /// Attend que la carte ne soit plus occupée (octets à zéro)
+    fn wait_ready(&mut self) -> Result<(), FsError> {
+        for _ in 0..MAX_POLLS {
+            if self.spi.transfer(0xFF)? == 0xFF {
+                return Ok(());
+            }
+        }
+        Err(FsError::Io)
+    }
+
+    /// Lit les quatre octets qui suivent R1 dans les réponses R3 et R7
+    fn receive_u32(&mut self) -> Result<u32, FsError> {
+        let mut bytes = [0u8; 4];
+        for byte in bytes.iter_mut() {
+            *byte = self.spi.transfer(0xFF)?;
+        }
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    /// Reçoit un bloc de données: jeton, contenu, CRC ignoré
+    fn receive_block(&mut self, buf: &mut [u8]) -> Result<(), FsError> {
+        let mut token = 0xFF;
+        for _ in 0..MAX_POLLS {
+            token = self.spi.transfer(0xFF)?;
+            if token != 0xFF {
+                break;
+            }
+        }
+        if token != DATA_TOKEN {
+            return Err(FsError::Io);
+        }
+        for byte in buf.iter_mut() {
+            *byte = self.spi.transfer(0xFF)?;
+        }
+        self.spi.transfer(0xFF)?;
+        self.spi.transfer(0xFF)?;
+        Ok(())
+    }
+
+    /// Exécute `f` carte sélectionnée, puis la libère dans tous les cas
+    fn selected<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, FsError>) -> Result<T, FsError> {
+        self.spi.select(true)?;
+        let result = f(self);
+        self.spi.select(false)?;
+        // Huit cycles d'horloge après la libération, pour que la carte relâche MISO
+        self.spi.transfer(0xFF)?;
+        result
+    }
+}
+
+/// Nombre de secteurs de 512 octets décrit par le registre CSD
+fn csd_sectors(csd: &[u8; 16]) -> Option<u64> {
+    match csd[0] >> 6 {
+        // CSD v1 (SDSC): (C_SIZE + 1) * 2^(C_SIZE_MULT + 2) blocs de 2^READ_BL_LEN octets
+        0 => {
+            let read_bl_len = (csd[5] & 0x0F) as u32;
+            let c_size = ((csd[6] & 0x03) as u64) << 10 | (csd[7] as u64) << 2 | (csd[8] >> 6) as u64;
+            let c_size_mult = ((csd[9] & 0x03) << 1 | csd[10] >> 7) as u32;
+            Some(((c_size + 1) << (c_size_mult + 2 + read_bl_len)) / SECTOR_SIZE as u64)
+        }
+        // CSD v2 (SDHC/SDXC): (C_SIZE + 1) * 512 Kio
+        1 => {
+            let c_size = ((csd[7] & 0x3F) as u64) << 16 | (csd[8] as u64) << 8 | csd[9] as u64;
+            Some((c_size + 1) * 1024)
+        }
+        _ => None,
+    }
+}
+
+impl<S: SpiTransport> BlockDevice for SdCard<S> {
+    fn sector_count(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let address = self.address(sector)?;
+        let buf = buf.get_mut(..SECTOR_SIZE).ok_or(FsError::OutOfBounds)?;
+        self.selected(|card| {
+            if card.command(CMD_READ_SINGLE_BLOCK, address)? != 0 {
+                return Err(FsError::Io);
+            }
+            card.receive_block(buf)
+        })
+    }
+
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        if !buf.len().is_multiple_of(SECTOR_SIZE) {
+            return Err(FsError::OutOfBounds);
+        }
+        let count = (buf.len() / SECTOR_SIZE) as u64;
+        match count {
+            0 => return Ok(()),
+            1 => return self.read_sector(start, buf),
+            _ => {}
+        }
+        let address = self.address(start)?;
+        self.address(start + count - 1)?;
+
+        self.selected(|card| {
+            if card.command(CMD_READ_MULTIPLE_BLOCK, address)? != 0 {
+                return Err(FsError::Io);
+            }
+            let received = buf.chunks_mut(SECTOR_SIZE).try_for_each(|block| card.receive_block(block));
+            // La transmission est arrêtée même après une erreur de bloc
+            let stopped = card.command(CMD_STOP_TRANSMISSION, 0).and_then(|_| card.wait_ready());
+            received.and(stopped)
+        })
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError> {
+        let address = self.address(sector)?;
+        let buf = buf.get(..SECTOR_SIZE).ok_or(FsError::OutOfBounds)?;
+        self.selected(|card| {
+            if card.command(CMD_WRITE_BLOCK, address)? != 0 {
+                return Err(FsError::Io);
+            }
+            card.spi.transfer(DATA_TOKEN)?;
+            for &byte in buf {
+                card.spi.transfer(byte)?;
+            }
+            card.spi.transfer(0xFF)?;
+            card.spi.transfer(0xFF)?;
+            if card.spi.transfer(0xFF)? & 0x1F != DATA_ACCEPTED {
+                return Err(FsError::Io);
+            }
+            card.wait_ready()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::device::read_clusters;
+    use crate::fat32::BootSector;
+    use crate::fat32::Fat32;
+    use crate::testimg::sdcard::SimulatedCard;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH};
+
+    #[test]
+    fn test_reads_and_writes() {
+        let image = TestImage::default().fragmented().build().unwrap();
+        let sectors = (image.len() / SECTOR_SIZE) as u64;
+
+        for block_addressing in [true, false] {
+            let mut card = SdCard::new(SimulatedCard::new(image.clone(), block_addressing), sectors, block_addressing);
+            let mut buf = [0u8; SECTOR_SIZE];
+            card.read_sector(0, &mut buf).unwrap();
+            assert_eq!(buf[..], image[..SECTOR_SIZE]);
+
+            let mut many = alloc::vec![0u8; 8 * SECTOR_SIZE];
+            card.read_sectors(3, &mut many).unwrap();
+            assert_eq!(many, image[3 * SECTOR_SIZE..11 * SECTOR_SIZE]);
+
+            card.write_sector(5, &[0xA5; SECTOR_SIZE]).unwrap();
+            card.read_sector(5, &mut buf).unwrap();
+            assert_eq!(buf, [0xA5; SECTOR_SIZE]);
+            assert_eq!(card.read_sector(sectors, &mut buf), Err(FsError::OutOfBounds));
+            assert_eq!(card.into_inner().commands(), [17, 18, 12, 24, 17]);
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let image = TestImage::default().fragmented().build().unwrap();
+        let sectors = (image.len() / SECTOR_SIZE) as u64;
+
+        for (high_capacity, expected) in [
+            (true, &[0, 8, 55, 41, 55, 41, 55, 41, 58, 9, 17][..]),
+            (false, &[0, 8, 55, 41, 55, 41, 55, 41, 58, 16, 9, 17][..]),
+        ] {
+            let mut card = SdCard::init(SimulatedCard::powered_off(image.clone(), high_capacity)).unwrap();
+            assert_eq!(card.block_addressing(), high_capacity);
+            assert_eq!(card.sector_count(), sectors);
+
+            let mut buf = [0u8; SECTOR_SIZE];
+            card.read_sector(0, &mut buf).unwrap();
+            assert!(BootSector::from_bytes(&buf).is_some());
+            assert_eq!(card.into_inner().commands(), expected);
+        }
+    }
+
+    /// Bus et broche CS embedded-hal qui se partagent une carte simulée
+    #[cfg(feature = "embedded-hal")]
+    mod hal {
+        use super::SimulatedCard;
+        use crate::fat32::sdcard::SpiTransport;
+        use alloc::rc::Rc;
+        use core::cell::RefCell;
+        use core::convert::Infallible;
+        use embedded_hal::digital::{ErrorType as PinErrorType, OutputPin};
+        use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+
+        pub struct Spi(pub Rc<RefCell<SimulatedCard>>);
+        pub struct Cs(pub Rc<RefCell<SimulatedCard>>);
+
+        impl ErrorType for Spi {
+            type Error = Infallible;
+        }
+
+        impl SpiDevice for Spi {
+            fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+                let mut card = self.0.borrow_mut();
+                for operation in operations {
+                    if let Operation::TransferInPlace(words) = operation {
+                        for word in words.iter_mut() {
+                            *word = card.transfer(*word).unwrap();
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        impl PinErrorType for Cs {
+            type Error = Infallible;
+        }
+
+        impl OutputPin for Cs {
+            fn set_low(&mut self) -> Result<(), Infallible> {
+                self.0.borrow_mut().select(true).unwrap();
+                Ok(())
+            }
+
+            fn set_high(&mut self) -> Result<(), Infallible> {
+                self.0.borrow_mut().select(false).unwrap();
+                Ok(())
+            }
+        }
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    #[test]
+    fn test_embedded_hal_pair() {
+        use alloc::rc::Rc;
+        use core::cell::RefCell;
+
+        let image = TestImage::default().fragmented().build().unwrap();
+        let sim = Rc::new(RefCell::new(SimulatedCard::powered_off(image.clone(), true)));
+
+        let mut card = SdCard::init((hal::Spi(sim.clone()), hal::Cs(sim.clone()))).unwrap();
+        assert_eq!(card.sector_count(), (image.len() / SECTOR_SIZE) as u64);
+        let mut many = alloc::vec![0u8; 4 * SECTOR_SIZE];
+        card.read_sectors(0, &mut many).unwrap();
+        assert_eq!(many, image[..4 * SECTOR_SIZE]);
+        assert_eq!(sim.borrow().commands(), [0, 8, 55, 41, 55, 41, 55, 41, 58, 9, 18, 12]);
+    }
+
+    #[test]
+    fn test_csd_sectors() {
+        // SDSC de 1 Gio: C_SIZE 4095, C_SIZE_MULT 7, blocs de 512 octets
+        let mut csd = [0u8; 16];
+        csd[5] = 0x09;
+        csd[6] = 0x03;
+        csd[7] = 0xFF;
+        csd[8] = 0xC0;
+        csd[9] = 0x03;
+        csd[10] = 0x80;
+        assert_eq!(csd_sectors(&csd), Some(2 * 1024 * 1024));
+
+        // SDHC de 8 Gio
+        let mut csd = [0u8; 16];
+        csd[0] = 0x40;
+        csd[8] = 0x3F;
+        csd[9] = 0xFF;
+        assert_eq!(csd_sectors(&csd), Some(8 * 1024 * 1024 * 2));
+
+        assert_eq!(csd_sectors(&[0x80; 16]), None);
+    }
+
+    #[test]
+    fn test_fragmented_file_by_runs() {
+        let image = TestImage::default().fragmented().build().unwrap();
+        let fs = Fat32::new(&image).unwrap();
+        let entry = fs.resolve_path(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+        let chain = fs.cluster_chain(entry.cluster());
+
+        let sectors = (image.len() / SECTOR_SIZE) as u64;
+        let mut card = SdCard::new(SimulatedCard::new(image.clone(), true), sectors, true);
+        let data = read_clusters(&mut card, fs.boot_sector(), &chain).unwrap();
+        assert_eq!(data, fs.read_cluster_chain(entry.cluster()));
+    }
+}
@@ -0,0 +1,293 @@
+//! Instantané de l'arborescence et comparaison de deux états
+//!
+//! Un `Snapshot` relève pour chaque chemin la taille, les attributs, la date
+//! de modification, l'empreinte SHA-256 du contenu et les plages de clusters.
+//! `Snapshot::diff` compare deux instantanés (avant et après une mise à jour
+//! de la carte, par exemple); le format texte permet de les conserver.
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{cluster_runs, DirEntry, Fat32, FatDateTime};
+use crate::checksum::{to_hex, Sha256};
+
+/// Première ligne du format texte
+pub const SNAPSHOT_HEADER: &str = "fat32-snapshot 1";
+
+/// État d'un fichier ou d'un répertoire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileState {
+    /// Chemin absolu, avec les noms longs
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u32,
+    pub attr: u8,
+    pub modified: FatDateTime,
+    /// SHA-256 du contenu en hexadécimal (vide pour un répertoire)
+    pub hash: String,
+    /// Plages de clusters (début, nombre)
+    pub clusters: Vec<(u32, u32)>,
+}
+
+/// États de toute l'arborescence, triés par chemin
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    pub files: Vec<FileState>,
+}
+
+/// Nature d'une différence entre deux instantanés
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// Contenu ou taille modifiés
+    Modified,
+    Attributes,
+    Timestamp,
+    /// Même contenu, clusters différents
+    Moved,
+}
+
+/// Différence sur un chemin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.kind {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Attributes => "attributes",
+            ChangeKind::Timestamp => "timestamp",
+            ChangeKind::Moved => "moved",
+        };
+        write!(f, "{:<10} {}", label, self.path)
+    }
+}
+
+impl<'a> Fat32<'a> {
+    /// Relève l'état de toute l'arborescence (parcourue comme avec `walk_tree`)
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        for (path, entry) in self.walk_tree() {
+            let hash = if entry.is_directory() {
+                String::new()
+            } else {
+                to_hex(&self.checksum_file(&entry, Sha256::new()))
+            };
+            snapshot.files.push(FileState {
+                path,
+                is_dir: entry.is_directory(),
+                size: entry.size,
+                attr: entry.attr,
+                modified: entry.modified(),
+                hash,
+                clusters: cluster_runs(&self.cluster_chain(entry.cluster())),
+            });
+        }
+        snapshot.files.sort_by(|a, b| a.path.cmp(&b.path));
+        snapshot
+    }
+
+    /// Chemins absolus (noms longs) et entrées de toute l'arborescence, répertoires compris
+    ///
+    /// Un répertoire déjà visité (boucle dans une image corrompue) n'est pas
+    /// reparcouru; la profondeur est bornée par `max_path_depth`.
+    pub(crate) fn walk_tree(&self) -> Vec<(String, DirEntry)> {
+        let mut entries = Vec::new();
+        let mut visited = Vec::new();
+        self.walk_dir(self.root_cluster(), "", 0, &mut visited, &mut entries);
+        entries
+    }
+
+    fn walk_dir(&self, cluster: u32, prefix: &str, depth: usize, visited: &mut Vec<u32>, entries: &mut Vec<(String, DirEntry)>) {
+        if depth >= self.limits().max_path_depth || visited.contains(&cluster) {
+            return;
+        }
+        visited.push(cluster);
+
+        for (entry, long_name) in self.read_directory_with_lfn(cluster) {
+            if entry.is_dot() || entry.is_dotdot() {
+                continue;
+            }
+            let path = format!("{}/{}", prefix, long_name.unwrap_or_else(|| entry.display_name()));
+            let subdir = (entry.is_directory() && entry.cluster() >= 2).then(|| entry.cluster());
+            entries.push((path.clone(), entry));
+            if let Some(subdir) = subdir {
+                self.walk_dir(subdir, &path, depth + 1, visited, entries);
+            }
+        }
+    }
+}
+
+impl Snapshot {
+    /// État du chemin `path`
+    pub fn get(&self, path: &str) -> Option<&FileState> {
+        self.files.binary_search_by(|f| f.path.as_str().cmp(path)).ok().map(|i| &self.files[i])
+    }
+
+    /// Différences entre cet instantané (avant) et `newer` (après), triées par chemin
+    pub fn diff(&self, newer: &Snapshot) -> Vec<Change> {
+        let mut changes = Vec::new();
+        let (mut old, mut new) = (self.files.iter().peekable(), newer.files.iter().peekable());
+
+        loop {
+            let (a, b) = match (old.peek(), new.peek()) {
+                (None, None) => break,
+                (Some(a), Some(b)) => (*a, *b),
+                (Some(a), None) => {
+                    changes.push(Change { path: a.path.clone(), kind: ChangeKind::Removed });
+                    old.next();
+                    continue;
+                }
+                (None, Some(b)) => {
+                    changes.push(Change { path: b.path.clone(), kind: ChangeKind::Added });
+                    new.next();
+                    continue;
+                }
+            };
+
+            match a.path.cmp(&b.path) {
+                core::cmp::Ordering::Less => {
+                    changes.push(Change { path: a.path.clone(), kind: ChangeKind::Removed });
+                    old.next();
+                }
+                core::cmp::Ordering::Greater => {
+                    changes.push(Change { path: b.path.clone(), kind: ChangeKind::Added });
+                    new.next();
+                }
+                core::cmp::Ordering::Equal => {
+                    let mut kinds = Vec::new();
+                    if a.is_dir != b.is_dir || a.size != b.size || a.hash != b.hash {
+                        kinds.push(ChangeKind::Modified);
+                    } else if a.clusters != b.clusters && !a.is_dir {
+                        kinds.push(ChangeKind::Moved);
+                    }
+                    if a.attr != b.attr {
+                        kinds.push(ChangeKind::Attributes);
+                    }
+                    if a.modified != b.modified {
+                        kinds.push(ChangeKind::Timestamp);
+                    }
+                    changes.extend(kinds.into_iter().map(|kind| Change { path: a.path.clone(), kind }));
+                    old.next();
+                    new.next();
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// Format texte: un en-tête, puis une ligne par chemin avec des champs séparés par des tabulations
+    pub fn to_text(&self) -> String {
+        let mut text = String::from(SNAPSHOT_HEADER);
+        text.push('\n');
+        for file in &self.files {
+            let (date, time) = file.modified.to_raw();
+            let runs: Vec<String> = file.clusters.iter().map(|(start, len)| format!("{}+{}", start, len)).collect();
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{:02x}\t{:04x}{:04x}\t{}\t{}\n",
+                file.path,
+                if file.is_dir { 'd' } else { 'f' },
+                file.size,
+                file.attr,
+                date,
+                time,
+                file.hash,
+                runs.join(","),
+            ));
+        }
+        text
+    }
+
+    /// Relit le format de `to_text`; `None` si l'en-tête ou une ligne est invalide
+    pub fn from_text(text: &str) -> Option<Snapshot> {
+        let mut lines = text.lines();
+        if lines.next()? != SNAPSHOT_HEADER {
+            return None;
+        }
+
+        let mut files = Vec::new();
+        for line in lines.filter(|l| !l.is_empty()) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [path, kind, size, attr, stamp, hash, runs] = <[&str; 7]>::try_from(fields).ok()?;
+            if stamp.len() != 8 {
+                return None;
+            }
+            let date = u16::from_str_radix(stamp.get(..4)?, 16).ok()?;
+            let time = u16::from_str_radix(stamp.get(4..)?, 16).ok()?;
+            let mut clusters = Vec::new();
+            for run in runs.split(',').filter(|r| !r.is_empty()) {
+                let (start, len) = run.split_once('+')?;
+                clusters.push((start.parse().ok()?, len.parse().ok()?));
+            }
+            files.push(FileState {
+                path: String::from(path),
+                is_dir: match kind {
+                    "d" => true,
+                    "f" => false,
+                    _ => return None,
+                },
+                size: size.parse().ok()?,
+                attr: u8::from_str_radix(attr, 16).ok()?,
+                modified: FatDateTime::from_raw(date, time),
+                hash: String::from(hash),
+                clusters,
+            });
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Some(Snapshot { files })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH, LONG_NAME_PATHS, README_PATH};
+
+    #[test]
+    fn test_snapshot_and_diff() {
+        let before = TestImage::default().long_names().fragmented().build().unwrap();
+        let old = Fat32::new(&before).unwrap().snapshot();
+        assert!(old.get(LONG_NAME_PATHS[1]).is_some());
+        assert!(old.get("/My Documents").unwrap().is_dir);
+        assert_eq!(old.diff(&old), []);
+
+        let mut after = before.clone();
+        let mut fs = Fat32::new_mut(&mut after).unwrap();
+        let root = fs.root_cluster();
+        fs.write_file(root, "update.bin", b"firmware v2").unwrap();
+        fs.write_file(root, "README.TXT", b"changed\n").unwrap();
+        fs.remove_file(root, "FRAG.BIN").unwrap();
+        fs.set_timestamps(root, "My Documents", FatDateTime::from_unix_seconds(1_709_209_810)).unwrap();
+        let new = fs.snapshot();
+
+        let changes = old.diff(&new);
+        let has = |path: &str, kind| changes.iter().any(|c| c.path == path && c.kind == kind);
+        assert!(has("/update.bin", ChangeKind::Added));
+        assert!(has(FRAGMENTED_PATH, ChangeKind::Removed));
+        assert!(has(README_PATH, ChangeKind::Modified));
+        assert!(has("/My Documents", ChangeKind::Timestamp));
+        assert!(!changes.iter().any(|c| c.path == LONG_NAME_PATHS[1]));
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let image = TestImage::default().long_names().fragmented().deep_tree(2).build().unwrap();
+        let snapshot = Fat32::new(&image).unwrap().snapshot();
+        let text = snapshot.to_text();
+        assert!(text.starts_with(SNAPSHOT_HEADER));
+        assert_eq!(Snapshot::from_text(&text), Some(snapshot));
+
+        assert_eq!(Snapshot::from_text("not a snapshot\n"), None);
+        assert_eq!(Snapshot::from_text(&format!("{}\n/A\tx\t0\t00\t00000000\t\t\n", SNAPSHOT_HEADER)), None);
+    }
+}
@@ -0,0 +1,52 @@
+//! Compteurs d'E/S et de cache
+//!
+//! Les compteurs sont tenus derrière `&self` (les lectures de `Fat32` ne
+//! prennent pas `&mut self`, voir `sync`) et relevés par `Fat32::stats`;
+//! `reset_stats` les remet à zéro avant une mesure.
+
+use core::fmt;
+
+use super::sync::Lock;
+
+/// Relevé des compteurs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoStats {
+    /// Secteurs de données lus
+    pub sectors_read: u64,
+    /// Recherches servies par un cache
+    pub cache_hits: u64,
+    /// Recherches qui ont dû lire le support
+    pub cache_misses: u64,
+    /// Entrées de la FAT consultées
+    pub fat_lookups: u64,
+    /// Octets copiés vers un tampon de l'appelant
+    pub bytes_copied: u64,
+}
+
+impl fmt::Display for IoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sectors read, {} cache hits, {} cache misses, {} FAT lookups, {} bytes copied",
+            self.sectors_read, self.cache_hits, self.cache_misses, self.fat_lookups, self.bytes_copied
+        )
+    }
+}
+
+/// Compteurs modifiables derrière une référence partagée
+#[derive(Default)]
+pub(crate) struct Counters(Lock<IoStats>);
+
+impl Counters {
+    pub(crate) fn update(&self, f: impl FnOnce(&mut IoStats)) {
+        f(&mut self.0.lock());
+    }
+
+    pub(crate) fn get(&self) -> IoStats {
+        *self.0.lock()
+    }
+
+    pub(crate) fn reset(&self) {
+        *self.0.lock() = IoStats::default();
+    }
+}
@@ -0,0 +1,56 @@
+//! Mutabilité intérieure partageable entre threads
+//!
+//! Le cache des noms et les compteurs sont modifiés derrière `&self`, sous
+//! le verrou à attente active de l'allocateur: `Fat32` est `Send` et `Sync`
+//! et peut être placé dans une statique (`OnceCell`, `lazy_static`).
+//!
+//! La crate a besoin d'opérations atomiques de comparaison-échange de la
+//! taille d'un pointeur: l'allocateur (`Locked`, position du heap) les
+//! utilise sans solution de repli. rustc ne définit `target_has_atomic` que
+//! pour les cibles qui les ont; les autres (Cortex-M0, `thumbv6m-none-eabi`)
+//! sont refusées à la compilation avec un message explicite.
+
+#[cfg(not(target_has_atomic = "ptr"))]
+compile_error!("fat32-exam needs pointer-sized compare-and-swap atomics (not available on thumbv6m)");
+
+/// Verrou à attente active de l'allocateur, sans dépendance au système
+pub(crate) type Lock<T> = crate::allocator::Locked<T>;
+
+// Vérifié à chaque compilation: `Fat32` se partage entre threads
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    #[allow(dead_code)]
+    fn check() {
+        assert_send_sync::<crate::fat32::Fat32<'static>>();
+    }
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::Fat32;
+    use crate::testimg::{TestImage, README_PATH, README_TEXT};
+
+    #[test]
+    fn test_shared_between_threads() {
+        let image = TestImage::default().build().unwrap();
+        let fs = Fat32::new(&image).unwrap().with_dir_cache();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let readme = fs.resolve_path(README_PATH, fs.root_cluster()).unwrap();
+                    assert_eq!(fs.read_file(&readme), README_TEXT);
+                });
+            }
+        });
+        assert_eq!(fs.stats().cache_hits + fs.stats().cache_misses, 4);
+
+        let counter = Lock::<u32>::default();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| (0..1000).for_each(|_| *counter.lock() += 1));
+            }
+        });
+        assert_eq!(*counter.lock(), 4000);
+    }
+}
@@ -0,0 +1,50 @@
+//! Points de trace: montage, lectures de clusters, parcours de la FAT
+//!
+//! `Fat32::with_trace` installe une fonction appelée à chaque événement, qui
+//! le relaie où il faut. Avec la feature defmt, chaque événement part aussi
+//! dans `defmt::trace!` (vers RTT par exemple), sans fonction à installer.
+//! Sans fonction installée ni defmt, un point de trace ne coûte qu'un test.
+
+use core::fmt;
+
+use super::error::FsError;
+
+/// Événement remonté à la fonction de trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub enum TraceEvent {
+    /// Géométrie du système de fichiers, émise quand la trace est installée
+    Mounted {
+        bytes_per_sector: u16,
+        sectors_per_cluster: u8,
+        root_cluster: u32,
+        cluster_count: u32,
+    },
+    /// Un cluster de données a été lu
+    ClusterRead { cluster: u32 },
+    /// Une chaîne a été suivie dans la FAT, éventuellement jusqu'à une erreur
+    ChainWalked {
+        start: u32,
+        length: usize,
+        error: Option<FsError>,
+    },
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::Mounted { bytes_per_sector, sectors_per_cluster, root_cluster, cluster_count } => write!(
+                f,
+                "mounted: {} bytes/sector, {} sectors/cluster, root cluster {}, {} clusters",
+                bytes_per_sector, sectors_per_cluster, root_cluster, cluster_count
+            ),
+            TraceEvent::ClusterRead { cluster } => write!(f, "read cluster {}", cluster),
+            TraceEvent::ChainWalked { start, length, error: None } => {
+                write!(f, "chain from cluster {}: {} clusters", start, length)
+            }
+            TraceEvent::ChainWalked { start, length, error: Some(error) } => {
+                write!(f, "chain from cluster {}: {} clusters, stopped: {}", start, length, error)
+            }
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Récupération des entrées supprimées (marqueur 0xE5)
+//!
+//! La suppression n'efface que le premier octet de chaque slot: les fragments
+//! LFN qui précèdent l'entrée courte gardent le nom long, et leur somme de
+//! contrôle permet de retrouver le premier caractère perdu du nom court.
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::directory::LfnEntry;
+use super::{byte_range, lfn_checksum, Fat32, FsError, DirEntry, ATTR_LONG_NAME, ATTR_VOLUME_ID};
+
+/// Marqueur d'entrée supprimée
+pub const DELETED_MARKER: u8 = 0xE5;
+
+/// Caractères interdits dans un nom court 8.3
+const INVALID_SHORT_CHARS: &[u8] = b"\"*+,./:;<=>?[\\]|";
+
+/// Nombre maximal de fragments LFN d'un nom (255 caractères, 13 par fragment)
+const LFN_MAX_PARTS: usize = 20;
+
+/// Entrée de répertoire supprimée, candidate à la récupération
+///
+/// La taille et l'ancien cluster de début restent lisibles dans `entry`.
+#[derive(Clone, Debug)]
+pub struct DeletedEntry {
+    /// Entrée parsée, le premier caractère perdu est remplacé par '?'
+    pub entry: DirEntry,
+    /// Nom long reconstruit depuis les fragments LFN supprimés, s'ils ont survécu
+    pub long_name: Option<String>,
+    /// Premier caractère du nom court, retrouvé avec le nom long
+    pub first_char: Option<char>,
+    /// Position de l'entrée dans l'image (octets)
+    pub offset: u64,
+    /// Cluster du répertoire qui contient l'entrée
+    pub dir_cluster: u32,
+    /// Vrai si tous les clusters nécessaires sont encore libres
+    pub recoverable: bool,
+}
+
+impl DeletedEntry {
+    /// Retourne le nom d'affichage (ex: "?EADME.TXT")
+    pub fn display_name(&self) -> String {
+        self.entry.display_name()
+    }
+
+    /// Nom long reconstruit, ou nom court d'affichage à défaut
+    pub fn name(&self) -> String {
+        self.long_name.clone().unwrap_or_else(|| self.display_name())
+    }
+}
+
+/// Fragments LFN supprimés consécutifs, dans l'ordre du disque
+///
+/// Le numéro de séquence est écrasé par le marqueur: l'ordre vient de la
+/// position, le dernier fragment du nom étant écrit en premier.
+#[derive(Default)]
+struct LfnRemnants {
+    parts: Vec<LfnEntry>,
+}
+
+impl LfnRemnants {
+    fn push(&mut self, chunk: &[u8]) {
+        let lfn = match LfnEntry::from_bytes(chunk) {
+            Some(lfn) => lfn,
+            None => return self.parts.clear(),
+        };
+        // Une somme différente commence le nom d'un autre fichier
+        if self.parts.first().is_some_and(|part| part.checksum != lfn.checksum) || self.parts.len() == LFN_MAX_PARTS {
+            self.parts.clear();
+        }
+        self.parts.push(lfn);
+    }
+
+    /// Nom long et premier caractère du nom court de l'entrée supprimée `raw`
+    ///
+    /// La somme de contrôle LFN est une bijection du premier octet du nom
+    /// court, les autres étant connus: un seul octet la reproduit. Un octet
+    /// impossible dans un nom court ASCII, ou un fragment final mal terminé,
+    /// signale des fragments étrangers.
+    fn take(&mut self, raw: &[u8; 32]) -> Option<(String, char)> {
+        let parts = core::mem::take(&mut self.parts);
+        let checksum = parts.first()?.checksum;
+
+        let mut short_name = [0u8; 11];
+        short_name.copy_from_slice(&raw[..11]);
+        let first = (0..=u8::MAX).find(|&b| {
+            short_name[0] = b;
+            lfn_checksum(&short_name) == checksum
+        })?;
+        if !first.is_ascii_graphic() || first.is_ascii_lowercase() || INVALID_SHORT_CHARS.contains(&first) {
+            return None;
+        }
+
+        // Fragment final: 0x0000 puis bourrage 0xFFFF, sauf s'il est plein
+        let last = parts.first()?;
+        let units: Vec<u16> = last.name1.iter().chain(&last.name2).chain(&last.name3).copied().collect();
+        if let Some(end) = units.iter().position(|&u| u == 0) {
+            if units[end + 1..].iter().any(|&u| u != 0xFFFF) {
+                return None;
+            }
+        }
+
+        // Tous les fragments sauf le dernier du nom sont pleins (13 caractères)
+        let mut name = String::new();
+        for (i, part) in parts.iter().rev().enumerate() {
+            let chars = part.get_chars();
+            if i + 1 < parts.len() && chars.len() != 13 {
+                return None;
+            }
+            name.extend(chars);
+        }
+        (!name.is_empty()).then_some((name, first as char))
+    }
+}
+
+impl<'a> Fat32<'a> {
+    /// Liste les entrées supprimées d'un répertoire
+    ///
+    /// Un répertoire illisible ou qui dépasse les limites de ressources est lu
+    /// comme vide; `read_directory_deleted` retourne l'erreur.
+    pub fn list_deleted(&self, dir_cluster: u32) -> Vec<DeletedEntry> {
+        self.read_directory_deleted(dir_cluster).unwrap_or_default()
+    }
+
+    /// Entrées supprimées (0xE5) d'un répertoire, avec leur nom long s'il a survécu
+    ///
+    /// Comme pour la lecture normale, le parcours s'arrête à la première
+    /// entrée libre, sauf avec `full_scan`. Les fragments LFN sont rattachés
+    /// à l'entrée courte qui les suit quand leur somme de contrôle concorde.
+    pub fn read_directory_deleted(&self, dir_cluster: u32) -> Result<Vec<DeletedEntry>, FsError> {
+        let mut result = Vec::new();
+        let mut remnants = LfnRemnants::default();
+
+        for cluster in self.try_cluster_chain(dir_cluster)? {
+            self.check_cancel()?;
+            let base = self.cluster_offset(cluster);
+
+            for (i, chunk) in self.read_cluster(cluster).chunks(32).enumerate() {
+                if chunk.len() < 32 || (chunk[0] == 0x00 && !self.options.full_scan) {
+                    return Ok(result);
+                }
+                if chunk[0] != DELETED_MARKER {
+                    remnants.parts.clear();
+                    continue;
+                }
+                if chunk[11] == ATTR_LONG_NAME {
+                    remnants.push(chunk);
+                    continue;
+                }
+                if chunk[11] & ATTR_VOLUME_ID != 0 {
+                    remnants.parts.clear();
+                    continue;
+                }
+
+                let mut raw = [0u8; 32];
+                raw.copy_from_slice(chunk);
+                let (long_name, first_char) = remnants.take(&raw).unzip();
+                raw[0] = b'?';
+
+                if let Some(entry) = DirEntry::from_bytes(&raw) {
+                    if result.len() == self.limits.max_dir_entries {
+                        return Err(FsError::LimitExceeded);
+                    }
+                    let recoverable = self.deleted_clusters(&entry)
+                        .is_some_and(|clusters| self.first_used_cluster(clusters).is_none());
+                    result.push(DeletedEntry {
+                        entry,
+                        long_name,
+                        first_char,
+                        offset: base + (i * 32) as u64,
+                        dir_cluster,
+                        recoverable,
+                    });
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Restaure une entrée supprimée avec le premier caractère donné
+    ///
+    /// La chaîne de clusters est reconstruite de façon contiguë à partir du
+    /// cluster de début, comme le font les outils d'undelete classiques.
+    /// Le nom restauré ne doit pas déjà désigner une entrée du répertoire.
+    pub fn undelete(&mut self, deleted: &DeletedEntry, first_char: char) -> Result<(), FsError> {
+        let first = first_char.to_ascii_uppercase();
+        if !first.is_ascii_graphic() || INVALID_SHORT_CHARS.contains(&(first as u8)) {
+            return Err(FsError::InvalidName);
+        }
+
+        let offset = match byte_range(deleted.offset, 1) {
+            Some(range) => range.start,
+            None => return Err(FsError::NotFound),
+        };
+        if self.disk_data().get(offset) != Some(&DELETED_MARKER) {
+            return Err(FsError::NotFound);
+        }
+
+        let mut restored = deleted.entry.clone();
+        restored.name[0] = first as u8;
+        if self.locate_entry(deleted.dir_cluster, &restored.display_name()).is_some() {
+            return Err(FsError::AlreadyExists);
+        }
+
+        let clusters = self.deleted_clusters(&deleted.entry).ok_or(FsError::OutOfBounds)?;
+        if let Some(cluster) = self.first_used_cluster(clusters.clone()) {
+            return Err(FsError::ClusterInUse(cluster));
+        }
+
+        let last = clusters.end.saturating_sub(1);
+        for cluster in clusters {
+            let next = if cluster == last { 0x0FFFFFFF } else { cluster + 1 };
+            self.write_fat_entry(cluster, next)?;
+        }
+
+        self.disk_data_mut()?[offset] = first as u8;
+        self.invalidate_free_count()
+    }
+
+    /// Nombre de clusters occupés par l'entrée d'après sa taille
+    fn clusters_needed(&self, entry: &DirEntry) -> u32 {
+        if entry.cluster() < 2 {
+            return 0;
+        }
+        if entry.is_directory() {
+            return 1;
+        }
+
+        let bytes_per_cluster = self.bytes_per_cluster();
+        entry.size.div_ceil(bytes_per_cluster)
+    }
+
+    /// Clusters contigus que l'entrée occupait, à partir de son cluster de début
+    ///
+    /// `None` si le début ou la fin sort du volume: un `cluster_high` corrompu
+    /// peut donner un cluster proche de 0xFFFFFFFF.
+    fn deleted_clusters(&self, entry: &DirEntry) -> Option<Range<u32>> {
+        let count = self.clusters_needed(entry);
+        if count == 0 {
+            return Some(0..0);
+        }
+        let start = entry.cluster();
+        let end = start.checked_add(count)?;
+        (end <= self.cluster_count().saturating_add(2)).then_some(start..end)
+    }
+
+    /// Retourne le premier cluster déjà alloué parmi `clusters`
+    fn first_used_cluster(&self, clusters: Range<u32>) -> Option<u32> {
+        let fat = self.fat_table();
+        clusters.into_iter().find(|&c| !fat.get_entry(c).is_free())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Image où OLD.TXT, 600 octets sur deux clusters de 512 octets, vient d'être supprimé
+    fn image_with_deleted_file() -> Vec<u8> {
+        let options = crate::fat32::FormatOptions::default().with_cluster_size(512).unwrap();
+        let mut data = crate::builder::FsBuilder::new(crate::builder::MB)
+            .options(options)
+            .file("OLD.TXT", &[0x4F; 600])
+            .build()
+            .unwrap();
+        let mut fs = Fat32::new_mut(&mut data).unwrap();
+        let root = fs.root_cluster();
+        fs.remove_file(root, "OLD.TXT").unwrap();
+        data
+    }
+
+    /// Entrée supprimée de `image_with_deleted_file`
+    fn deleted_entry(image: &[u8]) -> DeletedEntry {
+        let fs = Fat32::new(image).unwrap();
+        fs.list_deleted(fs.root_cluster()).remove(0)
+    }
+
+    #[test]
+    fn test_list_deleted() {
+        let image = image_with_deleted_file();
+        let fs = Fat32::new(&image).unwrap();
+
+        let deleted = fs.list_deleted(fs.root_cluster());
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].display_name(), "?LD.TXT");
+        assert_eq!(image[deleted[0].offset as usize], DELETED_MARKER);
+        assert_eq!(deleted[0].entry.size, 600);
+        assert!(deleted[0].recoverable);
+        assert!(fs.read_directory(fs.root_cluster()).is_empty());
+    }
+
+    #[test]
+    fn test_undelete_restores_entry_and_chain() {
+        let mut image = image_with_deleted_file();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+
+        let deleted = fs.list_deleted(fs.root_cluster()).remove(0);
+        let first = deleted.entry.cluster();
+        fs.undelete(&deleted, 'o').unwrap();
+
+        let entry = fs.find_entry(fs.root_cluster(), "OLD.TXT").unwrap();
+        assert_eq!(fs.cluster_chain(entry.cluster()), vec![first, first + 1]);
+        assert_eq!(fs.read_file(&entry), [0x4F; 600]);
+        assert!(fs.list_deleted(fs.root_cluster()).is_empty());
+        assert_eq!(fs.undelete(&deleted, 'O'), Err(FsError::NotFound));
+    }
+
+    #[test]
+    fn test_undelete_rejects_read_only_and_used_clusters() {
+        let image = image_with_deleted_file();
+        let mut fs = Fat32::new(&image).unwrap();
+        let deleted = fs.list_deleted(fs.root_cluster()).remove(0);
+        assert_eq!(fs.undelete(&deleted, 'O'), Err(FsError::ReadOnly));
+
+        // Le second cluster du fichier a été réutilisé
+        let mut image = image_with_deleted_file();
+        let second = deleted_entry(&image).entry.cluster() + 1;
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        fs.write_fat_entry(second, 0x0FFFFFFF).unwrap();
+        let deleted = fs.list_deleted(fs.root_cluster()).remove(0);
+        assert!(!deleted.recoverable);
+        assert_eq!(fs.undelete(&deleted, 'O'), Err(FsError::ClusterInUse(second)));
+        assert_eq!(fs.undelete(&deleted, '*'), Err(FsError::InvalidName));
+    }
+
+    #[test]
+    fn test_undelete_rejects_taken_name() {
+        let mut image = crate::builder::FsBuilder::new(crate::builder::MB)
+            .file("OLD.TXT", b"old")
+            .file("B.TXT", b"b")
+            .build()
+            .unwrap();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+        fs.remove_file(root, "OLD.TXT").unwrap();
+        // Nom long "Old.Txt" et nom court OLD~1.TXT, dans de nouveaux slots;
+        // vide, il laisse libre le cluster de l'ancien fichier
+        fs.write_file(root, "Old.Txt", b"").unwrap();
+
+        let deleted = fs.list_deleted(root).remove(0);
+        assert!(deleted.recoverable);
+        assert_eq!(fs.undelete(&deleted, 'O'), Err(FsError::AlreadyExists));
+        assert_eq!(fs.find_entry(root, "old.txt").unwrap().size, 0);
+        assert!(fs.fat_table().get_entry(deleted.entry.cluster()).is_free());
+        // Un autre premier caractère reste possible
+        fs.undelete(&deleted, 'N').unwrap();
+        assert!(fs.find_entry(root, "NLD.TXT").is_some());
+    }
+
+    #[test]
+    fn test_undelete_rejects_clusters_outside_volume() {
+        // cluster_high corrompu: le début et la fin de la plage dépassent le volume
+        for high in [0xFFFFu16, 0x0001] {
+            let mut image = image_with_deleted_file();
+            let offset = deleted_entry(&image).offset as usize;
+            image[offset + 20..offset + 22].copy_from_slice(&high.to_le_bytes());
+            let mut fs = Fat32::new_mut(&mut image).unwrap();
+            let deleted = fs.list_deleted(fs.root_cluster()).remove(0);
+            assert!(!deleted.recoverable);
+            assert_eq!(fs.undelete(&deleted, 'O'), Err(FsError::OutOfBounds));
+        }
+
+        // Chaîne qui déborde du dernier cluster
+        let mut image = image_with_deleted_file();
+        let offset = deleted_entry(&image).offset as usize;
+        let last = Fat32::new(&image).unwrap().cluster_count() + 1;
+        image[offset + 26..offset + 28].copy_from_slice(&(last as u16).to_le_bytes());
+        let fs = Fat32::new(&image).unwrap();
+        assert!(!fs.list_deleted(fs.root_cluster())[0].recoverable);
+    }
+
+    #[test]
+    fn test_read_directory_deleted_long_names() {
+        let mut image = crate::testimg::TestImage::default().build().unwrap();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+        let name = "Quarterly report for the board.txt";
+        let written = fs.write_file(root, name, &[0x42; 1500]).unwrap();
+        fs.write_file(root, "KEEP.TXT", b"kept").unwrap();
+        fs.remove_file(root, name).unwrap();
+
+        let deleted = fs.read_directory_deleted(root).unwrap();
+        assert_eq!(deleted.len(), 1);
+        let d = &deleted[0];
+        assert_eq!(d.long_name.as_deref(), Some(name));
+        assert_eq!(d.name(), name);
+        assert_eq!(d.first_char, Some(written.name[0] as char));
+        assert_eq!(d.display_name().as_bytes()[0], b'?');
+        assert_eq!((d.entry.size, d.entry.cluster()), (1500, written.cluster()));
+        assert!(d.recoverable);
+
+        // Somme de contrôle qui ne se retrouve qu'avec le marqueur: fragments étrangers
+        let offset = d.offset as usize;
+        let mut short_name = [0u8; 11];
+        short_name.copy_from_slice(&image[offset..offset + 11]);
+        let checksum = lfn_checksum(&short_name);
+        for slot in 1..=3 {
+            image[offset - 32 * slot + 13] = checksum;
+        }
+        let fs = Fat32::new(&image).unwrap();
+        let d = fs.read_directory_deleted(root).unwrap().remove(0);
+        assert_eq!((d.long_name.as_deref(), d.first_char), (None, None));
+        assert_eq!(d.name(), d.display_name());
+    }
+}
@@ -0,0 +1,118 @@
+//! Trait `FileSystem` pour la couche VFS d'un noyau
+//!
+//! Le trait est objet-safe: ni générique, ni `Self` par valeur, le parcours
+//! d'un répertoire passe par un `&mut dyn FnMut`. Un noyau peut donc ranger
+//! `&dyn FileSystem` (ou `Box<dyn FileSystem + Sync>`) à côté d'autres
+//! systèmes de fichiers. Les nœuds sont désignés par leur numéro d'inode
+//! (voir `inode`); `read_at` lit directement dans le tampon de l'appelant.
+
+use super::inode::{NodeAttr, NodeKind, ROOT_INODE};
+use super::{Fat32, FsError};
+
+/// Système de fichiers monté derrière une VFS
+pub trait FileSystem {
+    /// Nœud de la racine
+    fn root(&self) -> u64;
+
+    /// Nœud désigné par un chemin absolu
+    fn open(&self, path: &str) -> Result<u64, FsError>;
+
+    /// Nœud `name` dans le répertoire `parent` (`.` et `..` compris)
+    fn lookup(&self, parent: u64, name: &str) -> Result<u64, FsError>;
+
+    /// Attributs d'un nœud
+    fn metadata(&self, node: u64) -> Result<NodeAttr, FsError>;
+
+    /// Appelle `f` pour chaque entrée du répertoire `dir`, sans `.` ni `..`
+    fn read_dir(&self, dir: u64, f: &mut dyn FnMut(&str, &NodeAttr)) -> Result<(), FsError>;
+
+    /// Lit le fichier `node` à partir de `offset`; 0 en fin de fichier
+    fn read_at(&self, node: u64, offset: u64, buf: &mut [u8]) -> Result<usize, FsError>;
+}
+
+impl FileSystem for Fat32<'_> {
+    fn root(&self) -> u64 {
+        ROOT_INODE
+    }
+
+    fn open(&self, path: &str) -> Result<u64, FsError> {
+        let mut node = ROOT_INODE;
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            self.check_cancel()?;
+            node = FileSystem::lookup(self, node, component)?;
+        }
+        Ok(node)
+    }
+
+    fn lookup(&self, parent: u64, name: &str) -> Result<u64, FsError> {
+        self.node_lookup(parent, name).map(|attr| attr.ino).ok_or(FsError::NotFound)
+    }
+
+    fn metadata(&self, node: u64) -> Result<NodeAttr, FsError> {
+        self.node_attr(node).ok_or(FsError::NotFound)
+    }
+
+    fn read_dir(&self, dir: u64, f: &mut dyn FnMut(&str, &NodeAttr)) -> Result<(), FsError> {
+        let children = self.node_children(dir).ok_or(FsError::NotFound)?;
+        for (attr, name) in &children {
+            f(name, attr);
+        }
+        Ok(())
+    }
+
+    fn read_at(&self, node: u64, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let attr = FileSystem::metadata(self, node)?;
+        if attr.kind == NodeKind::Directory {
+            return Err(FsError::IsADirectory);
+        }
+        self.read_chain_into(attr.cluster, attr.size, offset, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH, README_PATH, README_TEXT};
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_dyn_filesystem() {
+        let image = TestImage::default().long_names().fragmented().build().unwrap();
+        let fat = Fat32::new(&image).unwrap();
+        let fs: &dyn FileSystem = &fat;
+
+        let mut names = Vec::new();
+        fs.read_dir(fs.root(), &mut |name, _| names.push(name.to_string())).unwrap();
+        assert!(names.iter().any(|n| n == "My Documents"));
+        assert!(!names.iter().any(|n| n == "." || n == ".."));
+
+        let readme = fs.open(README_PATH).unwrap();
+        assert_eq!(fs.metadata(readme).unwrap().size, README_TEXT.len() as u64);
+        let mut buf = [0u8; 64];
+        let read = fs.read_at(readme, 10, &mut buf).unwrap();
+        assert_eq!(&buf[..read], &README_TEXT[10..]);
+
+        let docs = fs.open("/My Documents").unwrap();
+        let notes = fs.lookup(docs, "notes about fat32.md").unwrap();
+        assert_eq!(fs.lookup(docs, "..").unwrap(), fs.root());
+        assert_eq!(fs.open("/My Documents/notes about fat32.md").unwrap(), notes);
+
+        // Lecture complète par morceaux, comparée à la lecture de la bibliothèque
+        let frag = fs.open(FRAGMENTED_PATH).unwrap();
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 1000];
+        loop {
+            let read = fs.read_at(frag, data.len() as u64, &mut chunk).unwrap();
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&chunk[..read]);
+        }
+        assert_eq!(data, TestImage::default().fragmented_data());
+
+        assert_eq!(fs.read_at(docs, 0, &mut buf), Err(FsError::IsADirectory));
+        assert_eq!(fs.open("/NOPE"), Err(FsError::NotFound));
+        assert_eq!(fs.lookup(readme, "X"), Err(FsError::NotFound));
+    }
+}
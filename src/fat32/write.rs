@@ -0,0 +1,852 @@
+//! Écriture des entrées de répertoire (noms courts/longs, allocation, renommage)
+//! et du contenu des fichiers
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{byte_range, casefold, Fat32, FsError, DirEntry, FatDateTime, ATTR_LONG_NAME, ATTR_ARCHIVE, ATTR_DIRECTORY};
+use super::directory::{LfnEntry, lfn_checksum};
+use super::undelete::DELETED_MARKER;
+
+/// Caractères interdits dans un nom long
+const FORBIDDEN_LONG_CHARS: &str = "\\/:*?\"<>|";
+
+/// Caractères autorisés dans un nom court, en plus de A-Z et 0-9
+const SHORT_NAME_SPECIALS: &[u8] = b"$%'-_@~`!(){}^#&";
+
+/// Nombre de caractères UTF-16 par entrée LFN
+const LFN_CHARS_PER_ENTRY: usize = 13;
+
+/// Longueur maximale d'un nom long, en unités UTF-16
+pub const LFN_MAX_UNITS: usize = 255;
+
+/// Raison du refus d'un nom long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub enum NameError {
+    /// Nom vide
+    Empty,
+    /// `.` ou `..`, réservés aux entrées de navigation
+    Reserved,
+    /// Plus de `LFN_MAX_UNITS` unités UTF-16 (nombre d'unités du nom)
+    TooLong(usize),
+    /// Caractère interdit (`\/:*?"<>|` ou caractère de contrôle)
+    ForbiddenChar(char),
+    /// Point ou espace final, que Windows retire du nom
+    TrailingChar(char),
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "empty name"),
+            NameError::Reserved => write!(f, "'.' and '..' are reserved"),
+            NameError::TooLong(units) => {
+                write!(f, "{} UTF-16 units, at most {} allowed", units, LFN_MAX_UNITS)
+            }
+            NameError::ForbiddenChar(c) if c.is_control() => {
+                write!(f, "forbidden control character U+{:04X}", *c as u32)
+            }
+            NameError::ForbiddenChar(c) => write!(f, "forbidden character '{}'", c),
+            NameError::TrailingChar(' ') => write!(f, "trailing space"),
+            NameError::TrailingChar(c) => write!(f, "trailing '{}'", c),
+        }
+    }
+}
+
+/// Position d'une entrée sur le disque: slots LFN éventuels puis slot du nom court
+#[derive(Debug, Clone)]
+pub struct EntryLocation {
+    pub slots: Vec<u64>,
+    pub entry: DirEntry,
+}
+
+impl EntryLocation {
+    /// Offset du slot contenant l'entrée courte
+    pub fn short_offset(&self) -> u64 {
+        self.slots[self.slots.len() - 1]
+    }
+}
+
+/// Vrai si `c` peut apparaître dans un nom long
+pub fn is_long_name_char(c: char) -> bool {
+    (c as u32) >= 0x20 && !FORBIDDEN_LONG_CHARS.contains(c)
+}
+
+/// Vérifie qu'un nom peut être utilisé pour une entrée
+///
+/// Au plus `LFN_MAX_UNITS` unités UTF-16, aucun caractère interdit, ni point
+/// ni espace final. L'erreur `InvalidLongName` nomme le problème.
+pub fn validate_long_name(name: &str) -> Result<(), FsError> {
+    let error = if name.is_empty() {
+        NameError::Empty
+    } else if name == "." || name == ".." {
+        NameError::Reserved
+    } else if let Some(c) = name.chars().find(|&c| !is_long_name_char(c)) {
+        NameError::ForbiddenChar(c)
+    } else if let Some(c) = name.chars().next_back().filter(|&c| c == '.' || c == ' ') {
+        NameError::TrailingChar(c)
+    } else {
+        match name.encode_utf16().count() {
+            units if units > LFN_MAX_UNITS => NameError::TooLong(units),
+            _ => return Ok(()),
+        }
+    };
+    Err(FsError::InvalidLongName(error))
+}
+
+/// Vérifie si un octet est valide dans un nom court
+fn is_short_char(b: u8) -> bool {
+    b.is_ascii_uppercase() || b.is_ascii_digit() || SHORT_NAME_SPECIALS.contains(&b)
+}
+
+/// Retourne le nom court 8.3 si `name` est déjà un nom court exact (sans LFN)
+pub fn exact_short_name(name: &str) -> Option<[u8; 11]> {
+    let (base, ext) = match name.rfind('.') {
+        Some(i) => (&name[..i], &name[i + 1..]),
+        None => (name, ""),
+    };
+
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 || (name.contains('.') && ext.is_empty()) {
+        return None;
+    }
+    if !base.bytes().chain(ext.bytes()).all(is_short_char) {
+        return None;
+    }
+
+    let mut short = [b' '; 11];
+    short[..base.len()].copy_from_slice(base.as_bytes());
+    short[8..8 + ext.len()].copy_from_slice(ext.as_bytes());
+    Some(short)
+}
+
+/// Génère un nom court unique ("BASENA~1.EXT") pour un nom long
+pub fn generate_short_name(name: &str, existing: &[[u8; 11]]) -> [u8; 11] {
+    let sanitize = |s: &str| -> Vec<u8> {
+        s.chars()
+            .filter(|&c| c != ' ' && c != '.')
+            .map(|c| {
+                let b = if c.is_ascii() { c.to_ascii_uppercase() as u8 } else { b'_' };
+                if is_short_char(b) { b } else { b'_' }
+            })
+            .collect()
+    };
+
+    let trimmed = name.trim_start_matches('.');
+    let (base, ext) = match trimmed.rfind('.') {
+        Some(i) => (sanitize(&trimmed[..i]), sanitize(&trimmed[i + 1..])),
+        None => (sanitize(trimmed), Vec::new()),
+    };
+    let base: &[u8] = if base.is_empty() { b"_" } else { &base };
+
+    let mut short = [b' '; 11];
+    let ext_len = ext.len().min(3);
+    short[8..8 + ext_len].copy_from_slice(&ext[..ext_len]);
+
+    for n in 1u32.. {
+        let tail = alloc::format!("~{}", n);
+        let keep = base.len().min(8 - tail.len());
+        short[..8].fill(b' ');
+        short[..keep].copy_from_slice(&base[..keep]);
+        short[keep..keep + tail.len()].copy_from_slice(tail.as_bytes());
+
+        if !existing.contains(&short) {
+            break;
+        }
+    }
+
+    short
+}
+
+/// Construit les slots LFN d'un nom, dans l'ordre où ils apparaissent sur le disque
+pub fn build_lfn_slots(name: &str, checksum: u8) -> Vec<[u8; 32]> {
+    let units: Vec<u16> = name.encode_utf16().collect();
+    let count = units.len().div_ceil(LFN_CHARS_PER_ENTRY);
+    let mut slots = Vec::with_capacity(count);
+
+    for seq in (1..=count).rev() {
+        let mut slot = [0u8; 32];
+        slot[0] = seq as u8 | if seq == count { 0x40 } else { 0 };
+        slot[11] = ATTR_LONG_NAME;
+        slot[13] = checksum;
+
+        let start = (seq - 1) * LFN_CHARS_PER_ENTRY;
+        for i in 0..LFN_CHARS_PER_ENTRY {
+            let unit = match units.get(start + i) {
+                Some(&u) => u,
+                None if start + i == units.len() => 0x0000,
+                None => 0xFFFF,
+            };
+            let offset = match i {
+                0..=4 => 1 + i * 2,
+                5..=10 => 14 + (i - 5) * 2,
+                _ => 28 + (i - 11) * 2,
+            };
+            slot[offset..offset + 2].copy_from_slice(&unit.to_le_bytes());
+        }
+
+        slots.push(slot);
+    }
+
+    slots
+}
+
+/// Renseigne le premier cluster et la taille d'une entrée courte brute
+fn set_cluster_and_size(raw: &mut [u8; 32], cluster: u32, size: u32) {
+    raw[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    raw[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+    raw[28..32].copy_from_slice(&size.to_le_bytes());
+}
+
+impl<'a> Fat32<'a> {
+    /// Retourne les offsets de tous les slots de 32 octets d'un répertoire
+    fn dir_slot_offsets(&self, dir_cluster: u32) -> Vec<u64> {
+        let slots_per_cluster = self.bytes_per_cluster() as u64 / 32;
+        let mut offsets = Vec::new();
+
+        for cluster in self.cluster_chain(dir_cluster) {
+            let base = self.cluster_offset(cluster);
+            offsets.extend((0..slots_per_cluster).map(|i| base + i * 32));
+        }
+
+        offsets
+    }
+
+    /// Lit un slot brut de 32 octets
+    fn read_slot(&self, offset: u64) -> [u8; 32] {
+        let mut raw = [0u8; 32];
+        if let Some(data) = byte_range(offset, 32).and_then(|range| self.disk_data().get(range)) {
+            raw.copy_from_slice(data);
+        }
+        raw
+    }
+
+    /// Écrit un slot brut de 32 octets
+    fn write_slot(&mut self, offset: u64, raw: &[u8; 32]) -> Result<(), FsError> {
+        let range = byte_range(offset, 32).ok_or(FsError::OutOfBounds)?;
+        let data = self.disk_data_mut()?;
+        data.get_mut(range)
+            .ok_or(FsError::OutOfBounds)?
+            .copy_from_slice(raw);
+        Ok(())
+    }
+
+    /// Parcourt un répertoire: chaque entrée avec ses slots et son nom long (vide si absent)
+    ///
+    /// Les entrées supprimées et le label de volume sont ignorés; le parcours
+    /// s'arrête à la première entrée libre, sauf avec `full_scan`. Des slots
+    /// LFN dont la somme de contrôle ne correspond pas au nom court qui les
+    /// suit sont orphelins: ils ne donnent pas leur nom à l'entrée.
+    pub(crate) fn scan_directory(&self, dir_cluster: u32) -> Vec<(EntryLocation, String)> {
+        let mut found = Vec::new();
+        let mut lfn_parts: Vec<(u64, u8, Vec<char>)> = Vec::new();
+        let mut lfn_checksums: Vec<u8> = Vec::new();
+
+        for offset in self.dir_slot_offsets(dir_cluster) {
+            let raw = self.read_slot(offset);
+            if raw[0] == 0x00 {
+                if !self.options.full_scan {
+                    break;
+                }
+                lfn_parts.clear();
+                lfn_checksums.clear();
+                continue;
+            }
+            if raw[0] == DELETED_MARKER {
+                lfn_parts.clear();
+                lfn_checksums.clear();
+                continue;
+            }
+            if raw[11] == ATTR_LONG_NAME {
+                if let Some(lfn) = LfnEntry::from_bytes(&raw) {
+                    lfn_checksums.push(lfn.checksum);
+                    lfn_parts.push((offset, lfn.order(), lfn.get_chars()));
+                }
+                continue;
+            }
+
+            let entry = match DirEntry::from_bytes(&raw) {
+                Some(e) if !e.is_volume_label() => e,
+                _ => {
+                    lfn_parts.clear();
+                    lfn_checksums.clear();
+                    continue;
+                }
+            };
+
+            let mut short_name = [0u8; 11];
+            short_name.copy_from_slice(&raw[..11]);
+            let checksum = lfn_checksum(&short_name);
+            if lfn_checksums.iter().any(|&c| c != checksum) {
+                log_debug!("orphan long name slots dropped before offset {}", offset);
+                lfn_parts.clear();
+            }
+            lfn_checksums.clear();
+
+            let mut sorted = lfn_parts.clone();
+            sorted.sort_by_key(|(_, order, _)| *order);
+            let long_name: String = sorted.iter().flat_map(|(_, _, c)| c.iter()).collect();
+
+            let mut slots: Vec<u64> = lfn_parts.iter().map(|(o, _, _)| *o).collect();
+            slots.push(offset);
+            found.push((EntryLocation { slots, entry }, long_name));
+            lfn_parts.clear();
+        }
+
+        found
+    }
+
+    /// Localise une entrée (et ses slots LFN) par nom, insensible à la casse
+    pub fn locate_entry(&self, dir_cluster: u32, name: &str) -> Option<EntryLocation> {
+        let folded = casefold::fold(name);
+
+        self.scan_directory(dir_cluster)
+            .into_iter()
+            .find(|(location, long_name)| {
+                (!long_name.is_empty() && casefold::matches_folded(long_name, &folded))
+                    || casefold::matches_folded(&location.entry.display_name(), &folded)
+            })
+            .map(|(location, _)| location)
+    }
+
+    /// Retourne les noms courts bruts déjà utilisés dans un répertoire
+    fn short_names_in(&self, dir_cluster: u32) -> Vec<[u8; 11]> {
+        self.read_directory(dir_cluster)
+            .iter()
+            .map(|e| {
+                let mut short = [0u8; 11];
+                short[..8].copy_from_slice(&e.name);
+                short[8..].copy_from_slice(&e.ext);
+                short
+            })
+            .collect()
+    }
+
+    /// Alloue un cluster libre, marqué fin de chaîne et remis à zéro
+    pub fn allocate_cluster(&mut self) -> Result<u32, FsError> {
+        let max_cluster = self.cluster_count() + 1;
+        let cluster = {
+            let fat = self.fat_table();
+            (2..=max_cluster).find(|&c| fat.get_entry(c).is_free())
+        }
+        .ok_or(FsError::NoSpace)?;
+
+        self.write_fat_entry(cluster, 0x0FFFFFFF)?;
+
+        let range = byte_range(self.cluster_offset(cluster), self.bytes_per_cluster() as u64).ok_or(FsError::OutOfBounds)?;
+        let data = self.disk_data_mut()?;
+        data.get_mut(range).ok_or(FsError::OutOfBounds)?.fill(0);
+
+        self.invalidate_free_count()?;
+        Ok(cluster)
+    }
+
+    /// Libère tous les clusters d'une chaîne
+    fn free_chain(&mut self, start: u32) -> Result<(), FsError> {
+        if start < 2 {
+            return Ok(());
+        }
+        for cluster in self.cluster_chain(start) {
+            self.write_fat_entry(cluster, 0)?;
+        }
+        self.invalidate_free_count()
+    }
+
+    /// Alloue une chaîne et y écrit `data`; retourne le premier cluster (0 si vide)
+    fn write_chain(&mut self, data: &[u8]) -> Result<u32, FsError> {
+        let bytes_per_cluster = self.bytes_per_cluster() as usize;
+        let mut first = 0;
+        let mut previous = 0;
+
+        for chunk in data.chunks(bytes_per_cluster) {
+            let cluster = match self.allocate_cluster() {
+                Ok(c) => c,
+                Err(e) => {
+                    self.free_chain(first)?;
+                    return Err(e);
+                }
+            };
+
+            if previous == 0 {
+                first = cluster;
+            } else {
+                self.write_fat_entry(previous, cluster)?;
+            }
+
+            let range = byte_range(self.cluster_offset(cluster), chunk.len() as u64).ok_or(FsError::OutOfBounds)?;
+            self.disk_data_mut()?
+                .get_mut(range)
+                .ok_or(FsError::OutOfBounds)?
+                .copy_from_slice(chunk);
+            previous = cluster;
+        }
+
+        Ok(first)
+    }
+
+    /// Met à jour le premier cluster et la taille d'une entrée existante
+    fn update_entry_data(&mut self, offset: u64, cluster: u32, size: u32) -> Result<DirEntry, FsError> {
+        let mut raw = self.read_slot(offset);
+        set_cluster_and_size(&mut raw, cluster, size);
+        self.write_slot(offset, &raw)?;
+        DirEntry::from_bytes(&raw).ok_or(FsError::InvalidName)
+    }
+
+    /// Crée un fichier ou remplace son contenu (troncature puis écriture)
+    ///
+    /// Un fichier existant garde son nom et ses attributs. Si l'espace manque,
+    /// il est laissé vide plutôt qu'à moitié écrit.
+    pub fn write_file(&mut self, dir_cluster: u32, name: &str, data: &[u8]) -> Result<DirEntry, FsError> {
+        validate_long_name(name)?;
+        let size = u32::try_from(data.len()).map_err(|_| FsError::NoSpace)?;
+
+        let existing = self.locate_entry(dir_cluster, name);
+        if let Some(location) = &existing {
+            if location.entry.is_directory() {
+                return Err(FsError::IsADirectory);
+            }
+            self.free_chain(location.entry.cluster())?;
+        }
+
+        let first = match self.write_chain(data) {
+            Ok(c) => c,
+            Err(e) => {
+                if let Some(location) = &existing {
+                    self.update_entry_data(location.short_offset(), 0, 0)?;
+                }
+                return Err(e);
+            }
+        };
+
+        match existing {
+            Some(location) => self.update_entry_data(location.short_offset(), first, size),
+            None => {
+                let mut template = [0u8; 32];
+                template[11] = ATTR_ARCHIVE;
+                set_cluster_and_size(&mut template, first, size);
+                self.insert_entry(dir_cluster, name, &template).inspect_err(|_| {
+                    let _ = self.free_chain(first);
+                })
+            }
+        }
+    }
+
+    /// Crée un sous-répertoire vide (avec ses entrées `.` et `..`)
+    pub fn create_dir(&mut self, dir_cluster: u32, name: &str) -> Result<DirEntry, FsError> {
+        validate_long_name(name)?;
+        if self.locate_entry(dir_cluster, name).is_some() {
+            return Err(FsError::AlreadyExists);
+        }
+
+        let cluster = self.allocate_cluster()?;
+        // `..` vers la racine s'écrit avec le cluster 0
+        let parent = if dir_cluster == self.root_cluster() { 0 } else { dir_cluster };
+        for (i, (dots, target)) in [(b".          ", cluster), (b"..         ", parent)].into_iter().enumerate() {
+            let mut raw = [0u8; 32];
+            raw[..11].copy_from_slice(dots);
+            raw[11] = ATTR_DIRECTORY;
+            set_cluster_and_size(&mut raw, target, 0);
+            self.write_slot(self.cluster_offset(cluster) + i as u64 * 32, &raw)?;
+        }
+
+        let mut template = [0u8; 32];
+        template[11] = ATTR_DIRECTORY;
+        set_cluster_and_size(&mut template, cluster, 0);
+        self.insert_entry(dir_cluster, name, &template).inspect_err(|_| {
+            let _ = self.free_chain(cluster);
+        })
+    }
+
+    /// Définit les dates de création, de dernier accès et de modification d'une entrée
+    pub fn set_timestamps(&mut self, dir_cluster: u32, name: &str, time: FatDateTime) -> Result<DirEntry, FsError> {
+        let location = self.locate_entry(dir_cluster, name).ok_or(FsError::NotFound)?;
+        let (date, clock) = time.to_raw();
+        let mut raw = self.read_slot(location.short_offset());
+        // La seconde impaire de la création passe dans les centièmes
+        raw[13] = if (1980..=2107).contains(&time.year) { time.second.min(59) % 2 * 100 } else { 0 };
+        raw[14..16].copy_from_slice(&clock.to_le_bytes());
+        raw[16..18].copy_from_slice(&date.to_le_bytes());
+        raw[18..20].copy_from_slice(&date.to_le_bytes());
+        raw[22..24].copy_from_slice(&clock.to_le_bytes());
+        raw[24..26].copy_from_slice(&date.to_le_bytes());
+        self.write_slot(location.short_offset(), &raw)?;
+        DirEntry::from_bytes(&raw).ok_or(FsError::InvalidName)
+    }
+
+    /// Supprime un fichier: ses slots sont marqués supprimés et sa chaîne libérée
+    ///
+    /// Le premier cluster reste dans l'entrée, qui peut donc être récupérée
+    /// par `undelete` tant que ses clusters ne sont pas réutilisés.
+    pub fn remove_file(&mut self, dir_cluster: u32, name: &str) -> Result<(), FsError> {
+        let location = self.locate_entry(dir_cluster, name).ok_or(FsError::NotFound)?;
+        if location.entry.is_directory() {
+            return Err(FsError::IsADirectory);
+        }
+
+        for &offset in &location.slots {
+            let mut raw = self.read_slot(offset);
+            raw[0] = DELETED_MARKER;
+            self.write_slot(offset, &raw)?;
+        }
+        self.free_chain(location.entry.cluster())
+    }
+
+    /// Ajoute un cluster vide à la fin d'un répertoire
+    fn extend_directory(&mut self, dir_cluster: u32) -> Result<(), FsError> {
+        let last = *self.cluster_chain(dir_cluster).last().ok_or(FsError::NotFound)?;
+        let cluster = self.allocate_cluster()?;
+        self.write_fat_entry(last, cluster)
+    }
+
+    /// Réserve `count` slots consécutifs libres dans un répertoire (l'étend si besoin)
+    fn allocate_slots(&mut self, dir_cluster: u32, count: usize) -> Result<Vec<u64>, FsError> {
+        loop {
+            let offsets = self.dir_slot_offsets(dir_cluster);
+            let mut run = 0;
+
+            for (i, &offset) in offsets.iter().enumerate() {
+                let first = self.read_slot(offset)[0];
+                if first == 0x00 || first == DELETED_MARKER {
+                    run += 1;
+                    if run == count {
+                        return Ok(offsets[i + 1 - count..=i].to_vec());
+                    }
+                } else {
+                    run = 0;
+                }
+            }
+
+            self.extend_directory(dir_cluster)?;
+        }
+    }
+
+    /// Écrit une entrée sous le nom donné (avec LFN si nécessaire)
+    ///
+    /// `template` fournit les attributs, dates, cluster et taille de l'entrée
+    /// courte ; seul le nom est remplacé. Si une écriture échoue en cours de
+    /// route, les slots déjà écrits sont marqués supprimés.
+    pub(crate) fn insert_entry(
+        &mut self,
+        dir_cluster: u32,
+        name: &str,
+        template: &[u8; 32],
+    ) -> Result<DirEntry, FsError> {
+        let (short, lfn_slots) = match exact_short_name(name) {
+            Some(short) => (short, Vec::new()),
+            None => {
+                let short = generate_short_name(name, &self.short_names_in(dir_cluster));
+                (short, build_lfn_slots(name, lfn_checksum(&short)))
+            }
+        };
+
+        let mut raw = *template;
+        raw[..11].copy_from_slice(&short);
+        let entry = DirEntry::from_bytes(&raw).ok_or(FsError::InvalidName)?;
+
+        let offsets = self.allocate_slots(dir_cluster, lfn_slots.len() + 1)?;
+        let slots = lfn_slots.iter().chain(core::iter::once(&raw));
+        for (written, (&offset, slot)) in offsets.iter().zip(slots).enumerate() {
+            if let Err(e) = self.write_slot(offset, slot) {
+                self.delete_slots(&offsets[..written]);
+                return Err(e);
+            }
+        }
+
+        Ok(entry)
+    }
+
+    /// Marque des slots supprimés, au mieux (annulation d'une écriture partielle)
+    fn delete_slots(&mut self, offsets: &[u64]) {
+        for &offset in offsets {
+            let mut raw = self.read_slot(offset);
+            raw[0] = DELETED_MARKER;
+            let _ = self.write_slot(offset, &raw);
+        }
+    }
+
+    /// Renomme une entrée dans son répertoire
+    pub fn rename(&mut self, dir_cluster: u32, old_name: &str, new_name: &str) -> Result<DirEntry, FsError> {
+        validate_long_name(new_name)?;
+        if old_name == "." || old_name == ".." {
+            return Err(FsError::InvalidName);
+        }
+
+        let location = self.locate_entry(dir_cluster, old_name).ok_or(FsError::NotFound)?;
+        if let Some(other) = self.locate_entry(dir_cluster, new_name) {
+            if other.short_offset() != location.short_offset() {
+                return Err(FsError::AlreadyExists);
+            }
+        }
+
+        let saved: Vec<[u8; 32]> = location.slots.iter().map(|&o| self.read_slot(o)).collect();
+        let template = saved[saved.len() - 1];
+
+        for (&offset, raw) in location.slots.iter().zip(saved.iter()) {
+            let mut deleted = *raw;
+            deleted[0] = DELETED_MARKER;
+            self.write_slot(offset, &deleted)?;
+        }
+
+        match self.insert_entry(dir_cluster, new_name, &template) {
+            Ok(entry) => Ok(entry),
+            Err(e) => {
+                for (&offset, raw) in location.slots.iter().zip(saved.iter()) {
+                    self.write_slot(offset, raw)?;
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{FsBuilder, MB};
+    use alloc::vec;
+
+    fn formatted_image() -> Vec<u8> {
+        FsBuilder::new(MB).build().unwrap()
+    }
+
+    fn file_template(size: u32) -> [u8; 32] {
+        let mut raw = [0u8; 32];
+        raw[11] = ATTR_ARCHIVE;
+        raw[28..32].copy_from_slice(&size.to_le_bytes());
+        raw
+    }
+
+    #[test]
+    fn test_exact_short_name() {
+        assert_eq!(exact_short_name("README.TXT"), Some(*b"README  TXT"));
+        assert_eq!(exact_short_name("DOCS"), Some(*b"DOCS       "));
+        assert_eq!(exact_short_name("readme.txt"), None);
+        assert_eq!(exact_short_name("LONGFILENAME.TXT"), None);
+        assert_eq!(exact_short_name("A.B.C"), None);
+    }
+
+    #[test]
+    fn test_generate_short_name() {
+        assert_eq!(&generate_short_name("Long File Name.txt", &[]), b"LONGFI~1TXT");
+        let existing = [*b"LONGFI~1TXT"];
+        assert_eq!(&generate_short_name("longfile.txt", &existing), b"LONGFI~2TXT");
+        assert_eq!(&generate_short_name(".hidden", &[]), b"HIDDEN~1   ");
+        assert_eq!(&generate_short_name("a+b.jpeg", &[]), b"A_B~1   JPE");
+    }
+
+    #[test]
+    fn test_lfn_slots_roundtrip() {
+        let slots = build_lfn_slots("A very long file name.txt", 0x42);
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[0][0], 0x42);
+        assert_eq!(slots[1][0], 0x01);
+
+        let mut chars = Vec::new();
+        for slot in slots.iter().rev() {
+            chars.extend(LfnEntry::from_bytes(slot).unwrap().get_chars());
+        }
+        assert_eq!(chars.into_iter().collect::<String>(), "A very long file name.txt");
+    }
+
+    #[test]
+    fn test_validate_long_name() {
+        let invalid = |error| Err(FsError::InvalidLongName(error));
+        assert!(validate_long_name("ok name.txt").is_ok());
+        assert!(validate_long_name(".hidden").is_ok());
+        assert_eq!(validate_long_name("a/b"), invalid(NameError::ForbiddenChar('/')));
+        assert_eq!(validate_long_name("what?.txt"), invalid(NameError::ForbiddenChar('?')));
+        assert_eq!(validate_long_name("back\\slash"), invalid(NameError::ForbiddenChar('\\')));
+        assert_eq!(validate_long_name("bell\u{7}"), invalid(NameError::ForbiddenChar('\u{7}')));
+        assert_eq!(validate_long_name(""), invalid(NameError::Empty));
+        assert_eq!(validate_long_name(".."), invalid(NameError::Reserved));
+        assert_eq!(validate_long_name("name."), invalid(NameError::TrailingChar('.')));
+        assert_eq!(validate_long_name("name "), invalid(NameError::TrailingChar(' ')));
+
+        // 255 unités UTF-16, y compris les paires de substitution
+        assert!(validate_long_name(&"a".repeat(LFN_MAX_UNITS)).is_ok());
+        assert_eq!(validate_long_name(&"a".repeat(256)), invalid(NameError::TooLong(256)));
+        let emoji = "\u{1F600}".repeat(128);
+        assert_eq!(validate_long_name(&emoji), invalid(NameError::TooLong(256)));
+
+        assert_eq!(
+            FsError::InvalidLongName(NameError::ForbiddenChar(':')).to_string(),
+            "Invalid name: forbidden character ':'"
+        );
+        assert_eq!(NameError::ForbiddenChar('\u{1}').to_string(), "forbidden control character U+0001");
+        assert_eq!(NameError::TrailingChar(' ').to_string(), "trailing space");
+    }
+
+    #[test]
+    fn test_rename_with_lfn_and_collision() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+
+        fs.insert_entry(root, "OLD.TXT", &file_template(10)).unwrap();
+        fs.insert_entry(root, "OTHER.TXT", &file_template(20)).unwrap();
+
+        let entry = fs.rename(root, "old.txt", "My Renamed File.txt").unwrap();
+        assert_eq!(entry.size, 10);
+        assert!(fs.find_entry(root, "OLD.TXT").is_none());
+
+        let listing = fs.read_directory_with_lfn(root);
+        assert_eq!(listing.len(), 2);
+        assert!(listing.iter().any(|(_, ln)| ln.as_deref() == Some("My Renamed File.txt")));
+
+        assert_eq!(fs.rename(root, "My Renamed File.txt", "other.txt").unwrap_err(), FsError::AlreadyExists);
+        assert_eq!(fs.rename(root, "missing", "x").unwrap_err(), FsError::NotFound);
+
+        // Renommage vers un nom court: les slots LFN sont libérés
+        fs.rename(root, "my renamed file.txt", "SHORT.TXT").unwrap();
+        let listing = fs.read_directory_with_lfn(root);
+        assert!(listing.iter().all(|(_, ln)| ln.is_none()));
+        assert!(fs.find_entry(root, "SHORT.TXT").is_some());
+    }
+
+    #[test]
+    fn test_create_dir() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+
+        let dir = fs.create_dir(root, "My Photos").unwrap();
+        assert!(dir.is_directory());
+        assert_eq!(fs.create_dir(root, "my photos").unwrap_err(), FsError::AlreadyExists);
+
+        let sub = fs.create_dir(dir.cluster(), "2024").unwrap();
+        let entries = fs.read_directory(sub.cluster());
+        assert!(entries[0].is_dot() && entries[0].cluster() == sub.cluster());
+        assert!(entries[1].is_dotdot() && entries[1].cluster() == dir.cluster());
+        assert_eq!(fs.read_directory(dir.cluster())[1].cluster(), 0);
+
+        fs.write_file(sub.cluster(), "a.txt", b"abc").unwrap();
+        let found = fs.resolve_path("/My Photos/2024/a.txt", root).unwrap();
+        assert_eq!(fs.read_file(&found), b"abc");
+    }
+
+    #[test]
+    fn test_remove_file() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+
+        let entry = fs.write_file(root, "Removed File.txt", b"gone").unwrap();
+        fs.remove_file(root, "removed file.txt").unwrap();
+        assert!(fs.find_entry(root, "Removed File.txt").is_none());
+        assert!(fs.fat_table().get_entry(entry.cluster()).is_free());
+
+        let deleted = fs.list_deleted(root);
+        assert_eq!(deleted.len(), 1);
+        assert!(deleted[0].recoverable);
+        assert_eq!(fs.remove_file(root, "Removed File.txt"), Err(FsError::NotFound));
+    }
+
+    #[test]
+    fn test_directory_grows_when_full() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+
+        // 512 octets par cluster = 16 slots
+        for i in 0..20 {
+            let name = alloc::format!("F{}.TXT", i);
+            fs.insert_entry(root, &name, &file_template(0)).unwrap();
+        }
+
+        assert_eq!(fs.cluster_chain(root).len(), 2);
+        assert_eq!(fs.read_directory(root).len(), 20);
+    }
+
+    #[test]
+    fn test_write_file_create_and_overwrite() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+        let free_before = fs.free_space();
+
+        let data: Vec<u8> = (0..1300u32).map(|i| (i % 251) as u8).collect();
+        let entry = fs.write_file(root, "report log.txt", &data).unwrap();
+        assert_eq!(entry.size, 1300);
+        assert_eq!(fs.cluster_chain(entry.cluster()).len(), 3);
+        assert_eq!(fs.read_file(&entry), data);
+
+        // Remplacement par un contenu plus court: les clusters en trop sont libérés
+        let entry = fs.write_file(root, "REPORT LOG.TXT", b"short").unwrap();
+        assert_eq!(fs.read_file(&entry), b"short");
+        assert_eq!(fs.read_directory(root).len(), 1);
+        assert_eq!(fs.free_space(), free_before - 512);
+
+        let empty = fs.write_file(root, "EMPTY.TXT", b"").unwrap();
+        assert_eq!((empty.cluster(), empty.size), (0, 0));
+    }
+
+    #[test]
+    fn test_write_file_errors() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+
+        let mut dir = file_template(0);
+        dir[11] = crate::fat32::ATTR_DIRECTORY;
+        fs.insert_entry(root, "DIR", &dir).unwrap();
+        assert_eq!(fs.write_file(root, "dir", b"x").unwrap_err(), FsError::IsADirectory);
+        assert_eq!(fs.write_file(root, "a/b", b"x").unwrap_err(), FsError::InvalidLongName(NameError::ForbiddenChar('/')));
+
+        let huge = vec![0u8; 2 * 1024 * 1024];
+        let free_before = fs.free_space();
+        fs.write_file(root, "OLD.TXT", b"old").unwrap();
+        assert_eq!(fs.write_file(root, "OLD.TXT", &huge).unwrap_err(), FsError::NoSpace);
+        assert_eq!(fs.find_entry(root, "OLD.TXT").unwrap().size, 0);
+        assert_eq!(fs.free_space(), free_before);
+    }
+
+    #[test]
+    fn test_orphan_lfn_slots_do_not_name_entry() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+        let written = fs.insert_entry(root, "Quarterly report.txt", &file_template(5)).unwrap();
+        let location = fs.locate_entry(root, "Quarterly report.txt").unwrap();
+        assert_eq!(location.slots.len(), 3);
+
+        // Le nom court est remplacé sans toucher aux slots LFN qui le précèdent
+        let mut raw = fs.read_slot(location.short_offset());
+        raw[..11].copy_from_slice(b"OTHER   TXT");
+        fs.write_slot(location.short_offset(), &raw).unwrap();
+        assert_ne!(written.name, raw[..8]);
+
+        assert!(fs.locate_entry(root, "Quarterly report.txt").is_none());
+        assert_eq!(fs.rename(root, "Quarterly report.txt", "x.txt").unwrap_err(), FsError::NotFound);
+        assert_eq!(fs.remove_file(root, "Quarterly report.txt"), Err(FsError::NotFound));
+        let other = fs.locate_entry(root, "OTHER.TXT").unwrap();
+        assert_eq!(other.slots, [location.short_offset()]);
+    }
+
+    #[test]
+    fn test_rename_failure_leaves_no_lfn_slots() {
+        let mut image = formatted_image();
+        let mut fs = Fat32::new_mut(&mut image).unwrap();
+        let root = fs.root_cluster();
+
+        // 14 slots occupés sur 16, puis un cluster de répertoire au-delà de
+        // l'image (volume agrandi après le montage, comme une image tronquée)
+        for i in 0..14 {
+            fs.insert_entry(root, &alloc::format!("F{}.TXT", i), &file_template(0)).unwrap();
+        }
+        let beyond = fs.cluster_count() + 3;
+        fs.boot_sector.total_sectors *= 2;
+        assert!(fs.cluster_offset(beyond) >= fs.disk_data().len() as u64);
+        fs.write_fat_entry(root, beyond).unwrap();
+        fs.write_fat_entry(beyond, 0x0FFFFFFF).unwrap();
+
+        // Deux slots LFN tiennent dans le cluster racine, le nom court non
+        assert_eq!(fs.rename(root, "F0.TXT", "A much longer name.txt").unwrap_err(), FsError::OutOfBounds);
+        let tail = fs.cluster_offset(root) + 14 * 32;
+        assert_eq!(fs.read_slot(tail)[0], DELETED_MARKER);
+        assert_eq!(fs.read_slot(tail + 32)[0], DELETED_MARKER);
+        assert_eq!(fs.find_entry(root, "F0.TXT").unwrap().size, 0);
+        assert_eq!(fs.read_directory_with_lfn(root).len(), 14);
+    }
+}
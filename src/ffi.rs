@@ -0,0 +1,312 @@
+//! Interface C (feature ffi): ouverture d'une image, liste, lecture, fermeture
+//!
+//! Les déclarations correspondantes sont dans `include/fat32.h`. Toutes les
+//! fonctions retournent un code `FAT32_OK` ou `FAT32_ERR_*` et écrivent leurs
+//! résultats dans des pointeurs fournis par l'appelant; aucune ne panique à
+//! travers la frontière C. L'image reste à la charge de l'appelant: elle doit
+//! rester valide et inchangée entre `fat32_open` et `fat32_close`.
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+use core::ffi::{c_char, CStr};
+
+use crate::fat32::{DirEntry, Fat32, FsError};
+
+pub const FAT32_OK: i32 = 0;
+/// Pointeur nul passé en argument
+pub const FAT32_ERR_NULL: i32 = -1;
+/// L'image n'est pas un volume FAT32 valide
+pub const FAT32_ERR_INVALID_IMAGE: i32 = -2;
+/// Chemin introuvable
+pub const FAT32_ERR_NOT_FOUND: i32 = -3;
+/// `fat32_list` sur un fichier
+pub const FAT32_ERR_NOT_A_DIRECTORY: i32 = -4;
+/// `fat32_read` sur un répertoire
+pub const FAT32_ERR_IS_A_DIRECTORY: i32 = -5;
+/// Chemin qui n'est pas de l'UTF-8
+pub const FAT32_ERR_INVALID_PATH: i32 = -6;
+/// Tableau d'entrées trop petit; `count` donne le nombre nécessaire
+pub const FAT32_ERR_BUFFER_TOO_SMALL: i32 = -7;
+/// Limite de ressources dépassée (chaîne, répertoire, fichier ou chemin)
+pub const FAT32_ERR_LIMIT: i32 = -8;
+/// Structure sur disque corrompue
+pub const FAT32_ERR_CORRUPTED: i32 = -9;
+/// Autre erreur du système de fichiers
+pub const FAT32_ERR_IO: i32 = -10;
+/// Le heap ne peut pas contenir le résultat
+pub const FAT32_ERR_NO_MEMORY: i32 = -11;
+
+/// Taille maximale d'un nom en octets UTF-8, sans le zéro final
+pub const FAT32_NAME_MAX: usize = 255;
+
+/// Volume ouvert, opaque côté C
+pub struct Fat32Handle {
+    fs: Fat32<'static>,
+}
+
+/// Entrée de répertoire telle que vue du C
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Fat32DirEntry {
+    /// Nom long (ou court à défaut), UTF-8 terminé par un zéro, tronqué à `FAT32_NAME_MAX`
+    pub name: [u8; FAT32_NAME_MAX + 1],
+    pub size: u32,
+    pub cluster: u32,
+    /// Octet d'attributs brut (`ATTR_*`)
+    pub attributes: u8,
+    pub is_directory: bool,
+}
+
+impl Fat32DirEntry {
+    fn new(entry: &DirEntry, long_name: Option<&str>) -> Self {
+        let short_name;
+        let name = match long_name {
+            Some(name) => name,
+            None => {
+                short_name = entry.display_name();
+                &short_name
+            }
+        };
+        let mut bytes = [0u8; FAT32_NAME_MAX + 1];
+        // Troncature sur une frontière de caractère
+        let mut len = name.len().min(FAT32_NAME_MAX);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+        Fat32DirEntry {
+            name: bytes,
+            size: entry.size,
+            cluster: entry.cluster(),
+            attributes: entry.attr,
+            is_directory: entry.is_directory(),
+        }
+    }
+}
+
+fn error_code(error: FsError) -> i32 {
+    match error {
+        FsError::NotFound => FAT32_ERR_NOT_FOUND,
+        FsError::IsADirectory => FAT32_ERR_IS_A_DIRECTORY,
+        FsError::InvalidName | FsError::InvalidLongName(_) => FAT32_ERR_INVALID_PATH,
+        FsError::LimitExceeded => FAT32_ERR_LIMIT,
+        FsError::Anomaly(_)
+            | FsError::CorruptFat { .. }
+            | FsError::InvalidGeometry
+            | FsError::InvalidClusterSize(_)
+            | FsError::TruncatedImage { .. } => FAT32_ERR_CORRUPTED,
+        FsError::OutOfMemory => FAT32_ERR_NO_MEMORY,
+        _ => FAT32_ERR_IO,
+    }
+}
+
+/// Chemin C converti en `&str`
+///
+/// # Safety
+/// `path` doit être nul ou pointer vers une chaîne terminée par un zéro.
+unsafe fn path_arg<'p>(path: *const c_char) -> Result<&'p str, i32> {
+    if path.is_null() {
+        return Err(FAT32_ERR_NULL);
+    }
+    // SAFETY: `path` est non nul et terminé par un zéro (contrat de l'appelant)
+    unsafe { CStr::from_ptr(path) }.to_str().map_err(|_| FAT32_ERR_INVALID_PATH)
+}
+
+/// Entrée désignée par `path`, `None` pour la racine
+fn lookup(fs: &Fat32<'_>, path: &str) -> Result<Option<DirEntry>, i32> {
+    if path.trim_matches('/').is_empty() {
+        return Ok(None);
+    }
+    fs.try_resolve_path(path, fs.root_cluster()).map(Some).map_err(error_code)
+}
+
+/// Ouvre l'image de `len` octets à `data` et écrit le volume dans `*out`
+///
+/// # Safety
+/// `data` doit pointer vers `len` octets lisibles, qui restent valides et ne
+/// sont pas modifiés jusqu'à `fat32_close`; `out` doit être valide en écriture.
+#[no_mangle]
+pub unsafe extern "C" fn fat32_open(data: *const u8, len: usize, out: *mut *mut Fat32Handle) -> i32 {
+    if data.is_null() || out.is_null() {
+        return FAT32_ERR_NULL;
+    }
+    // SAFETY: `data` couvre `len` octets valides jusqu'à `fat32_close` (contrat de l'appelant)
+    let image: &'static [u8] = unsafe { core::slice::from_raw_parts(data, len) };
+    let fs = match Fat32::new(image) {
+        Some(fs) => fs,
+        None => return FAT32_ERR_INVALID_IMAGE,
+    };
+    // SAFETY: `out` est valide en écriture (contrat de l'appelant)
+    unsafe { *out = Box::into_raw(Box::new(Fat32Handle { fs })) };
+    FAT32_OK
+}
+
+/// Liste le répertoire `path` dans `entries` (au plus `capacity` entrées)
+///
+/// `*count` reçoit le nombre d'entrées du répertoire. S'il dépasse
+/// `capacity`, les `capacity` premières sont écrites et la fonction retourne
+/// `FAT32_ERR_BUFFER_TOO_SMALL`.
+///
+/// # Safety
+/// `handle` doit venir de `fat32_open`, `path` être une chaîne terminée par
+/// un zéro, `entries` pointer vers `capacity` entrées (ou être nul si
+/// `capacity` vaut 0) et `count` être valide en écriture.
+#[no_mangle]
+pub unsafe extern "C" fn fat32_list(
+    handle: *const Fat32Handle,
+    path: *const c_char,
+    entries: *mut Fat32DirEntry,
+    capacity: usize,
+    count: *mut usize,
+) -> i32 {
+    if handle.is_null() || count.is_null() || (entries.is_null() && capacity > 0) {
+        return FAT32_ERR_NULL;
+    }
+    // SAFETY: `handle` vient de `fat32_open` et n'est pas encore fermé
+    let fs = unsafe { &(*handle).fs };
+    // SAFETY: `path` est terminé par un zéro (contrat de l'appelant)
+    let path = match unsafe { path_arg(path) } {
+        Ok(path) => path,
+        Err(code) => return code,
+    };
+    let cluster = match lookup(fs, path) {
+        Ok(None) => fs.root_cluster(),
+        // Un `..` qui remonte à la racine a le cluster 0
+        Ok(Some(entry)) if entry.is_directory() && entry.cluster() == 0 => fs.root_cluster(),
+        Ok(Some(entry)) if entry.is_directory() => entry.cluster(),
+        Ok(Some(_)) => return FAT32_ERR_NOT_A_DIRECTORY,
+        Err(code) => return code,
+    };
+    let listing = match fs.try_read_directory(cluster) {
+        Ok(listing) => listing,
+        Err(error) => return error_code(error),
+    };
+
+    for (i, (entry, long_name)) in listing.iter().take(capacity).enumerate() {
+        // SAFETY: `i < capacity`, `entries` couvre `capacity` entrées
+        unsafe { entries.add(i).write(Fat32DirEntry::new(entry, long_name.as_deref())) };
+    }
+    // SAFETY: `count` est valide en écriture (contrat de l'appelant)
+    unsafe { *count = listing.len() };
+    if listing.len() > capacity {
+        return FAT32_ERR_BUFFER_TOO_SMALL;
+    }
+    FAT32_OK
+}
+
+/// Lit au plus `len` octets du fichier `path` à partir de `offset`
+///
+/// `*read` reçoit le nombre d'octets écrits dans `buf`, 0 en fin de fichier.
+///
+/// # Safety
+/// `handle` doit venir de `fat32_open`, `path` être une chaîne terminée par
+/// un zéro, `buf` pointer vers `len` octets inscriptibles (ou être nul si
+/// `len` vaut 0) et `read` être valide en écriture.
+#[no_mangle]
+pub unsafe extern "C" fn fat32_read(
+    handle: *const Fat32Handle,
+    path: *const c_char,
+    offset: u64,
+    buf: *mut u8,
+    len: usize,
+    read: *mut usize,
+) -> i32 {
+    if handle.is_null() || read.is_null() || (buf.is_null() && len > 0) {
+        return FAT32_ERR_NULL;
+    }
+    // SAFETY: `handle` vient de `fat32_open` et n'est pas encore fermé
+    let fs = unsafe { &(*handle).fs };
+    // SAFETY: `path` est terminé par un zéro (contrat de l'appelant)
+    let path = match unsafe { path_arg(path) } {
+        Ok(path) => path,
+        Err(code) => return code,
+    };
+    let entry = match lookup(fs, path) {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return FAT32_ERR_IS_A_DIRECTORY,
+        Err(code) => return code,
+    };
+    let file = match fs.open(&entry) {
+        Ok(file) => file,
+        Err(error) => return error_code(error),
+    };
+    let out: &mut [u8] = if len == 0 {
+        &mut []
+    } else {
+        // SAFETY: `buf` couvre `len` octets inscriptibles (contrat de l'appelant)
+        unsafe { core::slice::from_raw_parts_mut(buf, len) }
+    };
+    // SAFETY: `read` est valide en écriture (contrat de l'appelant)
+    unsafe { *read = file.read_at(offset, out) };
+    FAT32_OK
+}
+
+/// Ferme un volume ouvert par `fat32_open`; sans effet sur un pointeur nul
+///
+/// # Safety
+/// `handle` doit être nul ou venir de `fat32_open` et ne plus être utilisé ensuite.
+#[no_mangle]
+pub unsafe extern "C" fn fat32_close(handle: *mut Fat32Handle) {
+    if !handle.is_null() {
+        // SAFETY: `handle` vient de `Box::into_raw` dans `fat32_open`, fermé une seule fois
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_PATH, README_PATH, README_TEXT};
+    use core::ptr::{null, null_mut};
+
+    const EMPTY: Fat32DirEntry =
+        Fat32DirEntry { name: [0; FAT32_NAME_MAX + 1], size: 0, cluster: 0, attributes: 0, is_directory: false };
+
+    fn name(entry: &Fat32DirEntry) -> &str {
+        let len = entry.name.iter().position(|&b| b == 0).unwrap();
+        core::str::from_utf8(&entry.name[..len]).unwrap()
+    }
+
+    #[test]
+    fn test_open_list_read_close() {
+        let image = TestImage::default().long_names().fragmented().build().unwrap();
+        let mut handle = null_mut();
+        unsafe {
+            assert_eq!(fat32_open(image.as_ptr(), image.len(), &mut handle), FAT32_OK);
+
+            // Liste trop petite: le nombre nécessaire est retourné
+            let mut count = 0;
+            assert_eq!(fat32_list(handle, c"/".as_ptr(), null_mut(), 0, &mut count), FAT32_ERR_BUFFER_TOO_SMALL);
+            let mut entries = vec![EMPTY; count];
+            assert_eq!(fat32_list(handle, c"/".as_ptr(), entries.as_mut_ptr(), count, &mut count), FAT32_OK);
+            assert!(entries.iter().any(|e| name(e) == "My Documents" && e.is_directory));
+            assert!(entries.iter().any(|e| name(e) == &README_PATH[1..] && e.size as usize == README_TEXT.len()));
+
+            // `..` d'un sous-répertoire de la racine liste la racine
+            let root_count = count;
+            assert_eq!(fat32_list(handle, c"/My Documents/..".as_ptr(), null_mut(), 0, &mut count), FAT32_ERR_BUFFER_TOO_SMALL);
+            assert_eq!(count, root_count);
+
+            // Lecture par morceaux à partir d'une position
+            let path = alloc::ffi::CString::new(README_PATH).unwrap();
+            let mut buf = [0u8; 8];
+            let mut read = 0;
+            assert_eq!(fat32_read(handle, path.as_ptr(), 2, buf.as_mut_ptr(), buf.len(), &mut read), FAT32_OK);
+            assert_eq!(&buf[..read], &README_TEXT[2..10]);
+            let frag = alloc::ffi::CString::new(FRAGMENTED_PATH).unwrap();
+            assert_eq!(fat32_read(handle, frag.as_ptr(), u64::MAX, buf.as_mut_ptr(), buf.len(), &mut read), FAT32_OK);
+            assert_eq!(read, 0);
+
+            assert_eq!(fat32_read(handle, c"/nope".as_ptr(), 0, buf.as_mut_ptr(), 8, &mut read), FAT32_ERR_NOT_FOUND);
+            assert_eq!(fat32_read(handle, c"/".as_ptr(), 0, buf.as_mut_ptr(), 8, &mut read), FAT32_ERR_IS_A_DIRECTORY);
+            assert_eq!(fat32_list(handle, path.as_ptr(), null_mut(), 0, &mut count), FAT32_ERR_NOT_A_DIRECTORY);
+            assert_eq!(fat32_list(handle, null(), null_mut(), 0, &mut count), FAT32_ERR_NULL);
+            fat32_close(handle);
+
+            let garbage = [0u8; 1024];
+            assert_eq!(fat32_open(garbage.as_ptr(), garbage.len(), &mut handle), FAT32_ERR_INVALID_IMAGE);
+            fat32_close(null_mut());
+        }
+    }
+}
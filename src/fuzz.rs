@@ -0,0 +1,185 @@
+//! Points d'entrée de fuzzing (cibles de `fuzz/`)
+//!
+//! Chaque fonction accepte des octets quelconques et, par contrat, ne panique
+//! pas et n'alloue pas plus qu'un multiple de la taille de l'entrée. Avec la
+//! feature `fuzz`, les structures de configuration implémentent `Arbitrary`.
+
+extern crate alloc;
+
+use crate::fat32::{parse_directory, parse_directory_with_lfn, BootSector, DirEntry, Fat32};
+
+/// Profondeur maximale parcourue par `mount` (un répertoire peut contenir son parent)
+const MAX_DEPTH: usize = 8;
+/// Nombre maximal d'entrées visitées par `mount`
+const MAX_ENTRIES: usize = 4096;
+
+/// Parse un boot sector et calcule sa géométrie
+pub fn boot_sector(data: &[u8]) {
+    let bytes: &[u8; 512] = match data.get(..512).and_then(|d| d.try_into().ok()) {
+        Some(bytes) => bytes,
+        None => return,
+    };
+    if let Some(boot) = BootSector::from_bytes(bytes) {
+        let _ = boot.is_valid_geometry();
+        let _ = boot.data_start_sector();
+        let _ = boot.cluster_to_sector(u32::MAX);
+        let _ = boot.bytes_per_cluster();
+    }
+}
+
+/// Parse un répertoire brut, avec et sans noms longs
+pub fn directory(data: &[u8]) {
+    for entry in parse_directory(data) {
+        touch_entry(&entry);
+    }
+    for (entry, _) in parse_directory_with_lfn(data) {
+        touch_entry(&entry);
+    }
+}
+
+/// Monte une image en lecture seule et parcourt son arborescence
+pub fn mount(data: &[u8]) {
+    let fs = match Fat32::new(data) {
+        Some(fs) => fs,
+        None => return,
+    };
+    let _ = fs.fs_info();
+    let _ = fs.free_space();
+    let mut budget = MAX_ENTRIES;
+    walk(&fs, fs.root_cluster(), 0, &mut budget);
+}
+
+fn walk(fs: &Fat32, cluster: u32, depth: usize, budget: &mut usize) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    for (entry, _) in fs.read_directory_with_lfn(cluster) {
+        if *budget == 0 {
+            return;
+        }
+        *budget -= 1;
+
+        touch_entry(&entry);
+        if entry.is_dot() || entry.is_dotdot() {
+            continue;
+        }
+        if entry.is_directory() {
+            walk(fs, entry.cluster(), depth + 1, budget);
+        } else {
+            let _ = fs.read_file(&entry);
+        }
+    }
+}
+
+fn touch_entry(entry: &DirEntry) {
+    let _ = entry.display_name();
+    let _ = entry.modified();
+    let _ = entry.attr_string();
+}
+
+#[cfg(feature = "fuzz")]
+mod arbitrary_impls {
+    use arbitrary::{Arbitrary, Result, Unstructured};
+
+    use crate::fat32::FormatOptions;
+    use crate::shell::{ShellConfig, Verbosity};
+
+    /// Options proches des valeurs valides, pour atteindre le code après la validation
+    impl<'a> Arbitrary<'a> for FormatOptions {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(FormatOptions {
+                sectors_per_cluster: 1 << u.int_in_range(0..=7)?,
+                fat_count: u.int_in_range(1..=2)?,
+                label: u.arbitrary()?,
+                volume_serial: u.arbitrary()?,
+            })
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Verbosity {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(*u.choose(&[Verbosity::Quiet, Verbosity::Normal, Verbosity::Verbose])?)
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for ShellConfig {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(ShellConfig {
+                color: u.arbitrary()?,
+                verbosity: u.arbitrary()?,
+                page_lines: u.arbitrary()?,
+                show_hidden: u.arbitrary()?,
+                case_sensitive: u.arbitrary()?,
+                json: u.arbitrary()?,
+                utc_offset: u.int_in_range(-14 * 3600..=14 * 3600)?,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use crate::testimg::{corrupt, TestImage, FRAGMENTED_PATH, LONG_NAME_PATHS, README_PATH};
+
+    /// Générateur pseudo-aléatoire reproductible (xorshift)
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    fn run_all(data: &[u8]) {
+        boot_sector(data);
+        directory(data);
+        mount(data);
+    }
+
+    #[test]
+    fn test_corrupted_images() {
+        let valid = TestImage::default().long_names().fragmented().deep_tree(3).build().unwrap();
+        run_all(&valid);
+
+        let mut looped = valid.clone();
+        corrupt::loop_chain(&mut looped, FRAGMENTED_PATH).unwrap();
+        let mut crossed = valid.clone();
+        corrupt::cross_link(&mut crossed, README_PATH, FRAGMENTED_PATH).unwrap();
+        let mut bad_lfn = valid.clone();
+        corrupt::break_lfn_checksum(&mut bad_lfn, LONG_NAME_PATHS[0]).unwrap();
+        let mut truncated = valid.clone();
+        corrupt::truncate_fat(&mut truncated).unwrap();
+        let mut zeroed = valid.clone();
+        corrupt::zero_boot_sector(&mut zeroed);
+
+        for image in [looped, crossed, bad_lfn, truncated, zeroed] {
+            run_all(&image);
+        }
+    }
+
+    #[test]
+    fn test_random_mutations() {
+        let valid = TestImage::default().long_names().deep_tree(2).build().unwrap();
+        let mut state = 0x2545_F491_4F6C_DD1D;
+
+        for _ in 0..200 {
+            let mut image = valid.clone();
+            // Quelques octets du boot sector, de la FAT et des répertoires modifiés au hasard
+            for _ in 0..16 {
+                let region = match xorshift(&mut state) % 3 {
+                    0 => 0..90,
+                    1 => 32 * 512..48 * 512,
+                    _ => 64 * 512..96 * 512,
+                };
+                let at = region.start + (xorshift(&mut state) as usize) % region.len();
+                image[at] = xorshift(&mut state) as u8;
+            }
+            run_all(&image);
+        }
+
+        let noise: Vec<u8> = (0..4096).map(|_| xorshift(&mut state) as u8).collect();
+        run_all(&noise);
+        run_all(&[]);
+    }
+}
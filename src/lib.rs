@@ -6,18 +6,69 @@
 // #![no_std]
 // #![feature(alloc_error_handler)]
 
-#![allow(static_mut_refs)]
-
 extern crate alloc;
 
+/// Messages de journal: transmis à la crate `log` avec la feature log, sans
+/// effet sinon (les arguments restent vérifiés à la compilation)
+macro_rules! log_message {
+    ($level:ident, $($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::$level!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)*);
+    }};
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => { log_message!(warn, $($arg)*) };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => { log_message!(debug, $($arg)*) };
+}
+
+macro_rules! log_trace {
+    ($($arg:tt)*) => { log_message!(trace, $($arg)*) };
+}
+
+/// Span `tracing` actif jusqu'à la fin du bloc (feature tracing, sans effet sinon)
+///
+/// `enter_span!(span = "nom", champ = valeur; plus_tard)` ouvre le span
+/// `span`; les champs listés après `;` sont renseignés ensuite par `span_record!`.
+macro_rules! enter_span {
+    ($span:ident = $name:literal $(, $field:ident = $value:expr)* $(; $($later:ident),*)?) => {
+        #[cfg(feature = "tracing")]
+        let $span = tracing::debug_span!(
+            $name, $($field = $value,)* $($($later = tracing::field::Empty,)*)?
+        ).entered();
+    };
+}
+
+/// Renseigne un champ déclaré par `enter_span!` (compteur d'octets ou d'entrées)
+macro_rules! span_record {
+    ($span:ident, $field:ident = $value:expr) => {
+        #[cfg(feature = "tracing")]
+        $span.record(stringify!($field), $value as u64);
+    };
+}
+
 pub mod fat32;
 pub mod shell;
 pub mod allocator;
+pub mod checksum;
+pub mod testimg;
+pub mod builder;
+pub mod fuzz;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 // Handlers no_std (décommenter pour la soumission):
 // use core::panic::PanicInfo;
 // #[panic_handler]
 // fn panic(_info: &PanicInfo) -> ! { loop {} }
+// (allocator::set_oom_handler est appelé avant, à l'échec de l'allocation)
 // #[alloc_error_handler]
 // fn alloc_error(_layout: core::alloc::Layout) -> ! { loop {} }
 
@@ -1,12 +1,240 @@
 //! FAT32 Shell - Programme de démonstration
 
-use std::io::{self, Write, BufRead};
-use fat32_exam::fat32::Fat32;
-use fat32_exam::shell::{ShellState, Output, Command, parse_command};
-use fat32_exam::shell::{cmd_ls, cmd_cd, cmd_cat, cmd_more, cmd_pwd, cmd_help};
+use std::io::{self, Write, BufRead, IsTerminal, Read, Seek, SeekFrom};
+use fat32_exam::fat32::{BlockDevice, Fat32, FsError, Snapshot, read_partitions};
+use fat32_exam::fat32::device::SECTOR_SIZE;
+use fat32_exam::fat32::snapshot::SNAPSHOT_HEADER;
+use fat32_exam::testimg::demo_image;
+use fat32_exam::shell::{ShellState, Verbosity, Output, Input, NoInput, TerminalInfo, execute_command, split_sequence, write_error};
+use fat32_exam::shell::{complete, Edit, History, KeyDecoder, LineEditor};
+use fat32_exam::shell::editor::HISTORY_SIZE;
 
 struct ConsoleOutput;
 
+/// Lance `stty` sur /dev/tty; retourne sa sortie si la commande a réussi
+fn stty(args: &[&str]) -> Option<String> {
+    let tty = std::fs::File::open("/dev/tty").ok()?;
+    let output = std::process::Command::new("stty").args(args).stdin(tty).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Terminal en mode caractère sans écho pendant l'édition d'une ligne
+///
+/// Le mode d'origine est rétabli quand la valeur est détruite, avant
+/// d'exécuter la commande (more lit alors des lignes normalement).
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Some(RawMode { saved: saved.trim().to_string() })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Fichier de l'historique: `$HOME/.fat32_history`
+fn history_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".fat32_history"))
+}
+
+fn load_history() -> History {
+    let mut history = History::new(HISTORY_SIZE);
+    if let Some(text) = history_path().and_then(|path| std::fs::read_to_string(path).ok()) {
+        text.lines().for_each(|line| history.push(line));
+    }
+    history
+}
+
+fn save_history(history: &History) {
+    if let Some(path) = history_path() {
+        let mut text = history.entries().join("\n");
+        text.push('\n');
+        let _ = std::fs::write(path, text);
+    }
+}
+
+/// Taille des blocs lus et écrits sur un périphérique (multiple de la taille de secteur)
+const DEVICE_BLOCK: usize = 4096;
+
+/// Taille maximale d'un périphérique chargé en mémoire (4 Gio)
+///
+/// `Fat32` travaille sur une image en mémoire: au-delà, mieux vaut refuser
+/// que tenter d'allouer toute une carte de 32 ou 64 Go.
+const MAX_DEVICE_BYTES: u64 = 4 << 30;
+
+/// Image ouverte depuis un fichier ou un périphérique bloc
+struct Image {
+    data: Vec<u8>,
+    /// Vrai si l'image vient d'un périphérique: seuls les blocs modifiés y sont réécrits
+    device: bool,
+}
+
+/// Vrai pour un périphérique (`/dev/sdb1`, `\\.\PhysicalDrive1`) plutôt qu'un fichier
+fn is_device(file: &std::fs::File) -> io::Result<bool> {
+    let metadata = file.metadata()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        let kind = metadata.file_type();
+        if kind.is_block_device() || kind.is_char_device() {
+            return Ok(true);
+        }
+    }
+    Ok(!metadata.is_file())
+}
+
+/// Périphérique bloc lu et écrit par secteurs alignés
+struct FileDevice {
+    file: std::fs::File,
+    sectors: u64,
+    /// Dernière erreur du système, que `FsError::Io` ne transporte pas
+    last_error: Option<io::Error>,
+}
+
+impl FileDevice {
+    fn new(mut file: std::fs::File) -> io::Result<Self> {
+        // La taille d'un périphérique n'est connue qu'en allant à sa fin
+        let size = file.seek(SeekFrom::End(0))?;
+        Ok(FileDevice { file, sectors: size / SECTOR_SIZE as u64, last_error: None })
+    }
+
+    /// Erreur d'entrée-sortie d'un appel qui a échoué avec `error`
+    fn io_error(&mut self, error: FsError) -> io::Error {
+        self.last_error.take().unwrap_or_else(|| io::Error::other(error.to_string()))
+    }
+
+    fn seek_to(&mut self, sector: u64) -> Result<(), FsError> {
+        match self.file.seek(SeekFrom::Start(sector * SECTOR_SIZE as u64)) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(self.record(e)),
+        }
+    }
+
+    fn record(&mut self, error: io::Error) -> FsError {
+        self.last_error = Some(error);
+        FsError::Io
+    }
+
+    /// Octets entiers du périphérique (un reste de moins d'un secteur est ignoré)
+    fn len(&self) -> u64 {
+        self.sectors * SECTOR_SIZE as u64
+    }
+}
+
+impl BlockDevice for FileDevice {
+    fn sector_count(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        self.read_sectors(sector, buf.get_mut(..SECTOR_SIZE).ok_or(FsError::OutOfBounds)?)
+    }
+
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let count = (buf.len() / SECTOR_SIZE) as u64;
+        if !buf.len().is_multiple_of(SECTOR_SIZE) || start.checked_add(count).is_none_or(|end| end > self.sectors) {
+            return Err(FsError::OutOfBounds);
+        }
+        self.seek_to(start)?;
+        self.file.read_exact(buf).map_err(|e| self.record(e))
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError> {
+        if sector >= self.sectors {
+            return Err(FsError::OutOfBounds);
+        }
+        let buf = buf.get(..SECTOR_SIZE).ok_or(FsError::OutOfBounds)?;
+        self.seek_to(sector)?;
+        self.file.write_all(buf).map_err(|e| self.record(e))
+    }
+}
+
+/// Lit une image entière; un périphérique est lu par blocs de secteurs alignés
+///
+/// Un périphérique de plus de `MAX_DEVICE_BYTES` est refusé.
+fn read_image(path: &str) -> io::Result<Image> {
+    let mut file = std::fs::File::open(path)?;
+    if !is_device(&file)? {
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        return Ok(Image { data, device: false });
+    }
+
+    let mut device = FileDevice::new(file)?;
+    if device.len() > MAX_DEVICE_BYTES {
+        return Err(io::Error::other(format!(
+            "device holds {} MiB, more than the {} MiB the shell can load in memory",
+            device.len() >> 20, MAX_DEVICE_BYTES >> 20
+        )));
+    }
+    let len = usize::try_from(device.len()).map_err(|_| io::Error::other("device too large"))?;
+    let mut data = Vec::new();
+    data.try_reserve_exact(len).map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+    data.resize(len, 0);
+    for (i, block) in data.chunks_mut(DEVICE_BLOCK).enumerate() {
+        let sector = (i * DEVICE_BLOCK / SECTOR_SIZE) as u64;
+        if let Err(e) = device.read_sectors(sector, block) {
+            return Err(device.io_error(e));
+        }
+    }
+    Ok(Image { data, device: true })
+}
+
+/// Enregistre l'image; sur un périphérique, seuls les blocs modifiés sont réécrits
+///
+/// Chaque bloc est comparé au contenu actuel du périphérique, sans garder de
+/// copie de l'image entière en mémoire.
+fn save_image(path: &str, image: &Image) -> io::Result<()> {
+    if !image.device {
+        return std::fs::write(path, &image.data);
+    }
+
+    let file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let mut device = FileDevice::new(file)?;
+    let mut current = vec![0u8; DEVICE_BLOCK];
+    for (i, block) in image.data.chunks(DEVICE_BLOCK).enumerate() {
+        let sector = (i * DEVICE_BLOCK / SECTOR_SIZE) as u64;
+        let current = &mut current[..block.len()];
+        if let Err(e) = device.read_sectors(sector, current) {
+            return Err(device.io_error(e));
+        }
+        if current != block {
+            for (j, data) in block.chunks(SECTOR_SIZE).enumerate() {
+                if let Err(e) = device.write_sector(sector + j as u64, data) {
+                    return Err(device.io_error(e));
+                }
+            }
+        }
+    }
+    device.file.sync_all()
+}
+
+/// Taille du terminal: `stty size` sur /dev/tty, sinon LINES/COLUMNS, sinon 24x80
+fn terminal_size() -> TerminalInfo {
+    let from_stty = stty(&["size"]).and_then(|text| {
+        let (rows, cols) = text.trim().split_once(' ')?;
+        Some(TerminalInfo { rows: rows.parse().ok()?, cols: cols.parse().ok()? })
+    });
+    let env = |name: &str, default: u16| {
+        std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+    };
+
+    from_stty
+        .filter(|t| t.rows > 0 && t.cols > 0)
+        .unwrap_or_else(|| TerminalInfo { rows: env("LINES", 24), cols: env("COLUMNS", 80) })
+}
+
 impl Output for ConsoleOutput {
     fn write_str(&mut self, s: &str) {
         print!("{}", s);
@@ -16,145 +244,571 @@ impl Output for ConsoleOutput {
     fn write_line(&mut self, s: &str) {
         println!("{}", s);
     }
+
+    fn write_err_line(&mut self, s: &str) {
+        eprintln!("{}", s);
+    }
+
+    fn terminal(&self) -> Option<TerminalInfo> {
+        io::stdout().is_terminal().then(terminal_size)
+    }
+}
+
+/// Entrée clavier: le terminal est en mode ligne, on lit le premier caractère
+/// (Entrée si vide) et on garde le reste de la ligne pour `read_line`
+#[derive(Default)]
+struct ConsoleInput {
+    pending: Option<String>,
+}
+
+impl ConsoleInput {
+    fn next_line() -> Option<String> {
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim_end_matches(['\r', '\n']).to_string()),
+        }
+    }
+}
+
+impl Input for ConsoleInput {
+    fn read_key(&mut self) -> Option<char> {
+        let line = Self::next_line()?;
+        let mut chars = line.chars();
+        let key = chars.next().unwrap_or('\n');
+        self.pending = Some(chars.as_str().to_string());
+        Some(key)
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        self.pending.take().or_else(Self::next_line)
+    }
+}
+
+/// Usage de la ligne de commande
+const USAGE: &str = "Usage: fat32-shell [-q|-v] [--rw] [--partition N] [-c commands] [image.img]
+       fat32-shell [--rw] [--partition N] <command> <image.img> [args]...
+       fat32-shell [--partition N] cp <image.img>:<path> <hostfile>
+       fat32-shell [--partition N] cp <hostfile> <image.img>:<path>
+       fat32-shell list-partitions <disk.img>
+       fat32-shell [--partition N] snapshot <image.img>
+       fat32-shell [--partition N] diff <old.img|old.snapshot> <new.img|new.snapshot>
+       fat32-shell [--partition N] mount <image.img> <dir>
+Commands: ls, cat, info, chain, crc32, sha256, cmp, rename, undelete, cp, list-partitions, snapshot, diff, mount";
+
+/// Sous-commandes façon mtools, exécutées sur l'image sans session interactive
+const SUBCOMMANDS: &[&str] = &["ls", "cat", "info", "chain", "crc32", "sha256", "cmp", "rename", "undelete", "cp",
+    "list-partitions", "snapshot", "diff", "mount"];
+
+/// Options de la ligne de commande
+#[derive(Default)]
+struct Options {
+    verbosity: Verbosity,
+    /// Image à ouvrir; l'image de démonstration si absente
+    image: Option<String>,
+    /// Monte l'image en écriture et enregistre les modifications en quittant
+    read_write: bool,
+    /// Commandes `a; b` à exécuter sans session interactive (`-c`)
+    commands: Option<String>,
+    /// Copie `cp <source> <destination>` entre l'hôte et une image
+    copy: Option<(String, String)>,
+    /// Partition à ouvrir dans une image de disque entier (`--partition N`)
+    partition: Option<usize>,
+    /// Image dont la table de partitions est affichée (`list-partitions`)
+    list_partitions: Option<String>,
+    /// Image dont l'instantané est affiché (`snapshot`)
+    snapshot: Option<String>,
+    /// Images ou instantanés à comparer (`diff`)
+    diff: Option<(String, String)>,
+    /// Image montée en lecture seule sur un répertoire (`mount`, feature fuse)
+    mount: Option<(String, String)>,
+}
+
+/// Parse `[-q|-v] [--rw] [--partition N] [-c commands] [image.img]`
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-q" | "--quiet" => options.verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => options.verbosity = Verbosity::Verbose,
+            "--rw" => options.read_write = true,
+            "--partition" => match args.next().and_then(|n| n.parse().ok()).filter(|&n| n > 0) {
+                Some(n) => options.partition = Some(n),
+                None => return Err(format!("--partition: expected a partition number\n{}", USAGE)),
+            },
+            "-c" => match args.next() {
+                Some(commands) => options.commands = Some(commands),
+                None => return Err(format!("-c: missing commands\n{}", USAGE)),
+            },
+            "-h" | "--help" => return Err(String::from(USAGE)),
+            a if a.starts_with('-') => return Err(format!("Unknown option: {}\n{}", a, USAGE)),
+            name if options.image.is_none() && options.commands.is_none() && SUBCOMMANDS.contains(&name) => {
+                return parse_subcommand(options, name, args.collect());
+            }
+            _ if options.image.is_some() => return Err(format!("Too many images\n{}", USAGE)),
+            _ => options.image = Some(arg),
+        }
+    }
+    Ok(options)
+}
+
+/// Traduit `<command> <image> [args]...` en `-c` sur l'image, ou en copie pour `cp`
+fn parse_subcommand(mut options: Options, name: &str, args: Vec<String>) -> Result<Options, String> {
+    if name == "cp" {
+        return match <[String; 2]>::try_from(args) {
+            Ok([from, to]) => {
+                options.copy = Some((from, to));
+                Ok(options)
+            }
+            Err(_) => Err(format!("cp: expected a source and a destination\n{}", USAGE)),
+        };
+    }
+    if name == "list-partitions" {
+        return match <[String; 1]>::try_from(args) {
+            Ok([image]) => {
+                options.list_partitions = Some(image);
+                Ok(options)
+            }
+            Err(_) => Err(format!("list-partitions: expected an image\n{}", USAGE)),
+        };
+    }
+    if name == "snapshot" {
+        return match <[String; 1]>::try_from(args) {
+            Ok([image]) => {
+                options.snapshot = Some(image);
+                Ok(options)
+            }
+            Err(_) => Err(format!("snapshot: expected an image\n{}", USAGE)),
+        };
+    }
+    if name == "diff" {
+        return match <[String; 2]>::try_from(args) {
+            Ok([old, new]) => {
+                options.diff = Some((old, new));
+                Ok(options)
+            }
+            Err(_) => Err(format!("diff: expected two images or snapshots\n{}", USAGE)),
+        };
+    }
+    if name == "mount" {
+        return match <[String; 2]>::try_from(args) {
+            Ok([image, dir]) => {
+                options.mount = Some((image, dir));
+                Ok(options)
+            }
+            Err(_) => Err(format!("mount: expected an image and a directory\n{}", USAGE)),
+        };
+    }
+
+    let mut args = args.into_iter();
+    options.image = Some(args.next().ok_or_else(|| format!("{}: missing image\n{}", name, USAGE))?);
+    let mut line = String::from(name);
+    for arg in args {
+        line.push(' ');
+        line.push_str(&quote_arg(&arg));
+    }
+    options.commands = Some(line);
+    Ok(options)
+}
+
+/// Met un argument entre guillemets s'il contient des espaces ou `; | >`
+fn quote_arg(arg: &str) -> String {
+    let special = |c: char| c.is_whitespace() || matches!(c, ';' | '|' | '>' | '"' | '\'');
+    if !arg.is_empty() && !arg.contains(special) {
+        return String::from(arg);
+    }
+    let quote = if arg.contains('"') { '\'' } else { '"' };
+    format!("{}{}{}", quote, arg, quote)
+}
+
+/// Plage d'octets du filesystem dans l'image: l'image entière, ou la partition `partition`
+fn partition_range(data: &[u8], partition: Option<usize>) -> Result<std::ops::Range<usize>, String> {
+    let index = match partition {
+        Some(index) => index,
+        None => return Ok(0..data.len()),
+    };
+    let partitions = read_partitions(data).ok_or("no partition table")?;
+    let found = partitions.iter().find(|p| p.index == index)
+        .ok_or_else(|| format!("partition {} not found", index))?;
+    found.range(data).ok_or_else(|| format!("partition {} extends beyond the image", index))
 }
 
-/// Crée une image FAT32 de démonstration
-fn create_demo_image() -> Vec<u8> {
-    let mut data = vec![0u8; 1024 * 1024];
-
-    // Boot sector
-    data[11] = 0x00; data[12] = 0x02;
-    data[13] = 1;
-    data[14] = 32; data[15] = 0;
-    data[16] = 2;
-    data[32..36].copy_from_slice(&2048u32.to_le_bytes());
-    data[36..40].copy_from_slice(&16u32.to_le_bytes());
-    data[44..48].copy_from_slice(&2u32.to_le_bytes());
-    data[510] = 0x55; data[511] = 0xAA;
-
-    // FAT table
-    let fat_start = 32 * 512;
-    data[fat_start..fat_start + 4].copy_from_slice(&0x0FFFFFF8u32.to_le_bytes());
-    data[fat_start + 4..fat_start + 8].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-    data[fat_start + 8..fat_start + 12].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-    data[fat_start + 12..fat_start + 16].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-    data[fat_start + 16..fat_start + 20].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-    data[fat_start + 20..fat_start + 24].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-    data[fat_start + 24..fat_start + 28].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-    // Root directory
-    let root_dir = 64 * 512;
-
-    // HELLO.TXT
-    data[root_dir..root_dir + 8].copy_from_slice(b"HELLO   ");
-    data[root_dir + 8..root_dir + 11].copy_from_slice(b"TXT");
-    data[root_dir + 11] = 0x20;
-    data[root_dir + 26..root_dir + 28].copy_from_slice(&4u16.to_le_bytes());
-    data[root_dir + 28..root_dir + 32].copy_from_slice(&45u32.to_le_bytes());
-
-    // DOCS directory
-    data[root_dir + 32..root_dir + 40].copy_from_slice(b"DOCS    ");
-    data[root_dir + 40..root_dir + 43].copy_from_slice(b"   ");
-    data[root_dir + 43] = 0x10;
-    data[root_dir + 58..root_dir + 60].copy_from_slice(&3u16.to_le_bytes());
-
-    // README.MD
-    data[root_dir + 64..root_dir + 72].copy_from_slice(b"README  ");
-    data[root_dir + 72..root_dir + 75].copy_from_slice(b"MD ");
-    data[root_dir + 75] = 0x20;
-    data[root_dir + 90..root_dir + 92].copy_from_slice(&5u16.to_le_bytes());
-    data[root_dir + 92..root_dir + 96].copy_from_slice(&89u32.to_le_bytes());
-
-    // DOCS directory content
-    let docs_dir = 65 * 512;
-    data[docs_dir..docs_dir + 8].copy_from_slice(b".       ");
-    data[docs_dir + 8..docs_dir + 11].copy_from_slice(b"   ");
-    data[docs_dir + 11] = 0x10;
-    data[docs_dir + 26..docs_dir + 28].copy_from_slice(&3u16.to_le_bytes());
-
-    data[docs_dir + 32..docs_dir + 40].copy_from_slice(b"..      ");
-    data[docs_dir + 40..docs_dir + 43].copy_from_slice(b"   ");
-    data[docs_dir + 43] = 0x10;
-    data[docs_dir + 58..docs_dir + 60].copy_from_slice(&0u16.to_le_bytes());
-
-    data[docs_dir + 64..docs_dir + 72].copy_from_slice(b"INFO    ");
-    data[docs_dir + 72..docs_dir + 75].copy_from_slice(b"TXT");
-    data[docs_dir + 75] = 0x20;
-    data[docs_dir + 90..docs_dir + 92].copy_from_slice(&6u16.to_le_bytes());
-    data[docs_dir + 92..docs_dir + 96].copy_from_slice(&42u32.to_le_bytes());
-
-    // File contents
-    let hello_content = b"Hello! This is a test file for FAT32 shell.\n";
-    let hello_file = 66 * 512;
-    data[hello_file..hello_file + hello_content.len()].copy_from_slice(hello_content);
-
-    let readme_content = b"# FAT32 Filesystem Demo\n\nThis is a demo FAT32 image.\nCreated for ESGI 4A Rust course.\n";
-    let readme_file = 67 * 512;
-    data[readme_file..readme_file + readme_content.len()].copy_from_slice(readme_content);
-
-    let info_content = b"Info file inside DOCS directory.\nTest OK!\n";
-    let info_file = 68 * 512;
-    data[info_file..info_file + info_content.len()].copy_from_slice(info_content);
-
-    data
+/// Message d'échec du montage, avec une indication si l'image est un disque partitionné
+fn parse_error(data: &[u8], partition: Option<usize>, error: FsError) -> String {
+    match read_partitions(data) {
+        Some(_) if partition.is_none() => {
+            String::from("Failed to parse FAT32 image (partitioned disk: see list-partitions and --partition N)")
+        }
+        _ if error != FsError::InvalidGeometry => format!("Failed to parse FAT32 image: {}", error),
+        _ => String::from("Failed to parse FAT32 image"),
+    }
+}
+
+/// Affiche la table de partitions d'une image de disque entier
+fn list_partitions(path: &str) -> Result<(), String> {
+    let disk = read_image(path).map_err(|e| format!("{}: {}", path, e))?;
+    let partitions = read_partitions(&disk.data).ok_or_else(|| format!("{}: no partition table", path))?;
+
+    println!("{:>3}  {:<22} {:>14} {:>14}  Name", "#", "Type", "Offset", "Size");
+    for partition in &partitions {
+        let kind = match partition.kind.name() {
+            Some(name) => String::from(name),
+            None => partition.kind.to_string(),
+        };
+        let line = format!("{:>3}  {:<22} {:>14} {:>14}  {}", partition.index, kind, partition.offset, partition.size, partition.name);
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+/// Instantané d'une image, ou instantané enregistré par la sous-commande `snapshot`
+fn load_snapshot(path: &str, partition: Option<usize>) -> Result<Snapshot, String> {
+    let disk = read_image(path).map_err(|e| format!("{}: {}", path, e))?;
+    if disk.data.starts_with(SNAPSHOT_HEADER.as_bytes()) {
+        let text = std::str::from_utf8(&disk.data).map_err(|_| format!("{}: invalid snapshot", path))?;
+        return Snapshot::from_text(text).ok_or_else(|| format!("{}: invalid snapshot", path));
+    }
+    let range = partition_range(&disk.data, partition).map_err(|e| format!("{}: {}", path, e))?;
+    let fs = Fat32::try_new(&disk.data[range])
+        .map_err(|e| format!("{}: {}", path, parse_error(&disk.data, partition, e)))?;
+    Ok(fs.snapshot())
+}
+
+/// Affiche les différences entre deux images ou instantanés; vrai s'il y en a
+fn diff(old: &str, new: &str, partition: Option<usize>) -> Result<bool, String> {
+    let changes = load_snapshot(old, partition)?.diff(&load_snapshot(new, partition)?);
+    for change in &changes {
+        println!("{}", change);
+    }
+    Ok(!changes.is_empty())
+}
+
+/// Sépare `image.img:/chemin` en image et chemin dans l'image
+fn image_path(arg: &str) -> Option<(&str, &str)> {
+    arg.split_once(':').filter(|(image, _)| !image.is_empty())
+}
+
+/// Copie un fichier de l'image vers l'hôte ou de l'hôte vers l'image
+///
+/// Une destination qui est un répertoire reçoit le fichier sous son nom d'origine.
+fn copy(from: &str, to: &str, partition: Option<usize>) -> Result<(), String> {
+    match (image_path(from), image_path(to)) {
+        (Some((image, path)), None) => {
+            let disk = read_image(image).map_err(|e| format!("{}: {}", image, e))?;
+            let range = partition_range(&disk.data, partition).map_err(|e| format!("{}: {}", image, e))?;
+            let fs = Fat32::try_new(&disk.data[range])
+                .map_err(|e| format!("{}: {}", image, parse_error(&disk.data, partition, e)))?;
+            let entry = fs.resolve_path(path, fs.root_cluster())
+                .ok_or_else(|| format!("{}: File not found", path))?;
+            if entry.is_directory() {
+                return Err(format!("{}: Is a directory", path));
+            }
+
+            let mut target = std::path::PathBuf::from(to);
+            if target.is_dir() {
+                target.push(entry.display_name());
+            }
+            std::fs::write(&target, fs.read_file_cow(&entry)).map_err(|e| format!("{}: {}", target.display(), e))
+        }
+        (None, Some((image, path))) => {
+            let data = std::fs::read(from).map_err(|e| format!("{}: {}", from, e))?;
+            let mut disk = read_image(image).map_err(|e| format!("{}: {}", image, e))?;
+            let range = partition_range(&disk.data, partition).map_err(|e| format!("{}: {}", image, e))?;
+            let mut fs = match Fat32::try_new_mut(&mut disk.data[range]) {
+                Ok(fs) => fs,
+                Err(e) => return Err(format!("{}: {}", image, parse_error(&disk.data, partition, e))),
+            };
+
+            // Chemin vers un répertoire (ou terminé par '/'): garder le nom du fichier hôte
+            let host_name = std::path::Path::new(from).file_name().and_then(|n| n.to_str()).unwrap_or(from);
+            let root = fs.root_cluster();
+            let dir_of = |entry: fat32_exam::DirEntry| if entry.cluster() == 0 { root } else { entry.cluster() };
+            let (dir_cluster, name) = match fs.resolve_path(path, root) {
+                Some(entry) if entry.is_directory() => (dir_of(entry), host_name),
+                _ if path.trim_matches('/').is_empty() => (root, host_name),
+                _ if path.ends_with('/') => return Err(format!("{}: Not a directory", path)),
+                _ => {
+                    let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+                    let dir_cluster = match parent.trim_matches('/') {
+                        "" => root,
+                        _ => match fs.resolve_path(parent, root) {
+                            Some(entry) if entry.is_directory() => dir_of(entry),
+                            _ => return Err(format!("{}: Not a directory", parent)),
+                        },
+                    };
+                    (dir_cluster, name)
+                }
+            };
+
+            fs.write_file(dir_cluster, name, &data).map_err(|e| format!("{}: {}", path, e))?;
+            save_image(image, &disk).map_err(|e| format!("{}: {}", image, e))
+        }
+        _ => Err(String::from("exactly one of source and destination must be <image.img>:<path>")),
+    }
+}
+
+/// Monte l'image en lecture seule sur `dir` jusqu'au démontage (`fusermount -u`)
+#[cfg(all(feature = "fuse", unix))]
+fn mount(path: &str, dir: &str, partition: Option<usize>) -> Result<(), String> {
+    use fat32_exam::fat32::fuse::{self, FuseImage};
+
+    let mut disk = read_image(path).map_err(|e| format!("{}: {}", path, e))?;
+    let range = partition_range(&disk.data, partition).map_err(|e| format!("{}: {}", path, e))?;
+    if let Err(e) = Fat32::try_new(&disk.data[range.clone()]) {
+        return Err(format!("{}: {}", path, parse_error(&disk.data, partition, e)));
+    }
+    disk.data.truncate(range.end);
+    disk.data.drain(..range.start);
+
+    // FAT n'a pas de propriétaire: les fichiers prennent celui du point de montage
+    use std::os::unix::fs::MetadataExt;
+    let owner = std::fs::metadata(dir).map_err(|e| format!("{}: {}", dir, e))?;
+    let image = FuseImage::new(disk.data)
+        .map_err(|e| format!("{}: {}", path, e))?
+        .with_owner(owner.uid(), owner.gid());
+    fuse::mount(image, std::path::Path::new(dir)).map_err(|e| format!("{}: {}", dir, e))
+}
+
+#[cfg(not(all(feature = "fuse", unix)))]
+fn mount(_path: &str, _dir: &str, _partition: Option<usize>) -> Result<(), String> {
+    Err(String::from("built without the fuse feature"))
 }
 
 fn main() {
-    println!("========================================");
-    println!("   FAT32 Filesystem Shell v0.1.0");
-    println!("   Noella IKIREZI - ESGI 4A");
-    println!("========================================");
-    println!();
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some((from, to)) = &options.copy {
+        if let Err(e) = copy(from, to, options.partition) {
+            eprintln!("cp: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some((image, dir)) = &options.mount {
+        if let Err(e) = mount(image, dir, options.partition) {
+            eprintln!("mount: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(path) = &options.list_partitions {
+        if let Err(e) = list_partitions(path) {
+            eprintln!("list-partitions: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(path) = &options.snapshot {
+        match load_snapshot(path, options.partition) {
+            Ok(snapshot) => print!("{}", snapshot.to_text()),
+            Err(e) => {
+                eprintln!("snapshot: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some((old, new)) = &options.diff {
+        // Comme diff(1): 1 s'il y a des différences, 2 en cas d'erreur
+        match diff(old, new, options.partition) {
+            Ok(true) => std::process::exit(1),
+            Ok(false) => return,
+            Err(e) => {
+                eprintln!("diff: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
 
-    let disk_data = create_demo_image();
+    let verbosity = options.verbosity;
+    let banner = verbosity > Verbosity::Quiet && options.commands.is_none();
 
-    let fs = match Fat32::new(&disk_data) {
-        Some(fs) => fs,
-        None => {
-            eprintln!("Error: Failed to parse FAT32 image");
-            return;
+    if banner {
+        println!("========================================");
+        println!("   FAT32 Filesystem Shell v0.1.0");
+        println!("   Noella IKIREZI - ESGI 4A");
+        println!("========================================");
+        println!();
+    }
+
+    let mut disk = match &options.image {
+        Some(path) => match read_image(path) {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("Error: cannot read {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        None => Image { data: demo_image(), device: false },
+    };
+
+    let range = match partition_range(&disk.data, options.partition) {
+        Ok(range) => range,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
     };
 
-    println!("FAT32 image loaded successfully!");
-    println!("Type 'help' for available commands, 'exit' to quit.");
-    println!();
+    // Une vraie image n'est modifiable qu'avec --rw; la démo reste en mémoire
+    let writable = options.read_write || options.image.is_none();
+    let fs = if writable { Fat32::try_new_mut(&mut disk.data[range]) } else { Fat32::try_new(&disk.data[range]) };
+    // Une session relit souvent les mêmes répertoires
+    let mut fs = match fs {
+        Ok(fs) => fs.with_dir_cache(),
+        Err(e) => {
+            eprintln!("Error: {}", parse_error(&disk.data, options.partition, e));
+            std::process::exit(1);
+        }
+    };
+
+    if banner {
+        match &options.image {
+            Some(path) if writable => println!("Opened {} (read-write)", path),
+            Some(path) => println!("Opened {} (read-only, use --rw to modify)", path),
+            None => println!("FAT32 image loaded successfully!"),
+        }
+        println!("Type 'help' for available commands, 'exit' to quit.");
+        println!();
+    }
 
     let mut state = ShellState::new(fs.root_cluster());
+    state.config.verbosity = verbosity;
+    state.config.color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
     let mut output = ConsoleOutput;
-    let stdin = io::stdin();
 
-    loop {
-        print!("{}> ", state.pwd());
-        io::stdout().flush().unwrap();
+    let status = match &options.commands {
+        Some(commands) => run_commands(&mut fs, &mut state, commands, &mut output),
+        None => {
+            run_interactive(&mut fs, &mut state, &mut output);
+            0
+        }
+    };
+
+    if let Some(path) = options.image.as_ref().filter(|_| options.read_write) {
+        if let Err(e) = save_image(path, &disk) {
+            eprintln!("Error: cannot save {}: {}", path, e);
+            std::process::exit(1);
+        }
+        if state.config.verbose() {
+            println!("Saved changes to {}", path);
+        }
+    }
 
-        let mut input = String::new();
-        match stdin.lock().read_line(&mut input) {
-            Ok(0) => break,
-            Ok(_) => {}
+    let _ = io::stdout().flush();
+    std::process::exit(status);
+}
+
+/// Exécute les commandes de `-c` l'une après l'autre
+///
+/// S'arrête à la première erreur (comme `sh -e`) et retourne son code de
+/// sortie, 0 si tout a réussi; `exit` termine la suite.
+fn run_commands(fs: &mut Fat32, state: &mut ShellState, commands: &str, output: &mut ConsoleOutput) -> i32 {
+    for command in split_sequence(commands) {
+        match execute_command(fs, state, command, output, &mut NoInput) {
+            Ok(true) => {}
+            Ok(false) => break,
             Err(e) => {
-                eprintln!("Error reading input: {}", e);
-                continue;
+                write_error(state, &e, output);
+                return e.exit_code();
             }
         }
+    }
+    0
+}
 
-        match parse_command(&input) {
-            Command::Ls(path) => cmd_ls(&fs, &state, path, &mut output),
-            Command::Cd(path) => cmd_cd(&fs, &mut state, path, &mut output),
-            Command::Cat(file) => cmd_cat(&fs, &state, file, &mut output),
-            Command::More(file) => cmd_more(&fs, &state, file, &mut output, 20),
-            Command::Pwd => cmd_pwd(&state, &mut output),
-            Command::Help => cmd_help(&mut output),
-            Command::Exit => {
-                println!("Goodbye!");
-                break;
+/// Lit une ligne avec l'éditeur; Ctrl-C recommence la saisie, `None` en fin d'entrée
+fn read_edited_line(
+    editor: &mut LineEditor,
+    fs: &Fat32,
+    state: &ShellState,
+    prompt: &str,
+    output: &mut ConsoleOutput,
+) -> Option<String> {
+    let _raw = RawMode::enable()?;
+    let mut decoder = KeyDecoder::new();
+    let mut byte = [0u8; 1];
+    output.write_str(prompt);
+
+    loop {
+        match io::stdin().lock().read(&mut byte) {
+            Ok(1) => {}
+            _ => return None,
+        }
+        let key = match decoder.feed(byte[0]) {
+            Some(key) => key,
+            None => continue,
+        };
+
+        match editor.handle(key, prompt, output) {
+            Edit::Pending => {}
+            Edit::Line(line) => return Some(line),
+            Edit::Interrupted => output.write_str(prompt),
+            Edit::EndOfInput => return None,
+            Edit::Complete => {
+                let candidates = complete(fs, state, &editor.completion_prefix());
+                editor.complete(&candidates, prompt, output);
             }
-            Command::Unknown(cmd) => {
-                println!("Unknown command: {}", cmd);
-                println!("Type 'help' for available commands.");
+        }
+    }
+}
+
+/// Lit une ligne telle quelle (entrée redirigée ou terminal sans stty)
+fn read_plain_line(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    loop {
+        match io::stdin().lock().read_line(&mut input) {
+            Ok(0) => return None,
+            Ok(_) => return Some(input),
+            Err(e) => eprintln!("Error reading input: {}", e),
+        }
+    }
+}
+
+/// Boucle interactive: lit et exécute une ligne à la fois jusqu'à `exit` ou EOF
+///
+/// Sur un terminal, la ligne est éditable (flèches, historique, Tab) et
+/// l'historique est conservé d'une session à l'autre.
+fn run_interactive(fs: &mut Fat32, state: &mut ShellState, output: &mut ConsoleOutput) {
+    let mut input_keys = ConsoleInput::default();
+    let mut editor = (io::stdin().is_terminal() && RawMode::enable().is_some())
+        .then(|| LineEditor::new(load_history()));
+
+    loop {
+        let prompt = format!("{}> ", state.pwd());
+        let input = match &mut editor {
+            Some(editor) => read_edited_line(editor, fs, state, &prompt, output),
+            None => read_plain_line(&prompt),
+        };
+        let input = match input {
+            Some(input) => input,
+            None => break,
+        };
+
+        match execute_command(fs, state, &input, output, &mut input_keys) {
+            Ok(true) => {}
+            Ok(false) => {
+                if state.config.summaries() {
+                    println!("Goodbye!");
+                }
+                break;
             }
-            Command::Empty => {}
+            Err(e) => write_error(state, &e, output),
         }
-        println!();
+        if state.config.summaries() {
+            println!();
+        }
+    }
+
+    if let Some(editor) = &editor {
+        save_history(editor.history());
     }
 }
@@ -0,0 +1,91 @@
+//! Couleurs ANSI optionnelles (désactivées par défaut pour les consoles série)
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+
+use crate::fat32::DirEntry;
+use super::config::ShellConfig;
+
+/// Réinitialise les attributs
+const RESET: &str = "\x1b[0m";
+
+/// Extensions mises en évidence comme exécutables
+const EXECUTABLE_EXTENSIONS: &[&str] = &["EXE", "COM", "BAT", "CMD", "SH", "BIN", "ELF"];
+/// Extensions mises en évidence comme archives
+const ARCHIVE_EXTENSIONS: &[&str] = &["ZIP", "TAR", "GZ", "TGZ", "BZ2", "XZ", "7Z", "RAR", "CAB"];
+
+/// Style d'affichage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Directory,
+    Executable,
+    Archive,
+    Error,
+}
+
+impl Style {
+    /// Séquence ANSI du style
+    fn code(self) -> &'static str {
+        match self {
+            Style::Directory => "\x1b[1;34m",
+            Style::Executable => "\x1b[1;32m",
+            Style::Archive => "\x1b[1;31m",
+            Style::Error => "\x1b[31m",
+        }
+    }
+}
+
+/// Style d'une entrée de répertoire selon son type et l'extension de `name`
+pub fn entry_style(entry: &DirEntry, name: &str) -> Option<Style> {
+    if entry.is_directory() {
+        return Some(Style::Directory);
+    }
+
+    let ext = name.rsplit_once('.')?.1;
+    if EXECUTABLE_EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(ext)) {
+        Some(Style::Executable)
+    } else if ARCHIVE_EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(ext)) {
+        Some(Style::Archive)
+    } else {
+        None
+    }
+}
+
+/// Entoure `text` des séquences du style si la couleur est activée
+pub fn paint(config: &ShellConfig, style: Option<Style>, text: &str) -> String {
+    match style {
+        Some(style) if config.color => format!("{}{}{}", style.code(), text, RESET),
+        _ => String::from(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &[u8; 11], attr: u8) -> DirEntry {
+        let mut raw = [0u8; 32];
+        raw[..11].copy_from_slice(name);
+        raw[11] = attr;
+        DirEntry::from_bytes(&raw).unwrap()
+    }
+
+    #[test]
+    fn test_entry_style() {
+        assert_eq!(entry_style(&entry(b"DOCS       ", 0x10), "DOCS"), Some(Style::Directory));
+        assert_eq!(entry_style(&entry(b"RUN     EXE", 0x20), "RUN.EXE"), Some(Style::Executable));
+        assert_eq!(entry_style(&entry(b"BACKUP~1ZIP", 0x20), "backup.tar.gz"), Some(Style::Archive));
+        assert_eq!(entry_style(&entry(b"README  TXT", 0x20), "README.TXT"), None);
+        assert_eq!(entry_style(&entry(b"MAKEFILE   ", 0x20), "MAKEFILE"), None);
+    }
+
+    #[test]
+    fn test_paint_respects_config() {
+        let off = ShellConfig::default();
+        let on = ShellConfig { color: true, ..ShellConfig::default() };
+        assert_eq!(paint(&off, Some(Style::Error), "oops"), "oops");
+        assert_eq!(paint(&on, Some(Style::Error), "oops"), "\x1b[31moops\x1b[0m");
+        assert_eq!(paint(&on, None, "plain"), "plain");
+    }
+}
@@ -1,33 +1,101 @@
-//! Implémentation des commandes shell: ls, cd, cat, more, pwd, help
+//! Implémentation des commandes shell (la liste et l'aide sont dans `registry`)
 
 extern crate alloc;
 use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::format;
+use alloc::collections::BTreeMap;
 
-use crate::fat32::Fat32;
+use crate::fat32::{Fat32, DirEntry, FormatOptions, PathError, PathErrorKind, FSINFO_UNKNOWN, cluster_runs};
+use crate::checksum::{Checksum, Crc32, Sha256, to_hex};
+use super::parser::Args;
+use super::error::{ShellError, CommandResult};
+use super::registry::{CommandInfo, COMMANDS, lookup};
+use super::pager;
+use super::path::ShellPath;
+use super::color::{self, Style};
+use super::config::ShellConfig;
+use super::messages::{self, Catalog, English};
+
+/// Répertoire mémorisé par pushd
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedDir {
+    pub path: ShellPath,
+    pub cluster: u32,
+}
 
 /// État du shell avec le répertoire courant
 pub struct ShellState {
     pub current_cluster: u32,
-    pub current_path: Vec<String>,
+    /// Chemin courant, relatif à la racine du shell
+    pub current_path: ShellPath,
+    /// Racine du shell: `/` désigne ce cluster et `..` ne remonte pas au-dessus
+    pub root_cluster: u32,
+    /// Chemin de la racine du shell dans le volume (vide si c'est la racine du volume)
+    pub root_prefix: String,
+    pub config: ShellConfig,
+    /// Alias définis par `alias name='command'`
+    pub aliases: BTreeMap<String, String>,
+    /// Pile de répertoires de pushd/popd (sommet en fin de vecteur)
+    pub dir_stack: Vec<SavedDir>,
+    /// Nombre de scripts `run` en cours d'exécution
+    pub script_depth: u8,
+    /// Traductions des messages (anglais par défaut)
+    pub catalog: &'static dyn Catalog,
 }
 
 impl ShellState {
     /// Crée un nouvel état au répertoire racine
     pub fn new(root_cluster: u32) -> Self {
+        ShellState::with_root(root_cluster, "")
+    }
+
+    /// Crée un état confiné au sous-arbre `cluster`, situé à `prefix` dans le volume
+    ///
+    /// Tous les chemins sont résolus dans ce sous-arbre: `/` y désigne `cluster`
+    /// et `..` ne permet pas d'en sortir. Les commandes d'accès brut au volume
+    /// (format, dumpcluster) sont refusées.
+    pub fn with_root(cluster: u32, prefix: &str) -> Self {
         ShellState {
-            current_cluster: root_cluster,
-            current_path: Vec::new(),
+            current_cluster: cluster,
+            current_path: ShellPath::new(),
+            root_cluster: cluster,
+            root_prefix: String::from(prefix.trim_end_matches('/')),
+            config: ShellConfig::default(),
+            aliases: BTreeMap::new(),
+            dir_stack: Vec::new(),
+            script_depth: 0,
+            catalog: &English,
         }
     }
 
+    /// Message traduit par le catalogue
+    pub fn tr<'s>(&'s self, message: &'s str) -> &'s str {
+        self.catalog.translate(message)
+    }
+
+    /// Message traduit dont les `{}` sont remplacés par `args`
+    pub fn tr_fill(&self, template: &str, args: &[&dyn core::fmt::Display]) -> String {
+        messages::fill(self.tr(template), args)
+    }
+
+    /// Mémorise le répertoire courant
+    pub fn save_dir(&self) -> SavedDir {
+        SavedDir { path: self.current_path.clone(), cluster: self.current_cluster }
+    }
+
+    /// Revient dans un répertoire mémorisé
+    pub fn restore_dir(&mut self, dir: SavedDir) {
+        self.current_path = dir.path;
+        self.current_cluster = dir.cluster;
+    }
+
     /// Retourne le chemin courant
     pub fn pwd(&self) -> String {
         if self.current_path.is_empty() {
             String::from("/")
         } else {
-            format!("/{}", self.current_path.join("/"))
+            format!("/{}", self.current_path.as_str())
         }
     }
 
@@ -35,6 +103,27 @@ impl ShellState {
     pub fn is_root(&self) -> bool {
         self.current_path.is_empty()
     }
+
+    /// Vrai si le shell est confiné à un sous-arbre du volume
+    pub fn is_restricted(&self) -> bool {
+        !self.root_prefix.is_empty()
+    }
+
+    /// Chemin courant dans le volume (préfixe de la racine compris)
+    pub fn volume_path(&self) -> String {
+        if self.is_root() && self.is_restricted() {
+            self.root_prefix.clone()
+        } else {
+            format!("{}{}", self.root_prefix, self.pwd())
+        }
+    }
+}
+
+/// Dimensions du terminal, en caractères
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalInfo {
+    pub rows: u16,
+    pub cols: u16,
 }
 
 /// Trait pour l'affichage
@@ -49,9 +138,57 @@ pub trait Output {
     fn write_fmt(&mut self, s: &str) {
         self.write_str(s);
     }
+
+    /// Écrit une ligne de diagnostic (par défaut sur la sortie normale)
+    fn write_err_line(&mut self, s: &str) {
+        self.write_line(s);
+    }
+
+    /// Taille du terminal; `None` si la sortie n'est pas un terminal (fichier, pipe, tests)
+    fn terminal(&self) -> Option<TerminalInfo> {
+        None
+    }
+}
+
+/// Trait pour la lecture des touches (pagination interactive)
+pub trait Input {
+    /// Lit une touche; `None` si l'entrée est terminée
+    fn read_key(&mut self) -> Option<char>;
+
+    /// Lit une ligne (jusqu'à Entrée, exclue); `None` si l'entrée est terminée
+    fn read_line(&mut self) -> Option<String> {
+        let mut line = String::new();
+        loop {
+            match self.read_key() {
+                Some('\n') | Some('\r') => return Some(line),
+                Some(c) => line.push(c),
+                None if line.is_empty() => return None,
+                None => return Some(line),
+            }
+        }
+    }
+
+    /// Faux si aucune touche ne peut être attendue (scripts, tests)
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+/// Entrée non interactive: les commandes n'attendent jamais de touche
+pub struct NoInput;
+
+impl Input for NoInput {
+    fn read_key(&mut self) -> Option<char> {
+        None
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
+#[derive(Default)]
 pub struct StringOutput {
     pub buffer: String,
 }
@@ -70,68 +207,248 @@ impl Output for StringOutput {
     }
 }
 
-/// Commande ls - liste le contenu d'un répertoire
+/// Ordre de tri pour ls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LsSort {
+    None,
+    Name,
+    Size,
+    Date,
+}
+
+/// Options de la commande ls
+struct LsOptions<'a> {
+    long: bool,
+    all: bool,
+    human: bool,
+    sort: LsSort,
+    path: Option<&'a str>,
+}
+
+/// Parse les options de ls (`-l`, `-a`, `-h`, `--sort=name|size|date`)
+fn parse_ls_options<'a>(state: &ShellState, args: &Args<'a>) -> Result<LsOptions<'a>, String> {
+    args.flags.check("lah", &["sort="])?;
+    if args.len() > 1 {
+        return Err(String::from(state.tr("Too many arguments")));
+    }
+
+    let sort = match args.flags.value("sort") {
+        None | Some("none") => LsSort::None,
+        Some("name") => LsSort::Name,
+        Some("size") => LsSort::Size,
+        Some("date") | Some("time") => LsSort::Date,
+        Some(other) => return Err(state.tr_fill("Invalid sort key: {}", &[&other])),
+    };
+
+    Ok(LsOptions {
+        long: args.flags.has('l'),
+        all: args.flags.has('a'),
+        human: args.flags.has('h'),
+        sort,
+        path: args.get(0),
+    })
+}
+
+/// Nom affiché par ls (nom long, sinon 8.3), écrit dans `buf` réutilisé d'une entrée à l'autre
+fn listed_name<'b>(entry: &DirEntry, long_name: &Option<String>, buf: &'b mut String) -> &'b mut String {
+    buf.clear();
+    match long_name {
+        Some(ln) => buf.push_str(ln),
+        None => {
+            // L'écriture dans une String n'échoue pas
+            let _ = entry.write_name(buf);
+        }
+    }
+    buf
+}
+
+/// Formate une taille en unités lisibles (1.5K, 3.2M...)
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+
+    if size < 1024 {
+        return format!("{}", size);
+    }
+
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
+
+/// Commande ls - liste le contenu d'un répertoire (`[-l] [-a] [-h] [--sort=...] [path]`)
 pub fn cmd_ls<O: Output>(
     fs: &Fat32,
     state: &ShellState,
-    path: Option<&str>,
+    args: &Args,
     out: &mut O,
-) {
-    let cluster = match path {
-        Some(p) if !p.is_empty() => {
-            match resolve_to_cluster(fs, state, p) {
-                Some((c, true)) => c,
-                Some((_, false)) => {
-                    out.write_line("Not a directory");
-                    return;
-                }
-                None => {
-                    out.write_line("Path not found");
-                    return;
-                }
-            }
-        }
-        _ => state.current_cluster,
+) -> CommandResult {
+    let options = parse_ls_options(state, args)
+        .map_err(|msg| usage_error(state, Some(&msg), "ls"))?;
+
+    let cluster = match options.path {
+        Some(p) => resolve_directory(fs, state, p)?,
+        None => state.current_cluster,
     };
 
-    let entries = fs.read_directory_with_lfn(cluster);
+    // Seuls les noms longs sont conservés: les noms 8.3 sont écrits à l'affichage
+    let mut entries: Vec<(DirEntry, Option<String>)> = fs.read_directory_with_lfn(cluster)
+        .into_iter()
+        .filter(|(entry, _)| {
+            options.all || state.config.show_hidden || !(entry.is_hidden() || entry.is_system())
+        })
+        .map(|(entry, long_name)| (entry, long_name.filter(|ln| !ln.is_empty())))
+        .collect();
+
+    match options.sort {
+        LsSort::None => {}
+        LsSort::Name => entries.sort_by_cached_key(|(entry, long_name)| {
+            let mut key = String::new();
+            listed_name(entry, long_name, &mut key).make_ascii_uppercase();
+            key
+        }),
+        LsSort::Size => entries.sort_by_key(|(e, _)| core::cmp::Reverse(e.size)),
+        LsSort::Date => entries.sort_by_key(|(e, _)| core::cmp::Reverse(e.modified())),
+    }
+
+    if state.config.json {
+        write_ls_json(&entries, out);
+        return Ok(());
+    }
 
     if entries.is_empty() {
-        out.write_line("(empty directory)");
-        return;
+        out.write_line(state.tr("(empty directory)"));
+        return Ok(());
     }
 
+    let terminal = out.terminal().filter(|_| !options.long);
+    let mut cells: Vec<(String, usize)> = Vec::new();
     let mut total_files = 0u32;
     let mut total_dirs = 0u32;
     let mut total_size = 0u64;
 
+    let mut name = String::new();
     for (entry, long_name) in &entries {
-        if entry.is_hidden() {
-            continue;
-        }
-
-        let name = long_name.as_ref()
-            .map(|s| s.as_str())
-            .unwrap_or_else(|| "");
-        let name = if name.is_empty() {
-            entry.display_name()
+        let name = listed_name(entry, long_name, &mut name);
+        let size = if options.human {
+            human_size(entry.size as u64)
         } else {
-            String::from(name)
+            format!("{}", entry.size)
         };
 
+        let suffix = if entry.is_directory() { "/" } else { "" };
+        let shown = color::paint(&state.config, color::entry_style(entry, name),
+            &format!("{}{}", name, suffix));
+
+        if terminal.is_some() {
+            cells.push((shown, name.chars().count() + suffix.len()));
+        } else if options.long {
+            let size = if entry.is_directory() { String::from("<DIR>") } else { size };
+            out.write_line(&format!("{}  {}  {:>10}  {:>8}  {}",
+                entry.attr_string(), entry.modified(), size, entry.cluster(), shown));
+        } else if entry.is_directory() {
+            out.write_line(&format!("  <DIR>       {}", shown));
+        } else {
+            out.write_line(&format!("{:>10}    {}", size, shown));
+        }
+
         if entry.is_directory() {
-            out.write_line(&format!("  <DIR>       {}/", name));
             total_dirs += 1;
         } else {
-            out.write_line(&format!("{:>10}    {}", entry.size, name));
             total_files += 1;
             total_size += entry.size as u64;
         }
     }
 
+    if let Some(terminal) = terminal {
+        write_columns(&cells, terminal.cols as usize, out);
+    }
+
+    if !state.config.summaries() {
+        return Ok(());
+    }
+
+    let total = if options.human { human_size(total_size) } else { format!("{}", total_size) };
     out.write_line("");
-    out.write_line(&format!("  {} file(s)  {} bytes", total_files, total_size));
-    out.write_line(&format!("  {} dir(s)", total_dirs));
+    out.write_line(&state.tr_fill("  {} file(s)  {} bytes", &[&total_files, &total]));
+    out.write_line(&state.tr_fill("  {} dir(s)", &[&total_dirs]));
+    Ok(())
+}
+
+/// Listing de ls en JSON: un tableau d'objets, un par ligne
+fn write_ls_json<O: Output>(entries: &[(DirEntry, Option<String>)], out: &mut O) {
+    out.write_line("[");
+    let mut name = String::new();
+    for (i, (entry, long_name)) in entries.iter().enumerate() {
+        let name = listed_name(entry, long_name, &mut name);
+        let separator = if i + 1 < entries.len() { "," } else { "" };
+        out.write_line(&format!(
+            "  {{\"name\": {}, \"dir\": {}, \"size\": {}, \"cluster\": {}, \"attributes\": \"{}\", \"modified\": \"{}\"}}{}",
+            json_string(name), entry.is_directory(), entry.size, entry.cluster(),
+            entry.attr_string(), entry.modified(), separator));
+    }
+    out.write_line("]");
+}
+
+/// Chaîne JSON entre guillemets, avec les caractères spéciaux échappés
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Espace entre deux colonnes de ls
+const COLUMN_GAP: usize = 2;
+
+/// Affiche des cellules `(texte, largeur visible)` en colonnes, remplies de haut en bas
+///
+/// Utilise le moins de lignes possible sans dépasser `width` caractères.
+fn write_columns<O: Output>(cells: &[(String, usize)], width: usize, out: &mut O) {
+    if cells.is_empty() {
+        return;
+    }
+
+    let mut rows = 1;
+    let widths = loop {
+        let widths: Vec<usize> = cells.chunks(rows)
+            .map(|column| column.iter().map(|(_, w)| *w).max().unwrap_or(0))
+            .collect();
+        let total = widths.iter().sum::<usize>() + COLUMN_GAP * (widths.len() - 1);
+        if total <= width || rows == cells.len() {
+            break widths;
+        }
+        rows += 1;
+    };
+
+    for row in 0..rows {
+        let mut line = String::new();
+        for (col, column_width) in widths.iter().enumerate() {
+            let (text, w) = match cells.get(col * rows + row) {
+                Some(cell) => cell,
+                None => break,
+            };
+            if col > 0 {
+                line.push_str(&" ".repeat(COLUMN_GAP));
+            }
+            line.push_str(text);
+            if cells.get((col + 1) * rows + row).is_some() {
+                line.push_str(&" ".repeat(column_width - w));
+            }
+        }
+        out.write_line(&line);
+    }
 }
 
 /// Commande cd - change de répertoire
@@ -139,222 +456,792 @@ pub fn cmd_cd<O: Output>(
     fs: &Fat32,
     state: &mut ShellState,
     path: &str,
-    out: &mut O,
-) {
-    match path {
-        "/" | "" => {
-            state.current_path.clear();
-            state.current_cluster = fs.root_cluster();
+    _out: &mut O,
+) -> CommandResult {
+    let components = normalize_path(state, path);
+    match walk_path(fs, state, components.as_str()) {
+        Ok((cluster, true)) => {
+            state.current_path = components;
+            state.current_cluster = cluster;
+            Ok(())
         }
+        Ok((_, false)) => Err(ShellError::Failed(state.tr("Not a directory").into())),
+        Err(e) => Err(path_error(state, path, &e, state.tr("Directory not found").into())),
+    }
+}
+
+/// Commande cat - affiche le contenu d'un ou plusieurs fichiers (`[-n] [file]...`)
+///
+/// Sans fichier, affiche l'entrée du pipe (`stdin`).
+pub fn cmd_cat<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    args: &Args,
+    stdin: Option<&str>,
+    out: &mut O,
+) -> CommandResult {
+    args.flags.check("n", &[]).map_err(|msg| usage_error(state, Some(&msg), "cat"))?;
+
+    let number_lines = args.flags.has('n');
+    let files = &args.args;
+    let mut line_number = 1usize;
 
-        ".." => {
-            if state.current_path.pop().is_some() {
-                state.current_cluster = navigate_from_root(fs, &state.current_path);
+    if files.is_empty() {
+        let text = stdin.ok_or_else(|| usage_error(state, None, "cat"))?;
+        write_text(text, number_lines, &mut line_number, out);
+        return Ok(());
+    }
+
+    let mut errors: Vec<ShellError> = Vec::new();
+
+    for filename in files {
+        let prefix = if files.len() > 1 { format!("{}: ", filename) } else { String::new() };
+
+        match try_find_file(fs, state, filename) {
+            Ok(ref e) if e.is_directory() => {
+                errors.push(ShellError::Failed(format!("{}{}", prefix, state.tr("Cannot cat a directory"))));
             }
-        }
+            Ok(ref e) => {
+                let data = fs.read_file_cow(e);
 
-        "." => {}
-
-        name => {
-            if let Some((cluster, is_dir)) = resolve_to_cluster(fs, state, name) {
-                if is_dir {
-                    if name.starts_with('/') {
-                        state.current_path.clear();
-                        for component in name.split('/').filter(|s| !s.is_empty()) {
-                            if component != ".." {
-                                state.current_path.push(String::from(component));
-                            } else if !state.current_path.is_empty() {
-                                state.current_path.pop();
-                            }
-                        }
-                    } else {
-                        for component in name.split('/').filter(|s| !s.is_empty()) {
-                            if component == ".." {
-                                state.current_path.pop();
-                            } else if component != "." {
-                                state.current_path.push(String::from(component));
-                            }
-                        }
-                    }
-                    state.current_cluster = cluster;
+                if let Ok(text) = core::str::from_utf8(&data) {
+                    write_text(text, number_lines, &mut line_number, out);
                 } else {
-                    out.write_line("Not a directory");
+                    hex_dump(state, &data, 0, out, 256);
                 }
-            } else {
-                out.write_line("Directory not found");
             }
+            Err(e) => {
+                errors.push(path_error(state, filename, &e, format!("{}{}", prefix, state.tr("File not found"))));
+            }
+        }
+    }
+
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        _ => {
+            let messages: Vec<String> = errors.iter().map(|e| format!("{}", e)).collect();
+            Err(ShellError::Failed(messages.join("\n")))
         }
     }
 }
 
-/// Commande cat - affiche le contenu d'un fichier
-pub fn cmd_cat<O: Output>(
+/// Affiche du texte pour cat, en numérotant les lignes à partir de `line_number` si demandé
+fn write_text<O: Output>(text: &str, number_lines: bool, line_number: &mut usize, out: &mut O) {
+    if number_lines {
+        for line in text.lines() {
+            out.write_line(&format!("{:>6}  {}", line_number, line));
+            *line_number += 1;
+        }
+    } else {
+        out.write_str(text);
+        if !text.is_empty() && !text.ends_with('\n') {
+            out.write_str("\n");
+        }
+    }
+}
+
+/// Commande more - affiche un fichier page par page (touches: voir `pager::page`)
+pub fn cmd_more<O: Output, I: Input>(
     fs: &Fat32,
     state: &ShellState,
     filename: &str,
     out: &mut O,
-) {
-    let entry = if filename.contains('/') {
-        fs.resolve_path(filename, state.current_cluster)
-    } else {
-        fs.find_entry(state.current_cluster, filename)
+    input: &mut I,
+    lines_per_page: usize,
+) -> CommandResult {
+    let entry = try_find_file(fs, state, filename)
+        .map_err(|e| path_error(state, filename, &e, state.tr("File not found").into()))?;
+    if entry.is_directory() {
+        return Err(ShellError::Failed(state.tr("Cannot display a directory").into()));
+    }
+
+    let data = fs.read_file_cow(&entry);
+    let text = core::str::from_utf8(&data)
+        .map_err(|_| ShellError::Failed(state.tr("Binary file - use cat for hex dump").into()))?;
+
+    pager::page(state.catalog, text, out, input, lines_per_page);
+    Ok(())
+}
+
+/// Commande chain - affiche la chaîne de clusters d'un fichier
+pub fn cmd_chain<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    filename: &str,
+    out: &mut O,
+) -> CommandResult {
+    let entry = try_find_file(fs, state, filename)
+        .map_err(|e| path_error(state, filename, &e, state.tr("File not found").into()))?;
+
+    let chain = fs.cluster_chain(entry.cluster());
+    if chain.is_empty() {
+        out.write_line(state.tr("(no clusters allocated)"));
+        return Ok(());
+    }
+
+    let runs = cluster_runs(&chain);
+    let bytes_per_cluster = fs.bytes_per_cluster() as u64;
+    let sectors_per_cluster = fs.boot_sector().sectors_per_cluster as u64;
+
+    out.write_line(&state.tr_fill("{}: {} cluster(s), {} bytes, {} fragment(s)",
+        &[&entry.display(), &chain.len(), &entry.size, &runs.len()]));
+
+    for (start, len) in runs {
+        let first = fs.cluster_offset(start);
+        let last = first + len as u64 * bytes_per_cluster - 1;
+        let clusters = if len == 1 {
+            format!("{}", start)
+        } else {
+            format!("{}-{}", start, start + len - 1)
+        };
+        let count = state.tr_fill("({} cluster(s))", &[&format!("{:>5}", len)]);
+        // Offsets dans le volume, secteurs sur le disque entier (comme un éditeur hexadécimal)
+        let first_lba = fs.cluster_lba(start);
+        let last_lba = first_lba + len as u64 * sectors_per_cluster - 1;
+        out.write_line(&format!("  {:<20} {}  0x{:08X}-0x{:08X}  LBA {}-{}", clusters, count, first, last, first_lba, last_lba));
+    }
+
+    Ok(())
+}
+
+/// Commande dumpcluster - dump hexadécimal brut de clusters (`<n> [count]`)
+pub fn cmd_dumpcluster<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    args: &Args,
+    out: &mut O,
+) -> CommandResult {
+    let first = args.get(0).and_then(|s| s.parse::<u32>().ok());
+    let count = match args.get(1) {
+        Some(c) => c.parse::<u32>().ok(),
+        None => Some(1),
     };
 
-    match entry {
-        Some(ref e) if e.is_directory() => {
-            out.write_line("Cannot cat a directory");
-        }
-        Some(ref e) => {
-            let data = fs.read_file(e);
+    let (first, count) = match (first, count) {
+        (Some(f), Some(c)) if c > 0 && args.len() <= 2 && args.flags.is_empty() => (f, c),
+        _ => return Err(usage_error(state, None, "dumpcluster")),
+    };
+    restricted_check(state, "dumpcluster")?;
 
-            if let Ok(text) = core::str::from_utf8(&data) {
-                out.write_str(text);
-                if !text.is_empty() && !text.ends_with('\n') {
-                    out.write_str("\n");
-                }
-            } else {
-                hex_dump(&data, out, 256);
-            }
+    let max_cluster = fs.cluster_count() + 1;
+    if first < 2 || first > max_cluster {
+        return Err(ShellError::Failed(state.tr_fill("Cluster out of range (2..={})", &[&max_cluster])));
+    }
+
+    let last = first.saturating_add(count - 1).min(max_cluster);
+    for cluster in first..=last {
+        let data = fs.read_cluster(cluster);
+        let offset = fs.cluster_offset(cluster);
+        out.write_line(&state.tr_fill("Cluster {} (offset {}, LBA {}):", &[&cluster, &format!("0x{:08X}", offset), &fs.cluster_lba(cluster)]));
+        hex_dump(state, data, offset, out, data.len());
+    }
+
+    Ok(())
+}
+
+/// Commande undelete - liste (`[dir]`) ou restaure (`[dir] <index> <char>`) des entrées supprimées
+///
+/// Les index de restauration se rapportent au répertoire `dir`, le courant
+/// par défaut, comme ceux de la liste. Le nombre d'arguments distingue les
+/// deux formes: un répertoire au nom numérique reste listable.
+pub fn cmd_undelete<O: Output>(
+    fs: &mut Fat32,
+    state: &ShellState,
+    args: &Args,
+    out: &mut O,
+) -> CommandResult {
+    if !args.flags.is_empty() {
+        return Err(usage_error(state, None, "undelete"));
+    }
+
+    let (dir, restore) = match args.args[..] {
+        [] => (None, None),
+        [dir] => (Some(dir), None),
+        [index, first_char] => (None, Some((index, first_char))),
+        [dir, index, first_char] => (Some(dir), Some((index, first_char))),
+        _ => return Err(usage_error(state, None, "undelete")),
+    };
+    let cluster = match dir {
+        Some(p) => resolve_directory(fs, state, p)?,
+        None => state.current_cluster,
+    };
+
+    if let Some((index, first_char)) = restore {
+        let index = index.parse::<usize>()
+            .map_err(|_| usage_error(state, Some("The index must be a number"), "undelete"))?;
+        let mut chars = first_char.chars();
+        let first = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return Err(usage_error(state, Some("The first character must be a single letter"), "undelete")),
+        };
+
+        let deleted = fs.list_deleted(cluster);
+        let d = deleted.get(index)
+            .ok_or_else(|| ShellError::Failed(state.tr("Invalid index").into()))?;
+        fs.undelete(d, first).map_err(|e| ShellError::Fs("Cannot restore", e))?;
+
+        let mut restored = d.entry.clone();
+        restored.name[0] = first.to_ascii_uppercase() as u8;
+        if state.config.summaries() {
+            out.write_line(&state.tr_fill("Restored {}", &[&restored.display()]));
         }
-        None => {
-            out.write_line("File not found");
+        return Ok(());
+    }
+
+    let deleted = fs.list_deleted(cluster);
+    if deleted.is_empty() {
+        out.write_line(state.tr("(no deleted entries)"));
+        return Ok(());
+    }
+
+    for (i, d) in deleted.iter().enumerate() {
+        let status = state.tr(if d.recoverable { "recoverable" } else { "overwritten" });
+        let cluster = state.tr_fill("cluster {}", &[&format!("{:<8}", d.entry.cluster())]);
+        let mut line = format!("{:>4}  {:<12} {:>10}  {} {}",
+            i, d.display_name(), d.entry.size, cluster, status);
+        if let Some(long_name) = &d.long_name {
+            line.push_str(&format!("  {}", long_name));
         }
+        out.write_line(&line);
     }
+
+    Ok(())
 }
 
-/// Commande more - affiche un fichier avec pagination
-pub fn cmd_more<O: Output>(
+/// Commande format - reformate l'image (`--yes [--cluster-size=N] [--label=NAME]`)
+pub fn cmd_format<O: Output>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    args: &Args,
+    out: &mut O,
+) -> CommandResult {
+    args.flags.check("", &["yes", "cluster-size=", "label="])
+        .map_err(|msg| usage_error(state, Some(&msg), "format"))?;
+    if !args.is_empty() {
+        return Err(usage_error(state, None, "format"));
+    }
+
+    let mut options = FormatOptions::default();
+
+    if let Some(size) = args.flags.value("cluster-size") {
+        options = match size.parse::<u32>().map(|bytes| options.with_cluster_size(bytes)) {
+            Ok(Ok(o)) => o,
+            _ => return Err(ShellError::Failed(state.tr_fill("Invalid cluster size: {}", &[&size]))),
+        };
+    }
+
+    if let Some(label) = args.flags.value("label") {
+        options = options.with_label(label)
+            .map_err(|e| ShellError::Failed(format!("{}: {}", state.tr(&format!("{}", e)), label)))?;
+    }
+
+    restricted_check(state, "format")?;
+
+    if !args.flags.has_long("yes") {
+        return Err(ShellError::Failed(String::from(state.tr(
+            "This will erase all data on the image.\nRe-run with 'format --yes' to confirm."))));
+    }
+
+    options.volume_serial = fs.boot_sector().volume_serial.wrapping_add(1);
+    fs.format(&options).map_err(|e| ShellError::Fs("Format failed", e))?;
+
+    state.current_path.clear();
+    state.current_cluster = fs.root_cluster();
+    state.root_cluster = fs.root_cluster();
+    state.dir_stack.clear();
+    if state.config.summaries() {
+        out.write_line(&state.tr_fill("Formatted: {} clusters of {} bytes",
+            &[&fs.cluster_count(), &fs.bytes_per_cluster()]));
+    }
+    Ok(())
+}
+
+/// Commandes crc32/sha256 - affiche l'empreinte d'un fichier
+pub fn cmd_checksum<H: Checksum, O: Output>(
     fs: &Fat32,
     state: &ShellState,
     filename: &str,
+    hasher: H,
     out: &mut O,
-    lines_per_page: usize,
-) {
-    let entry = if filename.contains('/') {
-        fs.resolve_path(filename, state.current_cluster)
-    } else {
-        fs.find_entry(state.current_cluster, filename)
+) -> CommandResult {
+    match try_find_file(fs, state, filename) {
+        Ok(ref e) if e.is_directory() => {
+            Err(ShellError::Failed(state.tr("Cannot hash a directory").into()))
+        }
+        Ok(ref e) => {
+            let digest = fs.checksum_file(e, hasher);
+            out.write_line(&format!("{}  {}", to_hex(&digest), filename));
+            Ok(())
+        }
+        Err(e) => Err(path_error(state, filename, &e, state.tr("File not found").into())),
+    }
+}
+
+/// Commande crc32 - CRC-32 IEEE d'un fichier
+pub fn cmd_crc32<O: Output>(fs: &Fat32, state: &ShellState, filename: &str, out: &mut O) -> CommandResult {
+    cmd_checksum(fs, state, filename, Crc32::new(), out)
+}
+
+/// Commande sha256 - SHA-256 d'un fichier
+pub fn cmd_sha256<O: Output>(fs: &Fat32, state: &ShellState, filename: &str, out: &mut O) -> CommandResult {
+    cmd_checksum(fs, state, filename, Sha256::new(), out)
+}
+
+/// Commande cmp - compare deux fichiers et affiche le premier octet différent
+pub fn cmd_cmp<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    file_a: &str,
+    file_b: &str,
+    out: &mut O,
+) -> CommandResult {
+    let (a, b) = match (try_find_file(fs, state, file_a), try_find_file(fs, state, file_b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) => return Err(path_error(state, file_a, &e, state.tr_fill("File not found: {}", &[&file_a]))),
+        (_, Err(e)) => return Err(path_error(state, file_b, &e, state.tr_fill("File not found: {}", &[&file_b]))),
+    };
+
+    if a.is_directory() || b.is_directory() {
+        return Err(ShellError::Failed(state.tr("Cannot compare directories").into()));
+    }
+
+    match fs.compare_files(&a, &b) {
+        None => out.write_line(state.tr("Files are identical")),
+        Some(offset) if offset == a.size.min(b.size) as u64 => {
+            let shorter = if a.size < b.size { file_a } else { file_b };
+            out.write_line(&state.tr_fill("EOF on {} after byte {}", &[&shorter, &offset]));
+        }
+        Some(offset) => {
+            out.write_line(&state.tr_fill("{} {} differ: byte {}", &[&file_a, &file_b, &(offset + 1)]));
+        }
+    }
+
+    Ok(())
+}
+
+/// Commande rename - renomme une entrée sans changer de répertoire
+pub fn cmd_rename<O: Output>(
+    fs: &mut Fat32,
+    state: &ShellState,
+    old_name: &str,
+    new_name: &str,
+    out: &mut O,
+) -> CommandResult {
+    if new_name.contains('/') {
+        return Err(ShellError::Usage(String::from(state.tr(
+            "rename only changes the name; the new name cannot contain '/'"))));
+    }
+
+    let (dir_cluster, old_leaf) = resolve_parent(fs, state, old_name)?;
+
+    fs.rename(dir_cluster, old_leaf, new_name).map_err(|e| ShellError::Fs("Cannot rename", e))?;
+    if state.config.verbose() {
+        out.write_line(&state.tr_fill("Renamed {} -> {}", &[&old_leaf, &new_name]));
+    }
+    Ok(())
+}
+
+/// Commande alias - liste (`alias`), affiche (`alias name`) ou définit (`alias name='command'`)
+pub fn cmd_alias<O: Output>(state: &mut ShellState, args: Option<&str>, out: &mut O) -> CommandResult {
+    let definition = match args {
+        None => {
+            for (name, value) in &state.aliases {
+                out.write_line(&format!("alias {}='{}'", name, value));
+            }
+            return Ok(());
+        }
+        Some(d) => d,
     };
 
-    match entry {
-        Some(ref e) if e.is_directory() => {
-            out.write_line("Cannot display a directory");
+    let (name, value) = match definition.split_once('=') {
+        Some((name, value)) => (name.trim(), value.trim()),
+        None => {
+            let value = state.aliases.get(definition)
+                .ok_or_else(|| ShellError::NotFound(state.tr_fill("alias: {}: not found", &[&definition])))?;
+            out.write_line(&format!("alias {}='{}'", definition, value));
+            return Ok(());
         }
-        Some(ref e) => {
-            let data = fs.read_file(e);
+    };
 
-            if let Ok(text) = core::str::from_utf8(&data) {
-                let mut line_count = 0;
+    let value = ['\'', '"'].iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+        .unwrap_or(value)
+        .trim();
 
-                for line in text.lines() {
-                    out.write_line(line);
-                    line_count += 1;
+    if name.is_empty() || name.contains(char::is_whitespace) || value.is_empty() {
+        return Err(usage_error(state, Some("Invalid alias definition"), "alias"));
+    }
 
-                    if line_count >= lines_per_page {
-                        out.write_line("-- More (press any key to continue) --");
-                        line_count = 0;
-                    }
-                }
-            } else {
-                out.write_line("Binary file - use cat for hex dump");
+    state.aliases.insert(String::from(name), String::from(value));
+    Ok(())
+}
+
+/// Commande set - affiche (`set [option]`) ou modifie (`set <option> <value>`) les réglages du shell
+pub fn cmd_set<O: Output>(state: &mut ShellState, args: &Args, out: &mut O) -> CommandResult {
+    if !args.flags.is_empty() {
+        return Err(usage_error(state, None, "set"));
+    }
+
+    match args.args[..] {
+        [] => {
+            for (name, value) in state.config.options() {
+                out.write_line(&format!("{:<9}{}", name, value));
             }
+            Ok(())
+        }
+        [name] => {
+            let value = state.config.get(name)
+                .ok_or_else(|| usage_error(state, Some(&state.tr_fill("Unknown option: {}", &[&name])), "set"))?;
+            out.write_line(&value);
+            Ok(())
         }
+        [name, value] => state.config.set(name, value).map_err(|msg| usage_error(state, Some(&msg), "set")),
+        _ => Err(usage_error(state, None, "set")),
+    }
+}
+
+/// Commande unalias - supprime un alias
+pub fn cmd_unalias<O: Output>(state: &mut ShellState, name: &str, _out: &mut O) -> CommandResult {
+    state.aliases.remove(name)
+        .map(|_| ())
+        .ok_or_else(|| ShellError::NotFound(state.tr_fill("unalias: {}: not found", &[&name])))
+}
+
+/// Commande pushd - empile le répertoire courant puis change de répertoire
+///
+/// Sans argument, échange le répertoire courant et le sommet de la pile.
+pub fn cmd_pushd<O: Output>(
+    fs: &Fat32,
+    state: &mut ShellState,
+    dir: Option<&str>,
+    out: &mut O,
+) -> CommandResult {
+    let saved = state.save_dir();
+
+    match dir {
+        Some(dir) => cmd_cd(fs, state, dir, out)?,
         None => {
-            out.write_line("File not found");
+            let top = state.dir_stack.pop()
+                .ok_or_else(|| ShellError::Failed(state.tr("pushd: no other directory").into()))?;
+            state.restore_dir(top);
         }
     }
+
+    state.dir_stack.push(saved);
+    cmd_dirs(state, out)
+}
+
+/// Commande popd - retourne au répertoire au sommet de la pile
+pub fn cmd_popd<O: Output>(state: &mut ShellState, out: &mut O) -> CommandResult {
+    let top = state.dir_stack.pop()
+        .ok_or_else(|| ShellError::Failed(state.tr("popd: directory stack empty").into()))?;
+    state.restore_dir(top);
+    cmd_dirs(state, out)
+}
+
+/// Commande dirs - affiche le répertoire courant puis la pile (sommet d'abord)
+pub fn cmd_dirs<O: Output>(state: &ShellState, out: &mut O) -> CommandResult {
+    let mut line = state.pwd();
+    for dir in state.dir_stack.iter().rev() {
+        line.push(' ');
+        line.push('/');
+        line.push_str(dir.path.as_str());
+    }
+    out.write_line(&line);
+    Ok(())
 }
 
 /// Commande pwd - affiche le répertoire courant
-pub fn cmd_pwd<O: Output>(state: &ShellState, out: &mut O) {
+pub fn cmd_pwd<O: Output>(state: &ShellState, out: &mut O) -> CommandResult {
     out.write_line(&state.pwd());
+    Ok(())
+}
+
+/// Commande info - affiche les paramètres du filesystem
+pub fn cmd_info<O: Output>(fs: &Fat32, state: &ShellState, out: &mut O) -> CommandResult {
+    let bs = fs.boot_sector();
+
+    if state.config.json {
+        let free = fs.fs_info().and_then(|i| i.free_clusters(fs.cluster_count()));
+        out.write_line(&format!(
+            "{{\"label\": {}, \"serial\": \"{:08X}\", \"bytes_per_sector\": {}, \"bytes_per_cluster\": {}, \"clusters\": {}, \"fat_count\": {}, \"root_cluster\": {}, \"hidden_sectors\": {}, \"free_clusters\": {}}}",
+            json_string(bs.label()), bs.volume_serial, bs.bytes_per_sector, bs.bytes_per_cluster(),
+            fs.cluster_count(), bs.fat_count, bs.root_cluster, bs.hidden_sectors,
+            free.map_or(String::from("null"), |n| format!("{}", n))));
+        return Ok(());
+    }
+
+    let label = if bs.label().is_empty() { state.tr("(none)") } else { bs.label() };
+
+    let mut fields: Vec<(&str, String)> = alloc::vec![
+        ("Volume label", String::from(label)),
+        ("Serial number", format!("{:04X}-{:04X}", bs.volume_serial >> 16, bs.volume_serial & 0xFFFF)),
+        ("Bytes per sector", format!("{}", bs.bytes_per_sector)),
+        ("Sectors per cluster", format!("{}", bs.sectors_per_cluster)),
+        ("Bytes per cluster", format!("{}", bs.bytes_per_cluster())),
+        ("Reserved sectors", format!("{}", bs.reserved_sectors)),
+        ("FAT count", format!("{}", bs.fat_count)),
+        ("Sectors per FAT", format!("{}", bs.sectors_per_fat)),
+        ("Root cluster", format!("{}", bs.root_cluster)),
+        ("Total sectors", format!("{}", bs.total_sectors)),
+        ("Hidden sectors", format!("{}", bs.hidden_sectors)),
+        ("Data start sector", format!("{}", bs.data_start_sector())),
+        ("FSInfo sector", format!("{}", bs.fs_info_sector)),
+        ("Backup boot sector", format!("{}", bs.backup_boot_sector)),
+    ];
+
+    match fs.fs_info() {
+        Some(info) => {
+            fields.push(("FSInfo signatures", format!("{:08X} {:08X} {:08X}",
+                info.lead_signature, info.struct_signature, info.trail_signature)));
+            fields.push(("FSInfo free count", fsinfo_value(state, info.free_count)));
+            fields.push(("FSInfo next free", fsinfo_value(state, info.next_free)));
+        }
+        None => fields.push(("FSInfo", String::from(state.tr("not present")))),
+    }
+
+    // Les libellés traduits n'ont pas tous la même longueur
+    let labels: Vec<&str> = fields.iter().map(|(name, _)| state.tr(name)).collect();
+    let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) + 1;
+    for (label, (_, value)) in labels.iter().zip(&fields) {
+        let padding = width - label.chars().count();
+        out.write_line(&format!("{}:{}{}", label, " ".repeat(padding), value));
+    }
+
+    Ok(())
+}
+
+/// Formate un champ FSInfo (0xFFFFFFFF = inconnu)
+fn fsinfo_value(state: &ShellState, value: u32) -> String {
+    if value == FSINFO_UNKNOWN {
+        String::from(state.tr("unknown"))
+    } else {
+        format!("{}", value)
+    }
 }
 
-/// Commande help - affiche l'aide
-pub fn cmd_help<O: Output>(out: &mut O) {
-    out.write_line("FAT32 Shell Commands:");
+/// Commande help - liste les commandes, ou détaille l'une d'elles (`help <command>`)
+pub fn cmd_help<O: Output>(state: &ShellState, topic: Option<&str>, out: &mut O) -> CommandResult {
+    if let Some(name) = topic {
+        let info = lookup(name)
+            .ok_or_else(|| ShellError::NotFound(state.tr_fill("No help for '{}'", &[&name])))?;
+        write_command_help(state, info, out);
+        return Ok(());
+    }
+
+    out.write_line(state.tr("FAT32 Shell Commands:"));
     out.write_line("");
-    out.write_line("  ls [path]     - List directory contents");
-    out.write_line("  cd <dir>      - Change directory");
-    out.write_line("  cat <file>    - Display file contents");
-    out.write_line("  more <file>   - Display file with pagination");
-    out.write_line("  pwd           - Print working directory");
-    out.write_line("  help          - Show this help");
-    out.write_line("  exit          - Exit shell");
+    for info in COMMANDS {
+        out.write_line(&format!("  {:<12} {}", info.name, state.catalog.summary(info)));
+    }
     out.write_line("");
-    out.write_line("Path examples:");
-    out.write_line("  cd /          - Go to root");
-    out.write_line("  cd ..         - Go up one level");
-    out.write_line("  cd Documents  - Enter subdirectory");
-    out.write_line("  cat /path/to/file.txt - Read file by path");
+    out.write_line(state.tr("Type 'help <command>' for usage, options and examples."));
+    out.write_line(state.tr("Paths may be absolute (/DOCS/A.TXT) or relative (../A.TXT)."));
+    out.write_line(state.tr("Append '> FILE' to a command to write its output to a file on the image."));
+    out.write_line(state.tr("Use 'a | b' to pass the output of a to b (cat, more, grep, head)."));
+    Ok(())
 }
 
-/// Navigate depuis la racine avec les composants du chemin
-fn navigate_from_root(fs: &Fat32, path: &[String]) -> u32 {
-    let mut cluster = fs.root_cluster();
+/// Affiche l'aide détaillée d'une commande
+fn write_command_help<O: Output>(state: &ShellState, info: &'static CommandInfo, out: &mut O) {
+    out.write_line(&format!("{} - {}", info.name, state.catalog.summary(info)));
+    out.write_line("");
+    out.write_line(&state.tr_fill("Usage: {}", &[&info.usage]));
 
-    for component in path {
-        if let Some(entry) = fs.find_entry(cluster, component) {
-            if entry.is_directory() {
-                cluster = entry.cluster();
-            } else {
-                break;
+    if !info.aliases.is_empty() {
+        out.write_line(&state.tr_fill("Aliases: {}", &[&info.aliases.join(", ")]));
+    }
+
+    if !info.options.is_empty() {
+        let width = info.options.iter().map(|(opt, _)| opt.len()).max().unwrap_or(0);
+        out.write_line("");
+        out.write_line(state.tr("Options:"));
+        for (opt, desc) in info.options {
+            out.write_line(&format!("  {:<width$}  {}", opt, state.tr(desc), width = width));
+        }
+    }
+
+    if !info.examples.is_empty() {
+        out.write_line("");
+        out.write_line(state.tr("Examples:"));
+        for example in info.examples {
+            out.write_line(&format!("  {}", example));
+        }
+    }
+}
+
+/// Affiche une erreur de commande sur le flux d'erreur (en rouge si la couleur est activée)
+pub fn write_error<O: Output>(state: &ShellState, err: &ShellError, out: &mut O) {
+    let text = err.describe(state.catalog);
+    out.write_err_line(&color::paint(&state.config, Some(Style::Error), &text));
+}
+
+/// Ligne d'usage d'une commande, tirée du registre
+fn usage(state: &ShellState, command: &str) -> String {
+    match lookup(command) {
+        Some(info) => state.tr_fill("Usage: {}", &[&info.usage]),
+        None => String::new(),
+    }
+}
+
+/// Erreur d'usage: message éventuel (traduit) suivi de la ligne d'usage de la commande
+pub(crate) fn usage_error(state: &ShellState, msg: Option<&str>, command: &str) -> ShellError {
+    match msg {
+        Some(msg) => ShellError::Usage(format!("{}\n{}", state.tr(msg), usage(state, command))),
+        None => ShellError::Usage(usage(state, command)),
+    }
+}
+
+/// Refuse les commandes d'accès brut au volume dans un shell confiné
+pub(crate) fn restricted_check(state: &ShellState, command: &str) -> CommandResult {
+    if state.is_restricted() {
+        return Err(ShellError::Failed(state.tr_fill("{}: not available in a restricted shell", &[&command])));
+    }
+    Ok(())
+}
+
+/// Résout un chemin qui doit désigner un répertoire
+fn resolve_directory(fs: &Fat32, state: &ShellState, path: &str) -> Result<u32, ShellError> {
+    match walk_path(fs, state, normalize_path(state, path).as_str()) {
+        Ok((c, true)) => Ok(c),
+        Ok((_, false)) => Err(ShellError::Failed(state.tr("Not a directory").into())),
+        Err(e) => Err(path_error(state, path, &e, state.tr("Path not found").into())),
+    }
+}
+
+/// Sépare un chemin en (cluster du répertoire parent, nom de la dernière composante)
+pub(crate) fn resolve_parent<'p>(
+    fs: &Fat32,
+    state: &ShellState,
+    path: &'p str,
+) -> Result<(u32, &'p str), ShellError> {
+    match path.rsplit_once('/') {
+        Some((dir, leaf)) => {
+            let dir = if dir.is_empty() { "/" } else { dir };
+            let missing = || state.tr("Directory not found").into();
+            match walk_path(fs, state, normalize_path(state, dir).as_str()) {
+                Ok((c, true)) => Ok((c, leaf)),
+                Ok((_, false)) => Err(ShellError::NotFound(missing())),
+                Err(e) => Err(path_error(state, dir, &e, missing())),
             }
-        } else {
-            break;
         }
+        None => Ok((state.current_cluster, path)),
+    }
+}
+
+/// Cherche un fichier par nom ou par chemin depuis le répertoire courant
+///
+/// L'erreur désigne le composant absent ou qui n'est pas un répertoire.
+pub(crate) fn try_find_file(fs: &Fat32, state: &ShellState, filename: &str) -> Result<DirEntry, PathError> {
+    if !filename.contains('/') && filename != "." && filename != ".." {
+        let missing = || PathError {
+            index: 0,
+            component: filename.into(),
+            path: filename.into(),
+            kind: PathErrorKind::NotFound,
+        };
+        return fs.find_entry(state.current_cluster, filename).ok_or_else(missing);
     }
 
-    cluster
+    let components = normalize_path(state, filename);
+    let (parent, name) = match components.split_last() {
+        Some(split) => split,
+        None => return Err(component_error("", 0, PathErrorKind::NotFound)),
+    };
+    let depth = parent.split('/').filter(|c| !c.is_empty()).count();
+    match walk_path(fs, state, parent)? {
+        (dir, true) => fs.find_entry(dir, name).ok_or_else(|| component_error(components.as_str(), depth, PathErrorKind::NotFound)),
+        (_, false) => Err(component_error(parent, depth - 1, PathErrorKind::NotADirectory)),
+    }
 }
 
-/// Résout un chemin vers un numéro de cluster
-fn resolve_to_cluster(fs: &Fat32, state: &ShellState, path: &str) -> Option<(u32, bool)> {
-    let (is_absolute, components) = super::parser::parse_path(path);
+/// Erreur sur le composant `index` de `components`, avec son chemin vu du shell
+fn component_error(components: &str, index: usize, kind: PathErrorKind) -> PathError {
+    let names = || components.split('/').filter(|c| !c.is_empty());
+    let mut path = String::new();
+    for name in names().take(index + 1) {
+        path.push('/');
+        path.push_str(name);
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    PathError { index, component: names().nth(index).unwrap_or("").into(), path, kind }
+}
 
-    let mut cluster = if is_absolute {
-        fs.root_cluster()
-    } else {
-        state.current_cluster
+/// Erreur à afficher pour un chemin qui n'a pas pu être suivi
+///
+/// Un nom seul garde le message court `missing`; dans un chemin, le message
+/// nomme le composant absent ou qui n'est pas un répertoire.
+pub(crate) fn path_error(state: &ShellState, path: &str, error: &PathError, missing: String) -> ShellError {
+    if !path.contains('/') {
+        return ShellError::NotFound(missing);
+    }
+    let message = match error.kind {
+        PathErrorKind::NotFound => state.tr_fill("{}: not found", &[&error.path]),
+        PathErrorKind::NotADirectory => state.tr_fill("{}: not a directory", &[&error.path]),
+        PathErrorKind::Fs(e) => format!("{}: {}", error.path, state.tr(&e.to_string())),
     };
+    ShellError::NotFound(message)
+}
 
-    for (i, component) in components.iter().enumerate() {
-        match *component {
-            ".." => continue,
-            "." => continue,
-            name => {
-                if let Some(entry) = fs.find_entry(cluster, name) {
-                    if i == components.len() - 1 {
-                        let new_cluster = if entry.cluster() == 0 {
-                            fs.root_cluster()
-                        } else {
-                            entry.cluster()
-                        };
-                        return Some((new_cluster, entry.is_directory()));
-                    } else if entry.is_directory() {
-                        cluster = entry.cluster();
-                        if cluster == 0 {
-                            cluster = fs.root_cluster();
-                        }
-                    } else {
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
+/// Composants d'un chemin depuis la racine du shell
+///
+/// `.` et `..` sont résolus sur le texte du chemin; `..` à la racine reste à
+/// la racine, ce qui empêche de sortir d'un shell confiné (`with_root`).
+pub(crate) fn normalize_path(state: &ShellState, path: &str) -> ShellPath {
+    let mut components = if path.starts_with('/') { ShellPath::new() } else { state.current_path.clone() };
+
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
             }
+            name => components.push(name),
+        }
+    }
+
+    components
+}
+
+/// Suit des composants (joints par `/`) depuis la racine du shell: (cluster, est un répertoire)
+///
+/// L'erreur désigne le composant absent, ou le fichier rencontré au milieu du chemin.
+fn walk_path(fs: &Fat32, state: &ShellState, components: &str) -> Result<(u32, bool), PathError> {
+    let mut cluster = state.root_cluster;
+
+    let mut names = components.split('/').filter(|c| !c.is_empty()).enumerate().peekable();
+    while let Some((index, name)) = names.next() {
+        let entry = fs.find_entry(cluster, name)
+            .ok_or_else(|| component_error(components, index, PathErrorKind::NotFound))?;
+        let is_last = names.peek().is_none();
+        if !entry.is_directory() {
+            return if is_last {
+                Ok((entry.cluster(), false))
+            } else {
+                Err(component_error(components, index, PathErrorKind::NotADirectory))
+            };
         }
+        // Un cluster 0 désigne la racine du volume
+        cluster = if entry.cluster() == 0 { fs.root_cluster() } else { entry.cluster() };
     }
 
-    Some((cluster, true))
+    Ok((cluster, true))
+}
+
+/// Résout un chemin vers un numéro de cluster
+pub(crate) fn resolve_to_cluster(fs: &Fat32, state: &ShellState, path: &str) -> Option<(u32, bool)> {
+    walk_path(fs, state, normalize_path(state, path).as_str()).ok()
 }
 
-/// Affiche un dump hexadécimal
-fn hex_dump<O: Output>(data: &[u8], out: &mut O, max_bytes: usize) {
+/// Affiche un dump hexadécimal (adresses relatives à `base`)
+fn hex_dump<O: Output>(state: &ShellState, data: &[u8], base: u64, out: &mut O, max_bytes: usize) {
     let display_len = data.len().min(max_bytes);
 
     for (i, chunk) in data[..display_len].chunks(16).enumerate() {
-        let mut line = format!("{:08X}:  ", i * 16);
+        let mut line = format!("{:08X}:  ", base + i as u64 * 16);
 
         for (j, byte) in chunk.iter().enumerate() {
             line.push_str(&format!("{:02X} ", byte));
@@ -385,7 +1272,7 @@ fn hex_dump<O: Output>(data: &[u8], out: &mut O, max_bytes: usize) {
     }
 
     if data.len() > max_bytes {
-        out.write_line(&format!("... ({} more bytes)", data.len() - max_bytes));
+        out.write_line(&state.tr_fill("... ({} more bytes)", &[&(data.len() - max_bytes)]));
     }
 }
 
@@ -403,10 +1290,177 @@ mod tests {
     #[test]
     fn test_pwd_with_path() {
         let mut state = ShellState::new(2);
-        state.current_path.push(String::from("Documents"));
-        state.current_path.push(String::from("Work"));
+        state.current_path.push("Documents");
+        state.current_path.push("Work");
 
         assert_eq!(state.pwd(), "/Documents/Work");
         assert!(!state.is_root());
     }
+
+    #[test]
+    fn test_normalize_path() {
+        let mut state = ShellState::with_root(5, "/PUBLIC/");
+        state.current_path = ShellPath::from("DOCS");
+        assert_eq!(normalize_path(&state, "A.TXT").as_str(), "DOCS/A.TXT");
+        assert_eq!(normalize_path(&state, "./../B/./C").as_str(), "B/C");
+        assert!(normalize_path(&state, "../../../..").is_empty());
+        assert_eq!(normalize_path(&state, "/X//Y/").as_str(), "X/Y");
+        assert_eq!(state.volume_path(), "/PUBLIC/DOCS");
+        assert!(state.is_restricted() && !ShellState::new(2).is_restricted());
+    }
+
+    #[test]
+    fn test_ls_options_parsing() {
+        let state = ShellState::new(2);
+        let o = parse_ls_options(&state, &Args::parse("-la --sort=size /DOCS")).unwrap();
+        assert!(o.long && o.all && !o.human);
+        assert_eq!(o.sort, LsSort::Size);
+        assert_eq!(o.path, Some("/DOCS"));
+
+        assert!(parse_ls_options(&state, &Args::parse("-x")).is_err());
+        assert!(parse_ls_options(&state, &Args::parse("--sort=color")).is_err());
+        assert!(parse_ls_options(&state, &Args::parse("--sort")).is_err());
+        assert_eq!(parse_ls_options(&state, &Args::default()).unwrap().path, None);
+    }
+
+    #[test]
+    fn test_help_from_registry() {
+        let mut out = StringOutput::new();
+        let state = ShellState::new(2);
+        cmd_help(&state, None, &mut out).unwrap();
+        assert!(COMMANDS.iter().all(|c| out.buffer.contains(c.name)));
+
+        let mut out = StringOutput::new();
+        cmd_help(&state, Some("DIR"), &mut out).unwrap();
+        assert!(out.buffer.starts_with("ls - List directory contents"));
+        assert!(out.buffer.contains("Usage: ls [-l]"));
+        assert!(out.buffer.contains("--sort=KEY"));
+
+        assert!(cmd_help(&state, Some("nope"), &mut out).is_err());
+        assert_eq!(usage_error(&state, None, "cat"), ShellError::Usage(String::from("Usage: cat [-n] [file]...")));
+    }
+
+    #[test]
+    fn test_alias_command() {
+        let mut state = ShellState::new(2);
+        let mut out = StringOutput::new();
+
+        cmd_alias(&mut state, Some("ll='ls -l'"), &mut out).unwrap();
+        cmd_alias(&mut state, Some("la=\"ls -a\""), &mut out).unwrap();
+        assert_eq!(state.aliases.get("ll").map(String::as_str), Some("ls -l"));
+
+        cmd_alias(&mut state, None, &mut out).unwrap();
+        assert_eq!(out.buffer, "alias la='ls -a'\nalias ll='ls -l'\n");
+
+        assert!(cmd_alias(&mut state, Some("bad name=ls"), &mut out).is_err());
+        assert!(cmd_alias(&mut state, Some("x=''"), &mut out).is_err());
+        assert!(cmd_unalias(&mut state, "ll", &mut out).is_ok());
+        assert!(cmd_unalias(&mut state, "ll", &mut out).is_err());
+    }
+
+    #[test]
+    fn test_set_command() {
+        let mut state = ShellState::new(2);
+        let mut out = StringOutput::new();
+
+        cmd_set(&mut state, &Args::parse("pagesize 30"), &mut out).unwrap();
+        cmd_set(&mut state, &Args::parse("pagesize"), &mut out).unwrap();
+        assert_eq!(out.buffer, "30\n");
+        assert_eq!(state.config.page_lines, Some(30));
+
+        let mut out = StringOutput::new();
+        cmd_set(&mut state, &Args::parse(""), &mut out).unwrap();
+        assert!(out.buffer.starts_with("verbose  off\nquiet    off\npagesize 30\n"));
+        assert!(matches!(cmd_set(&mut state, &Args::parse("verbose yes"), &mut out), Err(ShellError::Usage(_))));
+        assert!(matches!(cmd_set(&mut state, &Args::parse("colour"), &mut out), Err(ShellError::Usage(_))));
+    }
+
+    #[test]
+    fn test_undelete_in_directory() {
+        let mut data = crate::builder::FsBuilder::new(crate::builder::MB)
+            .file("ROOT.TXT", b"root")
+            .dir("DOCS", |d| d.file("A.TXT", b"a").file("B.TXT", b"b"))
+            .build()
+            .unwrap();
+        let mut fs = Fat32::new_mut(&mut data).unwrap();
+        let root = fs.root_cluster();
+        let docs = fs.find_entry(root, "DOCS").unwrap().cluster();
+        fs.remove_file(root, "ROOT.TXT").unwrap();
+        fs.remove_file(docs, "A.TXT").unwrap();
+        fs.remove_file(docs, "B.TXT").unwrap();
+        let state = ShellState::new(root);
+        let mut out = StringOutput::new();
+
+        // L'index vient de la liste de DOCS, pas du répertoire courant
+        cmd_undelete(&mut fs, &state, &Args::parse("DOCS 1 B"), &mut out).unwrap();
+        assert!(fs.find_entry(docs, "B.TXT").is_some());
+        assert!(fs.find_entry(root, "ROOT.TXT").is_none());
+
+        for args in ["DOCS A", "DOCS 0 A extra", "-x"] {
+            assert!(matches!(cmd_undelete(&mut fs, &state, &Args::parse(args), &mut out), Err(ShellError::Usage(_))), "{}", args);
+        }
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a \"b\"\\c\n"), "\"a \\\"b\\\"\\\\c\\n\"");
+    }
+
+    #[test]
+    fn test_dir_stack_swap_and_pop() {
+        let mut state = ShellState::new(2);
+        let mut out = StringOutput::new();
+        state.dir_stack.push(SavedDir { path: ShellPath::from("DOCS"), cluster: 3 });
+
+        assert!(cmd_popd(&mut state, &mut out).is_ok());
+        assert_eq!((state.pwd(), state.current_cluster), (String::from("/DOCS"), 3));
+        assert!(cmd_popd(&mut state, &mut out).is_err());
+
+        state.dir_stack.push(SavedDir { path: ShellPath::new(), cluster: 2 });
+        let mut out = StringOutput::new();
+        cmd_dirs(&state, &mut out).unwrap();
+        assert_eq!(out.buffer, "/DOCS /\n");
+    }
+
+    #[test]
+    fn test_write_columns() {
+        let cells: Vec<(String, usize)> = ["A.TXT", "BB.TXT", "C", "DOCS/", "E"].iter()
+            .map(|n| (String::from(*n), n.len()))
+            .collect();
+
+        let mut out = StringOutput::new();
+        write_columns(&cells, 80, &mut out);
+        assert_eq!(out.buffer, "A.TXT  BB.TXT  C  DOCS/  E\n");
+
+        let mut out = StringOutput::new();
+        write_columns(&cells, 16, &mut out);
+        assert_eq!(out.buffer, "A.TXT   C      E\nBB.TXT  DOCS/\n");
+
+        let mut out = StringOutput::new();
+        write_columns(&cells, 14, &mut out);
+        assert_eq!(out.buffer, "A.TXT   DOCS/\nBB.TXT  E\nC\n");
+
+        let mut out = StringOutput::new();
+        write_columns(&cells, 3, &mut out);
+        assert_eq!(out.buffer.lines().count(), 5);
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(512), "512");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(5 * 1024 * 1024), "5.0M");
+    }
+
+    #[test]
+    fn test_hex_dump_base_offset() {
+        let mut out = StringOutput::new();
+        hex_dump(&ShellState::new(2), b"ABCDEFGHIJKLMNOPQR", 0x8400, &mut out, 64);
+
+        let lines: Vec<&str> = out.buffer.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("00008400:  41 42"));
+        assert!(lines[0].ends_with("|ABCDEFGHIJKLMNOP|"));
+        assert!(lines[1].starts_with("00008410:  51 52"));
+    }
 }
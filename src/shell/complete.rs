@@ -0,0 +1,135 @@
+//! Complétion (touche Tab) des noms de commandes et des chemins
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::fat32::Fat32;
+use super::commands::{ShellState, resolve_to_cluster};
+use super::registry::{COMMANDS, lookup};
+
+/// Candidats de complétion pour le dernier mot de `input`
+///
+/// Le premier mot est complété avec les noms de commandes et les alias (et `help <cmd>`),
+/// les suivants avec les entrées du répertoire visé. Chaque candidat remplace
+/// le dernier mot en entier; les répertoires se terminent par `/` et les noms
+/// contenant des espaces sont entourés de guillemets.
+pub fn complete(fs: &Fat32, state: &ShellState, input: &str) -> Vec<String> {
+    let word_start = input.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    let word = &input[word_start..];
+    let before: Vec<&str> = input[..word_start].split_whitespace().collect();
+
+    match before[..] {
+        [] => {
+            let mut names = complete_command(word);
+            names.extend(state.aliases.keys()
+                .filter(|name| starts_with_ignore_case(name, word))
+                .cloned());
+            names.sort();
+            names.dedup();
+            names
+        }
+        [cmd] if lookup(cmd).is_some_and(|c| c.name == "help") => complete_command(word),
+        _ if word.starts_with('-') => Vec::new(),
+        _ => complete_path(fs, state, word),
+    }
+}
+
+/// Plus long préfixe commun des candidats (insensible à la casse ASCII)
+pub fn common_prefix(candidates: &[String]) -> String {
+    let first = match candidates.first() {
+        Some(f) => f,
+        None => return String::new(),
+    };
+
+    let mut len = first.len();
+    for other in &candidates[1..] {
+        len = first.char_indices()
+            .zip(other.chars())
+            .take_while(|((_, a), b)| a.eq_ignore_ascii_case(b))
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+
+    String::from(&first[..len])
+}
+
+/// Noms et alias de commandes commençant par `prefix`
+fn complete_command(prefix: &str) -> Vec<String> {
+    let mut names: Vec<String> = COMMANDS.iter()
+        .flat_map(|c| core::iter::once(c.name).chain(c.aliases.iter().copied()))
+        .filter(|name| starts_with_ignore_case(name, prefix))
+        .map(String::from)
+        .collect();
+    names.sort();
+    names
+}
+
+/// Entrées du répertoire visé par `word` dont le nom commence par son dernier composant
+fn complete_path(fs: &Fat32, state: &ShellState, word: &str) -> Vec<String> {
+    let word = word.trim_start_matches(['"', '\'']);
+    let (dir, prefix) = match word.rfind('/') {
+        Some(i) => (&word[..=i], &word[i + 1..]),
+        None => ("", word),
+    };
+
+    let cluster = if dir.is_empty() {
+        state.current_cluster
+    } else {
+        match resolve_to_cluster(fs, state, dir) {
+            Some((c, true)) => c,
+            _ => return Vec::new(),
+        }
+    };
+
+    let mut candidates: Vec<String> = fs.read_directory_with_lfn(cluster)
+        .into_iter()
+        .filter(|(entry, _)| !entry.is_dot() && !entry.is_dotdot())
+        .filter_map(|(entry, long_name)| {
+            let name = match long_name {
+                Some(ln) if !ln.is_empty() => ln,
+                _ => entry.display_name(),
+            };
+            if !starts_with_ignore_case(&name, prefix) {
+                return None;
+            }
+
+            let suffix = if entry.is_directory() { "/" } else { "" };
+            let path = format!("{}{}{}", dir, name, suffix);
+            Some(if path.contains(' ') { format!("\"{}\"", path) } else { path })
+        })
+        .collect();
+
+    candidates.sort();
+    candidates
+}
+
+/// `str::starts_with` insensible à la casse ASCII
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.len() >= prefix.len()
+        && s.is_char_boundary(prefix.len())
+        && s[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_command_names() {
+        assert_eq!(complete_command("un"), ["unalias", "undel", "undelete"]);
+        assert_eq!(complete_command("SHA"), ["sha256", "sha256sum"]);
+        assert!(complete_command("zz").is_empty());
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        let c = [String::from("DOCS/"), String::from("docs.txt")];
+        assert_eq!(common_prefix(&c), "DOCS");
+        assert_eq!(common_prefix(&[]), "");
+        assert_eq!(common_prefix(&[String::from("HELLO.TXT")]), "HELLO.TXT");
+    }
+}
@@ -0,0 +1,209 @@
+//! Réglages du shell modifiables à l'exécution (commande `set`)
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Lignes par page de more quand la taille du terminal est inconnue
+pub const DEFAULT_PAGE_LINES: usize = 20;
+
+/// Noms des réglages, dans l'ordre d'affichage de `set`
+pub const OPTION_NAMES: &[&str] = &["verbose", "quiet", "pagesize", "hidden", "color", "case", "json", "utc"];
+
+/// Niveau de détail des messages des commandes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Seulement le résultat demandé (pas de bilan), pour les scripts
+    Quiet,
+    /// Résultat et bilans ("N file(s)", "Restored ...")
+    #[default]
+    Normal,
+    /// Bilans et détails des opérations (octets écrits, lignes de script)
+    Verbose,
+}
+
+/// Réglages d'affichage du shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellConfig {
+    /// Couleurs ANSI (répertoires, exécutables, archives, erreurs)
+    pub color: bool,
+    /// Niveau de détail (`set verbose on`, `set quiet on`)
+    pub verbosity: Verbosity,
+    /// Lignes par page de more; `None` pour suivre la hauteur du terminal
+    pub page_lines: Option<usize>,
+    /// ls affiche les entrées cachées et système sans `-a`
+    pub show_hidden: bool,
+    /// grep distingue majuscules et minuscules (sinon comme `grep -i`)
+    pub case_sensitive: bool,
+    /// ls et info écrivent du JSON au lieu du texte
+    pub json: bool,
+    /// Décalage sur UTC de l'heure locale des dates FAT, en secondes (export et import)
+    pub utc_offset: i32,
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        ShellConfig {
+            color: false,
+            verbosity: Verbosity::Normal,
+            page_lines: None,
+            show_hidden: false,
+            case_sensitive: true,
+            json: false,
+            utc_offset: 0,
+        }
+    }
+}
+
+impl ShellConfig {
+    /// Vrai si les bilans des commandes doivent être affichés
+    pub fn summaries(&self) -> bool {
+        self.verbosity >= Verbosity::Normal
+    }
+
+    /// Vrai si les détails des opérations doivent être affichés
+    pub fn verbose(&self) -> bool {
+        self.verbosity == Verbosity::Verbose
+    }
+
+    /// Valeur d'un réglage telle qu'affichée par `set`
+    pub fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "verbose" => on_off(self.verbose()),
+            "quiet" => on_off(!self.summaries()),
+            "pagesize" => return Some(self.page_lines.map_or(String::from("auto"), |n| format!("{}", n))),
+            "utc" => return Some(format_offset(self.utc_offset)),
+            "hidden" => on_off(self.show_hidden),
+            "color" => on_off(self.color),
+            "case" => on_off(self.case_sensitive),
+            "json" => on_off(self.json),
+            _ => return None,
+        };
+        Some(String::from(value))
+    }
+
+    /// Modifie un réglage (`on`/`off`, un nombre/`auto` pour pagesize, `±HH:MM` pour utc)
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if name == "utc" {
+            self.utc_offset = parse_offset(value).ok_or_else(|| format!("Invalid UTC offset: {}", value))?;
+            return Ok(());
+        }
+        if name == "pagesize" {
+            self.page_lines = match value {
+                "auto" => None,
+                n => match n.parse::<usize>() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("Invalid page size: {}", value)),
+                },
+            };
+            return Ok(());
+        }
+
+        let on = match value {
+            "on" => true,
+            "off" => false,
+            _ => return Err(String::from("Value must be 'on' or 'off'")),
+        };
+
+        match name {
+            "verbose" | "quiet" => {
+                let level = if name == "verbose" { Verbosity::Verbose } else { Verbosity::Quiet };
+                // Désactiver un mode revient au niveau normal
+                if on {
+                    self.verbosity = level;
+                } else if self.verbosity == level {
+                    self.verbosity = Verbosity::Normal;
+                }
+            }
+            "hidden" => self.show_hidden = on,
+            "color" => self.color = on,
+            "case" => self.case_sensitive = on,
+            "json" => self.json = on,
+            _ => return Err(format!("Unknown option: {}", name)),
+        }
+        Ok(())
+    }
+
+    /// Tous les réglages `(nom, valeur)`, pour `set` sans argument
+    pub fn options(&self) -> Vec<(&'static str, String)> {
+        OPTION_NAMES.iter()
+            .filter_map(|name| self.get(name).map(|value| (*name, value)))
+            .collect()
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on { "on" } else { "off" }
+}
+
+/// Décalage `+HH:MM`, `-HH`, `0` ou `utc` en secondes (au plus 14 heures)
+fn parse_offset(value: &str) -> Option<i32> {
+    if value == "0" || value.eq_ignore_ascii_case("utc") {
+        return Some(0);
+    }
+    let (sign, rest) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    if minutes >= 60 || hours > 14 || hours * 60 + minutes > 14 * 60 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60) as i32)
+}
+
+/// Décalage en secondes affiché `+HH:MM`
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.unsigned_abs() / 60;
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_get() {
+        let mut config = ShellConfig::default();
+
+        config.set("verbose", "on").unwrap();
+        assert!(config.verbose());
+        config.set("quiet", "off").unwrap();
+        assert!(config.verbose());
+        config.set("quiet", "on").unwrap();
+        assert!(!config.summaries());
+        config.set("quiet", "off").unwrap();
+        assert_eq!(config.verbosity, Verbosity::Normal);
+
+        config.set("pagesize", "40").unwrap();
+        assert_eq!(config.page_lines, Some(40));
+        assert_eq!(config.get("pagesize").unwrap(), "40");
+        config.set("pagesize", "auto").unwrap();
+        assert_eq!(config.page_lines, None);
+        assert!(config.set("pagesize", "0").is_err());
+
+        config.set("json", "on").unwrap();
+        assert_eq!(config.get("json").unwrap(), "on");
+        assert!(config.set("color", "yes").is_err());
+        assert!(config.set("colour", "on").is_err());
+        assert_eq!(config.options().len(), OPTION_NAMES.len());
+
+        assert_eq!(config.get("utc").unwrap(), "+00:00");
+        config.set("utc", "+02:00").unwrap();
+        assert_eq!(config.utc_offset, 7200);
+        config.set("utc", "-05:30").unwrap();
+        assert_eq!(config.get("utc").unwrap(), "-05:30");
+        config.set("utc", "+9").unwrap();
+        assert_eq!(config.utc_offset, 9 * 3600);
+        for bad in ["2", "+15:00", "+01:60", "+-1", "+", "+100000000", "-4294967295:00"] {
+            assert!(config.set("utc", bad).is_err(), "{}", bad);
+        }
+        config.set("utc", "UTC").unwrap();
+        assert_eq!(config.utc_offset, 0);
+    }
+}
@@ -0,0 +1,386 @@
+//! Édition de ligne du shell interactif: curseur, historique et complétion
+//!
+//! Le module ne touche pas au terminal: l'appelant le met en mode caractère,
+//! passe chaque octet lu à `KeyDecoder` puis chaque touche à `LineEditor`,
+//! qui redessine la ligne sur une `Output`.
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::commands::Output;
+use super::complete::common_prefix;
+
+/// Nombre de lignes gardées dans l'historique par défaut
+pub const HISTORY_SIZE: usize = 500;
+
+/// Touche décodée depuis les octets du terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Up,
+    Down,
+    Tab,
+    /// Ctrl-C
+    Interrupt,
+    /// Ctrl-D
+    EndOfInput,
+    /// Touche de contrôle ou séquence non gérée
+    Other,
+}
+
+/// Décodeur des octets du terminal (UTF-8 et séquences `ESC [` des flèches)
+#[derive(Debug, Default)]
+pub struct KeyDecoder {
+    pending: Vec<u8>,
+}
+
+impl KeyDecoder {
+    pub fn new() -> Self {
+        KeyDecoder::default()
+    }
+
+    /// Ajoute un octet; retourne la touche dès qu'elle est complète
+    pub fn feed(&mut self, byte: u8) -> Option<Key> {
+        self.pending.push(byte);
+        let key = match self.pending[..] {
+            [0x1b] | [0x1b, b'['] | [0x1b, b'O'] => return None,
+            [0x1b, b'[', b'0'..=b'9'] => return None,
+            [0x1b, b'[' | b'O', code] => match code {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                b'C' => Key::Right,
+                b'D' => Key::Left,
+                b'H' => Key::Home,
+                b'F' => Key::End,
+                _ => Key::Other,
+            },
+            [0x1b, b'[', digit, b'~'] => match digit {
+                b'1' | b'7' => Key::Home,
+                b'4' | b'8' => Key::End,
+                b'3' => Key::Delete,
+                _ => Key::Other,
+            },
+            [0x1b, ..] => Key::Other,
+            [b'\r'] | [b'\n'] => Key::Enter,
+            [0x7f] | [0x08] => Key::Backspace,
+            [b'\t'] => Key::Tab,
+            [0x01] => Key::Home,
+            [0x05] => Key::End,
+            [0x03] => Key::Interrupt,
+            [0x04] => Key::EndOfInput,
+            [first, ..] if first < 0x20 => Key::Other,
+            [first, ..] => {
+                // Caractère UTF-8: attendre tous ses octets
+                let len = match first {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                if self.pending.len() < len {
+                    return None;
+                }
+                core::str::from_utf8(&self.pending)
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                    .map_or(Key::Other, Key::Char)
+            }
+            [] => return None,
+        };
+        self.pending.clear();
+        Some(key)
+    }
+}
+
+/// Historique des lignes saisies, de la plus ancienne à la plus récente
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: Vec<String>,
+    capacity: usize,
+}
+
+impl History {
+    /// Historique vide gardant au plus `capacity` lignes
+    pub fn new(capacity: usize) -> Self {
+        History { entries: Vec::new(), capacity }
+    }
+
+    /// Ajoute une ligne (ignorée si vide ou identique à la précédente)
+    pub fn push(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.entries.push(String::from(line));
+        if self.entries.len() > self.capacity {
+            let excess = self.entries.len() - self.capacity;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Lignes gardées, la plus récente en dernier
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+}
+
+/// Résultat d'une touche passée à `LineEditor::handle`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// La ligne est en cours de saisie
+    Pending,
+    /// Entrée: la ligne est terminée (et ajoutée à l'historique)
+    Line(String),
+    /// Ctrl-C: la ligne est abandonnée
+    Interrupted,
+    /// Ctrl-D sur une ligne vide
+    EndOfInput,
+    /// Tab: l'appelant calcule les candidats pour `completion_prefix`
+    /// et les passe à `complete`
+    Complete,
+}
+
+/// Ligne en cours d'édition avec son curseur et l'historique
+pub struct LineEditor {
+    line: Vec<char>,
+    cursor: usize,
+    history: History,
+    /// Position dans l'historique pendant Haut/Bas, et la ligne saisie avant
+    browsing: Option<(usize, Vec<char>)>,
+}
+
+impl LineEditor {
+    pub fn new(history: History) -> Self {
+        LineEditor { line: Vec::new(), cursor: 0, history, browsing: None }
+    }
+
+    /// Historique, pour l'enregistrer en fin de session
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Texte avant le curseur, à compléter avec `complete`
+    pub fn completion_prefix(&self) -> String {
+        self.line[..self.cursor].iter().collect()
+    }
+
+    /// Traite une touche et redessine la ligne après `prompt` si besoin
+    pub fn handle<O: Output>(&mut self, key: Key, prompt: &str, out: &mut O) -> Edit {
+        match key {
+            Key::Char(c) => {
+                self.line.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Enter => {
+                out.write_str("\n");
+                let line: String = self.line.iter().collect();
+                self.history.push(&line);
+                self.reset();
+                return Edit::Line(line);
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            Key::Delete if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            Key::EndOfInput if self.line.is_empty() => {
+                out.write_str("\n");
+                return Edit::EndOfInput;
+            }
+            Key::EndOfInput if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.line.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.line.len(),
+            Key::Up => self.browse_older(),
+            Key::Down => self.browse_newer(),
+            Key::Tab => return Edit::Complete,
+            Key::Interrupt => {
+                out.write_str("^C\n");
+                self.reset();
+                return Edit::Interrupted;
+            }
+            _ => return Edit::Pending,
+        }
+        self.redraw(prompt, out);
+        Edit::Pending
+    }
+
+    /// Applique les candidats de complétion du dernier mot avant le curseur
+    ///
+    /// Un seul candidat remplace le mot (suivi d'un espace sauf pour un
+    /// répertoire); sinon le préfixe commun est inséré, ou les candidats
+    /// sont listés s'il n'apporte rien.
+    pub fn complete<O: Output>(&mut self, candidates: &[String], prompt: &str, out: &mut O) {
+        let word_start = self.line[..self.cursor]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map_or(0, |i| i + 1);
+        let word_len = self.cursor - word_start;
+
+        let replacement = match candidates {
+            [] => return,
+            [single] if single.ends_with('/') => single.clone(),
+            [single] => format!("{} ", single),
+            _ => {
+                let prefix = common_prefix(candidates);
+                if prefix.chars().count() <= word_len {
+                    out.write_str("\n");
+                    out.write_line(&candidates.join("  "));
+                    self.redraw(prompt, out);
+                    return;
+                }
+                prefix
+            }
+        };
+
+        self.line.splice(word_start..self.cursor, replacement.chars());
+        self.cursor = word_start + replacement.chars().count();
+        self.redraw(prompt, out);
+    }
+
+    /// Remonte d'une ligne dans l'historique
+    fn browse_older(&mut self) {
+        let index = match &self.browsing {
+            Some((0, _)) => return,
+            Some((i, _)) => i - 1,
+            None if self.history.entries().is_empty() => return,
+            None => {
+                let draft = core::mem::take(&mut self.line);
+                self.browsing = Some((self.history.entries().len(), draft));
+                self.history.entries().len() - 1
+            }
+        };
+        self.show_history(index);
+    }
+
+    /// Redescend dans l'historique, jusqu'à la ligne saisie avant
+    fn browse_newer(&mut self) {
+        let index = match &self.browsing {
+            Some((i, _)) => i + 1,
+            None => return,
+        };
+        if index < self.history.entries().len() {
+            self.show_history(index);
+        } else if let Some((_, draft)) = self.browsing.take() {
+            self.line = draft;
+            self.cursor = self.line.len();
+        }
+    }
+
+    fn show_history(&mut self, index: usize) {
+        if let Some((i, _)) = &mut self.browsing {
+            *i = index;
+        }
+        self.line = self.history.entries()[index].chars().collect();
+        self.cursor = self.line.len();
+    }
+
+    fn reset(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.browsing = None;
+    }
+
+    /// Réécrit l'invite et la ligne, efface la fin et replace le curseur
+    fn redraw<O: Output>(&self, prompt: &str, out: &mut O) {
+        let line: String = self.line.iter().collect();
+        out.write_str(&format!("\r{}{}\x1b[K", prompt, line));
+        let back = self.line.len() - self.cursor;
+        if back > 0 {
+            out.write_str(&format!("\x1b[{}D", back));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::commands::StringOutput;
+
+    fn type_keys(editor: &mut LineEditor, keys: &[Key]) -> Edit {
+        let mut out = StringOutput::new();
+        let mut result = Edit::Pending;
+        for key in keys {
+            result = editor.handle(*key, "> ", &mut out);
+        }
+        result
+    }
+
+    fn chars(text: &str) -> Vec<Key> {
+        text.chars().map(Key::Char).collect()
+    }
+
+    #[test]
+    fn test_decode_keys() {
+        let mut decoder = KeyDecoder::new();
+        let mut decode = |bytes: &[u8]| -> Vec<Key> {
+            bytes.iter().filter_map(|b| decoder.feed(*b)).collect()
+        };
+
+        assert_eq!(decode(b"a\x1b[A\x1b[D\x1bOH"), [Key::Char('a'), Key::Up, Key::Left, Key::Home]);
+        assert_eq!(decode(b"\x1b[3~\x1b[4~\x7f\r"), [Key::Delete, Key::End, Key::Backspace, Key::Enter]);
+        assert_eq!(decode("é".as_bytes()), [Key::Char('é')]);
+        assert_eq!(decode(b"\x03\x04\t\x02"), [Key::Interrupt, Key::EndOfInput, Key::Tab, Key::Other]);
+    }
+
+    #[test]
+    fn test_edit_line() {
+        let mut editor = LineEditor::new(History::new(10));
+        let mut keys = chars("ct");
+        keys.extend([Key::Left, Key::Char('a'), Key::End, Key::Char('X'), Key::Backspace]);
+        keys.extend([Key::Home, Key::Delete, Key::Char('C'), Key::Enter]);
+        assert_eq!(type_keys(&mut editor, &keys), Edit::Line("Cat".into()));
+
+        type_keys(&mut editor, &chars("ls"));
+        assert_eq!(type_keys(&mut editor, &[Key::Interrupt]), Edit::Interrupted);
+        assert_eq!(type_keys(&mut editor, &[Key::EndOfInput]), Edit::EndOfInput);
+    }
+
+    #[test]
+    fn test_history_browsing() {
+        let mut history = History::new(2);
+        for line in ["ls", "ls", "", "cd DOCS", "pwd"] {
+            history.push(line);
+        }
+        assert_eq!(history.entries(), ["cd DOCS", "pwd"]);
+
+        let mut editor = LineEditor::new(history);
+        type_keys(&mut editor, &chars("ca"));
+        assert_eq!(type_keys(&mut editor, &[Key::Up, Key::Up, Key::Up, Key::Enter]), Edit::Line("cd DOCS".into()));
+
+        type_keys(&mut editor, &chars("ca"));
+        let keys = [Key::Up, Key::Down, Key::Down, Key::Char('t'), Key::Enter];
+        assert_eq!(type_keys(&mut editor, &keys), Edit::Line("cat".into()));
+        assert_eq!(editor.history().entries(), ["cd DOCS", "cat"]);
+    }
+
+    #[test]
+    fn test_completion() {
+        let mut editor = LineEditor::new(History::new(10));
+        let mut out = StringOutput::new();
+        type_keys(&mut editor, &chars("cat RE"));
+        assert_eq!(editor.completion_prefix(), "cat RE");
+
+        editor.complete(&["README.MD".into(), "README.TXT".into()], "> ", &mut out);
+        assert_eq!(editor.completion_prefix(), "cat README.");
+        editor.complete(&["README.MD".into(), "README.TXT".into()], "> ", &mut out);
+        assert!(out.buffer.contains("README.MD  README.TXT\n"));
+
+        editor.complete(&["README.MD".into()], "> ", &mut out);
+        assert_eq!(type_keys(&mut editor, &[Key::Enter]), Edit::Line("cat README.MD ".into()));
+    }
+}
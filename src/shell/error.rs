@@ -0,0 +1,76 @@
+//! Erreurs et statut de sortie des commandes shell
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
+use crate::fat32::FsError;
+use super::messages::{fill, Catalog};
+
+/// Erreur retournée par une commande shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellError {
+    /// Arguments ou options invalides (le message contient l'usage)
+    Usage(String),
+    /// Commande inconnue
+    UnknownCommand(String),
+    /// Fichier ou répertoire introuvable
+    NotFound(String),
+    /// Erreur du filesystem, avec le contexte de l'opération
+    Fs(&'static str, FsError),
+    /// Autre échec de la commande
+    Failed(String),
+    /// Erreur à une ligne d'un script
+    Script { line: usize, error: Box<ShellError> },
+}
+
+impl ShellError {
+    /// Code de sortie façon shell POSIX (2 = usage, 127 = commande inconnue)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ShellError::Usage(_) => 2,
+            ShellError::UnknownCommand(_) => 127,
+            ShellError::NotFound(_) | ShellError::Fs(..) | ShellError::Failed(_) => 1,
+            ShellError::Script { error, .. } => error.exit_code(),
+        }
+    }
+
+    /// Message affiché à l'utilisateur, avec les parties fixes traduites par `catalog`
+    ///
+    /// Les messages de `Usage`, `NotFound` et `Failed` sont déjà traduits par la commande.
+    pub fn describe(&self, catalog: &dyn Catalog) -> String {
+        match self {
+            ShellError::UnknownCommand(cmd) => fill(
+                catalog.translate("Unknown command: {}\nType 'help' for available commands"),
+                &[cmd],
+            ),
+            ShellError::Fs(context, e) => {
+                format!("{}: {}", catalog.translate(context), catalog.translate(&format!("{}", e)))
+            }
+            ShellError::Script { line, error } => {
+                format!("{}: {}", fill(catalog.translate("line {}"), &[line]), error.describe(catalog))
+            }
+            other => format!("{}", other),
+        }
+    }
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShellError::Usage(msg) => write!(f, "{}", msg),
+            ShellError::UnknownCommand(cmd) => {
+                write!(f, "Unknown command: {}\nType 'help' for available commands", cmd)
+            }
+            ShellError::NotFound(msg) => write!(f, "{}", msg),
+            ShellError::Fs(context, e) => write!(f, "{}: {}", context, e),
+            ShellError::Failed(msg) => write!(f, "{}", msg),
+            ShellError::Script { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+/// Résultat d'une commande shell
+pub type CommandResult = Result<(), ShellError>;
@@ -0,0 +1,153 @@
+//! Filtres de texte pour les pipes: grep et head lisent un fichier ou l'entrée du pipe
+
+extern crate alloc;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+
+use crate::fat32::Fat32;
+use super::commands::{Output, ShellState, path_error, try_find_file, usage_error};
+use super::error::{CommandResult, ShellError};
+use super::parser::Args;
+
+/// Nombre de lignes affichées par défaut par head
+const HEAD_LINES: usize = 10;
+
+/// Texte à filtrer: le fichier s'il est donné, sinon la sortie de la commande précédente
+pub(crate) fn read_text<'s>(
+    fs: &Fat32,
+    state: &ShellState,
+    file: Option<&str>,
+    stdin: Option<&'s str>,
+    command: &str,
+) -> Result<Cow<'s, str>, ShellError> {
+    let filename = match (file, stdin) {
+        (Some(f), _) => f,
+        (None, Some(text)) => return Ok(Cow::Borrowed(text)),
+        (None, None) => return Err(usage_error(state, None, command)),
+    };
+
+    let entry = try_find_file(fs, state, filename)
+        .map_err(|e| path_error(state, filename, &e, format!("{}: {}", filename, state.tr("File not found"))))?;
+    if entry.is_directory() {
+        return Err(ShellError::Failed(format!("{}: {}", filename, state.tr("Is a directory"))));
+    }
+
+    String::from_utf8(fs.read_file(&entry))
+        .map(Cow::Owned)
+        .map_err(|_| ShellError::Failed(format!("{}: {}", filename, state.tr("Binary file"))))
+}
+
+/// Commande grep - affiche les lignes contenant un motif (`[-i] [-v] [-n] [-c] <pattern> [file]`)
+///
+/// Le motif est une sous-chaîne littérale; `-i` (ou `set case off`) ignore la casse ASCII.
+pub fn cmd_grep<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    args: &Args,
+    stdin: Option<&str>,
+    out: &mut O,
+) -> CommandResult {
+    args.flags.check("ivnc", &[]).map_err(|_| usage_error(state, None, "grep"))?;
+    let (pattern, file) = match args.args[..] {
+        [p] => (p, None),
+        [p, f] => (p, Some(f)),
+        _ => return Err(usage_error(state, None, "grep")),
+    };
+
+    let text = read_text(fs, state, file, stdin, "grep")?;
+    let ignore_case = args.flags.has('i') || !state.config.case_sensitive;
+    let invert = args.flags.has('v');
+    let pattern = if ignore_case { pattern.to_ascii_lowercase() } else { String::from(pattern) };
+
+    let mut count = 0usize;
+    for (i, line) in text.lines().enumerate() {
+        let found = if ignore_case {
+            line.to_ascii_lowercase().contains(pattern.as_str())
+        } else {
+            line.contains(pattern.as_str())
+        };
+        if found == invert {
+            continue;
+        }
+
+        count += 1;
+        if args.flags.has('c') {
+            continue;
+        }
+        if args.flags.has('n') {
+            out.write_line(&format!("{}:{}", i + 1, line));
+        } else {
+            out.write_line(line);
+        }
+    }
+
+    if args.flags.has('c') {
+        out.write_line(&format!("{}", count));
+    }
+    Ok(())
+}
+
+/// Commande head - affiche les premières lignes (`[-n N] [file]`)
+pub fn cmd_head<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    args: &Args,
+    stdin: Option<&str>,
+    out: &mut O,
+) -> CommandResult {
+    args.flags.check("n", &[]).map_err(|_| usage_error(state, None, "head"))?;
+    let (count, file) = match (args.flags.has('n'), &args.args[..]) {
+        (false, []) => (Some(HEAD_LINES), None),
+        (false, [f]) => (Some(HEAD_LINES), Some(*f)),
+        (true, [n]) => (n.parse().ok(), None),
+        (true, [n, f]) => (n.parse().ok(), Some(*f)),
+        _ => return Err(usage_error(state, None, "head")),
+    };
+    let count = count.ok_or_else(|| usage_error(state, None, "head"))?;
+
+    let text = read_text(fs, state, file, stdin, "head")?;
+    for line in text.lines().take(count) {
+        out.write_line(line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{FsBuilder, MB};
+    use crate::shell::commands::StringOutput;
+
+    fn run(
+        cmd: fn(&Fat32, &ShellState, &Args, Option<&str>, &mut StringOutput) -> CommandResult,
+        args: &str,
+        stdin: &str,
+    ) -> Result<String, ShellError> {
+        let data = FsBuilder::new(MB).build().unwrap();
+        let fs = Fat32::new(&data).unwrap();
+        let state = ShellState::new(fs.root_cluster());
+        let mut out = StringOutput::new();
+        cmd(&fs, &state, &Args::parse(args), Some(stdin), &mut out)?;
+        Ok(out.buffer)
+    }
+
+    #[test]
+    fn test_grep_options() {
+        let text = "ERROR disk\ninfo ok\nerror net\n";
+        assert_eq!(run(cmd_grep, "ERROR", text).unwrap(), "ERROR disk\n");
+        assert_eq!(run(cmd_grep, "-i error", text).unwrap(), "ERROR disk\nerror net\n");
+        assert_eq!(run(cmd_grep, "-vn ERROR", text).unwrap(), "2:info ok\n3:error net\n");
+        assert_eq!(run(cmd_grep, "-ic error", text).unwrap(), "2\n");
+        assert!(matches!(run(cmd_grep, "", text), Err(ShellError::Usage(_))));
+    }
+
+    #[test]
+    fn test_head_lines() {
+        let text = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        assert_eq!(run(cmd_head, "", text).unwrap().lines().count(), 10);
+        assert_eq!(run(cmd_head, "-n 2", text).unwrap(), "1\n2\n");
+        assert!(matches!(run(cmd_head, "-n x", text), Err(ShellError::Usage(_))));
+        assert!(matches!(run(cmd_head, "-n 2 MISSING.TXT", text), Err(ShellError::NotFound(_))));
+    }
+}
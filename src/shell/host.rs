@@ -0,0 +1,405 @@
+//! Échanges avec le système de fichiers de l'hôte (feature `std`): export et import
+
+extern crate alloc;
+extern crate std;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::fat32::{DirEntry, Fat32, FsError};
+use crate::fat32::directory::FatDateTime;
+use super::commands::{Output, ShellState, normalize_path, resolve_to_cluster, restricted_check, try_find_file};
+use super::error::{CommandResult, ShellError};
+
+/// Bilan d'un export ou d'un import
+#[derive(Default)]
+struct Copied {
+    files: usize,
+    bytes: u64,
+}
+
+/// Commande export - copie un fichier, ou un répertoire récursivement, vers l'hôte
+///
+/// Si `target` est un répertoire existant, la copie y est créée sous son nom.
+/// Les dates de modification sont reportées quand l'hôte le permet, en
+/// lisant les dates FAT dans le fuseau du réglage `utc`.
+pub fn cmd_export<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    source: &str,
+    target: &str,
+    out: &mut O,
+) -> CommandResult {
+    restricted_check(state, "export")?;
+
+    let mut target = PathBuf::from(target);
+    let mut exported = Copied::default();
+
+    match resolve_to_cluster(fs, state, source) {
+        Some((cluster, true)) => {
+            let name = source.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+            if target.is_dir() && !name.is_empty() && name != "." && name != ".." {
+                target.push(name);
+            }
+            export_dir(fs, state, cluster, &target, &mut Vec::new(), &mut exported, out)?;
+        }
+        Some((_, false)) => {
+            let entry = match try_find_file(fs, state, source) {
+                Ok(entry) => entry,
+                Err(_) => return Err(not_found(state, source)),
+            };
+            if target.is_dir() {
+                let name = source.rsplit('/').next().unwrap_or(source);
+                target.push(name);
+            }
+            export_file(fs, state, &entry, &target, &mut exported, out)?;
+        }
+        None => return Err(not_found(state, source)),
+    }
+
+    if state.config.summaries() {
+        out.write_line(&state.tr_fill("Exported {} file(s), {} bytes", &[&exported.files, &exported.bytes]));
+    }
+    Ok(())
+}
+
+/// Crée `target` et y copie le contenu du répertoire `cluster`
+///
+/// `ancestors` contient les clusters des répertoires en cours de copie: un
+/// sous-répertoire qui y figure (boucle dans une image corrompue), ou une
+/// profondeur au-delà de `max_path_depth`, arrête l'export en erreur.
+fn export_dir<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    cluster: u32,
+    target: &Path,
+    ancestors: &mut Vec<u32>,
+    exported: &mut Copied,
+    out: &mut O,
+) -> CommandResult {
+    if ancestors.contains(&cluster) || ancestors.len() >= fs.limits().max_path_depth {
+        return Err(ShellError::Failed(state.tr_fill("{}: Directory loop or too deep", &[&target.display()])));
+    }
+    fs::create_dir_all(target).map_err(|e| host_error(target, e))?;
+    ancestors.push(cluster);
+
+    let mut dir_time = None;
+    for (entry, long_name) in fs.read_directory_with_lfn(cluster) {
+        if entry.is_dotdot() {
+            continue;
+        }
+        if entry.is_dot() {
+            dir_time = Some(entry.modified());
+            continue;
+        }
+
+        let name = match long_name {
+            Some(ln) if !ln.is_empty() => ln,
+            _ => entry.display_name(),
+        };
+        let path = match safe_join(target, &name) {
+            Some(path) => path,
+            None => return Err(ShellError::Failed(state.tr_fill("{}: Invalid name", &[&name]))),
+        };
+        if entry.is_directory() {
+            let child = if entry.cluster() == 0 { fs.root_cluster() } else { entry.cluster() };
+            export_dir(fs, state, child, &path, ancestors, exported, out)?;
+        } else {
+            export_file(fs, state, &entry, &path, exported, out)?;
+        }
+    }
+
+    ancestors.pop();
+
+    // Après les fichiers, sinon leur création changerait la date du répertoire
+    if let Some(time) = dir_time {
+        set_modified(state, target, time);
+    }
+    Ok(())
+}
+
+/// `target/name`, ou `None` si le nom lu dans l'image sortirait de `target`
+///
+/// Un nom vide, `.`, `..` ou contenant `/`, `\` ou NUL est refusé: joint
+/// tel quel, `../x` ou un chemin absolu écriraient hors du répertoire d'export.
+fn safe_join(target: &Path, name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']) {
+        return None;
+    }
+    let path = target.join(name);
+    (path.parent() == Some(target)).then_some(path)
+}
+
+/// Écrit le contenu d'un fichier de l'image dans `target`
+fn export_file<O: Output>(
+    fs: &Fat32,
+    state: &ShellState,
+    entry: &DirEntry,
+    target: &Path,
+    exported: &mut Copied,
+    out: &mut O,
+) -> CommandResult {
+    fs::write(target, fs.read_file_cow(entry)).map_err(|e| host_error(target, e))?;
+    set_modified(state, target, entry.modified());
+
+    exported.files += 1;
+    exported.bytes += entry.size as u64;
+    if state.config.verbose() {
+        out.write_line(&state.tr_fill("Exported {}", &[&target.display()]));
+    }
+    Ok(())
+}
+
+/// Commande import - écrit un fichier, ou un répertoire récursivement, de l'hôte dans l'image
+///
+/// Si `target` est un répertoire existant, la copie y est créée sous le nom
+/// de l'hôte; sinon les répertoires parents manquants sont créés. Les noms
+/// qui ne sont pas des noms courts 8.3 reçoivent une paire LFN/nom court.
+/// Seul `source` est suivi s'il est un lien symbolique: les liens trouvés
+/// dans l'arborescence sont signalés et ignorés.
+/// Les dates de modification des fichiers sont reprises dans le fuseau du réglage `utc`.
+pub fn cmd_import<O: Output>(
+    fs: &mut Fat32,
+    state: &ShellState,
+    source: &str,
+    target: &str,
+    out: &mut O,
+) -> CommandResult {
+    restricted_check(state, "import")?;
+
+    let source_path = Path::new(source);
+    let host_name = match source_path.canonicalize().ok().as_deref().and_then(Path::file_name) {
+        Some(name) => host_str(source_path, name)?,
+        None => return Err(ShellError::Failed(state.tr_fill("{}: Invalid name", &[&source]))),
+    };
+
+    let components = normalize_path(state, target);
+    let (parent, name) = match resolve_to_cluster(fs, state, target) {
+        Some((_, true)) => (components.as_str(), host_name),
+        _ => match components.split_last() {
+            Some((parent, name)) => (parent, String::from(name)),
+            None => ("", host_name),
+        },
+    };
+    let dir_cluster = make_dirs(fs, state, parent)?;
+
+    let mut imported = Copied::default();
+    import_path(fs, state, source_path, dir_cluster, &name, &mut imported, out)?;
+
+    if state.config.summaries() {
+        out.write_line(&state.tr_fill("Imported {} file(s), {} bytes", &[&imported.files, &imported.bytes]));
+    }
+    Ok(())
+}
+
+/// Descend dans `components` (joints par `/`) depuis la racine du shell en créant les répertoires manquants
+fn make_dirs(fs: &mut Fat32, state: &ShellState, components: &str) -> Result<u32, ShellError> {
+    let mut cluster = state.root_cluster;
+    for name in components.split('/').filter(|c| !c.is_empty()) {
+        cluster = child_dir(fs, state, cluster, name)?;
+    }
+    Ok(cluster)
+}
+
+/// Cluster du sous-répertoire `name`, créé s'il n'existe pas
+fn child_dir(fs: &mut Fat32, state: &ShellState, parent: u32, name: &str) -> Result<u32, ShellError> {
+    let entry = match fs.find_entry(parent, name) {
+        Some(entry) if entry.is_directory() => entry,
+        Some(_) => return Err(ShellError::Failed(state.tr_fill("{}: Not a directory", &[&name]))),
+        None => fs.create_dir(parent, name).map_err(|e| fs_error(state, name, e))?,
+    };
+    // Un cluster 0 désigne la racine du volume
+    Ok(if entry.cluster() == 0 { fs.root_cluster() } else { entry.cluster() })
+}
+
+/// Copie le fichier ou répertoire hôte `source` sous le nom `name` de `dir_cluster`
+fn import_path<O: Output>(
+    fs: &mut Fat32,
+    state: &ShellState,
+    source: &Path,
+    dir_cluster: u32,
+    name: &str,
+    imported: &mut Copied,
+    out: &mut O,
+) -> CommandResult {
+    let metadata = fs::metadata(source).map_err(|e| host_error(source, e))?;
+
+    if metadata.is_dir() {
+        let cluster = child_dir(fs, state, dir_cluster, name)?;
+        let mut children: Vec<_> = fs::read_dir(source)
+            .and_then(|entries| entries.map(|e| e.map(|e| e.path())).collect())
+            .map_err(|e| host_error(source, e))?;
+        children.sort();
+
+        for child in children {
+            let child_name = match child.file_name() {
+                Some(n) => host_str(&child, n)?,
+                None => continue,
+            };
+            // Un lien vers un ancêtre ferait recopier l'arborescence sans fin
+            if fs::symlink_metadata(&child).map_err(|e| host_error(&child, e))?.is_symlink() {
+                if state.config.summaries() {
+                    out.write_line(&state.tr_fill("Skipped symbolic link {}", &[&child.display()]));
+                }
+                continue;
+            }
+            import_path(fs, state, &child, cluster, &child_name, imported, out)?;
+        }
+    } else if metadata.is_file() {
+        let data = fs::read(source).map_err(|e| host_error(source, e))?;
+        fs.write_file(dir_cluster, name, &data).map_err(|e| fs_error(state, name, e))?;
+        if let Some(time) = fat_time(state, &metadata) {
+            fs.set_timestamps(dir_cluster, name, time).map_err(|e| fs_error(state, name, e))?;
+        }
+
+        imported.files += 1;
+        imported.bytes += data.len() as u64;
+        if state.config.verbose() {
+            out.write_line(&state.tr_fill("Imported {}", &[&source.display()]));
+        }
+    }
+    Ok(())
+}
+
+/// Nom hôte en UTF-8 (les noms longs FAT sont en UTF-16)
+fn host_str(path: &Path, name: &std::ffi::OsStr) -> Result<String, ShellError> {
+    match name.to_str() {
+        Some(name) => Ok(String::from(name)),
+        None => Err(ShellError::Failed(alloc::format!("{}: not valid UTF-8", path.display()))),
+    }
+}
+
+fn fs_error(state: &ShellState, name: &str, error: FsError) -> ShellError {
+    ShellError::Failed(alloc::format!("{}: {}", name, state.tr(&alloc::format!("{}", error))))
+}
+
+/// Reporte une date FAT sur un fichier ou répertoire de l'hôte (erreurs ignorées)
+fn set_modified(state: &ShellState, path: &Path, time: FatDateTime) {
+    if let (Some(time), Ok(file)) = (system_time(time, state.config.utc_offset), File::open(path)) {
+        let _ = file.set_modified(time);
+    }
+}
+
+/// Convertit une date FAT, heure locale décalée de `utc_offset` secondes, en `SystemTime`
+fn system_time(time: FatDateTime, utc_offset: i32) -> Option<SystemTime> {
+    let seconds = time.to_unix_seconds(utc_offset)?;
+    u64::try_from(seconds).ok().map(|s| SystemTime::UNIX_EPOCH + Duration::from_secs(s))
+}
+
+/// Date de modification d'un fichier hôte en heure locale FAT (réglage `utc`)
+fn fat_time(state: &ShellState, metadata: &fs::Metadata) -> Option<FatDateTime> {
+    let since_epoch = metadata.modified().ok()?.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+    Some(FatDateTime::from_unix_local(since_epoch.as_secs(), state.config.utc_offset))
+}
+
+fn not_found(state: &ShellState, path: &str) -> ShellError {
+    ShellError::NotFound(state.tr_fill("File not found: {}", &[&path]))
+}
+
+fn host_error(path: &Path, error: std::io::Error) -> ShellError {
+    ShellError::Failed(alloc::format!("{}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{FsBuilder, MB};
+
+    #[test]
+    fn test_system_time() {
+        let epoch = |y, mo, d, h, mi, s| FatDateTime { year: y, month: mo, day: d, hour: h, minute: mi, second: s };
+        let secs = |t, offset| system_time(t, offset).unwrap().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+
+        assert_eq!(secs(epoch(1980, 1, 1, 0, 0, 0), 0), 315_532_800);
+        assert_eq!(secs(epoch(2024, 2, 29, 12, 30, 10), 0), 1_709_209_810);
+        // 14:30 à UTC+2 est 12:30 UTC
+        assert_eq!(secs(epoch(2024, 2, 29, 14, 30, 10), 7200), 1_709_209_810);
+        assert_eq!(system_time(epoch(1980, 0, 0, 0, 0, 0), 0), None);
+    }
+
+    #[test]
+    fn test_export_tree() {
+        let mut data = FsBuilder::new(MB).build().unwrap();
+        let mut fs = Fat32::new_mut(&mut data).unwrap();
+        let root = fs.root_cluster();
+        fs.write_file(root, "hello.txt", b"hello").unwrap();
+
+        let dir = std::env::temp_dir().join(alloc::format!("fat32-export-{}", std::process::id()));
+        let state = ShellState::new(root);
+        let mut out = crate::shell::commands::StringOutput::new();
+        cmd_export(&fs, &state, "/", dir.to_str().unwrap(), &mut out).unwrap();
+
+        assert_eq!(fs::read(dir.join("hello.txt")).unwrap(), b"hello");
+        assert_eq!(out.buffer, "Exported 1 file(s), 5 bytes\n");
+        assert!(matches!(cmd_export(&fs, &state, "MISSING", "x", &mut out), Err(ShellError::NotFound(_))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_safe_join() {
+        let target = Path::new("/tmp/export");
+        assert_eq!(safe_join(target, "a b.txt"), Some(PathBuf::from("/tmp/export/a b.txt")));
+        for name in ["", ".", "..", "../../x", "/etc/cron.d/x", "a\\b", "a\0b"] {
+            assert_eq!(safe_join(target, name), None, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_export_loop() {
+        let mut data = FsBuilder::new(MB).dir("SUB", |d| d).build().unwrap();
+        let fs = Fat32::new(&data).unwrap();
+        let root = fs.root_cluster();
+        let short = fs.locate_entry(root, "SUB").unwrap().short_offset() as usize;
+        // Cluster 0: le sous-répertoire désigne la racine qui le contient
+        data[short + 20..short + 22].fill(0);
+        data[short + 26..short + 28].fill(0);
+
+        let fs = Fat32::new(&data).unwrap();
+        let dir = std::env::temp_dir().join(alloc::format!("fat32-export-loop-{}", std::process::id()));
+        let state = ShellState::new(root);
+        let mut out = crate::shell::commands::StringOutput::new();
+        assert!(matches!(cmd_export(&fs, &state, "/", dir.to_str().unwrap(), &mut out), Err(ShellError::Failed(_))));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_import_tree() {
+        let host = std::env::temp_dir().join(alloc::format!("fat32-import-{}", std::process::id()));
+        fs::create_dir_all(host.join("My Docs")).unwrap();
+        fs::write(host.join("My Docs/notes.txt"), b"notes").unwrap();
+        fs::write(host.join("README.MD"), b"readme").unwrap();
+
+        let mut data = FsBuilder::new(MB).build().unwrap();
+        let mut fs = Fat32::new_mut(&mut data).unwrap();
+        let root = fs.root_cluster();
+        let state = ShellState::new(root);
+        let mut out = crate::shell::commands::StringOutput::new();
+
+        cmd_import(&mut fs, &state, host.to_str().unwrap(), "/backup/2024", &mut out).unwrap();
+        assert_eq!(out.buffer, "Imported 2 file(s), 11 bytes\n");
+        let notes = fs.resolve_path("/backup/2024/My Docs/notes.txt", root).unwrap();
+        assert_eq!(fs.read_file(&notes), b"notes");
+
+        // Dans un répertoire existant, la copie garde le nom de l'hôte
+        let file = host.join("README.MD");
+        cmd_import(&mut fs, &state, file.to_str().unwrap(), "/backup", &mut out).unwrap();
+        assert!(fs.resolve_path("/backup/README.MD", root).is_some());
+        assert!(cmd_import(&mut fs, &state, file.to_str().unwrap(), "/backup/README.MD/x", &mut out).is_err());
+
+        // Un lien vers un ancêtre n'est pas suivi
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&host, host.join("My Docs/loop")).unwrap();
+            let mut out = crate::shell::commands::StringOutput::new();
+            cmd_import(&mut fs, &state, host.to_str().unwrap(), "/linked", &mut out).unwrap();
+            assert!(out.buffer.starts_with("Skipped symbolic link "));
+            assert!(out.buffer.ends_with("Imported 2 file(s), 11 bytes\n"));
+            assert!(fs.resolve_path("/linked/My Docs/loop", root).is_none());
+        }
+        fs::remove_dir_all(host).unwrap();
+    }
+}
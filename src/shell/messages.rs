@@ -0,0 +1,105 @@
+//! Catalogue des messages du shell, pour traduire sans modifier les commandes
+//!
+//! Les messages sont identifiés par leur texte anglais (comme gettext): un
+//! catalogue reçoit ce texte et retourne sa traduction, ou le texte lui-même
+//! s'il ne le connaît pas. Les arguments sont notés `{}` (dans l'ordre) ou
+//! `{0}`, `{1}`... pour qu'une traduction puisse les réordonner.
+
+extern crate alloc;
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use super::registry::CommandInfo;
+
+/// Catalogue de traductions; l'implémentation par défaut garde l'anglais
+pub trait Catalog {
+    /// Traduction du message anglais `message`
+    fn translate<'a>(&'a self, message: &'a str) -> &'a str {
+        message
+    }
+
+    /// Description courte d'une commande dans `help`
+    fn summary(&self, info: &'static CommandInfo) -> &str {
+        self.translate(info.summary)
+    }
+}
+
+/// Catalogue anglais (aucune traduction)
+pub struct English;
+
+impl Catalog for English {}
+
+/// Remplace les `{}` et `{N}` de `template` par `args`
+///
+/// Un indice hors limites ou une accolade isolée est recopié tel quel.
+pub fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut text = String::new();
+    let mut next = 0;
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let close = match after.find('}') {
+            Some(close) => close,
+            None => {
+                rest = &rest[open..];
+                break;
+            }
+        };
+
+        let spec = &after[..close];
+        let index = if spec.is_empty() {
+            next += 1;
+            Some(next - 1)
+        } else {
+            spec.parse::<usize>().ok()
+        };
+
+        match index.and_then(|i| args.get(i)) {
+            Some(arg) => {
+                let _ = write!(text, "{}", arg);
+            }
+            None => text.push_str(&rest[open..open + close + 2]),
+        }
+        rest = &after[close + 1..];
+    }
+
+    text.push_str(rest);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct French;
+
+    impl Catalog for French {
+        fn translate<'a>(&'a self, message: &'a str) -> &'a str {
+            match message {
+                "File not found" => "Fichier introuvable",
+                "{} file(s)  {} bytes" => "{1} octets dans {0} fichier(s)",
+                other => other,
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_placeholders() {
+        assert_eq!(fill("{} of {}", &[&1, &"two"]), "1 of two");
+        assert_eq!(fill("{1} before {0}", &[&"a", &"b"]), "b before a");
+        assert_eq!(fill("{} and {5} {", &[&7]), "7 and {5} {");
+        assert_eq!(fill("no args", &[]), "no args");
+    }
+
+    #[test]
+    fn test_catalog_translation() {
+        assert_eq!(English.translate("File not found"), "File not found");
+        assert_eq!(French.translate("File not found"), "Fichier introuvable");
+        assert_eq!(French.translate("Not a directory"), "Not a directory");
+
+        let template = French.translate("{} file(s)  {} bytes");
+        assert_eq!(fill(template, &[&3, &120]), "120 octets dans 3 fichier(s)");
+    }
+}
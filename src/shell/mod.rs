@@ -2,14 +2,38 @@
 
 pub mod parser;
 pub mod commands;
+pub mod error;
+pub mod registry;
+pub mod complete;
+pub mod pager;
+pub mod color;
+pub mod script;
+pub mod filter;
+pub mod config;
+pub mod messages;
+pub mod editor;
+pub mod path;
+#[cfg(feature = "std")]
+pub mod host;
 
-pub use parser::{Command, parse_command};
-pub use commands::{ShellState, Output, cmd_ls, cmd_cd, cmd_cat, cmd_more, cmd_pwd, cmd_help};
+pub use parser::{Command, Args, Flags, parse_command, split_command, expand_alias, split_redirect, split_pipeline, split_sequence};
+pub use error::{ShellError, CommandResult};
+pub use registry::{CommandInfo, COMMANDS};
+pub use complete::{complete, common_prefix};
+pub use script::{execute_script, cmd_run};
+pub use filter::{cmd_grep, cmd_head};
+pub use config::{ShellConfig, Verbosity};
+pub use messages::{Catalog, English};
+pub use editor::{Key, KeyDecoder, History, Edit, LineEditor};
+pub use path::ShellPath;
+#[cfg(feature = "std")]
+pub use host::{cmd_export, cmd_import};
+pub use commands::{ShellState, SavedDir, Output, TerminalInfo, write_error, Input, NoInput, cmd_ls, cmd_cd, cmd_cat, cmd_more, cmd_chain, cmd_dumpcluster, cmd_undelete, cmd_format, cmd_crc32, cmd_sha256, cmd_cmp, cmd_rename, cmd_alias, cmd_unalias, cmd_set, cmd_pushd, cmd_popd, cmd_dirs, cmd_pwd, cmd_info, cmd_help};
 
 use crate::fat32::Fat32;
 
 /// Boucle principale du shell interactif
-pub fn run_shell<O, F>(fs: &Fat32, out: &mut O, mut get_input: F)
+pub fn run_shell<O, F>(fs: &mut Fat32, out: &mut O, mut get_input: F)
 where
     O: Output,
     F: FnMut() -> Option<alloc::string::String>,
@@ -19,7 +43,7 @@ where
 
     let mut state = ShellState::new(fs.root_cluster());
 
-    out.write_line("FAT32 Shell - Type 'help' for commands");
+    out.write_line(state.tr("FAT32 Shell - Type 'help' for commands"));
     out.write_line("");
 
     loop {
@@ -30,68 +54,247 @@ where
             None => break,
         };
 
-        match parse_command(&input) {
-            Command::Ls(path) => cmd_ls(fs, &state, path, out),
-            Command::Cd(path) => cmd_cd(fs, &mut state, path, out),
-            Command::Cat(file) => cmd_cat(fs, &state, file, out),
-            Command::More(file) => cmd_more(fs, &state, file, out, 20),
-            Command::Pwd => cmd_pwd(&state, out),
-            Command::Help => cmd_help(out),
-            Command::Exit => {
-                out.write_line("Goodbye!");
+        // Les allocations temporaires de la commande sont rendues ensuite
+        // (refusé sans risque si la commande a gardé quelque chose)
+        let arena = crate::allocator::checkpoint();
+
+        match execute_command(fs, &mut state, &input, out, &mut LineInput { next_line: &mut get_input, pending: None }) {
+            Ok(true) => {}
+            Ok(false) => {
+                out.write_line(state.tr("Goodbye!"));
                 break;
             }
-            Command::Unknown(cmd) => {
-                out.write_line(&format!("Unknown command: {}", cmd));
-                out.write_line("Type 'help' for available commands");
-            }
-            Command::Empty => {}
+            Err(e) => write_error(&state, &e, out),
         }
 
         out.write_line("");
+        if let Some(arena) = arena {
+            let _ = arena.rollback();
+        }
     }
 }
 
-/// Exécute une seule commande (pour usage non-interactif)
-pub fn execute_command<O: Output>(
-    fs: &Fat32,
+/// Touches lues ligne par ligne: le premier caractère de chaque ligne (Entrée si vide)
+///
+/// Le reste de la ligne est conservé pour `read_line` (ex: `/motif` dans more).
+struct LineInput<'f, F> {
+    next_line: &'f mut F,
+    pending: Option<alloc::string::String>,
+}
+
+impl<F: FnMut() -> Option<alloc::string::String>> Input for LineInput<'_, F> {
+    fn read_key(&mut self) -> Option<char> {
+        let line = (self.next_line)()?;
+        let line = line.trim_end_matches(['\r', '\n']);
+        let mut chars = line.chars();
+        let key = chars.next().unwrap_or('\n');
+        self.pending = Some(chars.as_str().into());
+        Some(key)
+    }
+
+    fn read_line(&mut self) -> Option<alloc::string::String> {
+        match self.pending.take() {
+            Some(rest) => Some(rest),
+            None => (self.next_line)().map(|l| l.trim_end_matches(['\r', '\n']).into()),
+        }
+    }
+}
+
+/// Sortie capturée en mémoire pour une redirection; les erreurs restent sur la sortie d'origine
+///
+/// `errors` est un objet trait pour que les redirections imbriquées (via `run`)
+/// ne créent pas un nouveau type à chaque niveau.
+struct Capture<'o> {
+    buffer: alloc::string::String,
+    errors: &'o mut dyn Output,
+}
+
+impl Output for Capture<'_> {
+    fn write_str(&mut self, s: &str) {
+        self.buffer.push_str(s);
+    }
+
+    fn write_err_line(&mut self, s: &str) {
+        self.errors.write_err_line(s);
+    }
+}
+
+/// Exécute une seule ligne de commande
+///
+/// Retourne `Ok(false)` si la commande demande de quitter le shell; les erreurs
+/// ne sont pas affichées, c'est à l'appelant de les signaler. Passer `NoInput`
+/// pour un usage non interactif. `a | b` passe la sortie de `a` en entrée de
+/// `b`; `commande > fichier` écrit la sortie dans un fichier de l'image (créé
+/// ou remplacé).
+pub fn execute_command<O: Output, I: Input>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    line: &str,
+    out: &mut O,
+    input: &mut I,
+) -> Result<bool, ShellError> {
+    let line = expand_alias(line, &state.aliases);
+
+    match split_redirect(&line) {
+        (command, Some(target)) => redirect(fs, state, command, target, out),
+        (command, None) => pipeline(fs, state, command, out, input),
+    }
+}
+
+/// Exécute `command` en capturant sa sortie dans le fichier `target`
+fn redirect<O: Output>(
+    fs: &mut Fat32,
     state: &mut ShellState,
-    input: &str,
+    command: &str,
+    target: &str,
     out: &mut O,
-) -> bool {
+) -> Result<bool, ShellError> {
     extern crate alloc;
-    use alloc::format;
 
-    match parse_command(input) {
-        Command::Ls(path) => {
-            cmd_ls(fs, state, path, out);
-            true
-        }
-        Command::Cd(path) => {
-            cmd_cd(fs, state, path, out);
-            true
+    let target = match Args::parse(target) {
+        args if args.len() == 1 && args.flags.is_empty() => args.args[0],
+        _ => return Err(ShellError::Usage(state.tr_fill("Usage: {}", &[&"command > file"]))),
+    };
+    let (dir_cluster, name) = commands::resolve_parent(fs, state, target)?;
+
+    let (output, result) = capture(fs, state, out, |fs, state, capture| {
+        pipeline(fs, state, command, capture, &mut NoInput)
+    });
+
+    fs.write_file(dir_cluster, name, output.as_bytes())
+        .map_err(|e| ShellError::Fs("Cannot write output", e))?;
+    if state.config.verbose() {
+        out.write_line(&state.tr_fill("Wrote {} bytes to {}", &[&output.len(), &target]));
+    }
+    result
+}
+
+/// Exécute les étapes de `a | b | c`, la sortie de chacune devenant l'entrée de la suivante
+///
+/// Les alias sont développés pour chaque étape; une étape en échec arrête le pipe.
+fn pipeline<O: Output, I: Input>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    line: &str,
+    out: &mut O,
+    input: &mut I,
+) -> Result<bool, ShellError> {
+    extern crate alloc;
+
+    let stages = split_pipeline(line);
+    if stages.len() > 1 && stages.iter().any(|s| s.is_empty()) {
+        return Err(ShellError::Usage(state.tr_fill("Usage: {}", &[&"command | command"])));
+    }
+
+    let mut piped: Option<alloc::string::String> = None;
+    for (i, stage) in stages.iter().enumerate() {
+        // La première étape a déjà été développée par execute_command
+        let stage = if i == 0 { (*stage).into() } else { expand_alias(stage, &state.aliases) };
+
+        if i + 1 == stages.len() {
+            return dispatch(fs, state, &stage, piped.as_deref(), out, input);
         }
-        Command::Cat(file) => {
-            cmd_cat(fs, state, file, out);
-            true
+
+        let stdin = piped.take();
+        let (output, result) = capture(fs, state, out, |fs, state, capture| {
+            dispatch(fs, state, &stage, stdin.as_deref(), capture, &mut NoInput)
+        });
+        if !result? {
+            return Ok(false);
         }
-        Command::More(file) => {
-            cmd_more(fs, state, file, out, 20);
-            true
+        piped = Some(output);
+    }
+
+    Ok(true)
+}
+
+/// Exécute `run` en capturant sa sortie normale; les erreurs vont sur `out`
+fn capture<O, F>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    out: &mut O,
+    run: F,
+) -> (alloc::string::String, Result<bool, ShellError>)
+where
+    O: Output,
+    F: FnOnce(&mut Fat32, &mut ShellState, &mut Capture) -> Result<bool, ShellError>,
+{
+    extern crate alloc;
+
+    // Pas de séquences ANSI dans les fichiers ni dans les pipes
+    let color = core::mem::replace(&mut state.config.color, false);
+    let mut capture = Capture { buffer: alloc::string::String::new(), errors: out };
+    let result = run(fs, state, &mut capture);
+    state.config.color = color;
+
+    (capture.buffer, result)
+}
+
+/// Exécute une commande déjà développée (alias), sans pipe ni redirection
+///
+/// `stdin` est la sortie de l'étape précédente d'un pipe, lue par cat, more,
+/// grep et head quand aucun fichier n'est donné.
+fn dispatch<O: Output, I: Input>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    line: &str,
+    stdin: Option<&str>,
+    out: &mut O,
+    input: &mut I,
+) -> Result<bool, ShellError> {
+    extern crate alloc;
+
+    match parse_command(line) {
+        Command::Ls(args) => cmd_ls(fs, state, &args, out)?,
+        Command::Cd(path) => cmd_cd(fs, state, path, out)?,
+        Command::Cat(args) => cmd_cat(fs, state, &args, stdin, out)?,
+        Command::More(Some(file)) => {
+            let lines = pager::page_lines(&state.config, out.terminal());
+            cmd_more(fs, state, file, out, input, lines)?
         }
-        Command::Pwd => {
-            cmd_pwd(state, out);
-            true
+        Command::More(None) => match stdin {
+            Some(text) => pager::page(state.catalog, text, out, input, pager::page_lines(&state.config, out.terminal())),
+            None => return Err(commands::usage_error(state, None, "more")),
+        },
+        Command::Chain(file) => cmd_chain(fs, state, file, out)?,
+        Command::Grep(args) => cmd_grep(fs, state, &args, stdin, out)?,
+        Command::Head(args) => cmd_head(fs, state, &args, stdin, out)?,
+        Command::DumpCluster(args) => cmd_dumpcluster(fs, state, &args, out)?,
+        Command::Undelete(args) => cmd_undelete(fs, state, &args, out)?,
+        Command::Format(args) => cmd_format(fs, state, &args, out)?,
+        Command::Crc32(file) => cmd_crc32(fs, state, file, out)?,
+        Command::Sha256(file) => cmd_sha256(fs, state, file, out)?,
+        Command::Cmp(a, b) => cmd_cmp(fs, state, a, b, out)?,
+        Command::Rename(old, new) => cmd_rename(fs, state, old, new, out)?,
+        #[cfg(feature = "std")]
+        Command::Export(source, target) => cmd_export(fs, state, source, target, out)?,
+        #[cfg(feature = "std")]
+        Command::Import(source, target) => cmd_import(fs, state, source, target, out)?,
+        #[cfg(not(feature = "std"))]
+        Command::Export(..) => {
+            return Err(ShellError::Failed(state.tr("export: requires the std feature").into()));
         }
-        Command::Help => {
-            cmd_help(out);
-            true
+        #[cfg(not(feature = "std"))]
+        Command::Import(..) => {
+            return Err(ShellError::Failed(state.tr("import: requires the std feature").into()));
         }
-        Command::Exit => false,
+        Command::Alias(args) => cmd_alias(state, args, out)?,
+        Command::Unalias(name) => cmd_unalias(state, name, out)?,
+        Command::Set(args) => cmd_set(state, &args, out)?,
+        Command::Pushd(dir) => cmd_pushd(fs, state, dir, out)?,
+        Command::Popd => cmd_popd(state, out)?,
+        Command::Dirs => cmd_dirs(state, out)?,
+        Command::Run(args) => cmd_run(fs, state, &args, out, input)?,
+        Command::Pwd => cmd_pwd(state, out)?,
+        Command::Info => cmd_info(fs, state, out)?,
+        Command::Help(topic) => cmd_help(state, topic, out)?,
+        Command::Exit => return Ok(false),
         Command::Unknown(cmd) => {
-            out.write_line(&format!("Unknown command: {}", cmd));
-            true
+            return Err(ShellError::UnknownCommand(alloc::string::String::from(cmd)));
         }
-        Command::Empty => true,
+        Command::Usage(name) => return Err(commands::usage_error(state, None, name)),
+        Command::Empty => {}
     }
+
+    Ok(true)
 }
@@ -0,0 +1,217 @@
+//! Pager de la commande more: pages avant/arrière, recherche et position
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::commands::{Input, Output, TerminalInfo};
+use super::config::{ShellConfig, DEFAULT_PAGE_LINES};
+use super::messages::{fill, Catalog};
+
+/// Invite affichée tant qu'il reste des lignes
+const MORE_PROMPT: &str = "-- More (space, enter, b, /pattern, =, q) --";
+/// Invite affichée en fin de fichier
+const END_PROMPT: &str = "-- (END) (b, /pattern, =, q) --";
+
+/// Lignes par page: `set pagesize`, sinon la hauteur du terminal moins la ligne d'invite
+pub fn page_lines(config: &ShellConfig, terminal: Option<TerminalInfo>) -> usize {
+    match (config.page_lines, terminal) {
+        (Some(n), _) => n,
+        (None, Some(t)) => (t.rows as usize).saturating_sub(1).max(1),
+        (None, None) => DEFAULT_PAGE_LINES,
+    }
+}
+
+/// Index des débuts de ligne d'un texte (calculé une seule fois)
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    /// Indexe les lignes de `text` (mêmes lignes que `str::lines`)
+    pub fn new(text: &str) -> Self {
+        let mut starts = Vec::new();
+        if !text.is_empty() {
+            starts.push(0);
+        }
+        for (i, b) in text.bytes().enumerate() {
+            if b == b'\n' && i + 1 < text.len() {
+                starts.push(i + 1);
+            }
+        }
+        LineIndex { starts }
+    }
+
+    /// Nombre de lignes
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Vrai si le texte ne contient aucune ligne
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Ligne `n` (sans fin de ligne)
+    pub fn line<'t>(&self, text: &'t str, n: usize) -> &'t str {
+        let start = self.starts[n];
+        let end = self.starts.get(n + 1).copied().unwrap_or(text.len());
+        let line = &text[start..end];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    /// Première ligne à partir de `from` contenant `pattern`
+    pub fn find(&self, text: &str, from: usize, pattern: &str) -> Option<usize> {
+        (from..self.len()).find(|&n| self.line(text, n).contains(pattern))
+    }
+}
+
+/// Affiche `text` page par page en lisant les touches sur `input`
+///
+/// Espace: page suivante, Entrée: une ligne, `b`: page précédente,
+/// `/motif`: recherche vers l'avant (motif vide = dernier motif), `=`: position,
+/// `q`: quitter. Sans entrée interactive, tout le texte est affiché.
+pub fn page<O: Output, I: Input>(
+    catalog: &dyn Catalog,
+    text: &str,
+    out: &mut O,
+    input: &mut I,
+    lines_per_page: usize,
+) {
+    let index = LineIndex::new(text);
+    let total = index.len();
+    let page = lines_per_page.max(1);
+
+    if !input.is_interactive() || total <= page {
+        for n in 0..total {
+            out.write_line(index.line(text, n));
+        }
+        return;
+    }
+
+    let draw = |out: &mut O, top: usize| -> usize {
+        let bottom = (top + page).min(total);
+        for n in top..bottom {
+            out.write_line(index.line(text, n));
+        }
+        bottom
+    };
+
+    let mut top = 0;
+    let mut bottom = draw(out, top);
+    let mut last_pattern = String::new();
+
+    loop {
+        let at_end = bottom >= total;
+        let prompt = catalog.translate(if at_end { END_PROMPT } else { MORE_PROMPT });
+        out.write_str(prompt);
+        let key = input.read_key();
+        clear_prompt(out, prompt.chars().count());
+
+        match key {
+            Some(' ') | Some('\n') | Some('\r') if at_end => break,
+            Some(' ') => {
+                top = bottom;
+                bottom = draw(out, top);
+            }
+            Some('\n') | Some('\r') => {
+                out.write_line(index.line(text, bottom));
+                bottom += 1;
+                top = bottom.saturating_sub(page);
+            }
+            Some('b') | Some('B') => {
+                top = top.saturating_sub(page);
+                bottom = draw(out, top);
+            }
+            Some('/') => {
+                let pattern = input.read_line().unwrap_or_default();
+                if !pattern.is_empty() {
+                    last_pattern = pattern;
+                }
+                if last_pattern.is_empty() {
+                    continue;
+                }
+                match index.find(text, top + 1, &last_pattern) {
+                    Some(n) => {
+                        top = n;
+                        bottom = draw(out, top);
+                    }
+                    None => out.write_line(&fill(catalog.translate("Pattern not found: {}"), &[&last_pattern])),
+                }
+            }
+            Some('=') => {
+                out.write_line(&fill(catalog.translate("lines {}-{} of {} ({}%)"),
+                    &[&(top + 1), &bottom, &total, &(bottom * 100 / total)]));
+            }
+            Some('q') | Some('Q') | None => break,
+            Some(_) => {}
+        }
+    }
+}
+
+/// Efface l'invite sur la ligne courante
+fn clear_prompt<O: Output>(out: &mut O, len: usize) {
+    out.write_str("\r");
+    for _ in 0..len {
+        out.write_str(" ");
+    }
+    out.write_str("\r");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::commands::StringOutput;
+    use crate::shell::messages::English;
+
+    struct Keys(&'static str);
+
+    impl Input for Keys {
+        fn read_key(&mut self) -> Option<char> {
+            let c = self.0.chars().next()?;
+            self.0 = &self.0[c.len_utf8()..];
+            Some(c)
+        }
+    }
+
+    /// Lignes visibles une fois les invites effacées
+    fn visible(out: &StringOutput) -> Vec<&str> {
+        out.buffer.lines()
+            .filter_map(|l| l.rsplit('\r').next())
+            .filter(|l| !l.trim().is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn test_line_index() {
+        let text = "one\r\ntwo\n\nfour";
+        let index = LineIndex::new(text);
+        assert_eq!(index.len(), text.lines().count());
+        assert_eq!(index.line(text, 0), "one");
+        assert_eq!(index.line(text, 2), "");
+        assert_eq!(index.line(text, 3), "four");
+        assert_eq!(index.find(text, 1, "o"), Some(1));
+        assert_eq!(index.find(text, 2, "two"), None);
+        assert!(LineIndex::new("").is_empty());
+    }
+
+    #[test]
+    fn test_back_search_and_position() {
+        let text = "l1\nl2\nl3\nl4\nERR a\nl6\nl7\nERR b\nl9\n";
+
+        let mut out = StringOutput::new();
+        page(&English, text, &mut out, &mut Keys(" b=q"), 3);
+        assert_eq!(visible(&out), ["l1", "l2", "l3", "l4", "ERR a", "l6", "l1", "l2", "l3",
+                                   "lines 1-3 of 9 (33%)"]);
+
+        let mut out = StringOutput::new();
+        page(&English, text, &mut out, &mut Keys("/ERR\n/\n/zzz\nq"), 2);
+        assert_eq!(visible(&out), ["l1", "l2", "ERR a", "l6", "ERR b", "l9",
+                                   "Pattern not found: zzz"]);
+
+        let mut out = StringOutput::new();
+        page(&English, text, &mut out, &mut Keys("  x "), 4);
+        assert_eq!(visible(&out).len(), 9);
+    }
+}
@@ -1,16 +1,193 @@
 //! Parser de commandes pour le shell FAT32
 
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+
+use super::registry::lookup;
+
+/// Options d'une commande (`-abc`, `--name`, `--name=value`)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Flags<'a> {
+    short: Vec<char>,
+    long: Vec<(&'a str, Option<&'a str>)>,
+}
+
+impl<'a> Flags<'a> {
+    /// Vrai si aucune option n'a été donnée
+    pub fn is_empty(&self) -> bool {
+        self.short.is_empty() && self.long.is_empty()
+    }
+
+    /// Vrai si l'option courte `-c` est présente
+    pub fn has(&self, flag: char) -> bool {
+        self.short.contains(&flag)
+    }
+
+    /// Vrai si l'option longue `--name` est présente (avec ou sans valeur)
+    pub fn has_long(&self, name: &str) -> bool {
+        self.long.iter().any(|(n, _)| *n == name)
+    }
+
+    /// Valeur de la dernière occurrence de `--name=value`
+    pub fn value(&self, name: &str) -> Option<&'a str> {
+        self.long.iter().rev().find(|(n, _)| *n == name).and_then(|(_, v)| *v)
+    }
+
+    /// Vérifie les options contre celles déclarées par la commande
+    ///
+    /// `short` liste les options courtes acceptées (ex: `"lah"`), `long` les
+    /// options longues; un `=` final (ex: `"sort="`) indique qu'une valeur est requise.
+    pub fn check(&self, short: &str, long: &[&str]) -> Result<(), String> {
+        if let Some(c) = self.short.iter().find(|c| !short.contains(**c)) {
+            return Err(format!("Unknown option: -{}", c));
+        }
+
+        for (name, value) in &self.long {
+            let takes_value = if long.contains(name) {
+                false
+            } else if long.iter().any(|l| l.strip_suffix('=') == Some(name)) {
+                true
+            } else {
+                return Err(format!("Unknown option: --{}", name));
+            };
+
+            match (takes_value, value) {
+                (true, None) => return Err(format!("Option --{} requires a value", name)),
+                (false, Some(_)) => return Err(format!("Option --{} takes no value", name)),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Arguments d'une commande: positionnels et options
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Args<'a> {
+    pub args: Vec<&'a str>,
+    pub flags: Flags<'a>,
+}
+
+impl<'a> Args<'a> {
+    /// Découpe une ligne d'arguments à la manière de getopt
+    ///
+    /// Les mots entre guillemets et tout ce qui suit `--` sont positionnels;
+    /// `-` seul et les nombres négatifs aussi.
+    pub fn parse(input: &'a str) -> Self {
+        let mut result = Args::default();
+        let mut options_done = false;
+
+        for (word, quoted) in split_words(input) {
+            if quoted || options_done {
+                result.args.push(word);
+            } else if word == "--" {
+                options_done = true;
+            } else if let Some(long) = word.strip_prefix("--") {
+                match long.split_once('=') {
+                    Some((name, value)) => result.flags.long.push((name, Some(value))),
+                    None => result.flags.long.push((long, None)),
+                }
+            } else if let Some(short) = word.strip_prefix('-')
+                .filter(|s| s.chars().next().is_some_and(|c| !c.is_ascii_digit()))
+            {
+                result.flags.short.extend(short.chars());
+            } else {
+                result.args.push(word);
+            }
+        }
+
+        result
+    }
+
+    /// Nombre d'arguments positionnels
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    /// Vrai si aucun argument positionnel n'a été donné
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    /// Argument positionnel d'index `i`
+    pub fn get(&self, i: usize) -> Option<&'a str> {
+        self.args.get(i).copied()
+    }
+}
+
+/// Découpe une ligne en mots; `"..."` ou `'...'` regroupe des espaces
+fn split_words(input: &str) -> Vec<(&str, bool)> {
+    let mut words = Vec::new();
+    let mut rest = input.trim_start();
+
+    while !rest.is_empty() {
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let (word, remaining) = match quote {
+            Some(q) => {
+                let inner = &rest[1..];
+                match inner.find(q) {
+                    Some(end) => (&inner[..end], &inner[end + 1..]),
+                    None => (inner, ""),
+                }
+            }
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+        };
+
+        words.push((word, quote.is_some()));
+        rest = remaining.trim_start();
+    }
+
+    words
+}
+
+/// Sépare le nom de la commande de ses arguments
+pub fn split_command(input: &str) -> (&str, Args<'_>) {
+    let trimmed = input.trim();
+    let (cmd, rest) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+    (cmd, Args::parse(rest))
+}
+
 /// Commande parsée
 #[derive(Debug, PartialEq)]
 pub enum Command<'a> {
-    Ls(Option<&'a str>),
+    Ls(Args<'a>),
     Cd(&'a str),
-    Cat(&'a str),
-    More(&'a str),
+    Cat(Args<'a>),
+    More(Option<&'a str>),
+    Chain(&'a str),
+    Grep(Args<'a>),
+    Head(Args<'a>),
+    DumpCluster(Args<'a>),
+    Undelete(Args<'a>),
+    Format(Args<'a>),
+    Crc32(&'a str),
+    Sha256(&'a str),
+    Cmp(&'a str, &'a str),
+    Rename(&'a str, &'a str),
+    Export(&'a str, &'a str),
+    Import(&'a str, &'a str),
+    Alias(Option<&'a str>),
+    Unalias(&'a str),
+    Set(Args<'a>),
+    Pushd(Option<&'a str>),
+    Popd,
+    Dirs,
+    Run(Args<'a>),
     Pwd,
-    Help,
+    Info,
+    Help(Option<&'a str>),
     Exit,
     Unknown(&'a str),
+    /// Commande connue dont un argument obligatoire manque (nom canonique)
+    Usage(&'static str),
     Empty,
 }
 
@@ -22,43 +199,190 @@ pub fn parse_command(input: &str) -> Command<'_> {
         return Command::Empty;
     }
 
-    let mut parts = trimmed.splitn(2, ' ');
-    let cmd = parts.next().unwrap_or("");
-    let arg = parts.next().map(|s| s.trim());
+    let (cmd, arg) = match trimmed.split_once(char::is_whitespace) {
+        Some((cmd, rest)) => (cmd, Some(rest.trim())),
+        None => (trimmed, None),
+    };
 
-    match cmd.to_ascii_lowercase().as_str() {
-        "ls" | "dir" | "list" => Command::Ls(arg),
+    let args = Args::parse(arg.unwrap_or(""));
 
-        "cd" | "chdir" => match arg {
-            Some(path) if !path.is_empty() => Command::Cd(path),
-            _ => Command::Cd("/"),
+    let name = match lookup(cmd) {
+        Some(info) => info.name,
+        None => return Command::Unknown(cmd),
+    };
+
+    match name {
+        "ls" => Command::Ls(args),
+
+        "cd" => match single(&args) {
+            Some(path) => Command::Cd(path.unwrap_or("/")),
+            None => Command::Usage(name),
         },
 
-        "cat" | "type" | "read" => match arg {
-            Some(filename) if !filename.is_empty() => Command::Cat(filename),
-            _ => Command::Empty,
+        "cat" => Command::Cat(args),
+
+        "more" => match single(&args) {
+            Some(file) => Command::More(file),
+            None => Command::Usage(name),
         },
 
-        "more" | "less" | "page" => match arg {
-            Some(filename) if !filename.is_empty() => Command::More(filename),
-            _ => Command::Empty,
+        "chain" => match single(&args) {
+            Some(Some(file)) => Command::Chain(file),
+            _ => Command::Usage(name),
         },
 
-        "pwd" | "cwd" => Command::Pwd,
+        "grep" => Command::Grep(args),
+
+        "head" => Command::Head(args),
 
-        "help" | "?" | "h" => Command::Help,
+        "dumpcluster" if !args.is_empty() => Command::DumpCluster(args),
+
+        "undelete" => Command::Undelete(args),
+
+        "format" => Command::Format(args),
+
+        "crc32" => match single(&args) {
+            Some(Some(file)) => Command::Crc32(file),
+            _ => Command::Usage(name),
+        },
+
+        "sha256" => match single(&args) {
+            Some(Some(file)) => Command::Sha256(file),
+            _ => Command::Usage(name),
+        },
+
+        "cmp" => match args.args[..] {
+            [a, b] if args.flags.is_empty() => Command::Cmp(a, b),
+            _ => Command::Usage(name),
+        },
+
+        "rename" => match args.args[..] {
+            [old, new] if args.flags.is_empty() => Command::Rename(old, new),
+            _ => Command::Usage(name),
+        },
+
+        "export" => match args.args[..] {
+            [source, target] if args.flags.is_empty() => Command::Export(source, target),
+            _ => Command::Usage(name),
+        },
+
+        "import" => match args.args[..] {
+            [source, target] if args.flags.is_empty() => Command::Import(source, target),
+            _ => Command::Usage(name),
+        },
+
+        // `alias nom='commande'`: la définition garde ses guillemets
+        "alias" => Command::Alias(arg.filter(|a| !a.is_empty())),
+
+        "unalias" => match single(&args) {
+            Some(Some(name)) => Command::Unalias(name),
+            _ => Command::Usage(name),
+        },
+
+        "set" => Command::Set(args),
+
+        "pushd" => match single(&args) {
+            Some(dir) => Command::Pushd(dir),
+            None => Command::Usage(name),
+        },
 
-        "exit" | "quit" | "q" => Command::Exit,
+        "popd" => Command::Popd,
+
+        "dirs" => Command::Dirs,
+
+        "run" if !args.is_empty() => Command::Run(args),
+
+        "pwd" => Command::Pwd,
+
+        "info" => Command::Info,
+
+        "help" => Command::Help(arg.filter(|a| !a.is_empty())),
+
+        "exit" => Command::Exit,
+
+        "dumpcluster" | "run" => Command::Usage(name),
 
         _ => Command::Unknown(cmd),
     }
 }
 
-/// Parse un chemin en composants
-pub fn parse_path(path: &str) -> (bool, alloc::vec::Vec<&str>) {
-    extern crate alloc;
-    use alloc::vec::Vec;
+/// Argument positionnel unique et facultatif d'une commande sans option
+///
+/// `None` s'il y a une option ou plus d'un argument (erreur d'usage).
+fn single<'a>(args: &Args<'a>) -> Option<Option<&'a str>> {
+    (args.flags.is_empty() && args.len() <= 1).then(|| args.get(0))
+}
+
+/// Remplace le premier mot de la ligne par son alias, s'il en a un
+///
+/// L'expansion n'est faite qu'une fois: `alias ls='ls -l'` ne boucle pas.
+pub fn expand_alias<'a>(input: &'a str, aliases: &BTreeMap<String, String>) -> Cow<'a, str> {
+    let trimmed = input.trim_start();
+    let (first, rest) = match trimmed.find(char::is_whitespace) {
+        Some(i) => trimmed.split_at(i),
+        None => (trimmed, ""),
+    };
+
+    match aliases.get(first) {
+        Some(expansion) => Cow::Owned(format!("{}{}", expansion, rest)),
+        None => Cow::Borrowed(input),
+    }
+}
+
+/// Sépare une redirection `commande > fichier` (un `>` entre guillemets est ignoré)
+pub fn split_redirect(input: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return (input[..i].trim_end(), Some(input[i + 1..].trim())),
+            _ => {}
+        }
+    }
 
+    (input, None)
+}
+
+/// Découpe une ligne `a | b | c` en étapes (un `|` entre guillemets est ignoré)
+pub fn split_pipeline(input: &str) -> Vec<&str> {
+    split_unquoted(input, '|')
+}
+
+/// Découpe une suite `a; b; c` en commandes (un `;` entre guillemets est ignoré)
+///
+/// Les commandes vides (`a;;b`, `;` final) sont retirées.
+pub fn split_sequence(input: &str) -> Vec<&str> {
+    let mut commands = split_unquoted(input, ';');
+    commands.retain(|c| !c.is_empty());
+    commands
+}
+
+/// Découpe `input` à chaque `separator` hors guillemets
+fn split_unquoted(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, c) if c == separator => {
+                parts.push(input[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(input[start..].trim());
+    parts
+}
+
+/// Parse un chemin en composants
+pub fn parse_path(path: &str) -> (bool, Vec<&str>) {
     let is_absolute = path.starts_with('/');
     let components: Vec<&str> = path
         .split('/')
@@ -74,12 +398,12 @@ mod tests {
 
     #[test]
     fn test_ls_command() {
-        assert!(matches!(parse_command("ls"), Command::Ls(None)));
-        assert!(matches!(parse_command("LS"), Command::Ls(None)));
-        assert!(matches!(parse_command("dir"), Command::Ls(None)));
+        assert_eq!(parse_command("ls"), Command::Ls(Args::default()));
+        assert_eq!(parse_command("LS"), Command::Ls(Args::default()));
+        assert_eq!(parse_command("dir"), Command::Ls(Args::default()));
 
-        if let Command::Ls(Some(path)) = parse_command("ls /Documents") {
-            assert_eq!(path, "/Documents");
+        if let Command::Ls(args) = parse_command("ls /Documents") {
+            assert_eq!(args.get(0), Some("/Documents"));
         } else {
             panic!("Expected Ls with path");
         }
@@ -104,23 +428,134 @@ mod tests {
         } else {
             panic!("Expected Cd to root");
         }
+
+        assert_eq!(parse_command("cd \"My Documents\""), Command::Cd("My Documents"));
+        assert_eq!(parse_command("cd -x"), Command::Usage("cd"));
+        assert_eq!(parse_command("cd A B"), Command::Usage("cd"));
     }
 
     #[test]
     fn test_cat_command() {
-        if let Command::Cat(file) = parse_command("cat readme.txt") {
-            assert_eq!(file, "readme.txt");
+        if let Command::Cat(args) = parse_command("cat readme.txt") {
+            assert_eq!(args.args, ["readme.txt"]);
+        } else {
+            panic!("Expected Cat");
+        }
+
+        assert!(matches!(parse_command("cat"), Command::Cat(a) if a.is_empty()));
+        assert!(matches!(parse_command("more"), Command::More(None)));
+        if let Command::Cat(args) = parse_command("cat -n A.TXT B.TXT") {
+            assert!(args.flags.has('n'));
+            assert_eq!(args.args, ["A.TXT", "B.TXT"]);
         } else {
             panic!("Expected Cat");
         }
+    }
 
-        assert!(matches!(parse_command("cat"), Command::Empty));
+    #[test]
+    fn test_undelete_command() {
+        assert_eq!(parse_command("undelete"), Command::Undelete(Args::default()));
+        assert_eq!(parse_command("undelete /DOCS"), Command::Undelete(Args::parse("/DOCS")));
+        if let Command::Undelete(args) = parse_command("undelete 0 A") {
+            assert_eq!(args.args, ["0", "A"]);
+        } else {
+            panic!("Expected Undelete");
+        }
+    }
+
+    #[test]
+    fn test_cmp_command() {
+        assert_eq!(parse_command("cmp A.TXT  /DOCS/B.TXT"), Command::Cmp("A.TXT", "/DOCS/B.TXT"));
+        assert_eq!(parse_command("cmp A.TXT"), Command::Usage("cmp"));
+    }
+
+    #[test]
+    fn test_args_getopt() {
+        let args = Args::parse("-la --sort=size --yes /DOCS -3 - \"my file\" -- -n");
+        assert!(args.flags.has('l') && args.flags.has('a') && !args.flags.has('n'));
+        assert_eq!(args.flags.value("sort"), Some("size"));
+        assert!(args.flags.has_long("yes"));
+        assert_eq!(args.args, ["/DOCS", "-3", "-", "my file", "-n"]);
+
+        assert!(args.flags.check("lah", &["sort=", "yes"]).is_ok());
+        assert_eq!(args.flags.check("l", &["sort=", "yes"]).unwrap_err(), "Unknown option: -a");
+        assert!(args.flags.check("la", &["sort", "yes"]).is_err());
+        assert!(Args::parse("--sort").flags.check("", &["sort="]).is_err());
+    }
+
+    #[test]
+    fn test_split_command() {
+        let (cmd, args) = split_command("  cmp 'a b.txt' c.txt ");
+        assert_eq!(cmd, "cmp");
+        assert_eq!(args.args, ["a b.txt", "c.txt"]);
+        assert!(args.flags.is_empty());
+        assert_eq!(parse_command("cmp \"a b.txt\" c.txt"), Command::Cmp("a b.txt", "c.txt"));
+    }
+
+    #[test]
+    fn test_alias_expansion() {
+        let mut aliases = BTreeMap::new();
+        aliases.insert(String::from("ll"), String::from("ls -l"));
+        aliases.insert(String::from("ls"), String::from("ls -a"));
+
+        assert_eq!(expand_alias("ll /DOCS", &aliases), "ls -l /DOCS");
+        assert_eq!(expand_alias("  ls", &aliases), "ls -a");
+        assert_eq!(expand_alias("lll", &aliases), "lll");
+        assert!(matches!(expand_alias("cat ll", &aliases), Cow::Borrowed(_)));
+
+        assert_eq!(parse_command("alias ll='ls -l'"), Command::Alias(Some("ll='ls -l'")));
+        assert_eq!(parse_command("alias"), Command::Alias(None));
+        assert_eq!(parse_command("unalias"), Command::Usage("unalias"));
+        assert_eq!(parse_command("unalias 'll'"), Command::Unalias("ll"));
+        assert_eq!(parse_command("cmp -s A B"), Command::Usage("cmp"));
+    }
+
+    #[test]
+    fn test_split_redirect() {
+        assert_eq!(split_redirect("ls -l > /LOGS/OUT.TXT"), ("ls -l", Some("/LOGS/OUT.TXT")));
+        assert_eq!(split_redirect("cat 'a>b' >x"), ("cat 'a>b'", Some("x")));
+        assert_eq!(split_redirect("pwd >"), ("pwd", Some("")));
+        assert_eq!(split_redirect("pwd"), ("pwd", None));
+    }
+
+    #[test]
+    fn test_split_pipeline() {
+        assert_eq!(split_pipeline("cat LOG | grep ERROR | head"), ["cat LOG", "grep ERROR", "head"]);
+        assert_eq!(split_pipeline("grep 'a|b' X"), ["grep 'a|b' X"]);
+        assert_eq!(split_pipeline("ls |"), ["ls", ""]);
+    }
+
+    #[test]
+    fn test_split_sequence() {
+        assert_eq!(split_sequence("ls /DOCS; cat README.MD"), ["ls /DOCS", "cat README.MD"]);
+        assert_eq!(split_sequence("grep 'a;b' X;; pwd;"), ["grep 'a;b' X", "pwd"]);
+        assert!(split_sequence(" ").is_empty());
+    }
+
+    #[test]
+    fn test_chain_command() {
+        assert_eq!(parse_command("chain /DOCS/INFO.TXT"), Command::Chain("/DOCS/INFO.TXT"));
+        assert_eq!(parse_command("chain"), Command::Usage("chain"));
+        assert_eq!(parse_command("chain 'a b.txt'"), Command::Chain("a b.txt"));
+        assert_eq!(parse_command("sha256 -q A.TXT"), Command::Usage("sha256"));
+        assert_eq!(parse_command("crc32 \"a b.txt\""), Command::Crc32("a b.txt"));
+        assert_eq!(parse_command("more 'a b.txt'"), Command::More(Some("a b.txt")));
+        assert_eq!(parse_command("more A B"), Command::Usage("more"));
     }
 
     #[test]
     fn test_special_commands() {
         assert!(matches!(parse_command("pwd"), Command::Pwd));
-        assert!(matches!(parse_command("help"), Command::Help));
+        assert_eq!(parse_command("pushd /DOCS"), Command::Pushd(Some("/DOCS")));
+        assert_eq!(parse_command("pushd"), Command::Pushd(None));
+        assert_eq!(parse_command("pushd \"My Documents\""), Command::Pushd(Some("My Documents")));
+        assert_eq!(parse_command("pushd --x"), Command::Usage("pushd"));
+        assert!(matches!(parse_command("popd"), Command::Popd));
+        assert!(matches!(parse_command("dirs"), Command::Dirs));
+        assert!(matches!(parse_command("info"), Command::Info));
+        assert!(matches!(parse_command("vol"), Command::Info));
+        assert!(matches!(parse_command("help"), Command::Help(None)));
+        assert_eq!(parse_command("? ls"), Command::Help(Some("ls")));
         assert!(matches!(parse_command("exit"), Command::Exit));
         assert!(matches!(parse_command("quit"), Command::Exit));
     }
@@ -0,0 +1,93 @@
+//! Chemin du shell stocké dans une seule chaîne
+//!
+//! Les composants sont séparés par `/` dans un unique `String`: un chemin ne
+//! coûte qu'une allocation, quelle que soit sa profondeur, au lieu d'un
+//! `String` par composant (le bump allocator ne libère jamais).
+
+extern crate alloc;
+use alloc::string::String;
+
+/// Chemin relatif à la racine du shell, sans `/` initial ni final
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellPath {
+    text: String,
+}
+
+impl ShellPath {
+    /// Chemin vide (racine du shell)
+    pub fn new() -> Self {
+        ShellPath::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Composants joints par `/` (`DOCS/Work`)
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Itère sur les composants
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.text.split('/').filter(|c| !c.is_empty())
+    }
+
+    /// Ajoute un composant (sans `/`)
+    pub fn push(&mut self, name: &str) {
+        if !self.text.is_empty() {
+            self.text.push('/');
+        }
+        self.text.push_str(name);
+    }
+
+    /// Retire le dernier composant; sans effet sur un chemin vide
+    pub fn pop(&mut self) {
+        let end = self.text.rfind('/').unwrap_or(0);
+        self.text.truncate(end);
+    }
+
+    /// Parent (composants joints par `/`) et dernier composant
+    pub fn split_last(&self) -> Option<(&str, &str)> {
+        match self.text.rsplit_once('/') {
+            Some(split) => Some(split),
+            None if self.text.is_empty() => None,
+            None => Some(("", &self.text)),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+    }
+}
+
+/// Chemin à partir de composants séparés par `/` (les composants vides sont ignorés)
+impl From<&str> for ShellPath {
+    fn from(text: &str) -> Self {
+        let mut path = ShellPath::new();
+        for component in text.split('/').filter(|c| !c.is_empty()) {
+            path.push(component);
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop_split() {
+        let mut path = ShellPath::from("/DOCS//Work/");
+        assert_eq!(path.as_str(), "DOCS/Work");
+        assert_eq!(path.split_last(), Some(("DOCS", "Work")));
+        path.push("a.txt");
+        assert_eq!(path.components().count(), 3);
+        path.pop();
+        path.pop();
+        assert_eq!(path.split_last(), Some(("", "DOCS")));
+        path.pop();
+        path.pop();
+        assert!(path.is_empty() && path.split_last().is_none());
+    }
+}
@@ -0,0 +1,313 @@
+//! Registre des commandes du shell: noms, alias, usage, options et exemples
+
+/// Description d'une commande du shell
+#[derive(Debug)]
+pub struct CommandInfo {
+    /// Nom canonique
+    pub name: &'static str,
+    /// Autres noms acceptés
+    pub aliases: &'static [&'static str],
+    /// Ligne d'usage (sans le préfixe "Usage: ")
+    pub usage: &'static str,
+    /// Description courte
+    pub summary: &'static str,
+    /// Options et arguments: (syntaxe, description)
+    pub options: &'static [(&'static str, &'static str)],
+    /// Exemples d'utilisation
+    pub examples: &'static [&'static str],
+}
+
+/// Toutes les commandes, dans l'ordre d'affichage de `help`
+pub const COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "ls",
+        aliases: &["dir", "list"],
+        usage: "ls [-l] [-a] [-h] [--sort=name|size|date] [path]",
+        summary: "List directory contents",
+        options: &[
+            ("-l", "Long listing: attributes, date, size, first cluster"),
+            ("-a", "Include hidden and system entries"),
+            ("-h", "Human-readable sizes (1.5K, 3.2M)"),
+            ("--sort=KEY", "Sort by name, size (largest first) or date (newest first)"),
+        ],
+        examples: &["ls", "ls -la /DOCS", "ls -lh --sort=size"],
+    },
+    CommandInfo {
+        name: "cd",
+        aliases: &["chdir"],
+        usage: "cd [dir]",
+        summary: "Change directory",
+        options: &[("dir", "Absolute or relative path; defaults to /")],
+        examples: &["cd /", "cd ..", "cd DOCS/WORK"],
+    },
+    CommandInfo {
+        name: "cat",
+        aliases: &["type", "read"],
+        usage: "cat [-n] [file]...",
+        summary: "Display file contents",
+        options: &[
+            ("-n", "Number output lines"),
+            ("file", "Files to print; without one, print the piped input"),
+        ],
+        examples: &["cat README.MD", "cat -n A.TXT /DOCS/B.TXT", "ls | cat -n"],
+    },
+    CommandInfo {
+        name: "more",
+        aliases: &["less", "page"],
+        usage: "more [file]",
+        summary: "Display a file with pagination",
+        options: &[
+            ("space", "Next page"),
+            ("enter", "Next line"),
+            ("b", "Previous page"),
+            ("/pattern", "Search forward (empty pattern repeats the last search)"),
+            ("=", "Show current line numbers"),
+            ("q", "Quit"),
+        ],
+        examples: &["more /DOCS/INFO.TXT", "ls -l | more"],
+    },
+    CommandInfo {
+        name: "chain",
+        aliases: &[],
+        usage: "chain <file>",
+        summary: "Show a file's cluster chain",
+        options: &[],
+        examples: &["chain HELLO.TXT"],
+    },
+    CommandInfo {
+        name: "grep",
+        aliases: &[],
+        usage: "grep [-i] [-v] [-n] [-c] <pattern> [file]",
+        summary: "Print lines containing a pattern",
+        options: &[
+            ("-i", "Ignore case"),
+            ("-v", "Print lines that do not match"),
+            ("-n", "Prefix lines with their line number"),
+            ("-c", "Only print the number of matching lines"),
+            ("file", "File to search; without one, search the piped input"),
+        ],
+        examples: &["grep ERROR BIG.LOG", "cat BIG.LOG | grep -i error | head -n 20"],
+    },
+    CommandInfo {
+        name: "head",
+        aliases: &[],
+        usage: "head [-n N] [file]",
+        summary: "Print the first lines of a file or of the piped input",
+        options: &[("-n N", "Number of lines to print (default 10)")],
+        examples: &["head README.MD", "ls -l | head -n 5"],
+    },
+    CommandInfo {
+        name: "dumpcluster",
+        aliases: &["dc"],
+        usage: "dumpcluster <n> [count]",
+        summary: "Hex dump raw clusters",
+        options: &[("count", "Number of consecutive clusters to dump (default 1)")],
+        examples: &["dumpcluster 2", "dc 5 3"],
+    },
+    CommandInfo {
+        name: "undelete",
+        aliases: &["undel"],
+        usage: "undelete [dir] | undelete [dir] <index> <firstchar>",
+        summary: "List or restore deleted entries",
+        options: &[
+            ("dir", "Directory to scan; defaults to the current one"),
+            ("<index> <firstchar>", "Restore entry <index> of the listing of dir"),
+        ],
+        examples: &["undelete", "undelete 0 O", "undelete /DOCS 1 R"],
+    },
+    CommandInfo {
+        name: "crc32",
+        aliases: &["cksum"],
+        usage: "crc32 <file>",
+        summary: "Print the CRC-32 of a file",
+        options: &[],
+        examples: &["crc32 HELLO.TXT"],
+    },
+    CommandInfo {
+        name: "sha256",
+        aliases: &["sha256sum"],
+        usage: "sha256 <file>",
+        summary: "Print the SHA-256 of a file",
+        options: &[],
+        examples: &["sha256 /DOCS/INFO.TXT"],
+    },
+    CommandInfo {
+        name: "cmp",
+        aliases: &["compare"],
+        usage: "cmp <file1> <file2>",
+        summary: "Compare two files",
+        options: &[],
+        examples: &["cmp A.TXT /DOCS/A.TXT"],
+    },
+    CommandInfo {
+        name: "rename",
+        aliases: &["ren"],
+        usage: "rename <old> <new>",
+        summary: "Rename an entry in place",
+        options: &[("new", "New name (long names allowed, no '/')")],
+        examples: &["rename NOTES.TXT \"meeting notes.txt\"", "rename /DOCS/A.TXT B.TXT"],
+    },
+    CommandInfo {
+        name: "export",
+        aliases: &[],
+        usage: "export <image-path> <host-path>",
+        summary: "Copy a file or directory out to the host",
+        options: &[("host-path", "Destination; an existing directory receives the copy under its name")],
+        examples: &["export README.MD /tmp/readme.md", "export /DOCS /tmp"],
+    },
+    CommandInfo {
+        name: "import",
+        aliases: &[],
+        usage: "import <host-path> <image-path>",
+        summary: "Copy a host file or directory into the image",
+        options: &[("image-path", "Destination; missing parent directories are created")],
+        examples: &["import ~/notes.txt /DOCS", "import ~/photos \"/My Photos\""],
+    },
+    CommandInfo {
+        name: "format",
+        aliases: &["mkfs"],
+        usage: "format --yes [--cluster-size=BYTES] [--label=NAME]",
+        summary: "Reformat the image",
+        options: &[
+            ("--yes", "Confirm that all data will be erased"),
+            ("--cluster-size=BYTES", "Cluster size, power of two from 512 to 65536"),
+            ("--label=NAME", "Volume label, up to 11 characters"),
+        ],
+        examples: &["format --yes", "format --yes --cluster-size=4096 --label=DATA"],
+    },
+    CommandInfo {
+        name: "alias",
+        aliases: &[],
+        usage: "alias [name[='command']]",
+        summary: "Define or list command aliases",
+        options: &[
+            ("name", "Show the alias for name"),
+            ("name='command'", "Make name expand to command (first word only)"),
+        ],
+        examples: &["alias", "alias ll='ls -l'", "alias ll"],
+    },
+    CommandInfo {
+        name: "unalias",
+        aliases: &[],
+        usage: "unalias <name>",
+        summary: "Remove a command alias",
+        options: &[],
+        examples: &["unalias ll"],
+    },
+    CommandInfo {
+        name: "set",
+        aliases: &[],
+        usage: "set [option [value]]",
+        summary: "Show or change shell settings",
+        options: &[
+            ("verbose on|off", "Report details such as bytes written and script lines"),
+            ("quiet on|off", "Omit summaries such as the ls totals"),
+            ("pagesize N|auto", "Lines per page for more; auto follows the terminal"),
+            ("hidden on|off", "Show hidden and system entries in ls without -a"),
+            ("color on|off", "ANSI colors for ls and errors"),
+            ("case on|off", "Case-sensitive grep; off behaves like grep -i"),
+            ("json on|off", "JSON output for ls and info"),
+            ("utc +HH:MM", "UTC offset of FAT timestamps (local time) for export and import"),
+        ],
+        examples: &["set", "set verbose on", "set pagesize 40", "set json on", "set utc +02:00"],
+    },
+    CommandInfo {
+        name: "pushd",
+        aliases: &[],
+        usage: "pushd [dir]",
+        summary: "Save the current directory and change to dir",
+        options: &[("dir", "Target directory; without it, swap with the top of the stack")],
+        examples: &["pushd /ASSETS/SPRITES/PLAYER", "pushd"],
+    },
+    CommandInfo {
+        name: "popd",
+        aliases: &[],
+        usage: "popd",
+        summary: "Return to the directory on top of the stack",
+        options: &[],
+        examples: &[],
+    },
+    CommandInfo {
+        name: "dirs",
+        aliases: &[],
+        usage: "dirs",
+        summary: "Show the directory stack",
+        options: &[],
+        examples: &[],
+    },
+    CommandInfo {
+        name: "run",
+        aliases: &[],
+        usage: "run [-k] <script>",
+        summary: "Run a script of shell commands from the image",
+        options: &[
+            ("-k", "Keep going after a failing line"),
+            ("script", "One command per line; lines starting with # are comments"),
+        ],
+        examples: &["run /SETUP.BAT", "run -k CHECKS.BAT"],
+    },
+    CommandInfo {
+        name: "pwd",
+        aliases: &["cwd"],
+        usage: "pwd",
+        summary: "Print working directory",
+        options: &[],
+        examples: &[],
+    },
+    CommandInfo {
+        name: "info",
+        aliases: &["vol", "fsinfo"],
+        usage: "info",
+        summary: "Show filesystem parameters",
+        options: &[],
+        examples: &[],
+    },
+    CommandInfo {
+        name: "help",
+        aliases: &["?", "h"],
+        usage: "help [command]",
+        summary: "Show help",
+        options: &[],
+        examples: &["help", "help ls"],
+    },
+    CommandInfo {
+        name: "exit",
+        aliases: &["quit", "q"],
+        usage: "exit",
+        summary: "Exit shell",
+        options: &[],
+        examples: &[],
+    },
+];
+
+/// Cherche une commande par nom ou alias (insensible à la casse)
+pub fn lookup(name: &str) -> Option<&'static CommandInfo> {
+    COMMANDS.iter().find(|c| {
+        c.name.eq_ignore_ascii_case(name) || c.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use super::*;
+
+    #[test]
+    fn test_lookup_by_name_and_alias() {
+        assert_eq!(lookup("ls").unwrap().name, "ls");
+        assert_eq!(lookup("DIR").unwrap().name, "ls");
+        assert_eq!(lookup("mkfs").unwrap().name, "format");
+        assert!(lookup("frobnicate").is_none());
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        let mut names: alloc::vec::Vec<&str> = COMMANDS.iter()
+            .flat_map(|c| core::iter::once(c.name).chain(c.aliases.iter().copied()))
+            .collect();
+        let total = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), total);
+    }
+}
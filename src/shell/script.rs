@@ -0,0 +1,106 @@
+//! Exécution de scripts: une commande par ligne, `#` pour les commentaires
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::format;
+
+use crate::fat32::Fat32;
+use super::commands::{Input, Output, ShellState, path_error, try_find_file, usage_error, write_error};
+use super::error::{CommandResult, ShellError};
+use super::execute_command;
+use super::parser::Args;
+
+/// Profondeur maximale de `run` imbriqués
+const MAX_SCRIPT_DEPTH: u8 = 8;
+
+/// Exécute un script ligne par ligne via `execute_command`
+///
+/// Les lignes vides et celles commençant par `#` sont ignorées; `exit` termine
+/// le script. S'arrête à la première erreur sauf si `keep_going` est vrai,
+/// auquel cas chaque erreur est affichée et un bilan est retourné à la fin.
+pub fn execute_script<O: Output, I: Input>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    script: &str,
+    keep_going: bool,
+    out: &mut O,
+    input: &mut I,
+) -> CommandResult {
+    if state.script_depth >= MAX_SCRIPT_DEPTH {
+        return Err(ShellError::Failed(state.tr("run: scripts nested too deeply").into()));
+    }
+
+    state.script_depth += 1;
+    let result = run_lines(fs, state, script, keep_going, out, input);
+    state.script_depth -= 1;
+    result
+}
+
+/// Corps de `execute_script`, hors gestion de la profondeur
+fn run_lines<O: Output, I: Input>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    script: &str,
+    keep_going: bool,
+    out: &mut O,
+    input: &mut I,
+) -> CommandResult {
+    let mut failures = 0usize;
+
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if state.config.verbose() {
+            out.write_err_line(&format!("+ {}", line));
+        }
+
+        match execute_command(fs, state, line, out, input) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => {
+                let err = ShellError::Script { line: i + 1, error: Box::new(e) };
+                if !keep_going {
+                    return Err(err);
+                }
+                write_error(state, &err, out);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        Err(ShellError::Failed(state.tr_fill("run: {} command(s) failed", &[&failures])))
+    } else {
+        Ok(())
+    }
+}
+
+/// Commande run - exécute un script lu dans l'image (`[-k] <script>`)
+pub fn cmd_run<O: Output, I: Input>(
+    fs: &mut Fat32,
+    state: &mut ShellState,
+    args: &Args,
+    out: &mut O,
+    input: &mut I,
+) -> CommandResult {
+    args.flags.check("k", &[]).map_err(|_| usage_error(state, None, "run"))?;
+    let filename = match args.args[..] {
+        [f] => f,
+        _ => return Err(usage_error(state, None, "run")),
+    };
+
+    let entry = try_find_file(fs, state, filename)
+        .map_err(|e| path_error(state, filename, &e, state.tr("File not found").into()))?;
+    if entry.is_directory() {
+        return Err(ShellError::Failed(state.tr("Cannot run a directory").into()));
+    }
+
+    let data = fs.read_file(&entry);
+    let script = core::str::from_utf8(&data)
+        .map_err(|_| ShellError::Failed(state.tr("Script is not valid text").into()))?;
+
+    execute_script(fs, state, script, args.flags.has('k'), out, input)
+}
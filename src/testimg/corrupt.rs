@@ -0,0 +1,159 @@
+//! Défauts injectés dans une image valide, pour tester la robustesse
+//!
+//! Chaque fonction modifie l'image en place et ne touche que ce qui est
+//! nécessaire au défaut; les chemins désignent des fichiers non vides.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use crate::fat32::{lfn_checksum, Fat32, FsError};
+
+/// Chaîne de clusters du fichier `path`
+fn file_chain(image: &[u8], path: &str) -> Result<Vec<u32>, FsError> {
+    let fs = Fat32::new(image).ok_or(FsError::InvalidGeometry)?;
+    let entry = fs.resolve_path(path, fs.root_cluster()).ok_or(FsError::NotFound)?;
+    if entry.cluster() < 2 {
+        return Err(FsError::NotFound);
+    }
+    Ok(fs.cluster_chain(entry.cluster()))
+}
+
+/// Fait pointer le dernier cluster du fichier `path` vers son premier
+pub fn loop_chain(image: &mut [u8], path: &str) -> Result<(), FsError> {
+    let chain = file_chain(image, path)?;
+    let mut fs = Fat32::new_mut(image).ok_or(FsError::InvalidGeometry)?;
+    fs.write_fat_entry(chain[chain.len() - 1], chain[0])
+}
+
+/// Fait pointer la fin de la chaîne de `first` vers le début de celle de `second`
+///
+/// Les clusters de `second` appartiennent alors aux deux fichiers.
+pub fn cross_link(image: &mut [u8], first: &str, second: &str) -> Result<(), FsError> {
+    let first = file_chain(image, first)?;
+    let second = file_chain(image, second)?;
+    let mut fs = Fat32::new_mut(image).ok_or(FsError::InvalidGeometry)?;
+    fs.write_fat_entry(first[first.len() - 1], second[0])
+}
+
+/// Fait pointer le dernier cluster du fichier `path` vers `next`
+///
+/// `next` peut désigner un cluster libre ou hors du volume.
+pub fn relink_chain(image: &mut [u8], path: &str, next: u32) -> Result<(), FsError> {
+    let chain = file_chain(image, path)?;
+    let mut fs = Fat32::new_mut(image).ok_or(FsError::InvalidGeometry)?;
+    fs.write_fat_entry(chain[chain.len() - 1], next)
+}
+
+/// Fausse la somme de contrôle des entrées LFN de `path`
+///
+/// Le nom long ne correspond plus au nom court qui le suit.
+pub fn break_lfn_checksum(image: &mut [u8], path: &str) -> Result<(), FsError> {
+    let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let fs = Fat32::new(image).ok_or(FsError::InvalidGeometry)?;
+    let root = fs.root_cluster();
+    let dir = match parent.trim_matches('/') {
+        "" => root,
+        parent => fs.resolve_path(parent, root).filter(|e| e.is_directory()).ok_or(FsError::NotFound)?.cluster(),
+    };
+    let location = fs.locate_entry(dir, name).ok_or(FsError::NotFound)?;
+    if location.slots.len() < 2 {
+        return Err(FsError::InvalidName);
+    }
+
+    let short = location.short_offset() as usize;
+    let checksum = !lfn_checksum(image[short..short + 11].try_into().map_err(|_| FsError::OutOfBounds)?);
+    for &slot in &location.slots[..location.slots.len() - 1] {
+        image[slot as usize + 13] = checksum;
+    }
+    Ok(())
+}
+
+/// Coupe l'image au milieu de la première FAT
+pub fn truncate_fat(image: &mut Vec<u8>) -> Result<(), FsError> {
+    let fs = Fat32::new(image).ok_or(FsError::InvalidGeometry)?;
+    let boot = fs.boot_sector();
+    let sector = boot.bytes_per_sector as usize;
+    let cut = (boot.fat_start_sector() as usize + boot.sectors_per_fat as usize / 2) * sector;
+    image.truncate(cut.max(sector));
+    Ok(())
+}
+
+/// Remet le boot sector (premier secteur) à zéro
+pub fn zero_boot_sector(image: &mut [u8]) {
+    let end = image.len().min(512);
+    image[..end].fill(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, FRAGMENTED_CLUSTERS, FRAGMENTED_PATH, LONG_NAME_PATHS, README_PATH};
+
+    fn image() -> Vec<u8> {
+        TestImage::default().long_names().fragmented().build().unwrap()
+    }
+
+    #[test]
+    fn test_chain_defects() {
+        let mut looped = image();
+        loop_chain(&mut looped, FRAGMENTED_PATH).unwrap();
+        let fs = Fat32::new(&looped).unwrap();
+        let entry = fs.resolve_path(FRAGMENTED_PATH, fs.root_cluster()).unwrap();
+        // La chaîne lue s'arrête au retour sur le premier cluster
+        let chain = fs.cluster_chain(entry.cluster());
+        assert_eq!(chain.len(), FRAGMENTED_CLUSTERS);
+        let fat = fs.boot_sector().fat_start_sector() as usize * 512 + chain[FRAGMENTED_CLUSTERS - 1] as usize * 4;
+        assert_eq!(looped[fat..fat + 4], chain[0].to_le_bytes());
+
+        let mut crossed = image();
+        cross_link(&mut crossed, README_PATH, FRAGMENTED_PATH).unwrap();
+        let fs = Fat32::new(&crossed).unwrap();
+        let root = fs.root_cluster();
+        let readme = fs.cluster_chain(fs.resolve_path(README_PATH, root).unwrap().cluster());
+        let frag = fs.cluster_chain(fs.resolve_path(FRAGMENTED_PATH, root).unwrap().cluster());
+        assert_eq!(readme[1..], frag[..]);
+
+        let mut outside = image();
+        relink_chain(&mut outside, README_PATH, 0x00100000).unwrap();
+        let fs = Fat32::new(&outside).unwrap();
+        let readme = fs.resolve_path(README_PATH, fs.root_cluster()).unwrap().cluster();
+        assert_eq!(fs.try_cluster_chain(readme), Err(FsError::CorruptFat { cluster: readme, next: 0x00100000 }));
+
+        assert_eq!(loop_chain(&mut image(), "/MISSING.BIN"), Err(FsError::NotFound));
+    }
+
+    #[test]
+    fn test_lfn_checksum_defect() {
+        let mut data = image();
+        let fs = Fat32::new(&data).unwrap();
+        let docs = fs.resolve_path("/My Documents", fs.root_cluster()).unwrap().cluster();
+        let location = fs.locate_entry(docs, "notes about fat32.md").unwrap();
+        break_lfn_checksum(&mut data, LONG_NAME_PATHS[1]).unwrap();
+
+        let short = location.short_offset() as usize;
+        let checksum = lfn_checksum(data[short..short + 11].try_into().unwrap());
+        assert!(location.slots[..location.slots.len() - 1].iter().all(|&s| data[s as usize + 13] != checksum));
+        // Les slots orphelins ne nomment plus l'entrée
+        let fs = Fat32::new(&data).unwrap();
+        assert!(fs.locate_entry(docs, "notes about fat32.md").is_none());
+
+        assert_eq!(break_lfn_checksum(&mut data, README_PATH), Err(FsError::InvalidName));
+    }
+
+    #[test]
+    fn test_truncated_and_zeroed() {
+        let mut data = image();
+        let len = data.len();
+        truncate_fat(&mut data).unwrap();
+        assert!(data.len() < len);
+        // Refusée au montage plutôt que des lectures vides plus tard
+        let expected = Fat32::new(&image()).unwrap().boot_sector().volume_bytes();
+        let actual = data.len() as u64;
+        assert_eq!(Fat32::try_new(&data).err(), Some(FsError::TruncatedImage { expected, actual }));
+
+        let mut data = image();
+        zero_boot_sector(&mut data);
+        assert!(Fat32::new(&data).is_none());
+        assert_eq!(truncate_fat(&mut data), Err(FsError::InvalidGeometry));
+    }
+}
@@ -0,0 +1,148 @@
+//! Périphérique bloc qui échoue sur commande, pour tester les erreurs d'E/S
+//!
+//! Les pannes sont déterministes: secteurs désignés, ou toutes les opérations
+//! après les `n` premières (coupure d'alimentation pendant une écriture).
+//! Une opération en échec ne modifie pas le périphérique.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use crate::fat32::device::BlockDevice;
+use crate::fat32::FsError;
+
+/// Enveloppe de `D` qui injecte des erreurs `FsError::Io`
+#[derive(Debug)]
+pub struct FaultyDevice<D> {
+    inner: D,
+    bad_sectors: Vec<u64>,
+    fail_after: Option<usize>,
+    reads_fail: bool,
+    operations: usize,
+}
+
+impl<D: BlockDevice> FaultyDevice<D> {
+    /// Enveloppe sans panne configurée
+    pub fn new(inner: D) -> Self {
+        FaultyDevice { inner, bad_sectors: Vec::new(), fail_after: None, reads_fail: true, operations: 0 }
+    }
+
+    /// Les accès au secteur `sector` échouent
+    pub fn fail_at(mut self, sector: u64) -> Self {
+        self.bad_sectors.push(sector);
+        self
+    }
+
+    /// Toutes les opérations échouent après les `n` premières
+    pub fn fail_after(mut self, n: usize) -> Self {
+        self.fail_after = Some(n);
+        self
+    }
+
+    /// Les pannes ne touchent que les écritures
+    pub fn writes_only(mut self) -> Self {
+        self.reads_fail = false;
+        self
+    }
+
+    /// Nombre d'opérations demandées, réussies ou non
+    pub fn operations(&self) -> usize {
+        self.operations
+    }
+
+    /// Rend le périphérique enveloppé
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// Compte l'opération et indique si elle doit échouer
+    fn fails(&mut self, sector: u64, write: bool) -> bool {
+        self.operations += 1;
+        if !write && !self.reads_fail {
+            return false;
+        }
+        self.bad_sectors.contains(&sector) || self.fail_after.is_some_and(|n| self.operations > n)
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for FaultyDevice<D> {
+    fn sector_size(&self) -> usize {
+        self.inner.sector_size()
+    }
+
+    fn sector_count(&self) -> u64 {
+        self.inner.sector_count()
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        if self.fails(sector, false) {
+            return Err(FsError::Io);
+        }
+        self.inner.read_sector(sector, buf)
+    }
+
+    /// Une seule opération pour toute la plage, en échec si un secteur de la plage l'est
+    fn read_sectors(&mut self, start: u64, buf: &mut [u8]) -> Result<(), FsError> {
+        let count = (buf.len() / self.inner.sector_size().max(1)) as u64;
+        let bad = self.bad_sectors.iter().any(|&s| s >= start && s < start + count);
+        if self.fails(start, false) || (bad && self.reads_fail) {
+            return Err(FsError::Io);
+        }
+        self.inner.read_sectors(start, buf)
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> Result<(), FsError> {
+        if self.fails(sector, true) {
+            return Err(FsError::Io);
+        }
+        self.inner.write_sector(sector, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fat32::device::{write_changes, SECTOR_SIZE};
+    use crate::fat32::Fat32;
+    use crate::testimg::{TestImage, README_PATH, README_TEXT};
+
+    #[test]
+    fn test_configured_faults() {
+        let mut data = alloc::vec![0u8; 4 * SECTOR_SIZE];
+        let mut device = FaultyDevice::new(&mut data[..]).fail_at(2).writes_only();
+        let mut buf = [0u8; SECTOR_SIZE];
+        assert_eq!(device.read_sector(2, &mut buf), Ok(()));
+        assert_eq!(device.write_sector(2, &[1; SECTOR_SIZE]), Err(FsError::Io));
+        assert_eq!(device.write_sector(1, &[1; SECTOR_SIZE]), Ok(()));
+        assert_eq!(device.read_sector(4, &mut buf), Err(FsError::OutOfBounds));
+        assert_eq!(device.operations(), 4);
+
+        let mut device = FaultyDevice::new(device.into_inner()).fail_after(1);
+        assert_eq!(device.read_sector(1, &mut buf), Ok(()));
+        assert_eq!(buf, [1; SECTOR_SIZE]);
+        assert_eq!(device.read_sector(0, &mut buf), Err(FsError::Io));
+        assert!(data[2 * SECTOR_SIZE..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_interrupted_write_back() {
+        let before = TestImage::default().build().unwrap();
+        let mut after = before.clone();
+        let mut fs = Fat32::new_mut(&mut after).unwrap();
+        let root = fs.root_cluster();
+        fs.write_file(root, "a new file.bin", &[5u8; 3000]).unwrap();
+
+        let changed = write_changes(&mut before.clone(), &before, &after).unwrap();
+        assert!(changed > 1);
+
+        // Coupure après chaque nombre possible de secteurs écrits
+        for n in 0..changed {
+            let mut disk = before.clone();
+            let mut device = FaultyDevice::new(&mut disk).fail_after(n);
+            assert_eq!(write_changes(&mut device, &before, &after), Err(FsError::Io));
+
+            let fs = Fat32::new(&disk).unwrap();
+            let readme = fs.resolve_path(README_PATH, fs.root_cluster()).unwrap();
+            assert_eq!(fs.read_file(&readme), README_TEXT);
+        }
+    }
+}
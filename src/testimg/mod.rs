@@ -0,0 +1,222 @@
+//! Images FAT32 de test: image de démonstration et générateur configurable
+//!
+//! `TestImage` construit une image avec `FsBuilder` puis y ajoute le contenu demandé
+//! (noms longs, fichier fragmenté, arborescence profonde, entrée supprimée).
+//! Les chemins et contenus sont fixes pour que les tests puissent les vérifier.
+//! Le sous-module `corrupt` y injecte ensuite des défauts, `faulty` simule
+//! un périphérique qui tombe en panne.
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::builder::{DirBuilder, FsBuilder, MB};
+use crate::fat32::{Fat32, FormatOptions, FsError};
+
+pub mod corrupt;
+pub mod faulty;
+#[cfg(feature = "sdcard")]
+pub mod sdcard;
+
+/// Fichier toujours présent à la racine
+pub const README_PATH: &str = "/README.TXT";
+/// Contenu de `README_PATH`
+pub const README_TEXT: &[u8] = b"Generated FAT32 test image\n";
+/// Fichiers à nom long (avec `long_names`)
+pub const LONG_NAME_PATHS: &[&str] = &["/Long File Name.txt", "/My Documents/notes about fat32.md"];
+/// Fichier dont les clusters ne sont pas contigus (avec `fragmented`)
+pub const FRAGMENTED_PATH: &str = "/FRAG.BIN";
+/// Nombre de clusters de `FRAGMENTED_PATH`
+pub const FRAGMENTED_CLUSTERS: usize = 4;
+/// Nom de l'entrée supprimée, récupérable avec `undelete` (avec `deleted`)
+pub const DELETED_NAME: &str = "DELETED.TXT";
+/// Contenu de `DELETED_NAME` avant sa suppression
+pub const DELETED_TEXT: &[u8] = b"This file was deleted\n";
+
+/// Paramètres d'une image de test générée
+#[derive(Debug, Clone)]
+pub struct TestImage {
+    /// Taille de l'image en octets
+    pub size: usize,
+    /// Taille des clusters en octets
+    pub cluster_size: u32,
+    /// Label du volume
+    pub label: String,
+    /// Ajoute les fichiers de `LONG_NAME_PATHS`
+    pub long_names: bool,
+    /// Ajoute `FRAGMENTED_PATH`
+    pub fragmented: bool,
+    /// Profondeur de `/DEEP/D1/.../Dn/LEAF.TXT` (0 = pas d'arborescence)
+    pub depth: usize,
+    /// Ajoute l'entrée supprimée `DELETED_NAME` à la racine
+    pub deleted: bool,
+}
+
+impl Default for TestImage {
+    fn default() -> Self {
+        TestImage {
+            size: 1024 * 1024,
+            cluster_size: 512,
+            label: String::from("TEST IMAGE"),
+            long_names: false,
+            fragmented: false,
+            depth: 0,
+            deleted: false,
+        }
+    }
+}
+
+impl TestImage {
+    /// Image de `size` octets contenant seulement `README_PATH`
+    pub fn new(size: usize) -> Self {
+        TestImage { size, ..TestImage::default() }
+    }
+
+    pub fn cluster_size(mut self, bytes: u32) -> Self {
+        self.cluster_size = bytes;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = String::from(label);
+        self
+    }
+
+    pub fn long_names(mut self) -> Self {
+        self.long_names = true;
+        self
+    }
+
+    pub fn fragmented(mut self) -> Self {
+        self.fragmented = true;
+        self
+    }
+
+    pub fn deep_tree(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn deleted(mut self) -> Self {
+        self.deleted = true;
+        self
+    }
+
+    /// Chemin du fichier au fond de l'arborescence profonde
+    pub fn leaf_path(&self) -> String {
+        let mut path = String::from("/DEEP");
+        for level in 1..=self.depth {
+            path.push_str(&format!("/D{}", level));
+        }
+        path.push_str("/LEAF.TXT");
+        path
+    }
+
+    /// Contenu de `FRAGMENTED_PATH`: les octets 0, 1, 2... modulo 251
+    pub fn fragmented_data(&self) -> Vec<u8> {
+        (0..FRAGMENTED_CLUSTERS * self.cluster_size as usize).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Génère l'image
+    pub fn build(&self) -> Result<Vec<u8>, FsError> {
+        let options = FormatOptions::default()
+            .with_cluster_size(self.cluster_size)?
+            .with_label(&self.label)?;
+        let mut builder = FsBuilder::new(self.size).options(options).file(&README_PATH[1..], README_TEXT);
+        if self.long_names {
+            builder = builder
+                .file(&LONG_NAME_PATHS[0][1..], b"A file with a long name\n")
+                .dir("My Documents", |d| d.file("notes about fat32.md", b"# Notes\n"));
+        }
+        if self.depth > 0 {
+            builder = builder.dir("DEEP", |d| deep_tree(d, 1, self.depth));
+        }
+        let mut data = builder.build()?;
+
+        let mut fs = Fat32::new_mut(&mut data).ok_or(FsError::InvalidGeometry)?;
+        let root = fs.root_cluster();
+        if self.fragmented {
+            write_fragmented(&mut fs, root, &FRAGMENTED_PATH[1..], &self.fragmented_data())?;
+        }
+
+        // En dernier, pour que ses clusters libérés ne soient pas réutilisés
+        if self.deleted {
+            fs.write_file(root, DELETED_NAME, DELETED_TEXT)?;
+            fs.remove_file(root, DELETED_NAME)?;
+        }
+
+        Ok(data)
+    }
+}
+
+/// Répertoires `D<level>` à `D<depth>` emboîtés, avec `LEAF.TXT` au fond
+fn deep_tree(dir: DirBuilder, level: usize, depth: usize) -> DirBuilder {
+    if level > depth {
+        return dir.file("LEAF.TXT", format!("depth {}\n", depth).as_bytes());
+    }
+    dir.dir(&format!("D{}", level), |d| deep_tree(d, level + 1, depth))
+}
+
+/// Écrit un fichier dont un cluster sur deux est séparé du suivant par un trou
+///
+/// Des clusters sont réservés puis libérés un sur deux: l'allocation
+/// (premier cluster libre) remplit les trous avant de continuer plus loin.
+fn write_fragmented(fs: &mut Fat32, dir: u32, name: &str, data: &[u8]) -> Result<(), FsError> {
+    let reserved = (0..FRAGMENTED_CLUSTERS)
+        .map(|_| fs.allocate_cluster())
+        .collect::<Result<Vec<u32>, FsError>>()?;
+
+    for &cluster in reserved.iter().step_by(2) {
+        fs.write_fat_entry(cluster, 0)?;
+    }
+    fs.write_file(dir, name, data)?;
+    for &cluster in reserved.iter().skip(1).step_by(2) {
+        fs.write_fat_entry(cluster, 0)?;
+    }
+    fs.invalidate_free_count()
+}
+
+/// Image de démonstration du shell (1 Mio, clusters de 512 octets)
+///
+/// Contient HELLO.TXT, README.MD, DOCS/INFO.TXT et l'entrée supprimée
+/// récupérable OLD.TXT.
+pub fn demo_image() -> Vec<u8> {
+    let options = FormatOptions {
+        volume_serial: 0x20240A4E,
+        ..FormatOptions::default().with_label("FAT32 DEMO").expect("valid label")
+    };
+    let mut data = FsBuilder::new(MB)
+        .options(options)
+        .file("HELLO.TXT", b"Hello! This is a test file for FAT32 shell.\n")
+        .dir("DOCS", |d| d.file("INFO.TXT", b"Info file inside DOCS directory.\nTest OK!\n"))
+        .file("README.MD", b"# FAT32 Filesystem Demo\n\nThis is a demo FAT32 image.\nCreated for ESGI 4A Rust course.\n")
+        .file("OLD.TXT", b"This file was deleted earlier\n")
+        .build()
+        .expect("demo image fits in 1 MiB");
+
+    let mut fs = Fat32::new_mut(&mut data).expect("demo image is valid");
+    let root = fs.root_cluster();
+    fs.remove_file(root, "OLD.TXT").expect("OLD.TXT was just written");
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_image() {
+        let image = demo_image();
+        let fs = Fat32::new(&image).unwrap();
+        let readme = fs.resolve_path("/README.MD", fs.root_cluster()).unwrap();
+        assert!(fs.read_file(&readme).starts_with(b"# FAT32 Filesystem Demo"));
+        assert_eq!(fs.list_deleted(fs.root_cluster()).len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_geometry() {
+        assert!(TestImage::new(1024 * 1024).cluster_size(1000).build().is_err());
+        assert!(TestImage::default().label("FAR TOO LONG LABEL").build().is_err());
+    }
+}
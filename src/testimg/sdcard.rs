@@ -0,0 +1,257 @@
+//! Carte SD simulée derrière un `SpiTransport`, pour tester `SdCard`
+//!
+//! La carte répond octet par octet comme une vraie carte en mode SPI:
+//! réponse R1 après un octet d'attente, jetons de données, réponse
+//! d'écriture suivie d'octets « occupé ». Les commandes reçues sont notées.
+//! `powered_off` donne une carte v2 qui doit d'abord passer la séquence
+//! d'initialisation; ACMD41 y répond « en cours » deux fois avant d'aboutir.
+
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::fat32::device::SECTOR_SIZE;
+use crate::fat32::sdcard::SpiTransport;
+use crate::fat32::FsError;
+
+/// R1 pour une adresse hors de la carte
+const R1_PARAMETER_ERROR: u8 = 0x40;
+/// R1 pour une commande inconnue
+const R1_ILLEGAL_COMMAND: u8 = 0x04;
+/// R1 tant que la carte n'est pas initialisée
+const R1_IDLE: u8 = 0x01;
+/// Réponses « en cours » de ACMD41 avant que la carte soit prête
+const OP_COND_POLLS: u8 = 2;
+
+/// Ce que la carte attend du prochain octet reçu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Command,
+    /// CMD18 en cours: envoie les blocs à partir de ce secteur
+    Streaming(u64),
+    /// CMD24: attend le jeton de données pour ce secteur
+    WriteToken(u64),
+    /// CMD24: reçoit 512 octets puis 2 octets de CRC
+    WriteData(u64),
+}
+
+/// Carte SD simulée sur une image en mémoire
+#[derive(Debug)]
+pub struct SimulatedCard {
+    image: Vec<u8>,
+    block_addressing: bool,
+    /// `None`: carte prête; sinon ACMD41 restants avant qu'elle le soit
+    initializing: Option<u8>,
+    app_command: bool,
+    selected: bool,
+    mode: Mode,
+    frame: Vec<u8>,
+    incoming: Vec<u8>,
+    outgoing: VecDeque<u8>,
+    commands: Vec<u8>,
+}
+
+impl SimulatedCard {
+    /// Carte prête (déjà initialisée) contenant `image`
+    pub fn new(image: Vec<u8>, block_addressing: bool) -> Self {
+        SimulatedCard {
+            image,
+            block_addressing,
+            initializing: None,
+            app_command: false,
+            selected: false,
+            mode: Mode::Command,
+            frame: Vec::new(),
+            incoming: Vec::new(),
+            outgoing: VecDeque::new(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// Carte v2 à la mise sous tension: haute capacité (SDHC) ou SDSC
+    pub fn powered_off(image: Vec<u8>, high_capacity: bool) -> Self {
+        SimulatedCard { initializing: Some(OP_COND_POLLS), ..SimulatedCard::new(image, high_capacity) }
+    }
+
+    /// Index des commandes reçues, dans l'ordre
+    pub fn commands(&self) -> &[u8] {
+        &self.commands
+    }
+
+    /// Contenu de la carte
+    pub fn into_image(self) -> Vec<u8> {
+        self.image
+    }
+
+    /// Secteur désigné par l'argument d'une commande
+    fn sector(&self, arg: u32) -> Option<u64> {
+        let sector = if self.block_addressing { arg as u64 } else { arg as u64 / SECTOR_SIZE as u64 };
+        (sector < (self.image.len() / SECTOR_SIZE) as u64).then_some(sector)
+    }
+
+    /// Met en file un bloc de données: attente, jeton, secteur, CRC
+    fn queue_block(&mut self, sector: u64) {
+        let start = sector as usize * SECTOR_SIZE;
+        self.outgoing.extend([0xFF, 0xFE]);
+        self.outgoing.extend(&self.image[start..start + SECTOR_SIZE]);
+        self.outgoing.extend([0x00, 0x00]);
+    }
+
+    /// Registre CSD: v2 pour une carte haute capacité, v1 sinon
+    fn csd(&self) -> [u8; 16] {
+        let sectors = (self.image.len() / SECTOR_SIZE) as u64;
+        let mut csd = [0u8; 16];
+        if self.block_addressing {
+            let c_size = sectors / 1024 - 1;
+            csd[0] = 0x40;
+            csd[7] = (c_size >> 16) as u8 & 0x3F;
+            csd[8] = (c_size >> 8) as u8;
+            csd[9] = c_size as u8;
+        } else {
+            // Blocs de 512 octets, le plus petit multiplicateur où C_SIZE tient sur 12 bits
+            let mult = (0..8).find(|mult| sectors >> (mult + 2) <= 4096).unwrap_or(7);
+            let c_size = (sectors >> (mult + 2)) - 1;
+            csd[5] = 0x09;
+            csd[6] = (c_size >> 10) as u8 & 0x03;
+            csd[7] = (c_size >> 2) as u8;
+            csd[8] = (c_size << 6) as u8;
+            csd[9] = (mult >> 1) as u8;
+            csd[10] = (mult << 7) as u8;
+        }
+        csd
+    }
+
+    fn execute(&mut self, index: u8, arg: u32) {
+        self.commands.push(index);
+        self.outgoing.clear();
+        self.outgoing.push_back(0xFF);
+        let app_command = core::mem::take(&mut self.app_command);
+        let r1 = if self.initializing.is_some() { R1_IDLE } else { 0x00 };
+        match (index, self.sector(arg)) {
+            (0, _) => {
+                self.initializing = Some(OP_COND_POLLS);
+                self.mode = Mode::Command;
+                self.outgoing.push_back(R1_IDLE);
+            }
+            (8, _) => {
+                // R7: R1, puis la tension et le motif renvoyés
+                self.outgoing.push_back(r1);
+                self.outgoing.extend([0x00, 0x00, (arg >> 8) as u8 & 0x0F, arg as u8]);
+            }
+            (9, _) => {
+                self.outgoing.push_back(r1);
+                self.outgoing.extend([0xFF, 0xFE]);
+                self.outgoing.extend(self.csd());
+                self.outgoing.extend([0x00, 0x00]);
+            }
+            (16, _) if arg == SECTOR_SIZE as u32 => self.outgoing.push_back(r1),
+            (16, _) => self.outgoing.push_back(r1 | R1_PARAMETER_ERROR),
+            (55, _) => {
+                self.app_command = true;
+                self.outgoing.push_back(r1);
+            }
+            (41, _) if app_command => {
+                // Une carte haute capacité reste occupée si l'hôte n'annonce pas HCS
+                let hcs = arg & (1 << 30) != 0;
+                self.initializing = match self.initializing {
+                    Some(0) if hcs || !self.block_addressing => None,
+                    Some(polls) => Some(polls.saturating_sub(1)),
+                    None => None,
+                };
+                let r1 = if self.initializing.is_some() { R1_IDLE } else { 0x00 };
+                self.outgoing.push_back(r1);
+            }
+            (58, _) => {
+                // R3: R1, puis l'OCR (bit 31: prête, bit 30: CCS)
+                let ccs = if self.block_addressing { 0x40 } else { 0x00 };
+                let ready = if self.initializing.is_none() { 0x80 } else { 0x00 };
+                self.outgoing.push_back(r1);
+                self.outgoing.extend([ready | ccs, 0xFF, 0x80, 0x00]);
+            }
+            _ if self.initializing.is_some() => self.outgoing.push_back(R1_IDLE | R1_ILLEGAL_COMMAND),
+            (12, _) => {
+                // Octet de bourrage, R1, puis occupé le temps d'arrêter
+                self.mode = Mode::Command;
+                self.outgoing.clear();
+                self.outgoing.extend([0x3C, 0x00, 0x00, 0x00]);
+            }
+            (17, Some(sector)) => {
+                self.outgoing.push_back(0x00);
+                self.queue_block(sector);
+            }
+            (18, Some(sector)) => {
+                self.outgoing.push_back(0x00);
+                self.mode = Mode::Streaming(sector);
+            }
+            (24, Some(sector)) => {
+                self.outgoing.push_back(0x00);
+                self.mode = Mode::WriteToken(sector);
+            }
+            (17 | 18 | 24, None) => self.outgoing.push_back(R1_PARAMETER_ERROR),
+            _ => self.outgoing.push_back(R1_ILLEGAL_COMMAND),
+        }
+    }
+
+    /// Traite un octet reçu de l'hôte
+    fn receive(&mut self, byte: u8) {
+        match self.mode {
+            Mode::WriteToken(sector) => {
+                if byte == 0xFE {
+                    self.mode = Mode::WriteData(sector);
+                    self.incoming.clear();
+                }
+            }
+            Mode::WriteData(sector) => {
+                self.incoming.push(byte);
+                if self.incoming.len() == SECTOR_SIZE + 2 {
+                    let start = sector as usize * SECTOR_SIZE;
+                    self.image[start..start + SECTOR_SIZE].copy_from_slice(&self.incoming[..SECTOR_SIZE]);
+                    self.mode = Mode::Command;
+                    self.outgoing.extend([0xE5, 0x00, 0x00]);
+                }
+            }
+            Mode::Command | Mode::Streaming(_) => {
+                if self.frame.is_empty() && byte & 0xC0 != 0x40 {
+                    return;
+                }
+                self.frame.push(byte);
+                if self.frame.len() == 6 {
+                    let arg = u32::from_be_bytes([self.frame[1], self.frame[2], self.frame[3], self.frame[4]]);
+                    let index = self.frame[0] & 0x3F;
+                    self.frame.clear();
+                    self.execute(index, arg);
+                }
+            }
+        }
+    }
+}
+
+impl SpiTransport for SimulatedCard {
+    fn transfer(&mut self, byte: u8) -> Result<u8, FsError> {
+        if !self.selected {
+            return Ok(0xFF);
+        }
+        let reply = self.outgoing.pop_front().unwrap_or(0xFF);
+        self.receive(byte);
+        if let Mode::Streaming(sector) = self.mode {
+            // Le bloc suivant est prêt dès que le précédent est parti
+            if self.outgoing.is_empty() && self.frame.is_empty() {
+                let last = (self.image.len() / SECTOR_SIZE) as u64;
+                if sector < last {
+                    self.queue_block(sector);
+                    self.mode = Mode::Streaming(sector + 1);
+                }
+            }
+        }
+        Ok(reply)
+    }
+
+    fn select(&mut self, selected: bool) -> Result<(), FsError> {
+        self.selected = selected;
+        if !selected {
+            self.frame.clear();
+            self.outgoing.clear();
+        }
+        Ok(())
+    }
+}
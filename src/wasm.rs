@@ -0,0 +1,141 @@
+//! Façade JavaScript (feature wasm) pour un inspecteur d'images dans le navigateur
+//!
+//! Compilée pour `wasm32-unknown-unknown` sans la feature std, puis passée à
+//! `wasm-bindgen`:
+//!
+//! ```js
+//! const image = new Image(new Uint8Array(await file.arrayBuffer()));
+//! for (const entry of image.list("/")) console.log(entry.name, entry.size);
+//! const bytes = image.read("/README.TXT"); // Uint8Array
+//! ```
+//!
+//! Les erreurs deviennent des exceptions JavaScript portant le message de `FsError`.
+
+extern crate alloc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use crate::fat32::{DirEntry, Fat32, FsError};
+
+/// Image FAT32 chargée en mémoire
+#[wasm_bindgen]
+pub struct Image {
+    data: Vec<u8>,
+}
+
+/// Entrée d'un répertoire listé
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Nom long, ou nom court à défaut
+    pub name: String,
+    pub size: u32,
+    pub cluster: u32,
+    #[wasm_bindgen(js_name = isDirectory)]
+    pub is_directory: bool,
+}
+
+fn js_error(error: FsError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+impl Image {
+    fn fs(&self) -> Result<Fat32<'_>, FsError> {
+        Fat32::try_new(&self.data)
+    }
+
+    /// Entrée désignée par `path`, `None` pour la racine
+    fn lookup(fs: &Fat32<'_>, path: &str) -> Result<Option<DirEntry>, FsError> {
+        if path.trim_matches('/').is_empty() {
+            return Ok(None);
+        }
+        fs.try_resolve_path(path, fs.root_cluster()).map(Some)
+    }
+
+    fn list_entries(&self, path: &str) -> Result<Vec<Entry>, FsError> {
+        let fs = self.fs()?;
+        let cluster = match Self::lookup(&fs, path)? {
+            None => fs.root_cluster(),
+            Some(entry) if entry.is_directory() => entry.cluster(),
+            Some(_) => return Err(FsError::NotFound),
+        };
+        let entries = fs.try_read_directory(cluster)?;
+        Ok(entries
+            .into_iter()
+            .map(|(entry, long_name)| Entry {
+                name: long_name.unwrap_or_else(|| entry.display_name()),
+                size: entry.size,
+                cluster: entry.cluster(),
+                is_directory: entry.is_directory(),
+            })
+            .collect())
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, FsError> {
+        let fs = self.fs()?;
+        match Self::lookup(&fs, path)? {
+            Some(entry) => fs.try_read_file(&entry),
+            None => Err(FsError::IsADirectory),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Image {
+    /// Charge une image (copie du `Uint8Array`); exception si ce n'est pas du FAT32
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<Image, JsError> {
+        match Fat32::try_new(&data) {
+            Ok(_) => Ok(Image { data }),
+            Err(error) => Err(js_error(error)),
+        }
+    }
+
+    /// Nom de volume
+    #[wasm_bindgen(getter)]
+    pub fn label(&self) -> String {
+        self.fs().map(|fs| fs.boot_sector().label().to_string()).unwrap_or_default()
+    }
+
+    /// Taille de l'image en octets
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Entrées du répertoire `path` ("/" pour la racine)
+    pub fn list(&self, path: &str) -> Result<Vec<Entry>, JsError> {
+        self.list_entries(path).map_err(js_error)
+    }
+
+    /// Contenu du fichier `path` (`Uint8Array`)
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, JsError> {
+        self.read_file(path).map_err(js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testimg::{TestImage, README_PATH, README_TEXT};
+
+    // Les méthodes exportées construisent des `JsError`, utilisables seulement
+    // sous wasm: les tests passent par les fonctions internes.
+    #[test]
+    fn test_list_and_read() {
+        let data = TestImage::default().long_names().build().unwrap();
+        let image = Image { data };
+        assert_eq!(image.label(), "TEST IMAGE");
+
+        let root = image.list_entries("/").unwrap();
+        assert!(root.iter().any(|e| e.name == "My Documents" && e.is_directory));
+        let docs = image.list_entries("/My Documents").unwrap();
+        assert!(docs.iter().any(|e| e.name == "notes about fat32.md" && !e.is_directory));
+
+        assert_eq!(image.read_file(README_PATH).unwrap(), README_TEXT);
+        assert_eq!(image.read_file("/"), Err(FsError::IsADirectory));
+        assert_eq!(image.list_entries(README_PATH), Err(FsError::NotFound));
+    }
+}
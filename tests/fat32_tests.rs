@@ -2,181 +2,33 @@
 //!
 //! These tests verify the FAT32 filesystem implementation.
 
+use fat32_exam::builder::{FsBuilder, MB};
 use fat32_exam::fat32::*;
+use fat32_exam::shell::Output;
 
-/// Create a minimal FAT32 image for testing
-fn create_test_image() -> Vec<u8> {
-    let mut data = vec![0u8; 1024 * 1024]; // 1MB image
-
-    // === Boot Sector (Sector 0) ===
-    // Jump instruction
-    data[0] = 0xEB;
-    data[1] = 0x58;
-    data[2] = 0x90;
-
-    // OEM Name
-    data[3..11].copy_from_slice(b"MSWIN4.1");
-
-    // Bytes per sector = 512 (0x0200)
-    data[11] = 0x00;
-    data[12] = 0x02;
-
-    // Sectors per cluster = 1
-    data[13] = 1;
-
-    // Reserved sectors = 32
-    data[14] = 32;
-    data[15] = 0;
-
-    // Number of FATs = 2
-    data[16] = 2;
-
-    // Root entry count (FAT32 = 0)
-    data[17] = 0;
-    data[18] = 0;
-
-    // Total sectors 16-bit (FAT32 = 0)
-    data[19] = 0;
-    data[20] = 0;
-
-    // Media type
-    data[21] = 0xF8;
-
-    // FAT size 16-bit (FAT32 = 0)
-    data[22] = 0;
-    data[23] = 0;
-
-    // Sectors per track
-    data[24] = 63;
-    data[25] = 0;
-
-    // Number of heads
-    data[26] = 255;
-    data[27] = 0;
-
-    // Hidden sectors
-    data[28..32].copy_from_slice(&0u32.to_le_bytes());
-
-    // Total sectors 32-bit = 2048
-    let total_sectors: u32 = 2048;
-    data[32..36].copy_from_slice(&total_sectors.to_le_bytes());
-
-    // === FAT32 Extended Boot Sector ===
-
-    // Sectors per FAT (FAT32) = 16
-    data[36..40].copy_from_slice(&16u32.to_le_bytes());
-
-    // Extended flags
-    data[40] = 0;
-    data[41] = 0;
-
-    // FS Version
-    data[42] = 0;
-    data[43] = 0;
-
-    // Root cluster = 2
-    data[44..48].copy_from_slice(&2u32.to_le_bytes());
-
-    // FSInfo sector
-    data[48] = 1;
-    data[49] = 0;
-
-    // Backup boot sector
-    data[50] = 6;
-    data[51] = 0;
-
-    // Boot signature
-    data[510] = 0x55;
-    data[511] = 0xAA;
-
-    // === FAT Table (starts at sector 32, offset 16384) ===
-    let fat_start = 32 * 512;
-
-    // FAT entry 0: Media type
-    data[fat_start..fat_start + 4].copy_from_slice(&0x0FFFFFF8u32.to_le_bytes());
+/// Shell output collected into a string
+struct Sink(String);
 
-    // FAT entry 1: End of chain marker
-    data[fat_start + 4..fat_start + 8].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-    // FAT entry 2 (root directory): End of chain
-    data[fat_start + 8..fat_start + 12].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-    // FAT entry 3 (DOCS directory): End of chain
-    data[fat_start + 12..fat_start + 16].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-    // FAT entry 4 (test file): Points to cluster 5
-    data[fat_start + 16..fat_start + 20].copy_from_slice(&5u32.to_le_bytes());
-
-    // FAT entry 5: End of chain
-    data[fat_start + 20..fat_start + 24].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-    // === Data Region (starts at sector 64, offset 32768) ===
-    // Cluster 2 = Root directory
-    let root_dir = 64 * 512;
-
-    // Entry 1: TEST.TXT file
-    data[root_dir..root_dir + 8].copy_from_slice(b"TEST    ");
-    data[root_dir + 8..root_dir + 11].copy_from_slice(b"TXT");
-    data[root_dir + 11] = 0x20; // Archive
-    data[root_dir + 26..root_dir + 28].copy_from_slice(&4u16.to_le_bytes()); // Cluster low = 4
-    data[root_dir + 28..root_dir + 32].copy_from_slice(&13u32.to_le_bytes()); // Size = 13 bytes
-
-    // Entry 2: DOCS directory
-    data[root_dir + 32..root_dir + 40].copy_from_slice(b"DOCS    ");
-    data[root_dir + 40..root_dir + 43].copy_from_slice(b"   ");
-    data[root_dir + 43] = 0x10; // Directory
-    data[root_dir + 58..root_dir + 60].copy_from_slice(&3u16.to_le_bytes()); // Cluster low = 3
-
-    // Entry 3: README.MD file
-    data[root_dir + 64..root_dir + 72].copy_from_slice(b"README  ");
-    data[root_dir + 72..root_dir + 75].copy_from_slice(b"MD ");
-    data[root_dir + 75] = 0x20; // Archive
-    data[root_dir + 90..root_dir + 92].copy_from_slice(&6u16.to_le_bytes()); // Cluster low = 6
-    data[root_dir + 92..root_dir + 96].copy_from_slice(&19u32.to_le_bytes()); // Size = 19 bytes
-
-    // FAT entry 6: End of chain
-    data[fat_start + 24..fat_start + 28].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-    // === Cluster 3 = DOCS directory ===
-    let docs_dir = 65 * 512;
-
-    // . entry
-    data[docs_dir..docs_dir + 8].copy_from_slice(b".       ");
-    data[docs_dir + 8..docs_dir + 11].copy_from_slice(b"   ");
-    data[docs_dir + 11] = 0x10;
-    data[docs_dir + 26..docs_dir + 28].copy_from_slice(&3u16.to_le_bytes());
-
-    // .. entry
-    data[docs_dir + 32..docs_dir + 40].copy_from_slice(b"..      ");
-    data[docs_dir + 40..docs_dir + 43].copy_from_slice(b"   ");
-    data[docs_dir + 43] = 0x10;
-    data[docs_dir + 58..docs_dir + 60].copy_from_slice(&0u16.to_le_bytes()); // 0 = root
-
-    // INFO.TXT in DOCS
-    data[docs_dir + 64..docs_dir + 72].copy_from_slice(b"INFO    ");
-    data[docs_dir + 72..docs_dir + 75].copy_from_slice(b"TXT");
-    data[docs_dir + 75] = 0x20;
-    data[docs_dir + 90..docs_dir + 92].copy_from_slice(&7u16.to_le_bytes());
-    data[docs_dir + 92..docs_dir + 96].copy_from_slice(&18u32.to_le_bytes());
-
-    // FAT entry 7: End of chain
-    data[fat_start + 28..fat_start + 32].copy_from_slice(&0x0FFFFFFFu32.to_le_bytes());
-
-    // === File Contents ===
-
-    // Cluster 4-5: TEST.TXT content
-    let test_file = 66 * 512;
-    data[test_file..test_file + 13].copy_from_slice(b"Hello, FAT32!");
-
-    // Cluster 6: README.MD content (19 bytes)
-    let readme_file = 68 * 512;
-    data[readme_file..readme_file + 19].copy_from_slice(b"# FAT32 Test Image\n");
+impl Output for Sink {
+    fn write_str(&mut self, s: &str) {
+        self.0.push_str(s);
+    }
+}
 
-    // Cluster 7: INFO.TXT content
-    let info_file = 69 * 512;
-    data[info_file..info_file + 18].copy_from_slice(b"Info file content\n");
+/// Content of CHAIN.BIN: 600 bytes, two 512-byte clusters
+fn chain_bin() -> Vec<u8> {
+    (0..600).map(|i| (i % 251) as u8).collect()
+}
 
-    data
+/// Create a minimal FAT32 image for testing
+fn create_test_image() -> Vec<u8> {
+    FsBuilder::new(MB)
+        .file("TEST.TXT", b"Hello, FAT32!")
+        .dir("DOCS", |d| d.file("INFO.TXT", b"Info file content\n"))
+        .file("README.MD", b"# FAT32 Test Image\n")
+        .file("CHAIN.BIN", &chain_bin())
+        .build()
+        .unwrap()
 }
 
 #[test]
@@ -197,13 +49,14 @@ fn test_root_directory_listing() {
 
     let entries = fs.read_directory(fs.root_cluster());
 
-    // Should have TEST.TXT, DOCS, and README.MD
-    assert_eq!(entries.len(), 3);
+    // Should have TEST.TXT, DOCS, README.MD and CHAIN.BIN
+    assert_eq!(entries.len(), 4);
 
     let names: Vec<String> = entries.iter().map(|e| e.display_name()).collect();
     assert!(names.contains(&String::from("TEST.TXT")));
     assert!(names.contains(&String::from("DOCS")));
     assert!(names.contains(&String::from("README.MD")));
+    assert!(names.contains(&String::from("CHAIN.BIN")));
 }
 
 #[test]
@@ -328,12 +181,16 @@ fn test_cluster_chain() {
     let image = create_test_image();
     let fs = Fat32::new(&image).unwrap();
 
-    // TEST.TXT spans clusters 4 and 5
-    let entry = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap();
-    let data = fs.read_cluster_chain(entry.cluster());
+    // CHAIN.BIN spans two clusters
+    let entry = fs.find_entry(fs.root_cluster(), "CHAIN.BIN").unwrap();
+    let chain = fs.cluster_chain(entry.cluster());
+    assert_eq!(chain.len(), 2);
 
     // Should have data from 2 clusters (1 sector each = 512 bytes per cluster)
-    assert!(data.len() >= 512);
+    let data = fs.read_cluster_chain(entry.cluster());
+    assert_eq!(data.len(), 1024);
+    assert_eq!(data[..600], chain_bin()[..]);
+    assert_eq!(fs.read_file(&entry), chain_bin());
 }
 
 #[test]
@@ -352,3 +209,778 @@ fn test_directory_attributes() {
         }
     }
 }
+
+#[test]
+fn test_cluster_chain_and_offsets() {
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let root = fs.root_cluster();
+    fs.write_file(root, "BIG.BIN", &[1u8; 1200]).unwrap();
+
+    // Three clusters allocated one after the other
+    let entry = fs.find_entry(root, "BIG.BIN").unwrap();
+    let first = entry.cluster();
+    let chain = fs.cluster_chain(first);
+    assert_eq!(chain, vec![first, first + 1, first + 2]);
+    assert_eq!(cluster_runs(&chain), vec![(first, 3)]);
+
+    // Cluster 2 is the first sector of the data region, one sector per cluster
+    let data_start = fs.boot_sector().data_start_sector() as u64;
+    assert_eq!(fs.cluster_offset(2), data_start * 512);
+    assert_eq!(fs.cluster_offset(first), (data_start + first as u64 - 2) * 512);
+}
+
+#[test]
+fn test_fat_link_outside_volume() {
+    use fat32_exam::testimg::corrupt;
+
+    // TEST.TXT: its last cluster links past the last cluster of the 1MB volume
+    let mut image = create_test_image();
+    corrupt::relink_chain(&mut image, "/TEST.TXT", 0x00100000).unwrap();
+    let fs = Fat32::new(&image).unwrap();
+
+    let entry = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap();
+    // The lenient chain stops before the bad link
+    let chain = fs.cluster_chain(entry.cluster());
+    assert_eq!(chain[0], entry.cluster());
+    let last = chain[chain.len() - 1];
+    let error = FsError::CorruptFat { cluster: last, next: 0x00100000 };
+    assert_eq!(fs.try_cluster_chain(entry.cluster()), Err(error));
+    assert_eq!(fs.try_read_file(&entry), Err(error));
+    let message = format!("Corrupted FAT: cluster {} links to cluster 1048576 outside the volume", last);
+    assert_eq!(error.to_string(), message);
+}
+
+#[test]
+fn test_entry_start_outside_volume() {
+    // TEST.TXT: the directory entry itself points past the last cluster
+    let mut image = create_test_image();
+    let short = {
+        let fs = Fat32::new(&image).unwrap();
+        fs.locate_entry(fs.root_cluster(), "TEST.TXT").unwrap().short_offset() as usize
+    };
+    image[short + 20..short + 22].copy_from_slice(&0x0010u16.to_le_bytes());
+    image[short + 26..short + 28].copy_from_slice(&0u16.to_le_bytes());
+    let fs = Fat32::new(&image).unwrap();
+
+    let entry = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap();
+    let error = FsError::CorruptFat { cluster: 0, next: 0x00100000 };
+    assert_eq!(fs.try_cluster_chain(entry.cluster()), Err(error));
+    assert_eq!(fs.try_read_file(&entry), Err(error));
+}
+
+#[test]
+fn test_illegal_sectors_per_cluster() {
+    for sectors_per_cluster in [0u8, 3, 255] {
+        let mut image = create_test_image();
+        image[13] = sectors_per_cluster;
+        assert!(Fat32::new(&image).is_none());
+        let error = Fat32::try_new(&image).err().unwrap();
+        assert_eq!(error, FsError::InvalidClusterSize(sectors_per_cluster));
+        assert!(error.to_string().contains("power of two"));
+    }
+}
+
+#[test]
+fn test_truncated_image() {
+    let mut image = create_test_image();
+    image.truncate(512 * 1024);
+    let error = Fat32::try_new(&image).err().unwrap();
+    assert_eq!(error, FsError::TruncatedImage { expected: 1024 * 1024, actual: 512 * 1024 });
+    assert_eq!(error.to_string(), "Truncated image: the boot sector describes 1048576 bytes, the image holds 524288");
+    assert!(Fat32::new_mut(&mut image).is_none());
+
+    // Extra bytes after the volume are fine
+    image.resize(2 * 1024 * 1024, 0);
+    assert!(Fat32::new(&image).is_some());
+}
+
+#[test]
+fn test_verify_fat_mirrors() {
+    let mut image = create_test_image();
+    let fs = Fat32::new(&image).unwrap();
+    assert!(fs.verify_fat_mirrors().unwrap().is_empty());
+
+    // Clear TEST.TXT's cluster in the second copy only
+    let cluster = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap().cluster();
+    let entry = fs.boot_sector().fat_offset(1) as usize + cluster as usize * 4;
+    image[entry..entry + 4].fill(0);
+    let fs = Fat32::new(&image).unwrap();
+    let mismatches = fs.verify_fat_mirrors().unwrap();
+    assert_eq!(mismatches, vec![FatMismatch { copy: 1, cluster, primary: 0x0FFFFFFF, mirror: 0 }]);
+
+    // Writes keep both copies in step and leave the old mismatch alone
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let root = fs.root_cluster();
+    fs.write_file(root, "NEW.BIN", &[7u8; 2000]).unwrap();
+    assert_eq!(fs.verify_fat_mirrors().unwrap(), mismatches);
+}
+
+#[test]
+fn test_size_chain_mismatch() {
+    use fat32_exam::testimg::corrupt;
+
+    // TEST.TXT holds 13 bytes but its chain goes on to a second cluster
+    let mut image = create_test_image();
+    let extra = Fat32::new_mut(&mut image).unwrap().allocate_cluster().unwrap();
+    corrupt::relink_chain(&mut image, "/TEST.TXT", extra).unwrap();
+    let fs = Fat32::new(&image).unwrap();
+
+    let entry = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap();
+    let first = entry.cluster();
+    assert_eq!(fs.cluster_chain(first), vec![first, extra]);
+    let (data, mismatch) = fs.read_file_checked(&entry).unwrap();
+    assert_eq!(data, b"Hello, FAT32!");
+    assert_eq!(mismatch, Some(Anomaly::LongChain { cluster: first, size: 13 }));
+    assert_eq!(fs.try_read_file(&entry).unwrap(), data);
+
+    // A size past the two clusters: partial data, flagged
+    let mut grown = entry.clone();
+    grown.size = 2000;
+    let (data, mismatch) = fs.read_file_checked(&grown).unwrap();
+    assert_eq!(data.len(), 1024);
+    assert_eq!(mismatch, Some(Anomaly::ShortChain { cluster: first, size: 2000, available: 1024 }));
+    assert_eq!(fs.try_read_file(&grown).unwrap().len(), 1024);
+    assert_eq!(mismatch.unwrap().to_string(), format!("chain from cluster {} holds 1024 of 2000 bytes", first));
+
+    // Strict reads refuse both
+    let strict = Fat32::new(&image).unwrap().with_options(ParseOptions::strict()).unwrap();
+    assert_eq!(strict.try_read_file(&entry), Err(FsError::Anomaly(Anomaly::LongChain { cluster: first, size: 13 })));
+    assert!(matches!(strict.open(&grown).map(|_| ()), Err(FsError::Anomaly(Anomaly::ShortChain { .. }))));
+    assert_eq!(strict.read_file_checked(&grown).unwrap().0.len(), 1024);
+
+    // A chain that matches the size is not flagged
+    let readme = fs.find_entry(fs.root_cluster(), "README.MD").unwrap();
+    assert_eq!(fs.read_file_checked(&readme).unwrap().1, None);
+}
+
+#[test]
+fn test_full_scan_past_zeroed_slot() {
+    // DOCS slot zeroed: README.MD now sits after a 0x00 terminator
+    let mut image = create_test_image();
+    let docs = {
+        let fs = Fat32::new(&image).unwrap();
+        fs.locate_entry(fs.root_cluster(), "DOCS").unwrap().short_offset() as usize
+    };
+    image[docs..docs + 32].fill(0);
+
+    let fs = Fat32::new(&image).unwrap();
+    let root = fs.root_cluster();
+    assert_eq!(fs.try_read_directory(root).unwrap().len(), 1);
+    assert!(fs.find_entry(root, "README.MD").is_none());
+
+    let fs = Fat32::new(&image).unwrap().with_options(ParseOptions::default().with_full_scan()).unwrap();
+    let names: Vec<String> = fs.try_read_directory(root).unwrap().iter().map(|(e, _)| e.display_name()).collect();
+    assert_eq!(names, ["TEST.TXT", "README.MD", "CHAIN.BIN"]);
+    let readme = fs.find_entry(root, "README.MD").unwrap();
+    assert_eq!(fs.read_file(&readme).len(), 19);
+    assert!(fs.find_entry_in(root, "README.MD").unwrap().is_some());
+    assert!(fs.locate_entry(root, "README.MD").is_some());
+    assert!(fs.resolve_path("/README.MD", root).is_some());
+}
+
+#[test]
+fn test_long_name_rules() {
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let root = fs.root_cluster();
+
+    let error = fs.write_file(root, "report:v2.txt", b"x").unwrap_err();
+    assert_eq!(error, FsError::InvalidLongName(NameError::ForbiddenChar(':')));
+    assert_eq!(error.to_string(), "Invalid name: forbidden character ':'");
+    assert!(fs.create_dir(root, "notes.").is_err());
+    assert!(fs.rename(root, "TEST.TXT", "test.txt ").is_err());
+    assert!(fs.write_file(root, &"x".repeat(LFN_MAX_UNITS + 1), b"x").is_err());
+
+    // Nothing was written by the rejected calls
+    assert_eq!(fs.read_directory(root).len(), 4);
+    fs.write_file(root, &"x".repeat(LFN_MAX_UNITS), b"x").unwrap();
+    assert!(is_long_name_char('é') && !is_long_name_char('|'));
+}
+
+#[test]
+fn test_unicode_name_lookup() {
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let root = fs.root_cluster();
+    fs.write_file(root, "Übersicht.txt", b"Alt").unwrap();
+    // Same name in another case: the file is replaced, not duplicated
+    fs.write_file(root, "übersicht.txt", b"Inhalt").unwrap();
+    assert_eq!(fs.read_directory(root).len(), 5);
+
+    // Latin-1 folding needs no tables: every lookup path agrees
+    let entry = fs.find_entry(root, "übersicht.TXT").unwrap();
+    assert_eq!(fs.read_file(&entry), b"Inhalt");
+    assert!(fs.find_entry_in(root, "ÜBERSICHT.TXT").unwrap().is_some());
+    assert!(fs.locate_entry(root, "übersicht.txt").is_some());
+    assert!(fs.resolve_path("/ÜBERSICHT.txt", root).is_some());
+    assert!(fs.find_entry(root, "ubersicht.txt").is_none());
+
+    let cached = Fat32::new(&image).unwrap().with_dir_cache();
+    assert!(cached.find_entry(root, "übersicht.txt").is_some());
+    assert!(cached.find_entry(root, "test.txt").is_some());
+}
+
+#[test]
+fn test_hidden_sectors_in_chain_output() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellState};
+
+    let mut image = create_test_image();
+    let (cluster, offset) = {
+        let fs = Fat32::new(&image).unwrap();
+        let cluster = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap().cluster();
+        (cluster, fs.cluster_offset(cluster))
+    };
+
+    // Partition starting at sector 2048 of the disk
+    image[28..32].copy_from_slice(&2048u32.to_le_bytes());
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    assert_eq!(fs.boot_sector().hidden_sectors, 2048);
+    // Offsets stay volume-relative, sector numbers are disk-absolute
+    let lba = 2048 + offset / 512;
+    assert_eq!(fs.cluster_offset(cluster), offset);
+    assert_eq!(fs.cluster_lba(cluster), lba);
+
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Sink(String::new());
+    execute_command(&mut fs, &mut state, "chain TEST.TXT", &mut out, &mut NoInput).unwrap();
+    assert!(out.0.contains(&format!("LBA {}-{}", lba, lba)), "{}", out.0);
+
+    let mut out = Sink(String::new());
+    execute_command(&mut fs, &mut state, &format!("dumpcluster {}", cluster), &mut out, &mut NoInput).unwrap();
+    let header = format!("Cluster {} (offset 0x{:08X}, LBA {}):", cluster, offset, lba);
+    assert!(out.0.starts_with(&header), "{}", out.0);
+}
+
+#[test]
+fn test_checksum_file_streams_over_clusters() {
+    use fat32_exam::checksum::{to_hex, Crc32, Sha256};
+
+    let image = create_test_image();
+    let fs = Fat32::new(&image).unwrap();
+
+    let entry = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap();
+    let mut chunks = Vec::new();
+    fs.for_each_file_chunk(&entry, |c| chunks.push(c.len()));
+    assert_eq!(chunks, vec![13]);
+
+    assert_eq!(to_hex(&fs.checksum_file(&entry, Crc32::new())), "5469e036");
+    assert_eq!(to_hex(&fs.checksum_file(&entry, Sha256::new())).len(), 64);
+}
+
+#[test]
+fn test_compare_files() {
+    let mut image = create_test_image();
+    // Second copy of TEST.TXT content, differing at byte 7
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let root = fs.root_cluster();
+    fs.write_file(root, "COPY.TXT", b"Hello, fat32!").unwrap();
+
+    let fs = Fat32::new(&image).unwrap();
+    let test = fs.find_entry(fs.root_cluster(), "TEST.TXT").unwrap();
+    let copy = fs.find_entry(fs.root_cluster(), "COPY.TXT").unwrap();
+    let readme = fs.find_entry(fs.root_cluster(), "README.MD").unwrap();
+
+    assert_eq!(fs.compare_files(&test, &test), None);
+    assert_eq!(fs.compare_files(&test, &copy), Some(7));
+    assert_eq!(fs.compare_files(&test, &readme), Some(0));
+
+    let mut prefix = test.clone();
+    prefix.size = 5;
+    assert_eq!(fs.compare_files(&test, &prefix), Some(5));
+}
+
+#[test]
+fn test_path_errors_name_component() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellError, ShellState};
+
+    let mut image = create_test_image();
+    let fs = Fat32::new(&image).unwrap();
+    let root = fs.root_cluster();
+
+    let error = fs.resolve_path_detailed("/DOCS/REPORTS/a.txt", root).unwrap_err();
+    assert_eq!((error.index, error.component.as_str()), (1, "REPORTS"));
+    assert_eq!(error.kind, PathErrorKind::NotFound);
+    assert_eq!(error.to_string(), "/DOCS/REPORTS: not found");
+
+    let error = fs.resolve_path_detailed("TEST.TXT/x", root).unwrap_err();
+    assert_eq!(error.kind, PathErrorKind::NotADirectory);
+    assert_eq!(error.to_string(), "TEST.TXT: not a directory");
+    assert_eq!(fs.try_resolve_path("TEST.TXT/x", root).unwrap_err(), FsError::NotFound);
+
+    let limited = Fat32::new(&image).unwrap().with_limits(Limits { max_path_depth: 1, ..Limits::default() });
+    let error = limited.resolve_path_detailed("/DOCS/INFO.TXT", root).unwrap_err();
+    assert_eq!((error.index, error.kind), (1, PathErrorKind::Fs(FsError::LimitExceeded)));
+    assert!(fs.resolve_path_detailed("/DOCS/INFO.TXT", root).is_ok());
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Sink(String::new());
+    let mut run = |line: &str| execute_command(&mut fs, &mut state, line, &mut out, &mut NoInput).unwrap_err();
+    assert_eq!(run("cat /DOCS/REPORTS/a.txt"), ShellError::NotFound("/DOCS/REPORTS: not found".into()));
+    assert_eq!(run("cd /TEST.TXT/x"), ShellError::NotFound("/TEST.TXT: not a directory".into()));
+    assert_eq!(run("ls DOCS/NOPE"), ShellError::NotFound("/DOCS/NOPE: not found".into()));
+    // A bare name keeps the short message
+    assert_eq!(run("cat NOPE"), ShellError::NotFound("File not found".into()));
+}
+
+#[test]
+fn test_execute_command_status() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellError, ShellState};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Sink(String::new());
+
+    assert_eq!(execute_command(&mut fs, &mut state, "cat TEST.TXT", &mut out, &mut NoInput), Ok(true));
+    assert!(out.0.contains("Hello, FAT32!"));
+    assert_eq!(execute_command(&mut fs, &mut state, "exit", &mut out, &mut NoInput), Ok(false));
+
+    let err = execute_command(&mut fs, &mut state, "cat MISSING.TXT", &mut out, &mut NoInput).unwrap_err();
+    assert_eq!(err, ShellError::NotFound(String::from("File not found")));
+    assert_eq!(err.exit_code(), 1);
+
+    let err = execute_command(&mut fs, &mut state, "ls -z", &mut out, &mut NoInput).unwrap_err();
+    assert_eq!(err.exit_code(), 2);
+    for line in ["chain", "dumpcluster", "crc32", "sha256", "cmp A", "rename X", "export", "import", "unalias", "run"] {
+        let err = execute_command(&mut fs, &mut state, line, &mut out, &mut NoInput).unwrap_err();
+        assert!(matches!(err, ShellError::Usage(_)), "{}", line);
+        assert_eq!(err.exit_code(), 2);
+    }
+    let err = execute_command(&mut fs, &mut state, "frobnicate", &mut out, &mut NoInput).unwrap_err();
+    assert_eq!(err.exit_code(), 127);
+
+    let err = execute_command(&mut fs, &mut state, "cd TEST.TXT", &mut out, &mut NoInput).unwrap_err();
+    assert_eq!(err, ShellError::Failed(String::from("Not a directory")));
+    assert_eq!(state.pwd(), "/");
+}
+
+#[test]
+fn test_tab_completion() {
+    use fat32_exam::shell::{complete, ShellState};
+
+    let image = create_test_image();
+    let fs = Fat32::new(&image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+
+    assert_eq!(complete(&fs, &state, "ch"), ["chain", "chdir"]);
+    assert_eq!(complete(&fs, &state, "help cm"), ["cmp"]);
+    assert_eq!(complete(&fs, &state, "cat te"), ["TEST.TXT"]);
+    assert_eq!(complete(&fs, &state, "cd d"), ["DOCS/"]);
+    assert_eq!(complete(&fs, &state, "cat /DOCS/"), ["/DOCS/INFO.TXT"]);
+    assert!(complete(&fs, &state, "cat NOPE/").is_empty());
+    assert!(complete(&fs, &state, "ls -").is_empty());
+
+    state.current_cluster = fs.find_entry(fs.root_cluster(), "DOCS").unwrap().cluster();
+    assert_eq!(complete(&fs, &state, "cat "), ["INFO.TXT"]);
+}
+
+#[test]
+fn test_more_pages_on_keys() {
+    use fat32_exam::shell::{cmd_more, Input, ShellState};
+
+    struct Keys(Vec<char>);
+    impl Input for Keys {
+        fn read_key(&mut self) -> Option<char> {
+            if self.0.is_empty() { None } else { Some(self.0.remove(0)) }
+        }
+    }
+
+    let image = FsBuilder::new(MB).file("TEST.TXT", b"1\n2\n3\n4\n5\n6\n7").build().unwrap();
+    let fs = Fat32::new(&image).unwrap();
+    let state = ShellState::new(fs.root_cluster());
+
+    // Enter: one more line, 'x' is ignored, space: next page, then 'q'
+    let mut out = Sink(String::new());
+    let mut keys = Keys(vec!['\n', 'x', ' ', 'q']);
+    cmd_more(&fs, &state, "TEST.TXT", &mut out, &mut keys, 2).unwrap();
+    let shown: Vec<&str> = out.0.lines().filter_map(|l| l.rsplit('\r').next())
+        .filter(|l| !l.trim().is_empty()).collect();
+    assert_eq!(shown, ["1", "2", "3", "4", "5"]);
+    assert!(keys.0.is_empty());
+
+    let mut out = Sink(String::new());
+    cmd_more(&fs, &state, "TEST.TXT", &mut out, &mut fat32_exam::shell::NoInput, 2).unwrap();
+    assert_eq!(out.0, "1\n2\n3\n4\n5\n6\n7\n");
+}
+
+#[test]
+fn test_pushd_popd() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellState};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Sink(String::new());
+    let docs = fs.find_entry(fs.root_cluster(), "DOCS").unwrap().cluster();
+
+    assert!(execute_command(&mut fs, &mut state, "pushd DOCS", &mut out, &mut NoInput).is_ok());
+    assert_eq!((state.pwd(), state.current_cluster), (String::from("/DOCS"), docs));
+    assert!(execute_command(&mut fs, &mut state, "pushd", &mut out, &mut NoInput).is_ok());
+    assert_eq!(state.pwd(), "/");
+    assert!(execute_command(&mut fs, &mut state, "popd", &mut out, &mut NoInput).is_ok());
+    assert_eq!(state.current_cluster, docs);
+    assert_eq!(out.0, "/DOCS /\n/ /DOCS\n/DOCS\n");
+
+    assert!(execute_command(&mut fs, &mut state, "pushd NOPE", &mut out, &mut NoInput).is_err());
+    assert!(state.dir_stack.is_empty());
+
+    // Formatting forgets directories saved from the old layout
+    assert!(execute_command(&mut fs, &mut state, "pushd /", &mut out, &mut NoInput).is_ok());
+    assert!(execute_command(&mut fs, &mut state, "format --yes", &mut out, &mut NoInput).is_ok());
+    assert!(state.dir_stack.is_empty());
+    assert!(execute_command(&mut fs, &mut state, "popd", &mut out, &mut NoInput).is_err());
+    assert_eq!(state.current_cluster, fs.root_cluster());
+}
+
+#[test]
+fn test_execute_script() {
+    use fat32_exam::shell::{execute_command, execute_script, NoInput, ShellError, ShellState};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Sink(String::new());
+
+    let script = "# go to docs\n\ncd DOCS\ncat INFO.TXT\ncat MISSING\npwd\n";
+    let err = execute_script(&mut fs, &mut state, script, false, &mut out, &mut NoInput).unwrap_err();
+    assert!(matches!(err, ShellError::Script { line: 5, .. }));
+    assert_eq!(err.exit_code(), 1);
+    assert_eq!(out.0, "Info file content\n");
+
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Sink(String::new());
+    let err = execute_script(&mut fs, &mut state, script, true, &mut out, &mut NoInput).unwrap_err();
+    assert_eq!(err.to_string(), "run: 1 command(s) failed");
+    assert_eq!(out.0, "Info file content\nline 5: File not found\n/DOCS\n");
+
+    let mut state = ShellState::new(fs.root_cluster());
+    let err = execute_command(&mut fs, &mut state, "run TEST.TXT", &mut out, &mut NoInput).unwrap_err();
+    assert_eq!(err.to_string(), "line 1: Unknown command: Hello,\nType 'help' for available commands");
+}
+
+#[test]
+fn test_errors_go_to_error_stream() {
+    use fat32_exam::shell::{execute_script, NoInput, Output, ShellState};
+
+    #[derive(Default)]
+    struct Split {
+        out: String,
+        err: String,
+    }
+    impl Output for Split {
+        fn write_str(&mut self, s: &str) {
+            self.out.push_str(s);
+        }
+        fn write_err_line(&mut self, s: &str) {
+            self.err.push_str(s);
+            self.err.push('\n');
+        }
+    }
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Split::default();
+
+    let script = "cat MISSING\ncat TEST.TXT\n";
+    assert!(execute_script(&mut fs, &mut state, script, true, &mut out, &mut NoInput).is_err());
+    assert_eq!(out.out, "Hello, FAT32!\n");
+    assert_eq!(out.err, "line 1: File not found\n");
+}
+
+#[test]
+fn test_output_redirection() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellState};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+    let mut out = Sink(String::new());
+
+    let run = |fs: &mut Fat32, state: &mut ShellState, out: &mut Sink, line: &str| {
+        execute_command(fs, state, line, out, &mut NoInput)
+    };
+
+    assert!(run(&mut fs, &mut state, &mut out, "cat TEST.TXT > /DOCS/COPY.TXT").is_ok());
+    assert!(run(&mut fs, &mut state, &mut out, "pwd > \"where am i.txt\"").is_ok());
+    assert_eq!(out.0, "");
+
+    let docs = fs.find_entry(fs.root_cluster(), "DOCS").unwrap().cluster();
+    let copy = fs.find_entry(docs, "COPY.TXT").unwrap();
+    assert_eq!(fs.read_file(&copy), b"Hello, FAT32!\n");
+    let pwd = fs.find_entry(fs.root_cluster(), "where am i.txt").unwrap();
+    assert_eq!(fs.read_file(&pwd), b"/\n");
+
+    // The file is still created when the command fails, like a POSIX shell
+    assert!(run(&mut fs, &mut state, &mut out, "cat NOPE > ERR.TXT").is_err());
+    assert_eq!(fs.find_entry(fs.root_cluster(), "ERR.TXT").unwrap().size, 0);
+    assert!(run(&mut fs, &mut state, &mut out, "pwd > /NOPE/X.TXT").is_err());
+    assert!(run(&mut fs, &mut state, &mut out, "pwd >").is_err());
+}
+
+#[test]
+fn test_command_pipes() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellState, ShellError};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+
+    let mut run = |line: &str| {
+        let mut out = Sink(String::new());
+        execute_command(&mut fs, &mut state, line, &mut out, &mut NoInput).map(|_| out.0)
+    };
+
+    assert!(run("ls | grep DOCS").unwrap().trim().ends_with("DOCS/"));
+    assert_eq!(run("cat TEST.TXT | grep -c fat32").unwrap(), "0\n");
+    assert_eq!(run("cat TEST.TXT | grep -i fat32 | cat -n").unwrap(), "     1  Hello, FAT32!\n");
+    assert_eq!(run("ls | head -n 1").unwrap().lines().count(), 1);
+    assert_eq!(run("alias g='grep -i'").unwrap(), "");
+    assert_eq!(run("cat TEST.TXT | g HELLO").unwrap(), "Hello, FAT32!\n");
+    assert_eq!(run("cat TEST.TXT | more").unwrap(), "Hello, FAT32!\n");
+
+    // Pipes combine with redirection; a failing stage stops the pipe
+    assert_eq!(run("cat TEST.TXT | grep Hello > OUT.TXT").unwrap(), "");
+    assert_eq!(run("cat OUT.TXT").unwrap(), "Hello, FAT32!\n");
+    assert!(matches!(run("cat NOPE | grep x"), Err(ShellError::NotFound(_))));
+    assert!(matches!(run("ls |"), Err(ShellError::Usage(_))));
+    assert!(matches!(run("grep x"), Err(ShellError::Usage(_))));
+}
+
+#[test]
+fn test_terminal_size_layout() {
+    use fat32_exam::shell::{execute_command, NoInput, Output, ShellState, TerminalInfo};
+
+    struct Tty(String, TerminalInfo);
+    impl Output for Tty {
+        fn write_str(&mut self, s: &str) {
+            self.0.push_str(s);
+        }
+
+        fn terminal(&self) -> Option<TerminalInfo> {
+            Some(self.1)
+        }
+    }
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+
+    // A wide terminal puts every name on the first line
+    let mut out = Tty(String::new(), TerminalInfo { rows: 24, cols: 80 });
+    execute_command(&mut fs, &mut state, "ls", &mut out, &mut NoInput).unwrap();
+    let first = out.0.lines().next().unwrap();
+    assert!(first.contains("TEST.TXT") && first.contains("DOCS/"));
+
+    // Through a pipe, ls keeps one entry per line
+    let mut out = Tty(String::new(), TerminalInfo { rows: 24, cols: 80 });
+    execute_command(&mut fs, &mut state, "ls | grep TEST", &mut out, &mut NoInput).unwrap();
+    assert!(!out.0.contains("DOCS"));
+
+    // more uses the terminal height minus the prompt line
+    struct Quit;
+    impl fat32_exam::shell::Input for Quit {
+        fn read_key(&mut self) -> Option<char> {
+            Some('q')
+        }
+    }
+
+    let text: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+    fs.write_file(fs.root_cluster(), "TEN.TXT", text.as_bytes()).unwrap();
+    let mut out = Tty(String::new(), TerminalInfo { rows: 5, cols: 80 });
+    execute_command(&mut fs, &mut state, "more TEN.TXT", &mut out, &mut Quit).unwrap();
+    assert!(out.0.contains("line 4\n") && !out.0.contains("line 5"));
+}
+
+#[test]
+fn test_quiet_and_verbose_modes() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellState, Verbosity};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+
+    let mut run = |state: &mut ShellState, line: &str| {
+        let mut out = Sink(String::new());
+        execute_command(&mut fs, state, line, &mut out, &mut NoInput).unwrap();
+        out.0
+    };
+
+    assert!(run(&mut state, "ls").contains("file(s)"));
+
+    state.config.verbosity = Verbosity::Quiet;
+    let listing = run(&mut state, "ls");
+    assert!(listing.contains("TEST.TXT") && !listing.contains("file(s)"));
+
+    assert_eq!(run(&mut state, "set verbose on"), "");
+    assert_eq!(run(&mut state, "pwd > WHERE.TXT"), "Wrote 2 bytes to WHERE.TXT\n");
+    assert_eq!(run(&mut state, "rename WHERE.TXT HERE.TXT"), "Renamed WHERE.TXT -> HERE.TXT\n");
+}
+
+#[test]
+fn test_set_shell_options() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellState};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+
+    let mut run = |state: &mut ShellState, line: &str| {
+        let mut out = Sink(String::new());
+        execute_command(&mut fs, state, line, &mut out, &mut NoInput).unwrap();
+        out.0
+    };
+
+    assert_eq!(run(&mut state, "cat TEST.TXT | grep hello"), "");
+    run(&mut state, "set case off");
+    assert_eq!(run(&mut state, "cat TEST.TXT | grep hello"), "Hello, FAT32!\n");
+
+    run(&mut state, "set json on");
+    let listing = run(&mut state, "ls");
+    assert!(listing.starts_with("[\n") && listing.ends_with("]\n"));
+    assert!(listing.contains("{\"name\": \"TEST.TXT\", \"dir\": false, \"size\": "));
+    assert!(run(&mut state, "info").starts_with("{\"label\": "));
+
+    run(&mut state, "set pagesize 7");
+    assert_eq!(state.config.page_lines, Some(7));
+    assert_eq!(run(&mut state, "set pagesize"), "7\n");
+}
+
+#[test]
+fn test_translated_messages() {
+    use fat32_exam::shell::{execute_command, write_error, Catalog, CommandInfo, NoInput, ShellState};
+
+    struct French;
+    impl Catalog for French {
+        fn translate<'a>(&'a self, message: &'a str) -> &'a str {
+            match message {
+                "File not found" => "Fichier introuvable",
+                "Unknown command: {}\nType 'help' for available commands" => "Commande inconnue : {}",
+                "  {} file(s)  {} bytes" => "  {1} octets dans {0} fichier(s)",
+                other => other,
+            }
+        }
+
+        fn summary(&self, info: &'static CommandInfo) -> &str {
+            match info.name {
+                "ls" => "Lister le contenu d'un répertoire",
+                _ => info.summary,
+            }
+        }
+    }
+    static FRENCH: French = French;
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let mut state = ShellState::new(fs.root_cluster());
+    state.catalog = &FRENCH;
+    let mut out = Sink(String::new());
+
+    let err = execute_command(&mut fs, &mut state, "cat NOPE.TXT", &mut out, &mut NoInput).unwrap_err();
+    write_error(&state, &err, &mut out);
+    let err = execute_command(&mut fs, &mut state, "frobnicate", &mut out, &mut NoInput).unwrap_err();
+    write_error(&state, &err, &mut out);
+    assert_eq!(out.0, "Fichier introuvable\nCommande inconnue : frobnicate\n");
+
+    let mut out = Sink(String::new());
+    execute_command(&mut fs, &mut state, "ls", &mut out, &mut NoInput).unwrap();
+    assert!(out.0.contains("octets dans 3 fichier(s)"));
+
+    let mut out = Sink(String::new());
+    execute_command(&mut fs, &mut state, "help", &mut out, &mut NoInput).unwrap();
+    assert!(out.0.contains("Lister le contenu d'un répertoire"));
+}
+
+#[test]
+fn test_restricted_root() {
+    use fat32_exam::shell::{execute_command, NoInput, ShellError, ShellState};
+
+    let mut image = create_test_image();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let docs = fs.find_entry(fs.root_cluster(), "DOCS").unwrap().cluster();
+    let mut state = ShellState::with_root(docs, "/DOCS");
+
+    let mut run = |state: &mut ShellState, line: &str| {
+        let mut out = Sink(String::new());
+        execute_command(&mut fs, state, line, &mut out, &mut NoInput).map(|_| out.0)
+    };
+
+    // "/" is the subtree root, and ".." cannot climb above it
+    assert!(run(&mut state, "cat /INFO.TXT").is_ok());
+    assert!(run(&mut state, "cat ../../INFO.TXT").is_ok());
+    assert!(matches!(run(&mut state, "cat /TEST.TXT"), Err(ShellError::NotFound(_))));
+    assert!(matches!(run(&mut state, "cat ../TEST.TXT"), Err(ShellError::NotFound(_))));
+    assert!(matches!(run(&mut state, "cat ../README.MD"), Err(ShellError::NotFound(_))));
+
+    run(&mut state, "cd ..").unwrap();
+    assert_eq!(state.current_cluster, docs);
+    assert_eq!(run(&mut state, "pwd").unwrap(), "/\n");
+    assert_eq!(state.volume_path(), "/DOCS");
+    assert!(!run(&mut state, "ls ..").unwrap().contains("TEST.TXT"));
+
+    // Raw volume access is refused
+    assert!(matches!(run(&mut state, "dumpcluster 2"), Err(ShellError::Failed(_))));
+    assert!(matches!(run(&mut state, "format --yes"), Err(ShellError::Failed(_))));
+}
+
+#[test]
+fn test_generated_image() {
+    use fat32_exam::testimg::*;
+
+    let spec = TestImage::new(2 * 1024 * 1024)
+        .cluster_size(1024)
+        .long_names()
+        .fragmented()
+        .deep_tree(6)
+        .deleted();
+    let image = spec.build().unwrap();
+    let fs = Fat32::new(&image).unwrap();
+    let root = fs.root_cluster();
+    assert_eq!(fs.bytes_per_cluster(), 1024);
+    assert_eq!(fs.boot_sector().label(), "TEST IMAGE");
+
+    let readme = fs.resolve_path(README_PATH, root).unwrap();
+    assert_eq!(fs.read_file(&readme), README_TEXT);
+    for path in LONG_NAME_PATHS {
+        assert!(fs.resolve_path(path, root).is_some(), "{} is missing", path);
+    }
+    assert!(fs.resolve_path(&spec.leaf_path(), root).is_some());
+
+    // The fragmented file reads back intact although its clusters are not contiguous
+    let frag = fs.resolve_path(FRAGMENTED_PATH, root).unwrap();
+    let chain = fs.cluster_chain(frag.cluster());
+    assert_eq!(chain.len(), FRAGMENTED_CLUSTERS);
+    assert!(chain.windows(2).any(|w| w[1] != w[0] + 1));
+    assert_eq!(fs.read_file(&frag), spec.fragmented_data());
+
+    let deleted = fs.list_deleted(root);
+    assert_eq!(deleted.len(), 1);
+    assert!(deleted[0].recoverable);
+    assert_eq!(deleted[0].display_name(), "?ELETED.TXT");
+}
+
+#[test]
+fn test_read_directory_deleted() {
+    use fat32_exam::testimg::*;
+
+    let mut image = TestImage::default().long_names().deleted().build().unwrap();
+    let mut fs = Fat32::new_mut(&mut image).unwrap();
+    let docs = fs.resolve_path("/My Documents", fs.root_cluster()).unwrap().cluster();
+    let notes = fs.find_entry(docs, "notes about fat32.md").unwrap();
+    fs.remove_file(docs, "notes about fat32.md").unwrap();
+
+    // The long name survives in the deleted LFN slots, the short name gets its first letter back
+    let deleted = fs.read_directory_deleted(docs).unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].name(), "notes about fat32.md");
+    assert_eq!(deleted[0].first_char, Some(notes.name[0] as char));
+    assert_eq!(deleted[0].entry.size, notes.size);
+    assert_eq!(deleted[0].entry.cluster(), notes.cluster());
+
+    // A short-only entry has nothing to rebuild from
+    let root = fs.read_directory_deleted(fs.root_cluster()).unwrap();
+    assert_eq!(root.len(), 1);
+    assert_eq!((root[0].long_name.as_deref(), root[0].first_char), (None, None));
+    assert_eq!(root[0].name(), "?ELETED.TXT");
+}